@@ -1,10 +1,11 @@
 //! a very simplified DNS query to quad9 primary server 9.9.9.9
 
 // NTP protocol implemebtation
+use std::error::Error;
 use std::net::{Ipv4Addr, UdpSocket};
-use std::{error::Error, io::Cursor};
 
 // need this to serialize/deserialize to network
+use type2network::socket::UdpSocketExt;
 use type2network::{FromNetworkOrder, ToNetworkOrder};
 use type2network_derive::{FromNetwork, ToNetwork};
 
@@ -72,25 +73,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     q.question.qclass = 1;
     println!("question: {:#?}", q);
 
-    // serialize to send the DNS packet through the wire using UDP
-    let mut buffer: Vec<u8> = Vec::new();
-    q.serialize_to(&mut buffer)?;
-
     // bind to an ephemeral local port
     let socket = UdpSocket::bind("0.0.0.0:0")?;
 
-    // send packet to destination
+    // serialize and send the DNS packet through the wire using UDP
     let dest = "9.9.9.9:53";
-    socket.send_to(&buffer, dest)?;
-
-    // now receive the request using a sufficient buffer
-    let mut recbuf = [0; 512];
-    let received = socket.recv(&mut recbuf)?;
-    let mut cursor = Cursor::new(&recbuf[..received]);
+    socket.send_msg_to(&q, dest)?;
 
     // get response
-    let mut r = Response::default();
-    r.deserialize_from(&mut cursor)?;
+    let r: Response = socket.recv_msg()?;
     println!("{:#X?}", r);
 
     // convert u32 ip to ipv4
@@ -1,10 +1,11 @@
 // NTP protocol implemebtation
+use std::error::Error;
 use std::net::UdpSocket;
-use std::{error::Error, io::Cursor};
 
 // need this to serialize/deserialize to network
+use type2network::socket::UdpSocketExt;
 use type2network::{FromNetworkOrder, ToNetworkOrder};
-use type2network_derive::{FromNetwork, ToNetwork};
+use type2network_derive::{FromNetwork, ToNetwork, WireDefault};
 
 // https://datatracker.ietf.org/doc/html/rfc4330
 // 1                   2                   3
@@ -43,9 +44,11 @@ use type2network_derive::{FromNetwork, ToNetwork};
 // |                                                                |
 // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 
-#[derive(Debug, Default, ToNetwork, FromNetwork)]
+#[derive(Debug, WireDefault, ToNetwork, FromNetwork)]
 struct Header {
-    values: u8, // includes Leap Indicator, Version Number & Mode
+    // includes Leap Indicator, Version Number & Mode: version number 4 and mode 3 (client)
+    #[network(expect = 4 << 3 | 3)]
+    values: u8,
     stratum: u8,
     poll: u8,
     precision: u8,
@@ -73,30 +76,17 @@ struct SNTPPacket {
 
 fn main() -> Result<(), Box<dyn Error>> {
     // fill client packet
-    let mut ntp = SNTPPacket::default();
-
-    // set version number to 4 and mode to 3 (client)
-    ntp.header.values = 4 << 3 | 3;
-
-    // serialize to send the SNTP packet through the wire using UDP
-    let mut buffer: Vec<u8> = Vec::new();
-    ntp.serialize_to(&mut buffer)?;
+    let ntp = SNTPPacket::default();
 
     // bind to an ephemeral local port
     let socket = UdpSocket::bind("0.0.0.0:0")?;
 
-    // send packet to destination
+    // serialize and send the SNTP packet through the wire using UDP
     let dest = "fr.pool.ntp.org:123";
-    socket.send_to(&buffer, dest)?;
-
-    // now receive the request using a sufficient buffer
-    let mut recbuf = [0; 512];
-    let received = socket.recv(&mut recbuf)?;
-    let mut cursor = Cursor::new(&recbuf[..received]);
+    socket.send_msg_to(&ntp, dest)?;
 
     // get response
-    ntp = SNTPPacket::default();
-    ntp.deserialize_from(&mut cursor)?;
+    let ntp: SNTPPacket = socket.recv_msg()?;
     println!("{:#?}", ntp);
 
     Ok(())
@@ -32,6 +32,13 @@ where
 
         Ok(length)
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        match self {
+            Either::Left(l) => l.write_to(writer),
+            Either::Right(r) => r.write_to(writer),
+        }
+    }
 }
 
 impl ToNetworkOrder for Bytes {
@@ -50,6 +57,11 @@ impl ToNetworkOrder for Bytes {
 
         Ok(self.len())
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(self)?;
+        Ok(self.len())
+    }
 }
 
 impl<'a> FromNetworkOrder<'a> for BytesMut {
@@ -71,3 +83,560 @@ impl<'a> FromNetworkOrder<'a> for BytesMut {
         Ok(())
     }
 }
+
+#[cfg(feature = "smallvec")]
+impl<A> ToNetworkOrder for smallvec::SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: ToNetworkOrder,
+{
+    /// Example:
+    /// ```
+    /// use smallvec::{smallvec, SmallVec};
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let v: SmallVec<[u16; 4]> = smallvec![0x1234, 0x5678];
+    /// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(&buffer, &[0x12, 0x34, 0x56, 0x78]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+
+        for item in self {
+            length += item.serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::size::write_items(self, writer)
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<'a, A> FromNetworkOrder<'a> for smallvec::SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: FromNetworkOrder<'a>,
+{
+    /// The elements already in the vector are deserialized in place. Use the ```#[network(count = "expr")]```
+    /// field attribute to read a given number of elements.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use smallvec::{smallvec, SmallVec};
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = vec![0x12, 0x34, 0x56, 0x78];
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// let mut v: SmallVec<[u16; 4]> = smallvec![0; 2];
+    /// assert!(v.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(v.as_slice(), &[0x1234, 0x5678]);
+    /// assert!(!v.spilled());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        for item in self {
+            item.deserialize_from(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array> crate::vec::Push<A::Item> for smallvec::SmallVec<A> {
+    fn clear(&mut self) {
+        smallvec::SmallVec::clear(self)
+    }
+
+    fn try_push(&mut self, item: A::Item) -> std::io::Result<()> {
+        self.push(item);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T: ToNetworkOrder, const N: usize> ToNetworkOrder for heapless::Vec<T, N> {
+    /// Example:
+    /// ```
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let v: heapless::Vec<u16, 4> = heapless::Vec::from_slice(&[0x1234, 0x5678]).unwrap();
+    /// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(&buffer, &[0x12, 0x34, 0x56, 0x78]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+
+        for item in self {
+            length += item.serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::size::write_items(self, writer)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, T: FromNetworkOrder<'a>, const N: usize> FromNetworkOrder<'a> for heapless::Vec<T, N> {
+    /// The elements already in the vector are deserialized in place. Use the ```#[network(count = "expr")]```
+    /// field attribute to read a given number of elements: an error is returned if it's more than ```N```.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = vec![0x12, 0x34, 0x56, 0x78];
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// let mut v: heapless::Vec<u16, 4> = heapless::Vec::from_slice(&[0, 0]).unwrap();
+    /// assert!(v.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(&v, &[0x1234, 0x5678]);
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        for item in self {
+            item.deserialize_from(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> crate::vec::Push<T> for heapless::Vec<T, N> {
+    fn clear(&mut self) {
+        heapless::Vec::clear(self)
+    }
+
+    fn try_push(&mut self, item: T) -> std::io::Result<()> {
+        self.push(item).map_err(|_| crate::vec::full(N))
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> ToNetworkOrder for heapless::String<N> {
+    /// Example:
+    /// ```
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let s: heapless::String<16> = heapless::String::try_from("I ❤ 東").unwrap();
+    /// assert_eq!(s.serialize_to(&mut buffer).unwrap(), 9);
+    /// assert_eq!(buffer, &[73, 32, 226, 157, 164, 32, 230, 157, 177]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(self.as_bytes());
+        Ok(self.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, const N: usize> FromNetworkOrder<'a> for heapless::String<N> {
+    /// The remaining bytes of the buffer are read: an error is returned if they are more than ```N```
+    /// or not valid UTF-8.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = "東京".as_bytes();
+    /// let mut buffer = Cursor::new(b);
+    /// let mut s: heapless::String<8> = heapless::String::new();
+    /// assert!(s.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(s, "東京");
+    ///
+    /// let mut buffer = Cursor::new(b);
+    /// let mut s: heapless::String<4> = heapless::String::new();
+    /// assert!(s.deserialize_from(&mut buffer).is_err());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let position = (buffer.position() as usize).min(buffer.get_ref().len());
+        let bytes = &buffer.get_ref()[position..];
+
+        if bytes.len() > N {
+            return Err(crate::vec::full(N));
+        }
+        let s = std::str::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        self.clear();
+        // can't fail as the length was checked
+        let _ = self.push_str(s);
+        buffer.set_position(buffer.get_ref().len() as u64);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T: ToNetworkOrder, const N: usize> ToNetworkOrder for arrayvec::ArrayVec<T, N> {
+    /// Example:
+    /// ```
+    /// use arrayvec::ArrayVec;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let v: ArrayVec<u16, 4> = [0x1234, 0x5678].into_iter().collect();
+    /// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(&buffer, &[0x12, 0x34, 0x56, 0x78]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+
+        for item in self {
+            length += item.serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::size::write_items(self, writer)
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<'a, T: FromNetworkOrder<'a>, const N: usize> FromNetworkOrder<'a>
+    for arrayvec::ArrayVec<T, N>
+{
+    /// The elements already in the vector are deserialized in place. Use the ```#[network(count = "expr")]```
+    /// field attribute to read a given number of elements: an error is returned if it's more than ```N```.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use arrayvec::ArrayVec;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = vec![0x12, 0x34, 0x56, 0x78];
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// let mut v: ArrayVec<u16, 4> = [0, 0].into_iter().collect();
+    /// assert!(v.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(v.as_slice(), &[0x1234, 0x5678]);
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        for item in self {
+            item.deserialize_from(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T, const N: usize> crate::vec::Push<T> for arrayvec::ArrayVec<T, N> {
+    fn clear(&mut self) {
+        arrayvec::ArrayVec::clear(self)
+    }
+
+    fn try_push(&mut self, item: T) -> std::io::Result<()> {
+        arrayvec::ArrayVec::try_push(self, item).map_err(|_| crate::vec::full(N))
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<const N: usize> ToNetworkOrder for arrayvec::ArrayString<N> {
+    /// Example:
+    /// ```
+    /// use arrayvec::ArrayString;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let s = ArrayString::<8>::from("eth0").unwrap();
+    /// assert_eq!(s.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(buffer, b"eth0");
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(self.as_bytes());
+        Ok(self.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<'a, const N: usize> FromNetworkOrder<'a> for arrayvec::ArrayString<N> {
+    /// The remaining bytes of the buffer are read: an error is returned if they are more than ```N```
+    /// or not valid UTF-8.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use arrayvec::ArrayString;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let mut buffer = Cursor::new(b"eth0".as_slice());
+    /// let mut s = ArrayString::<8>::new();
+    /// assert!(s.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(s.as_str(), "eth0");
+    ///
+    /// let mut buffer = Cursor::new(b"eth0".as_slice());
+    /// let mut s = ArrayString::<2>::new();
+    /// assert!(s.deserialize_from(&mut buffer).is_err());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let position = (buffer.position() as usize).min(buffer.get_ref().len());
+        let bytes = &buffer.get_ref()[position..];
+
+        if bytes.len() > N {
+            return Err(crate::vec::full(N));
+        }
+        let s = std::str::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        self.clear();
+        // can't fail as the length was checked
+        let _ = self.try_push_str(s);
+        buffer.set_position(buffer.get_ref().len() as u64);
+
+        Ok(())
+    }
+}
+
+// the 16-byte layout of a Decimal on the wire:
+//
+// byte 0       sign: 0x00 positive, 0x80 negative
+// byte 1       scale: the number of decimal digits, from 0 to 28
+// bytes 2..4   reserved, zero
+// bytes 4..16  the 96-bit unsigned mantissa, big-endian
+#[cfg(feature = "rust_decimal")]
+impl ToNetworkOrder for rust_decimal::Decimal {
+    /// The value is sent as 16 bytes: the sign (```0x80``` if negative), the scale, 2 reserved zero bytes
+    /// and the 96-bit mantissa in big-endian order. The value is ```mantissa / 10^scale```.
+    ///
+    /// Example:
+    /// ```
+    /// use std::str::FromStr;
+    /// use rust_decimal::Decimal;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// let v = Decimal::from_str("-12.34").unwrap();
+    /// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 16);
+    /// assert_eq!(&buffer, &[0x80, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x04, 0xD2]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&decimal_bytes(self));
+        Ok(16)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&decimal_bytes(self))?;
+        Ok(16)
+    }
+}
+
+// the 16 bytes of a Decimal on the wire
+#[cfg(feature = "rust_decimal")]
+fn decimal_bytes(value: &rust_decimal::Decimal) -> [u8; 16] {
+    // flags, lo, mid and hi as little-endian u32, each part being written in big-endian order
+    let bytes = value.serialize();
+    let part = |i: usize| [bytes[i + 3], bytes[i + 2], bytes[i + 1], bytes[i]];
+
+    let sign = if value.is_sign_negative() { 0x80 } else { 0 };
+    let mut wire = [0u8; 16];
+    wire[0] = sign;
+    wire[1] = value.scale() as u8;
+    wire[4..8].copy_from_slice(&part(12));
+    wire[8..12].copy_from_slice(&part(8));
+    wire[12..16].copy_from_slice(&part(4));
+    wire
+}
+
+#[cfg(feature = "rust_decimal")]
+impl<'a> FromNetworkOrder<'a> for rust_decimal::Decimal {
+    /// An ```InvalidData``` error is returned if the sign or reserved bytes are not as expected, or if the
+    /// scale is greater than 28. A negative zero is read as zero.
+    ///
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use std::str::FromStr;
+    /// use rust_decimal::Decimal;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = vec![0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xE2, 0x40];
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// let mut v = Decimal::default();
+    /// assert!(v.deserialize_from(&mut buffer).is_ok());
+    /// assert_eq!(v, Decimal::from_str("123.456").unwrap());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let mut header = [0u8; 4];
+        std::io::Read::read_exact(buffer, &mut header)?;
+        let hi = crate::codec::get_u32(buffer)?;
+        let mid = crate::codec::get_u32(buffer)?;
+        let lo = crate::codec::get_u32(buffer)?;
+
+        let [sign, scale, 0, 0] = header else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "reserved bytes of a decimal are not zero",
+            ));
+        };
+        if sign & 0x7F != 0 || scale > 28 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid decimal sign {:#04x} or scale {}", sign, scale),
+            ));
+        }
+
+        *self = rust_decimal::Decimal::from_parts(lo, mid, hi, sign != 0, scale as u32);
+        Ok(())
+    }
+}
+
+// the wide integers and hashes are sent as their 32 big-endian bytes
+#[cfg(any(feature = "primitive-types", feature = "ethnum"))]
+fn get_32_bytes(buffer: &mut std::io::Cursor<&[u8]>) -> std::io::Result<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    std::io::Read::read_exact(buffer, &mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "primitive-types")]
+impl ToNetworkOrder for primitive_types::U256 {
+    /// The value is sent as 32 big-endian bytes.
+    ///
+    /// Example:
+    /// ```
+    /// use primitive_types::U256;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert_eq!(U256::from(0x1234).serialize_to(&mut buffer).unwrap(), 32);
+    /// assert_eq!(&buffer[30..], &[0x12, 0x34]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&self.to_big_endian());
+        Ok(32)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&self.to_big_endian())?;
+        Ok(32)
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl<'a> FromNetworkOrder<'a> for primitive_types::U256 {
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use primitive_types::U256;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let mut b = vec![0xFF; 32];
+    /// b[0] = 0x7F;
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// let v = U256::deserialize_new(&mut buffer).unwrap();
+    /// assert_eq!(v, U256::MAX >> 1);
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = primitive_types::U256::from_big_endian(&get_32_bytes(buffer)?);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl ToNetworkOrder for primitive_types::H256 {
+    /// The hash is sent as its 32 bytes.
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(self.as_bytes());
+        Ok(32)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(32)
+    }
+}
+
+#[cfg(feature = "primitive-types")]
+impl<'a> FromNetworkOrder<'a> for primitive_types::H256 {
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = primitive_types::H256(get_32_bytes(buffer)?);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ethnum")]
+impl ToNetworkOrder for ethnum::U256 {
+    /// The value is sent as 32 big-endian bytes.
+    ///
+    /// Example:
+    /// ```
+    /// use ethnum::U256;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert_eq!(U256::new(0x1234).serialize_to(&mut buffer).unwrap(), 32);
+    /// assert_eq!(&buffer[30..], &[0x12, 0x34]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&self.to_be_bytes());
+        Ok(32)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&self.to_be_bytes())?;
+        Ok(32)
+    }
+}
+
+#[cfg(feature = "ethnum")]
+impl<'a> FromNetworkOrder<'a> for ethnum::U256 {
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = ethnum::U256::from_be_bytes(get_32_bytes(buffer)?);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ethnum")]
+impl ToNetworkOrder for ethnum::I256 {
+    /// The value is sent as 32 big-endian bytes, in two's complement.
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&self.to_be_bytes());
+        Ok(32)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&self.to_be_bytes())?;
+        Ok(32)
+    }
+}
+
+#[cfg(feature = "ethnum")]
+impl<'a> FromNetworkOrder<'a> for ethnum::I256 {
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use ethnum::I256;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = vec![0xFF; 32];
+    /// let mut buffer = Cursor::new(b.as_slice());
+    /// assert_eq!(I256::deserialize_new(&mut buffer).unwrap(), I256::MINUS_ONE);
+    /// assert!(I256::deserialize_new(&mut buffer).is_err());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = ethnum::I256::from_be_bytes(get_32_bytes(buffer)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! Alignment of fields relative to the start of the outermost message, as in NetBIOS or SMB.
+//!
+//! With ```#[network(align_from_parent = N)]```, padding bytes are added before the field when serializing, and
+//! skipped when deserializing, so the field starts at a multiple of ```N``` bytes from the start of the message
+//! being serialized or deserialized, even when the field belongs to a struct nested in that message:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Entry {
+//!     kind: u8,
+//!     #[network(align_from_parent = 4)]
+//!     value: u32,
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Message {
+//!     id: u16,
+//!     entry: Entry,
+//! }
+//!
+//! // the value is at offset 4 in the message, not at offset 4 in the entry
+//! let msg = Message { id: 1, entry: Entry { kind: 2, value: 3 } };
+//! let mut buffer = Vec::new();
+//! assert_eq!(msg.serialize_to(&mut buffer).unwrap(), 8);
+//! assert_eq!(buffer, &[0, 1, 2, 0, 0, 0, 0, 3]);
+//!
+//! let mut cursor = Cursor::new(buffer.as_slice());
+//! assert_eq!(Message::deserialize_new(&mut cursor).unwrap(), msg);
+//! ```
+//!
+//! The message starts at the beginning of the buffer: offset 0 of the ```Vec<u8>``` when serializing, and of the
+//! ```Cursor``` when deserializing. When a message is written after other bytes, e.g. a transport header,
+//! ```with_origin()``` gives the offset where it starts (```framing::write_frame()``` does it for its length prefix).
+//! With ```ToNetworkOrder::write_to()```, the message starts at the first byte written by the outermost call, whatever
+//! the origin.
+//!
+//! With ```#[network(pad_block = N)]``` on a struct, padding bytes are added after its last field so its length is a
+//! multiple of ```N``` bytes, e.g. before encrypting it with a block cipher or for fixed-size cells. They are skipped
+//! when deserializing, an ```UnexpectedEof``` error being returned if they're missing:
+//!
+//! ```
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! #[network(pad_block = 8)]
+//! struct Cell {
+//!     id: u16,
+//!     payload: [u8; 3],
+//! }
+//!
+//! let mut buffer = Vec::new();
+//! assert_eq!(Cell { id: 1, payload: [1, 2, 3] }.serialize_to(&mut buffer).unwrap(), 8);
+//! assert_eq!(buffer, &[0, 1, 1, 2, 3, 0, 0, 0]);
+//! ```
+//!
+//! The padding bytes are 0 by default. Some specifications mandate another value, given by ```pad_fill```, next to
+//! ```align_from_parent``` on a field or ```pad_block``` on a struct. With ```pad_verify```, the padding bytes read
+//! are also checked, and an ```InvalidData``` error is returned if one is different:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! #[network(pad_block = 4, pad_fill = b' ', pad_verify)]
+//! struct Record {
+//!     code: [u8; 3],
+//!     #[network(align_from_parent = 2, pad_fill = 0xFF)]
+//!     value: u16,
+//! }
+//!
+//! let record = Record { code: *b"ABC", value: 1 };
+//! let mut buffer = Vec::new();
+//! assert_eq!(record.serialize_to(&mut buffer).unwrap(), 8);
+//! assert_eq!(buffer, b"ABC\xFF\x00\x01  ");
+//!
+//! let mut cursor = Cursor::new(&b"ABC\x00\x00\x01\x00\x00"[..]);
+//! assert!(Record::deserialize_new(&mut cursor).is_err());
+//! ```
+use std::cell::Cell;
+use std::io::{Cursor, Error, ErrorKind, Write};
+
+thread_local! {
+    // offset of the outermost message in the buffer. It's negative when a value is serialized into a scratch
+    // buffer by write_to(), the message having started before the buffer
+    static ORIGIN: Cell<isize> = const { Cell::new(0) };
+
+    // number of bytes written since the outermost write_to() started, if any
+    static WRITTEN: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+// restores the enclosing origin, even if the closure panics
+struct Restore(isize);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        ORIGIN.with(|o| o.set(self.0));
+    }
+}
+
+// restores the enclosing count of bytes written, even if the closure panics
+struct RestoreWritten(Option<usize>);
+
+impl Drop for RestoreWritten {
+    fn drop(&mut self) {
+        WRITTEN.with(|w| w.set(self.0));
+    }
+}
+
+/// Runs ```f``` with the outermost message starting at ```origin``` in the buffers.
+pub fn with_origin<R>(origin: usize, f: impl FnOnce() -> R) -> R {
+    let _restore = Restore(ORIGIN.with(|o| o.replace(origin as isize)));
+    f()
+}
+
+/// Returns the offset in the buffers where the outermost message starts.
+pub fn origin() -> usize {
+    ORIGIN.with(|o| o.get()).max(0) as usize
+}
+
+/// Returns the number of padding bytes needed at ```offset``` in the buffer, for the next field to be aligned on
+/// ```align``` bytes from the start of the message.
+pub fn padding(offset: usize, align: usize) -> usize {
+    let offset = (offset as isize).saturating_sub(ORIGIN.with(|o| o.get()));
+    block_padding(offset.max(0) as usize, align)
+}
+
+// counts the bytes written to the inner writer
+struct Tracked<'a>(&'a mut dyn Write);
+
+impl Write for Tracked<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.0.write(buf)?;
+        WRITTEN.with(|w| w.set(w.get().map(|count| count + written)));
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Runs ```f``` with the bytes it writes counted from the start of the message, for the padding of the
+/// ```align_from_parent``` fields written by ```ToNetworkOrder::write_to()```. The outermost call starts the count,
+/// and the nested ones keep it. It's called by the derived code and the implementations writing several values.
+pub fn track_writes(
+    writer: &mut dyn Write,
+    f: impl FnOnce(&mut dyn Write) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    if WRITTEN.with(|w| w.get()).is_some() {
+        return f(writer);
+    }
+    new_message(writer, f)
+}
+
+// runs f with the count of bytes written starting again, as the bytes are those of a new message (e.g. when
+// computing the size of a value while writing another one)
+pub(crate) fn new_message(
+    writer: &mut dyn Write,
+    f: impl FnOnce(&mut dyn Write) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    let _restore = RestoreWritten(WRITTEN.with(|w| w.replace(Some(0))));
+    f(&mut Tracked(writer))
+}
+
+// runs f serializing into an empty buffer the bytes to be written next: the padding is computed as if they were
+// written, and the values written by f start a new message
+pub(crate) fn with_scratch_origin<R>(f: impl FnOnce() -> R) -> R {
+    let written = WRITTEN.with(|w| w.replace(None));
+    let _restore_written = RestoreWritten(written);
+    let shift = written.unwrap_or(0) as isize;
+    let _restore = Restore(ORIGIN.with(|o| o.replace(-shift)));
+    f()
+}
+
+/// Returns the number of padding bytes after a message of ```length``` bytes, for its length to be a multiple of
+/// ```block``` bytes.
+pub fn block_padding(length: usize, block: usize) -> usize {
+    (block - length % block) % block
+}
+
+/// Appends the padding bytes aligning the next field, all equal to ```fill```, and returns their number.
+pub fn write_padding(buffer: &mut Vec<u8>, align: usize, fill: u8) -> usize {
+    let count = padding(buffer.len(), align);
+    buffer.resize(buffer.len() + count, fill);
+    count
+}
+
+/// Writes the padding bytes aligning the next field, all equal to ```fill```, and returns their number. The offset
+/// is the number of bytes written since the start of the message (see ```track_writes()```).
+pub fn write_padding_to(writer: &mut dyn Write, align: usize, fill: u8) -> std::io::Result<usize> {
+    let offset = WRITTEN.with(|w| w.get()).unwrap_or(0);
+    write_fill(writer, block_padding(offset, align), fill)
+}
+
+/// Skips the padding bytes aligning the next field, or returns an ```UnexpectedEof``` error if the buffer is too
+/// short. If ```expected``` is given, an ```InvalidData``` error is returned if a padding byte is different.
+pub fn skip_padding(
+    buffer: &mut Cursor<&[u8]>,
+    align: usize,
+    expected: Option<u8>,
+) -> std::io::Result<()> {
+    let count = padding(buffer.position() as usize, align);
+    skip(buffer, count, expected)
+}
+
+/// Appends the padding bytes after a message of ```length``` bytes, all equal to ```fill```, and returns their
+/// number.
+pub fn write_block_padding(buffer: &mut Vec<u8>, length: usize, block: usize, fill: u8) -> usize {
+    let count = block_padding(length, block);
+    buffer.resize(buffer.len() + count, fill);
+    count
+}
+
+/// Writes the padding bytes after a message of ```length``` bytes, all equal to ```fill```, and returns their
+/// number.
+pub fn write_block_padding_to(
+    writer: &mut dyn Write,
+    length: usize,
+    block: usize,
+    fill: u8,
+) -> std::io::Result<usize> {
+    write_fill(writer, block_padding(length, block), fill)
+}
+
+// writes count bytes equal to fill
+fn write_fill(writer: &mut dyn Write, count: usize, fill: u8) -> std::io::Result<usize> {
+    let chunk = [fill; 16];
+    let mut left = count;
+    while left > 0 {
+        let size = left.min(chunk.len());
+        writer.write_all(&chunk[..size])?;
+        left -= size;
+    }
+    Ok(count)
+}
+
+/// Skips the padding bytes after a message of ```length``` bytes, or returns an ```UnexpectedEof``` error if the
+/// buffer is too short. If ```expected``` is given, an ```InvalidData``` error is returned if a padding byte is
+/// different.
+pub fn skip_block_padding(
+    buffer: &mut Cursor<&[u8]>,
+    length: usize,
+    block: usize,
+    expected: Option<u8>,
+) -> std::io::Result<()> {
+    skip(buffer, block_padding(length, block), expected)
+}
+
+// moves the buffer after the padding bytes, checking them if needed
+fn skip(buffer: &mut Cursor<&[u8]>, count: usize, expected: Option<u8>) -> std::io::Result<()> {
+    let position = buffer.position() as usize;
+
+    let Some(padding) = buffer.get_ref().get(position..position + count) else {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("{} bytes of padding expected", count),
+        ));
+    };
+
+    if let Some(expected) = expected {
+        if let Some(byte) = padding.iter().find(|b| **b != expected) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "unexpected padding byte 0x{:02X} (0x{:02X} expected)",
+                    byte, expected
+                ),
+            ));
+        }
+    }
+
+    buffer.set_position((position + count) as u64);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding_origin() {
+        assert_eq!(padding(0, 8), 0);
+        assert_eq!(padding(3, 8), 5);
+        assert_eq!(padding(8, 8), 0);
+        assert_eq!(block_padding(17, 16), 15);
+
+        // the message starts after a 4-byte header
+        let mut buffer = vec![0xFF; 5];
+        assert_eq!(with_origin(4, || write_padding(&mut buffer, 4, 0x20)), 3);
+        assert_eq!(buffer, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x20, 0x20, 0x20]);
+        assert_eq!(origin(), 0);
+
+        let bytes = [1u8, 0, 0];
+        let mut cursor = Cursor::new(&bytes[..]);
+        cursor.set_position(1);
+        assert!(skip_padding(&mut cursor, 2, Some(0)).is_ok());
+        assert_eq!(cursor.position(), 2);
+        cursor.set_position(1);
+        assert_eq!(
+            skip_padding(&mut cursor, 4, None).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        // the padding bytes are only checked if asked
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert!(skip_block_padding(&mut cursor, 1, 2, None).is_ok());
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert_eq!(
+            skip_block_padding(&mut cursor, 1, 2, Some(0x20))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
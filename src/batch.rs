@@ -0,0 +1,85 @@
+//! Parallel serialization of a batch of messages using ```rayon``` (```rayon``` feature).
+//!
+//! Each message is serialized to its own buffer on the ```rayon``` thread pool. The buffers are either
+//! returned as is, or concatenated in the order of the messages.
+//!
+//! ```
+//! use type2network::batch::{par_serialize_all, par_serialize_concat};
+//!
+//! let records: Vec<u32> = (0..1000).collect();
+//!
+//! let buffers = par_serialize_all(&records).unwrap();
+//! assert_eq!(buffers.len(), 1000);
+//! assert_eq!(buffers[1], &[0, 0, 0, 1]);
+//!
+//! let bytes = par_serialize_concat(&records).unwrap();
+//! assert_eq!(bytes.len(), 4000);
+//! assert_eq!(&bytes[4..8], &[0, 0, 0, 1]);
+//! ```
+use rayon::prelude::*;
+
+use crate::ToNetworkOrder;
+
+/// Serializes all messages in parallel, and returns one buffer per message, in the same order.
+/// If any serialization fails, one of the errors is returned.
+pub fn par_serialize_all<T>(messages: &[T]) -> std::io::Result<Vec<Vec<u8>>>
+where
+    T: ToNetworkOrder + Sync,
+{
+    messages
+        .par_iter()
+        .map(|msg| {
+            let mut buffer = Vec::new();
+            msg.serialize_to(&mut buffer)?;
+            Ok(buffer)
+        })
+        .collect()
+}
+
+/// Serializes all messages in parallel, and concatenates the buffers in the order of the messages.
+pub fn par_serialize_concat<T>(messages: &[T]) -> std::io::Result<Vec<u8>>
+where
+    T: ToNetworkOrder + Sync,
+{
+    let buffers = par_serialize_all(messages)?;
+
+    let mut bytes = Vec::with_capacity(buffers.iter().map(Vec::len).sum());
+    for buffer in buffers {
+        bytes.extend_from_slice(&buffer);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error, ErrorKind};
+
+    struct Failing(bool);
+
+    impl ToNetworkOrder for Failing {
+        fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+            if self.0 {
+                return Err(Error::new(ErrorKind::InvalidData, "failing"));
+            }
+            buffer.push(0xAA);
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let strings = vec!["ab", "", "cde"];
+        assert_eq!(
+            par_serialize_all(&strings).unwrap(),
+            vec![b"ab".to_vec(), vec![], b"cde".to_vec()]
+        );
+        assert_eq!(par_serialize_concat(&strings).unwrap(), b"abcde");
+        assert!(par_serialize_concat::<u8>(&[]).unwrap().is_empty());
+
+        let msgs = vec![Failing(false), Failing(true), Failing(false)];
+        assert!(par_serialize_all(&msgs).is_err());
+        assert!(par_serialize_concat(&msgs[..1]).is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+//! Element budget bounding the loops run while deserializing, so parsers can be checked with proof harnesses
+//! (e.g. [Kani](https://model-checking.github.io/kani/)) or fed untrusted bytes without unbounded work.
+//!
+//! The number of elements of collections is usually read from the wire (```vec::CountPrefixed```, ```vec::Rle```,
+//! ```#[network(count = "expr")]``` fields...) or given by the buffer length (```vec::UntilEof```, maps). Within
+//! ```with_budget()```, each element deserialized by those implementations is taken from the budget before the
+//! loop reading them is run, and an ```InvalidData``` error is returned once the budget is exhausted:
+//!
+//! ```
+//! use type2network::budget::with_budget;
+//! use type2network::vec::CountPrefixed;
+//! use type2network::FromNetworkOrder;
+//!
+//! // 200 elements announced
+//! let bytes = [200u8, 1, 2, 3];
+//!
+//! let mut buffer = std::io::Cursor::new(&bytes[..]);
+//! let mut v = CountPrefixed::<u8, u8>::default();
+//! let e = with_budget(16, || v.deserialize_from(&mut buffer)).unwrap_err();
+//! assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+//! ```
+//!
+//! With a budget set, every loop of the deserialization is bounded by the budget, so a proof harness only needs to
+//! unwind the loops once more than the budget to verify that a parser doesn't panic for any input:
+//!
+//! ```rust,ignore
+//! #[cfg(kani)]
+//! #[kani::proof]
+//! #[kani::unwind(9)]
+//! fn message_never_panics() {
+//!     let bytes: [u8; 16] = kani::any();
+//!     let _ = with_budget(8, || Message::try_from(&bytes[..]));
+//! }
+//! ```
+//!
+//! When compiled by Kani (```cfg(kani)```), the budget is kept in a static instead of a thread local, which
+//! the verifier handles better.
+use std::io::{Error, ErrorKind};
+
+// the budget left, None when no budget is set
+#[cfg(not(kani))]
+thread_local! {
+    static REMAINING: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+#[cfg(not(kani))]
+fn get() -> Option<usize> {
+    REMAINING.with(|r| r.get())
+}
+
+#[cfg(not(kani))]
+fn set(remaining: Option<usize>) {
+    REMAINING.with(|r| r.set(remaining))
+}
+
+// Kani verifies a single thread: usize::MAX stands for no budget
+#[cfg(kani)]
+static REMAINING: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(usize::MAX);
+
+#[cfg(kani)]
+fn get() -> Option<usize> {
+    match REMAINING.load(std::sync::atomic::Ordering::Relaxed) {
+        usize::MAX => None,
+        remaining => Some(remaining),
+    }
+}
+
+#[cfg(kani)]
+fn set(remaining: Option<usize>) {
+    REMAINING.store(
+        remaining.unwrap_or(usize::MAX),
+        std::sync::atomic::Ordering::Relaxed,
+    )
+}
+
+// restores the enclosing budget, minus the elements taken, even if the closure panics
+struct Restore {
+    previous: Option<usize>,
+    budget: usize,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let taken = self.budget - get().unwrap_or(self.budget);
+        set(self.previous.map(|p| p - taken));
+    }
+}
+
+/// Runs ```f``` with at most ```max_elements``` elements deserialized by the collections. Within an enclosing
+/// budget, the smallest one applies and the elements taken are also taken from the enclosing budget.
+pub fn with_budget<R>(max_elements: usize, f: impl FnOnce() -> R) -> R {
+    let previous = get();
+    let budget = previous.map_or(max_elements, |p| p.min(max_elements));
+    set(Some(budget));
+
+    let _restore = Restore { previous, budget };
+    f()
+}
+
+/// Returns the number of elements left in the current budget, or ```None``` if no budget is set.
+pub fn remaining() -> Option<usize> {
+    get()
+}
+
+/// Takes ```count``` elements from the current budget before reading them, or returns an ```InvalidData``` error if
+/// the budget is exhausted. It does nothing if no budget is set. It's called by the collection implementations
+/// and the derived code, and can be called by hand-written implementations.
+pub fn take(count: usize) -> std::io::Result<()> {
+    let Some(remaining) = get() else {
+        return Ok(());
+    };
+
+    if count > remaining {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "element budget exhausted ({} elements requested, {} left)",
+                count, remaining
+            ),
+        ));
+    }
+
+    set(Some(remaining - count));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        assert_eq!(remaining(), None);
+        assert!(take(1_000_000).is_ok());
+
+        with_budget(10, || {
+            assert!(take(4).is_ok());
+            assert_eq!(remaining(), Some(6));
+
+            // the smallest budget applies, and is taken from the enclosing one
+            with_budget(100, || {
+                assert_eq!(remaining(), Some(6));
+                assert!(take(2).is_ok());
+            });
+            assert_eq!(remaining(), Some(4));
+
+            let e = take(5).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+            assert_eq!(remaining(), Some(4));
+        });
+        assert_eq!(remaining(), None);
+    }
+}
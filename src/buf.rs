@@ -0,0 +1,390 @@
+//! Deserialization from non-contiguous buffers implementing ```bytes::Buf```, and serialization to ```bytes::BufMut```.
+//!
+//! Data split across several chunks (e.g. a chain of ```Bytes``` produced by a ```tokio``` codec) is deserialized without
+//! first copying it into a contiguous slice. The ```FromBuf``` trait is implemented for the usual types, and derived for a
+//! struct using the ```#[network(buf)]``` attribute along with ```#[derive(FromNetwork)]```:
+//!
+//! ```
+//! use bytes::{Buf, Bytes};
+//! use type2network::buf::from_buf;
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, PartialEq, FromNetwork)]
+//! #[network(buf)]
+//! struct Header {
+//!     kind: u8,
+//!     id: u32,
+//!     flags: [u8; 2],
+//! }
+//!
+//! // the id is split across 2 chunks
+//! let mut chain = Bytes::from_static(&[1, 0x12, 0x34]).chain(Bytes::from_static(&[0x56, 0x78, 0xFF, 0xFE]));
+//! let header: Header = from_buf(&mut chain).unwrap();
+//! assert_eq!(header.id, 0x12345678);
+//! assert_eq!(header.flags, [0xFF, 0xFE]);
+//! assert!(!chain.has_remaining());
+//! ```
+//!
+//! Symmetrically, the ```ToBufMut``` trait writes a value into any ```bytes::BufMut``` (e.g. a ```BytesMut``` or a chain of
+//! fixed buffers), and is derived with ```#[network(buf)]``` along with ```#[derive(ToNetwork)]```:
+//!
+//! ```
+//! use bytes::BufMut;
+//! use type2network::buf::ToBufMut;
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! #[network(buf)]
+//! struct Header {
+//!     kind: u8,
+//!     id: u32,
+//! }
+//!
+//! // the header is written across 2 buffers
+//! let (mut first, mut second) = ([0u8; 3], [0u8; 2]);
+//! let mut chain = (&mut first[..]).chain_mut(&mut second[..]);
+//! let length = Header { kind: 1, id: 0x12345678 }.serialize_to_buf(&mut chain).unwrap();
+//! assert_eq!(length, 5);
+//!
+//! // no room left
+//! assert!(Header { kind: 1, id: 0 }.serialize_to_buf(&mut chain).is_err());
+//! assert_eq!((first, second), ([1, 0x12, 0x34], [0x56, 0x78]));
+//! ```
+use std::io::{Error, ErrorKind};
+use std::marker::PhantomData;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use bytes::Bytes;
+
+// used by the code generated for #[network(buf)]
+pub use bytes::{Buf, BufMut};
+
+use crate::endian::{BeU16, BeU32, BeU64};
+
+/// Deserialization from a ```bytes::Buf```, mirroring [`crate::FromNetworkOrder`].
+pub trait FromBuf {
+    /// Deserializes the value in place, from the current position of the buffer.
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()>;
+
+    /// Deserializes a new value, starting from the default value.
+    fn deserialize_new_buf<B: Buf>(buf: &mut B) -> std::io::Result<Self>
+    where
+        Self: Sized + Default,
+    {
+        let mut value = Self::default();
+        value.deserialize_from_buf(buf)?;
+        Ok(value)
+    }
+}
+
+/// Deserializes a ```T``` from the buffer.
+pub fn from_buf<T: FromBuf + Default>(buf: &mut impl Buf) -> std::io::Result<T> {
+    T::deserialize_new_buf(buf)
+}
+
+// error returned when the buffer has not enough bytes left
+fn check_remaining<B: Buf>(buf: &B, needed: usize) -> std::io::Result<()> {
+    if buf.remaining() < needed {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "{} bytes needed, only {} left in buffer",
+                needed,
+                buf.remaining()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_from_buf {
+    ($($t:ty => $get:ident),*) => {
+        $(
+            impl FromBuf for $t {
+                fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+                    check_remaining(buf, std::mem::size_of::<$t>())?;
+                    *self = buf.$get();
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_buf!(
+    u8 => get_u8, u16 => get_u16, u32 => get_u32, u64 => get_u64,
+    i8 => get_i8, i16 => get_i16, i32 => get_i32, i64 => get_i64,
+    f32 => get_f32, f64 => get_f64
+);
+#[cfg(feature = "int128")]
+impl_from_buf!(u128 => get_u128, i128 => get_i128);
+
+// these types are built from an integer
+macro_rules! impl_from_buf_from {
+    ($($t:ty => $int:ty),*) => {
+        $(
+            impl FromBuf for $t {
+                fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+                    *self = <$t>::from(<$int>::deserialize_new_buf(buf)?);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_buf_from!(BeU16 => u16, BeU32 => u32, BeU64 => u64, Ipv4Addr => u32, Ipv6Addr => [u8; 16]);
+
+impl FromBuf for char {
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        let u = u32::deserialize_new_buf(buf)?;
+        *self = char::from_u32(u).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{:#x} is not a valid char", u),
+            )
+        })?;
+        Ok(())
+    }
+}
+
+impl<T: FromBuf, const N: usize> FromBuf for [T; N] {
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        for item in self {
+            item.deserialize_from_buf(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: FromBuf> FromBuf for Option<T> {
+    /// As for ```FromNetworkOrder```, nothing is read if the value is ```None```.
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        match self {
+            None => Ok(()),
+            Some(value) => value.deserialize_from_buf(buf),
+        }
+    }
+}
+
+impl<T: FromBuf + Default> FromBuf for Vec<T> {
+    /// As for ```FromNetworkOrder```, ```capacity()``` elements are read with the ```vec-capacity``` feature. Otherwise,
+    /// the elements already in the vector are deserialized in place.
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        #[cfg(feature = "vec-capacity")]
+        {
+            let length = self.capacity();
+            crate::budget::take(length)?;
+
+            for _ in 0..length {
+                self.push(T::deserialize_new_buf(buf)?);
+            }
+        }
+
+        #[cfg(not(feature = "vec-capacity"))]
+        for item in self {
+            item.deserialize_from_buf(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: FromBuf> FromBuf for Box<T> {
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        self.as_mut().deserialize_from_buf(buf)
+    }
+}
+
+impl<T> FromBuf for PhantomData<T> {
+    fn deserialize_from_buf<B: Buf>(&mut self, _buf: &mut B) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl FromBuf for Bytes {
+    /// All the bytes left are read. If they are in a single ```Bytes``` chunk, they are not copied.
+    fn deserialize_from_buf<B: Buf>(&mut self, buf: &mut B) -> std::io::Result<()> {
+        *self = buf.copy_to_bytes(buf.remaining());
+        Ok(())
+    }
+}
+
+/// Serialization to a ```bytes::BufMut```, mirroring [`crate::ToNetworkOrder`].
+pub trait ToBufMut {
+    /// Serializes the value at the end of the buffer, and returns the number of bytes written.
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize>;
+}
+
+// error returned when the buffer can't hold the value. Buffers growing on demand (e.g. BytesMut) have
+// virtually no limit
+fn check_remaining_mut<B: BufMut>(buf: &B, needed: usize) -> std::io::Result<()> {
+    if buf.remaining_mut() < needed {
+        return Err(Error::new(
+            ErrorKind::WriteZero,
+            format!(
+                "{} bytes needed, only {} left in buffer",
+                needed,
+                buf.remaining_mut()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_to_buf_mut {
+    ($($t:ty => $put:ident),*) => {
+        $(
+            impl ToBufMut for $t {
+                fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+                    check_remaining_mut(buf, std::mem::size_of::<$t>())?;
+                    buf.$put(*self);
+                    Ok(std::mem::size_of::<$t>())
+                }
+            }
+        )*
+    };
+}
+
+impl_to_buf_mut!(
+    u8 => put_u8, u16 => put_u16, u32 => put_u32, u64 => put_u64,
+    i8 => put_i8, i16 => put_i16, i32 => put_i32, i64 => put_i64,
+    f32 => put_f32, f64 => put_f64
+);
+#[cfg(feature = "int128")]
+impl_to_buf_mut!(u128 => put_u128, i128 => put_i128);
+
+// these types are converted to an integer
+macro_rules! impl_to_buf_mut_into {
+    ($($t:ty => $int:ty),*) => {
+        $(
+            impl ToBufMut for $t {
+                fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+                    <$int>::from(*self).serialize_to_buf(buf)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_buf_mut_into!(BeU16 => u16, BeU32 => u32, BeU64 => u64, Ipv4Addr => u32, char => u32);
+
+impl ToBufMut for Ipv6Addr {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        self.octets().serialize_to_buf(buf)
+    }
+}
+
+impl<T: ToBufMut, const N: usize> ToBufMut for [T; N] {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        self.as_slice().serialize_to_buf(buf)
+    }
+}
+
+impl<T: ToBufMut> ToBufMut for [T] {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        let mut length = 0usize;
+        for item in self {
+            length += item.serialize_to_buf(buf)?;
+        }
+        Ok(length)
+    }
+}
+
+impl<T: ToBufMut> ToBufMut for Vec<T> {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        self.as_slice().serialize_to_buf(buf)
+    }
+}
+
+impl<T: ToBufMut> ToBufMut for Option<T> {
+    /// As for ```ToNetworkOrder```, nothing is written if the value is ```None```.
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        match self {
+            None => Ok(0),
+            Some(value) => value.serialize_to_buf(buf),
+        }
+    }
+}
+
+impl<T: ToBufMut + ?Sized> ToBufMut for Box<T> {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        self.as_ref().serialize_to_buf(buf)
+    }
+}
+
+impl<T: ToBufMut + ?Sized> ToBufMut for &T {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        (*self).serialize_to_buf(buf)
+    }
+}
+
+impl<T> ToBufMut for PhantomData<T> {
+    fn serialize_to_buf<B: BufMut>(&self, _buf: &mut B) -> std::io::Result<usize> {
+        Ok(0)
+    }
+}
+
+impl ToBufMut for Bytes {
+    fn serialize_to_buf<B: BufMut>(&self, buf: &mut B) -> std::io::Result<usize> {
+        check_remaining_mut(buf, self.len())?;
+        buf.put_slice(self);
+        Ok(self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_buf_chain() {
+        let mut chain = Bytes::from_static(&[0, 0, 0]).chain(Bytes::from_static(&[0x41, 1, 2, 3]));
+        assert_eq!(from_buf::<char>(&mut chain).unwrap(), 'A');
+        assert_eq!(from_buf::<[u8; 2]>(&mut chain).unwrap(), [1, 2]);
+        assert_eq!(from_buf::<Option<u8>>(&mut chain).unwrap(), None);
+
+        // not enough bytes: nothing is read
+        assert!(from_buf::<u16>(&mut chain).is_err());
+        assert_eq!(chain.remaining(), 1);
+
+        let mut v = Some(0u8);
+        v.deserialize_from_buf(&mut chain).unwrap();
+        assert_eq!(v, Some(3));
+
+        let mut chain = Bytes::from_static(&[0x11, 0, 0]).chain(&[0, 0xFF][..]);
+        assert!(from_buf::<char>(&mut chain).is_err());
+    }
+
+    #[test]
+    fn to_buf_mut_chain() {
+        let mut buf = bytes::BytesMut::new();
+        assert_eq!('A'.serialize_to_buf(&mut buf).unwrap(), 4);
+        assert_eq!(
+            vec![BeU16::new(1), BeU16::new(2)]
+                .serialize_to_buf(&mut buf)
+                .unwrap(),
+            4
+        );
+        assert_eq!(None::<u8>.serialize_to_buf(&mut buf).unwrap(), 0);
+        assert_eq!(&buf[..], &[0, 0, 0, 0x41, 0, 1, 0, 2]);
+
+        // not enough room: nothing is written
+        let mut bytes = [0u8; 3];
+        let mut slice = &mut bytes[..];
+        let e = 0x12345678u32.serialize_to_buf(&mut slice).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::WriteZero);
+        assert_eq!(slice.remaining_mut(), 3);
+
+        let (mut first, mut second) = ([0u8; 1], [0u8; 2]);
+        let mut chain = (&mut first[..]).chain_mut(&mut second[..]);
+        Bytes::from_static(&[1, 2, 3])
+            .serialize_to_buf(&mut chain)
+            .unwrap();
+        assert_eq!((first, second), ([1], [2, 3]));
+    }
+}
@@ -0,0 +1,220 @@
+//! Byte order detection for formats existing in both big-endian and little-endian flavors.
+//!
+//! Some formats (e.g. pcap files) start with a magic number written in the byte order of the writer. The
+//! ```Magic16``` and ```Magic32``` types read such a magic and record the byte order detected. When the field is
+//! marked with ```#[network(byte_order)]```, the integer and float fields (or arrays of those) following it on the
+//! wire are read and written in this byte order, and the order is given to ```with_code``` blocks as
+//! ```ctx.byte_order```:
+//!
+//! ```
+//! use type2network::byte_order::{ByteOrder, Magic32};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct PcapHeader {
+//!     #[network(byte_order)]
+//!     magic: Magic32<0xA1B2C3D4>,
+//!     version_major: u16,
+//!     version_minor: u16,
+//! }
+//!
+//! // written by a little-endian machine
+//! let bytes = [0xD4, 0xC3, 0xB2, 0xA1, 2, 0, 4, 0];
+//! let header = PcapHeader::deserialize_new(&mut std::io::Cursor::new(&bytes[..])).unwrap();
+//! assert_eq!(header.magic.order(), ByteOrder::Little);
+//! assert_eq!((header.version_major, header.version_minor), (2, 4));
+//!
+//! // and written back the same way
+//! let mut buffer = Vec::new();
+//! header.serialize_to(&mut buffer).unwrap();
+//! assert_eq!(buffer, bytes);
+//! ```
+use std::io::{Cursor, Error, ErrorKind, Read};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Byte order of the data.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ByteOrder {
+    /// Big-endian, the network order.
+    #[default]
+    Big,
+
+    /// Little-endian.
+    Little,
+}
+
+impl ByteOrder {
+    /// Converts a value read in big-endian to this byte order, or a value in this byte order to big-endian.
+    pub fn adjust<T: SwapBytes + ?Sized>(self, value: &mut T) {
+        if self == ByteOrder::Little {
+            value.swap_bytes();
+        }
+    }
+}
+
+/// A value whose byte order can be reversed.
+pub trait SwapBytes {
+    /// Reverses the byte order of the value.
+    fn swap_bytes(&mut self);
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_swap_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl SwapBytes for $t {
+                fn swap_bytes(&mut self) {
+                    *self = <$t>::swap_bytes(*self);
+                }
+            }
+        )*
+    };
+}
+
+impl_swap_bytes!(u8, u16, u32, u64, i8, i16, i32, i64);
+#[cfg(feature = "int128")]
+impl_swap_bytes!(u128, i128);
+
+impl SwapBytes for f32 {
+    fn swap_bytes(&mut self) {
+        *self = f32::from_bits(self.to_bits().swap_bytes());
+    }
+}
+
+impl SwapBytes for f64 {
+    fn swap_bytes(&mut self) {
+        *self = f64::from_bits(self.to_bits().swap_bytes());
+    }
+}
+
+impl<T: SwapBytes> SwapBytes for [T] {
+    fn swap_bytes(&mut self) {
+        self.iter_mut().for_each(SwapBytes::swap_bytes);
+    }
+}
+
+impl<T: SwapBytes, const N: usize> SwapBytes for [T; N] {
+    fn swap_bytes(&mut self) {
+        self.as_mut_slice().swap_bytes();
+    }
+}
+
+/// A field giving the byte order of the next ones, used with ```#[network(byte_order)]```.
+pub trait DetectByteOrder {
+    /// Returns the byte order detected.
+    fn byte_order(&self) -> ByteOrder;
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_magic {
+    ($name:ident, $t:ty, $size:literal) => {
+        #[doc = concat!("A `", stringify!($t), "` magic number, read in either byte order.")]
+        ///
+        /// Deserialization fails if the bytes are not the magic number in any byte order.
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name<const M: $t> {
+            order: ByteOrder,
+        }
+
+        impl<const M: $t> $name<M> {
+            /// The magic number.
+            pub const MAGIC: $t = M;
+
+            /// Creates a magic written in the given byte order.
+            pub const fn new(order: ByteOrder) -> Self {
+                Self { order }
+            }
+
+            /// Returns the byte order of the magic.
+            pub const fn order(&self) -> ByteOrder {
+                self.order
+            }
+        }
+
+        impl<const M: $t> DetectByteOrder for $name<M> {
+            fn byte_order(&self) -> ByteOrder {
+                self.order
+            }
+        }
+
+        impl<const M: $t> ToNetworkOrder for $name<M> {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                match self.order {
+                    ByteOrder::Big => buffer.extend_from_slice(&M.to_be_bytes()),
+                    ByteOrder::Little => buffer.extend_from_slice(&M.to_le_bytes()),
+                }
+                Ok($size)
+            }
+
+            fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                match self.order {
+                    ByteOrder::Big => writer.write_all(&M.to_be_bytes())?,
+                    ByteOrder::Little => writer.write_all(&M.to_le_bytes())?,
+                }
+                Ok($size)
+            }
+        }
+
+        impl<'a, const M: $t> FromNetworkOrder<'a> for $name<M> {
+            fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+                let mut bytes = [0u8; $size];
+                buffer.read_exact(&mut bytes)?;
+
+                // a palindromic magic is considered as big-endian
+                self.order = if bytes == M.to_be_bytes() {
+                    ByteOrder::Big
+                } else if bytes == M.to_le_bytes() {
+                    ByteOrder::Little
+                } else {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "magic {:#x} expected, found {:#x}",
+                            M,
+                            <$t>::from_be_bytes(bytes)
+                        ),
+                    ));
+                };
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_magic!(Magic16, u16, 2);
+impl_magic!(Magic32, u32, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn magic() {
+        let m = Magic16::<0xFEFF>::new(ByteOrder::Little);
+        to_network_test(m, 2, &[0xFF, 0xFE]);
+        from_network_test(None, m, &vec![0xFF, 0xFE]);
+        from_network_test(
+            None,
+            Magic32::<0x0A0B0C0D>::default(),
+            &vec![10, 11, 12, 13],
+        );
+
+        let mut buffer = Cursor::new(&[0xFE, 0xFE][..]);
+        let e = Magic16::<0xFEFF>::deserialize_new(&mut buffer).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+        let mut v = [0x1234u16, 0x5678];
+        ByteOrder::Little.adjust(&mut v);
+        assert_eq!(v, [0x3412, 0x7856]);
+        ByteOrder::Big.adjust(&mut v);
+        assert_eq!(v, [0x3412, 0x7856]);
+
+        let mut f = 1.5f32;
+        ByteOrder::Little.adjust(&mut f);
+        assert_eq!(f.to_bits(), 1.5f32.to_bits().swap_bytes());
+    }
+}
@@ -0,0 +1,89 @@
+//! Canonical mode, rejecting the encodings a value can't be serialized to, when the bytes feed signatures or
+//! consensus algorithms and two encodings of the same value must not be accepted.
+//!
+//! Serializing always gives the canonical encoding, but some types accept other encodings when deserializing.
+//! Within ```with_canonical()```, those are rejected with an ```InvalidData``` error:
+//!
+//! * ```len::Varint``` and ```len::Zigzag``` (and so the ```Varint``` lengths) with trailing zero groups, e.g.
+//!   ```0x80 0x00``` for 0
+//! * ```BTreeMap``` pairs not sent in the increasing order of their keys
+//! * ```text::HexString``` with uppercase digits
+//! * ```text::Base64Bytes``` whose last digit has non-zero bits beyond the last byte, e.g. ```QR==``` for ```A```
+//! * ```vec::Rle``` with adjacent runs of the same element
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::canonical::with_canonical;
+//! use type2network::len::Varint;
+//! use type2network::FromNetworkOrder;
+//!
+//! // an overlong 1
+//! let bytes = [0x81, 0x00];
+//!
+//! assert_eq!(Varint::deserialize_new(&mut Cursor::new(&bytes[..])).unwrap(), Varint(1));
+//! let e = with_canonical(|| Varint::deserialize_new(&mut Cursor::new(&bytes[..]))).unwrap_err();
+//! assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+//! ```
+//!
+//! Some types are always strict: ```der::DerLength```, ```ssh::Mpint```, ```char``` and the duplicate keys of maps.
+//! The order of the ```HashMap``` pairs isn't checked, a ```BTreeMap``` should be used instead.
+use std::cell::Cell;
+use std::io::{Error, ErrorKind};
+
+thread_local! {
+    static CANONICAL: Cell<bool> = const { Cell::new(false) };
+}
+
+// restores the enclosing mode, even if the closure panics
+struct Restore(bool);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CANONICAL.with(|c| c.set(self.0))
+    }
+}
+
+/// Runs ```f``` with the non-canonical encodings rejected when deserializing.
+pub fn with_canonical<R>(f: impl FnOnce() -> R) -> R {
+    let _restore = Restore(CANONICAL.with(|c| c.replace(true)));
+    f()
+}
+
+/// Returns ```true``` within ```with_canonical()```.
+pub fn is_canonical() -> bool {
+    CANONICAL.with(|c| c.get())
+}
+
+/// Returns an ```InvalidData``` error in canonical mode if ```canonical``` is ```false```, and does nothing
+/// otherwise. It's called by the implementations accepting several encodings, and can be called by hand-written
+/// implementations.
+pub fn check(canonical: bool, what: &str) -> std::io::Result<()> {
+    if !canonical && is_canonical() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("non-canonical encoding: {}", what),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical() {
+        assert!(!is_canonical());
+        assert!(check(false, "test").is_ok());
+
+        with_canonical(|| {
+            assert!(is_canonical());
+            with_canonical(|| assert!(check(true, "test").is_ok()));
+            assert!(is_canonical());
+
+            let e = check(false, "test").unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+        });
+        assert!(!is_canonical());
+    }
+}
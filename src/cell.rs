@@ -1,7 +1,6 @@
 //! All functions/trait to convert DNS structures to network order back & forth
 use std::cell::{Cell, OnceCell, RefCell};
 use std::io::Error;
-use std::io::ErrorKind;
 
 //use crate::error::Error;
 use crate::{FromNetworkOrder, ToNetworkOrder};
@@ -50,6 +49,10 @@ where
     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
         self.get().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        self.get().write_to(writer)
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for Cell<T>
@@ -96,6 +99,13 @@ where
             Some(v) => v.serialize_to(buffer),
         }
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        match self.get() {
+            None => Ok(0),
+            Some(v) => v.write_to(writer),
+        }
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for OnceCell<T>
@@ -119,7 +129,7 @@ where
 
         match self.set(v) {
             Ok(_) => Ok(()),
-            Err(_) => Err(Error::new(ErrorKind::Other, "cell is full")),
+            Err(_) => Err(Error::other("cell is full")),
         }
     }
 }
@@ -140,6 +150,10 @@ where
     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
         self.borrow().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        self.borrow().write_to(writer)
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for RefCell<T>
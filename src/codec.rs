@@ -0,0 +1,79 @@
+//! Low-level functions writing and reading primitive types in network order (big-endian).
+//!
+//! These are the functions used by the ```ToNetworkOrder``` and ```FromNetworkOrder``` implementations of integers
+//! and floats, and so by the derived code. Hand-written implementations can use them directly:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::codec::{get_u16, get_u8, put_u16, put_u8};
+//!
+//! let mut buffer: Vec<u8> = Vec::new();
+//! put_u8(&mut buffer, 1);
+//! put_u16(&mut buffer, 0x1234);
+//! assert_eq!(buffer, [0x01, 0x12, 0x34]);
+//!
+//! let mut cursor = Cursor::new(buffer.as_slice());
+//! assert_eq!(get_u8(&mut cursor).unwrap(), 1);
+//! assert_eq!(get_u16(&mut cursor).unwrap(), 0x1234);
+//! assert!(get_u8(&mut cursor).is_err());
+//! ```
+use std::io::{Cursor, Read};
+
+// helper macro for boiler plate definitions
+macro_rules! impl_codec {
+    ($t:ty, $put:ident, $get:ident) => {
+        #[doc = concat!("Appends a ```", stringify!($t), "``` to the buffer in network order.")]
+        #[inline]
+        pub fn $put(buffer: &mut Vec<u8>, value: $t) {
+            buffer.extend_from_slice(&value.to_be_bytes());
+        }
+
+        #[doc = concat!("Reads a ```", stringify!($t), "``` in network order, or returns an ```UnexpectedEof``` error.")]
+        #[inline]
+        pub fn $get(buffer: &mut Cursor<&[u8]>) -> std::io::Result<$t> {
+            let mut bytes = [0u8; std::mem::size_of::<$t>()];
+            buffer.read_exact(&mut bytes)?;
+            Ok(<$t>::from_be_bytes(bytes))
+        }
+    };
+}
+
+impl_codec!(u8, put_u8, get_u8);
+impl_codec!(u16, put_u16, get_u16);
+impl_codec!(u32, put_u32, get_u32);
+impl_codec!(u64, put_u64, get_u64);
+#[cfg(feature = "int128")]
+impl_codec!(u128, put_u128, get_u128);
+impl_codec!(i8, put_i8, get_i8);
+impl_codec!(i16, put_i16, get_i16);
+impl_codec!(i32, put_i32, get_i32);
+impl_codec!(i64, put_i64, get_i64);
+#[cfg(feature = "int128")]
+impl_codec!(i128, put_i128, get_i128);
+impl_codec!(f32, put_f32, get_f32);
+impl_codec!(f64, put_f64, get_f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec() {
+        let mut buffer: Vec<u8> = Vec::new();
+        put_i16(&mut buffer, -2);
+        put_u32(&mut buffer, 0x12345678);
+        put_f32(&mut buffer, 1.0);
+        assert_eq!(
+            buffer,
+            [0xFF, 0xFE, 0x12, 0x34, 0x56, 0x78, 0x3F, 0x80, 0x00, 0x00]
+        );
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(get_i16(&mut cursor).unwrap(), -2);
+        assert_eq!(get_u32(&mut cursor).unwrap(), 0x12345678);
+        assert_eq!(get_f32(&mut cursor).unwrap(), 1.0);
+
+        let e = get_u64(&mut Cursor::new(&[0u8; 7][..])).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,74 @@
+//! Migration from [```deku```](https://crates.io/crates/deku) and [```binrw```](https://crates.io/crates/binrw) (```compat``` feature).
+//!
+//! To port existing protocol definitions, ```#[derive(DekuRead, DekuWrite)]``` or ```#[derive(BinRead, BinWrite)]```
+//! can be replaced by ```#[derive(ToNetwork, FromNetwork)]``` first. With the ```compat``` feature, the derives
+//! accept a subset of the ```#[deku(...)]```, ```#[br(...)]```, ```#[bw(...)]``` and ```#[brw(...)]``` attributes,
+//! which can then be converted one by one:
+//!
+//! | deku | binrw | type2network |
+//! |------|-------|--------------|
+//! | ```#[deku(skip)]``` | ```#[brw(ignore)]``` | ```#[network(skip)]``` |
+//! | | ```#[br(ignore)]``` | ```#[network(from(ignore))]``` |
+//! | | ```#[bw(ignore)]``` | ```#[network(to(ignore))]``` |
+//! | ```#[deku(count = "len")]``` | ```#[br(count = len)]``` | ```#[network(header)]``` on ```len``` and ```#[network(count = "*len")]``` |
+//! | ```#[deku(update = "self.data.len()")]``` | | ```#[network(compute = "self.data.len() as _")]``` with ```#[network(builder)]``` |
+//! | ```#[deku(endian = "big")]``` | ```#[br(big)]```, ```#[bw(big)]```, ```#[brw(big)]``` | nothing: the network order is the default one |
+//! | ```#[deku(magic = b"...")]``` | ```#[brw(magic = ...)]``` | a field with ```#[network(magic = ...)]``` |
+//! | ```#[deku(endian = "little")]``` | ```#[brw(little)]``` | a ```#[network(byte_order)]``` field, for formats existing in both orders |
+//!
+//! As with deku and binrw, the count expressions refer to the fields declared before by their name: by reference for
+//! deku (so ```"*len"``` or ```"len"``` can be used) and by value for binrw (```len as usize```). Any unsigned
+//! integer can be used as a count. The other options, such as magics or bit fields, are rejected with a compile error,
+//! as they have no equivalent or a different meaning.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! #[deku(endian = "big")]
+//! struct Record {
+//!     length: u8,
+//!     #[deku(count = "length")]
+//!     data: Vec<u8>,
+//!     #[deku(skip)]
+//!     cached: Option<String>,
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! #[brw(big)]
+//! struct Table {
+//!     count: u16,
+//!     #[br(count = count as usize * 2)]
+//!     entries: Vec<u8>,
+//! }
+//!
+//! let mut cursor = Cursor::new(&[2u8, 0xCA, 0xFE][..]);
+//! let record = Record::deserialize_new(&mut cursor).unwrap();
+//! assert_eq!(record.data, &[0xCA, 0xFE]);
+//!
+//! let mut cursor = Cursor::new(&[0u8, 1, 0xCA, 0xFE][..]);
+//! let table = Table::deserialize_new(&mut cursor).unwrap();
+//! assert_eq!(table.entries, &[0xCA, 0xFE]);
+//! ```
+
+/// The unsigned integers used as the counts of ```#[deku(count)]``` and ```#[br(count)]```.
+pub trait WireCount {
+    fn wire_count(&self) -> usize;
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_wire_count {
+    ($($t:ty),*) => {
+        $(
+            impl WireCount for $t {
+                fn wire_count(&self) -> usize {
+                    *self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_wire_count!(u8, u16, u32, u64, usize);
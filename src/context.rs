@@ -0,0 +1,51 @@
+//! Context data made available to the code injected by the derive macros.
+use std::io::Cursor;
+
+use crate::byte_order::ByteOrder;
+
+/// Context given to the blocks injected by the ```#[network(from(with_code(...)))]``` attribute as the
+/// ```ctx``` variable. The ```buffer``` variable (the cursor being read) is also accessible in those blocks.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::FromNetworkOrder;
+/// use type2network_derive::FromNetwork;
+///
+/// #[derive(Debug, Default, FromNetwork)]
+/// struct Tlv {
+///     tag: u8,
+///
+///     // peek the next byte without consuming it
+///     #[network(from(with_code(self.next = buffer.get_ref()[buffer.position() as usize]; let _ = ctx.offset(buffer);)))]
+///     next: u8,
+/// }
+///
+/// let b = vec![0x01, 0x02];
+/// let mut buffer = Cursor::new(b.as_slice());
+/// let mut tlv = Tlv::default();
+/// assert!(tlv.deserialize_from(&mut buffer).is_ok());
+/// assert_eq!(tlv.next, 0x02);
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeserializationContext {
+    /// Name of the type being deserialized.
+    pub type_name: &'static str,
+
+    /// Name of the field being deserialized.
+    pub field_name: &'static str,
+
+    /// Position of the buffer when the deserialization of the type started.
+    pub start: u64,
+
+    /// Byte order given by the field marked with ```#[network(byte_order)]``` if already read, big-endian otherwise.
+    pub byte_order: ByteOrder,
+}
+
+impl DeserializationContext {
+    /// Returns the number of bytes already read for the type being deserialized.
+    pub fn offset(&self, buffer: &Cursor<&[u8]>) -> u64 {
+        buffer.position() - self.start
+    }
+}
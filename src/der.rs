@@ -0,0 +1,330 @@
+//! ASN.1 DER lengths and tag-length-value elements, for certificate-adjacent or SNMP-like structures, without a full
+//! ASN.1 implementation.
+//!
+//! * ```DerLength```: a length in the short form (a single byte, below 128) or in the long form (```0x80``` plus the
+//!   number of bytes, followed by the length on these bytes). DER requires the shortest encoding, which is checked
+//!   when deserializing. It implements ```WireLen```, so ```Opaque<DerLength>``` is the content of an element
+//! * ```DerTlv<TAG, T>```: a ```T``` value preceded by the ```TAG``` byte and by its length as a ```DerLength```. When
+//!   deserialized, the value should use exactly the bytes given by the length
+//!
+//! * ```EndOfContents```: the ```0x00 0x00``` octets ending the content of a constructed element with the indefinite
+//!   length (```0x80```) of BER and CER, as the end marker of a [`crate::vec::Terminated`]
+//!
+//! Only the tags on a single byte (tag numbers below 31) are supported.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::der::DerTlv;
+//! use type2network::opaque::Opaque;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! // an INTEGER and an OCTET STRING in a SEQUENCE
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Entry {
+//!     version: DerTlv<0x02, u8>,
+//!     community: DerTlv<0x04, Opaque<type2network::len::Fixed<6>>>,
+//! }
+//!
+//! type Message = DerTlv<0x30, Entry>;
+//!
+//! let msg = Message::from(Entry {
+//!     version: 1.into(),
+//!     community: Opaque::from(b"public".to_vec()).into(),
+//! });
+//!
+//! let mut buffer = Vec::new();
+//! assert_eq!(msg.serialize_to(&mut buffer).unwrap(), 13);
+//! assert_eq!(buffer, b"\x30\x0B\x02\x01\x01\x04\x06public");
+//!
+//! let decoded = Message::deserialize_new(&mut Cursor::new(buffer.as_slice())).unwrap();
+//! assert_eq!(decoded, msg);
+//! ```
+//!
+//! A BER or CER element with the indefinite length is read as its tag, the ```0x80``` length and its content
+//! elements up to the end-of-contents octets:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::der::{DerTlv, EndOfContents};
+//! use type2network::vec::Terminated;
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! // a SEQUENCE of INTEGERs
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Streamed {
+//!     #[network(magic = 0x30)]
+//!     tag: u8,
+//!     #[network(magic = 0x80)]
+//!     length: u8,
+//!     items: Terminated<EndOfContents, DerTlv<0x02, u8>>,
+//! }
+//!
+//! let bytes = [0x30, 0x80, 0x02, 0x01, 0x00, 0x02, 0x01, 0x07, 0x00, 0x00];
+//! let seq = Streamed::deserialize_new(&mut Cursor::new(&bytes[..])).unwrap();
+//! assert_eq!(seq.items.iter().map(|i| **i).collect::<Vec<_>>(), &[0, 7]);
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+use std::ops::{Deref, DerefMut};
+
+use crate::codec;
+use crate::len::WireLen;
+use crate::vec::EndMarker;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A length encoded with the DER rules.
+///
+/// # Example
+/// ```
+/// use type2network::ToNetworkOrder;
+/// use type2network::der::DerLength;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(DerLength(127).serialize_to(&mut buffer).unwrap(), 1);
+/// assert_eq!(DerLength(300).serialize_to(&mut buffer).unwrap(), 3);
+/// assert_eq!(buffer, &[0x7F, 0x82, 0x01, 0x2C]);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DerLength(pub usize);
+
+impl DerLength {
+    // short form in a single byte, long form without leading zeros
+    fn encode(&self) -> ([u8; 9], usize) {
+        let mut encoded = [0u8; 9];
+        if self.0 < 0x80 {
+            encoded[0] = self.0 as u8;
+            return (encoded, 1);
+        }
+
+        let bytes = self.0.to_be_bytes();
+        let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        let length = &bytes[first..];
+        encoded[0] = 0x80 | length.len() as u8;
+        encoded[1..=length.len()].copy_from_slice(length);
+        (encoded, 1 + length.len())
+    }
+}
+
+impl ToNetworkOrder for DerLength {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let (encoded, length) = self.encode();
+        buffer.extend_from_slice(&encoded[..length]);
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let (encoded, length) = self.encode();
+        writer.write_all(&encoded[..length])?;
+        Ok(length)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for DerLength {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let first = codec::get_u8(buffer)?;
+        if first < 0x80 {
+            self.0 = first as usize;
+            return Ok(());
+        }
+
+        let count = (first & 0x7F) as usize;
+        if count == 0 {
+            return Err(invalid("indefinite length not allowed"));
+        }
+        if count > std::mem::size_of::<usize>() {
+            return Err(invalid(&format!("length on {} bytes too long", count)));
+        }
+
+        let mut value = 0usize;
+        for i in 0..count {
+            let byte = codec::get_u8(buffer)?;
+            if i == 0 && byte == 0 {
+                return Err(invalid("length with a leading zero"));
+            }
+            value = value << 8 | byte as usize;
+        }
+
+        // the short form is used when possible
+        if value < 0x80 {
+            return Err(invalid("long form used for a short length"));
+        }
+
+        self.0 = value;
+        Ok(())
+    }
+}
+
+impl WireLen for DerLength {
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        DerLength(len).serialize_to(buffer)
+    }
+
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        Ok(DerLength::deserialize_new(buffer)?.0)
+    }
+}
+
+// error returned for a length not following the DER rules
+fn invalid(reason: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("invalid DER: {}", reason))
+}
+
+/// The end-of-contents octets of the BER indefinite length.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EndOfContents;
+
+impl EndMarker for EndOfContents {
+    const MARKER: &'static [u8] = &[0x00, 0x00];
+}
+
+/// A ```T``` value preceded by the ```TAG``` byte and its DER length.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DerTlv<const TAG: u8, T>(pub T);
+
+impl<const TAG: u8, T> DerTlv<TAG, T> {
+    /// The tag sent before the length.
+    pub const TAG: u8 = TAG;
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<const TAG: u8, T> From<T> for DerTlv<TAG, T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<const TAG: u8, T> Deref for DerTlv<TAG, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const TAG: u8, T> DerefMut for DerTlv<TAG, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const TAG: u8, T: ToNetworkOrder> ToNetworkOrder for DerTlv<TAG, T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        codec::put_u8(buffer, TAG);
+
+        // the size of the length depends on the length of the value, known once serialized
+        let start = buffer.len();
+        let length = self.0.serialize_to(buffer)?;
+        let mut prefix = Vec::new();
+        DerLength(length).serialize_to(&mut prefix)?;
+        buffer.splice(start..start, prefix.iter().copied());
+
+        Ok(1 + prefix.len() + length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        // the value is written once its length is known, without being kept
+        let length = crate::size::wire_size_of(&self.0)?;
+        crate::align::track_writes(writer, |writer| {
+            let prefix = TAG.write_to(writer)? + DerLength(length).write_to(writer)?;
+            let written = self.0.write_to(writer)?;
+            Ok(prefix + written)
+        })
+    }
+}
+
+impl<'a, const TAG: u8, T: FromNetworkOrder<'a>> FromNetworkOrder<'a> for DerTlv<TAG, T> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let tag = codec::get_u8(buffer)?;
+        if tag != TAG {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("DER tag {:#04x} found, {:#04x} expected", tag, TAG),
+            ));
+        }
+
+        let length = DerLength::deserialize_new(buffer)?.0;
+        let bytes = *buffer.get_ref();
+        let position = buffer.position() as usize;
+        let content = position
+            .checked_add(length)
+            .and_then(|end| bytes.get(position..end))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("DER content of {} bytes truncated", length),
+                )
+            })?;
+
+        // the value is read from its content only
+        let mut inner = Cursor::new(content);
+        self.0.deserialize_from(&mut inner)?;
+        if inner.position() as usize != length {
+            return Err(invalid(&format!(
+                "{} bytes left in the content",
+                length - inner.position() as usize
+            )));
+        }
+
+        buffer.set_position((position + length) as u64);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opaque::Opaque;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn der_length() {
+        to_network_test(DerLength(0), 1, &[0x00]);
+        to_network_test(DerLength(0x80), 2, &[0x81, 0x80]);
+        to_network_test(DerLength(0x1_0000), 4, &[0x83, 0x01, 0x00, 0x00]);
+        from_network_test(None, DerLength(0x7F), &vec![0x7F]);
+        from_network_test(None, DerLength(0x0100), &vec![0x82, 0x01, 0x00]);
+
+        // indefinite, leading zero, long form for a short length, truncated
+        for bytes in [
+            &[0x80][..],
+            &[0x82, 0x00, 0x80],
+            &[0x81, 0x7F],
+            &[0x82, 0x01],
+            &[0x89, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        ] {
+            assert!(DerLength::deserialize_new(&mut Cursor::new(bytes)).is_err());
+        }
+    }
+
+    #[test]
+    fn der_tlv() {
+        let v: DerTlv<0x04, Opaque<DerLength>> = DerTlv(Opaque::from(vec![0xAA; 200]));
+        let mut buffer = Vec::new();
+        assert_eq!(v.serialize_to(&mut buffer).unwrap(), 205);
+        assert_eq!(&buffer[..4], &[0x04, 0x81, 0xCA, 0x81]);
+
+        to_network_test(DerTlv::<0x02, u16>(0x0102), 4, &[0x02, 0x02, 0x01, 0x02]);
+        from_network_test(
+            None,
+            DerTlv::<0x02, u16>(0x0102),
+            &vec![0x02, 0x02, 0x01, 0x02],
+        );
+
+        // wrong tag, content too short, bytes left in the content
+        for bytes in [
+            &[0x03, 0x02, 0x01, 0x02][..],
+            &[0x02, 0x03, 0x01, 0x02],
+            &[0x02, 0x01, 0x01, 0x02],
+        ] {
+            assert!(DerTlv::<0x02, u16>::deserialize_new(&mut Cursor::new(bytes)).is_err());
+        }
+        assert!(
+            DerTlv::<0x02, u8>::deserialize_new(&mut Cursor::new(&[0x02, 0x02, 1, 2][..])).is_err()
+        );
+    }
+}
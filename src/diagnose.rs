@@ -0,0 +1,232 @@
+//! Reports of how far the deserialization of a message went (```diagnose``` feature), for test failures
+//! or bug reports from production captures.
+//!
+//! When the feature is enabled, the code generated by ```#[derive(FromNetwork)]``` records the offsets of
+//! each field deserialized, which are collected by ```diagnose()```:
+//!
+//! ```
+//! use type2network::FromNetworkOrder;
+//! use type2network::diagnose::diagnose;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Header {
+//!     id: u16,
+//!     flags: u16,
+//!     count: u32,
+//! }
+//!
+//! let report = diagnose::<Header>(&[0x12, 0x34, 0, 1, 0xFF]);
+//! assert!(!report.is_ok());
+//! assert_eq!(report.fields().len(), 2);
+//! assert_eq!(report.fields()[1].start, 2);
+//! assert_eq!(report.failing_field().unwrap(), "Header.count");
+//! println!("{}", report);
+//! ```
+//!
+//! which prints:
+//!
+//! ```text
+//! Header.id                      0..2
+//! Header.flags                   2..4
+//! error at offset 4 in Header.count: failed to fill whole buffer
+//! 1 byte(s) left out of 5:
+//! 0000  ff                                                |.|
+//! ```
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Cursor;
+
+use crate::{FieldError, FromNetworkOrder};
+
+thread_local! {
+    // the fields deserialized, only collected while diagnose() is running
+    static RECORDER: RefCell<Option<Vec<ParsedField>>> = const { RefCell::new(None) };
+}
+
+// called by the generated code after each field is deserialized
+pub(crate) fn record(type_name: &'static str, field: &'static str, start: u64, end: u64) {
+    RECORDER.with(|r| {
+        if let Some(fields) = r.borrow_mut().as_mut() {
+            fields.push(ParsedField {
+                type_name,
+                field,
+                start: start as usize,
+                end: end as usize,
+            });
+        }
+    });
+}
+
+/// A field successfully deserialized. As a field is recorded once fully deserialized, the fields of
+/// a nested type come before the field holding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedField {
+    /// The name of the type holding the field.
+    pub type_name: &'static str,
+
+    /// The name of the field, or its index for a tuple struct.
+    pub field: &'static str,
+
+    /// Offset of the first byte of the field.
+    pub start: usize,
+
+    /// Offset following the last byte of the field.
+    pub end: usize,
+}
+
+/// The result of ```diagnose()```.
+#[derive(Debug)]
+pub struct Report {
+    fields: Vec<ParsedField>,
+    error: Option<std::io::Error>,
+    consumed: usize,
+    bytes: Vec<u8>,
+}
+
+impl Report {
+    /// True if the message was successfully deserialized.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns the fields deserialized, in the order they were completed.
+    pub fn fields(&self) -> &[ParsedField] {
+        &self.fields
+    }
+
+    /// Returns the deserialization error, if any.
+    pub fn error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the path of the field which failed to deserialize (e.g. ```Response.answer.rd_length```).
+    pub fn failing_field(&self) -> Option<String> {
+        self.error
+            .as_ref()
+            .and_then(FieldError::from_io)
+            .map(FieldError::path)
+    }
+
+    /// Returns the number of bytes read. In case of error, it's the end of the last field deserialized, i.e.
+    /// the offset of the failing field.
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Returns the bytes not read.
+    pub fn remaining(&self) -> &[u8] {
+        &self.bytes[self.consumed.min(self.bytes.len())..]
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            let name = format!("{}.{}", field.type_name, field.field);
+            writeln!(f, "{:<30} {}..{}", name, field.start, field.end)?;
+        }
+
+        match &self.error {
+            None => writeln!(f, "ok, {} byte(s) read", self.consumed)?,
+            Some(e) => match FieldError::from_io(e) {
+                Some(fe) => writeln!(
+                    f,
+                    "error at offset {} in {}: {}",
+                    self.consumed,
+                    fe.path(),
+                    fe.error()
+                )?,
+                None => writeln!(f, "error at offset {}: {}", self.consumed, e)?,
+            },
+        }
+
+        let remaining = self.remaining();
+        writeln!(
+            f,
+            "{} byte(s) left out of {}:",
+            remaining.len(),
+            self.bytes.len()
+        )?;
+        hexdump(f, remaining)
+    }
+}
+
+// 16 bytes per line, with offsets relative to the start of the bytes and the ASCII representation
+fn hexdump(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex: Vec<_> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(f, "{:04x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii)?;
+    }
+    Ok(())
+}
+
+/// Deserializes a ```T``` from ```bytes```, and reports the fields deserialized with their offsets, the
+/// error and the failing field if any, and the bytes left.
+pub fn diagnose<T>(bytes: &[u8]) -> Report
+where
+    T: for<'a> FromNetworkOrder<'a> + Default,
+{
+    // a previous report could be ongoing if diagnose() is called during a deserialization
+    let previous = RECORDER.with(|r| r.borrow_mut().replace(Vec::new()));
+
+    let mut buffer = Cursor::new(bytes);
+    let error = T::default().deserialize_from(&mut buffer).err();
+
+    let fields = RECORDER
+        .with(|r| std::mem::replace(&mut *r.borrow_mut(), previous))
+        .unwrap_or_default();
+
+    // the cursor position is not reliable on errors, as a failing read can consume the bytes left
+    let consumed = match error {
+        None => buffer.position() as usize,
+        Some(_) => fields.iter().map(|f| f.end).max().unwrap_or(0),
+    };
+
+    Report {
+        fields,
+        error,
+        consumed,
+        bytes: bytes.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let report = diagnose::<[u16; 2]>(&[1, 2, 3, 4, 5]);
+        assert!(report.is_ok());
+        assert_eq!(report.consumed(), 4);
+        assert_eq!(report.remaining(), &[5]);
+        assert!(report.fields().is_empty());
+        assert!(report.failing_field().is_none());
+
+        // not a derived type: no field path
+        let report = diagnose::<u32>(&[1, 2, 3]);
+        assert!(!report.is_ok());
+        assert!(report.failing_field().is_none());
+        assert_eq!(report.remaining(), &[1, 2, 3]);
+
+        let report = diagnose::<u8>(b"0123456789abcdefghij");
+        assert_eq!(
+            report.to_string(),
+            "ok, 1 byte(s) read\n\
+             19 byte(s) left out of 20:\n\
+             0000  31 32 33 34 35 36 37 38 39 61 62 63 64 65 66 67  |123456789abcdefg|\n\
+             0010  68 69 6a                                         |hij|\n"
+        );
+    }
+}
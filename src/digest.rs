@@ -0,0 +1,103 @@
+//! Digests of the serialized values, for deduplication, content addressing or as the pre-hash of a signature.
+//!
+//! ```sha256()``` returns the SHA-256 digest of the bytes of any ```ToNetworkOrder``` value. With
+//! ```#[network(digest = "sha256")]```, the ```ToNetwork``` derive also generates a ```wire_digest()``` method:
+//!
+//! ```
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! #[network(digest = "sha256")]
+//! struct Record {
+//!     id: u16,
+//!     name: &'static str,
+//! }
+//!
+//! let a = Record { id: 1, name: "abc" };
+//! let b = Record { id: 1, name: "abc" };
+//! assert_eq!(a.wire_digest(), b.wire_digest());
+//! assert_eq!(a.wire_digest(), type2network::digest::sha256(&(1u16, "abc")).unwrap());
+//! assert_eq!(a.try_wire_digest().unwrap(), a.wire_digest());
+//! ```
+//!
+//! The bytes are written to the hasher with ```write_to()```, without being kept. ```try_wire_digest()``` returns the
+//! error instead of panicking when the value can't be serialized.
+use std::io::Write;
+
+use sha2::{Digest, Sha256};
+
+use crate::ToNetworkOrder;
+
+/// Size of a SHA-256 digest.
+pub const SHA256_SIZE: usize = 32;
+
+/// Returns the SHA-256 digest of the serialized value, or the error returned when serializing it.
+pub fn sha256<T: ToNetworkOrder + ?Sized>(value: &T) -> std::io::Result<[u8; SHA256_SIZE]> {
+    let mut hasher = Hasher(Sha256::new());
+    value.write_to(&mut hasher)?;
+    Ok(hasher.0.finalize().into())
+}
+
+// feeds the bytes written to the hasher
+struct Hasher(Sha256);
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// called by the wire_digest() method generated for #[network(digest = "sha256")]
+#[doc(hidden)]
+pub fn wire_sha256<T: ToNetworkOrder + ?Sized>(type_name: &str, value: &T) -> [u8; SHA256_SIZE] {
+    match sha256(value) {
+        Ok(digest) => digest,
+        Err(e) => panic!("unable to serialize a {}: {}", type_name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest() {
+        // FIPS 180-2 example
+        assert_eq!(
+            sha256(&"abc").unwrap(),
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad
+            ]
+        );
+        assert_ne!(sha256(&1u8).unwrap(), sha256(&1u16).unwrap());
+
+        // the same digest as the serialized bytes
+        let values = vec![0x1234_u16; 1000];
+        let mut buffer = Vec::new();
+        values.serialize_to(&mut buffer).unwrap();
+        assert_eq!(
+            sha256(&values).unwrap(),
+            <[u8; 32]>::from(Sha256::digest(&buffer))
+        );
+
+        // the serialization error is returned
+        struct Invalid;
+
+        impl ToNetworkOrder for Invalid {
+            fn serialize_to(&self, _: &mut Vec<u8>) -> std::io::Result<usize> {
+                Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+            }
+        }
+
+        let e = sha256(&Invalid).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
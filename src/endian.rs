@@ -0,0 +1,249 @@
+//! Integers stored in wire order, for structs laid over received buffers without any deserialization step.
+//!
+//! ```BeU16```, ```BeU32``` and ```BeU64``` hold the big-endian bytes of an integer, converted only when accessed with
+//! ```get()``` and ```set()```. They have an alignment of 1, so a ```#[network(pod)]``` struct made of these types and bytes
+//! can be read in place from a buffer with the ```Overlay``` trait:
+//!
+//! ```
+//! use type2network::endian::{BeU16, BeU32, Overlay};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, ToNetwork, FromNetwork)]
+//! #[network(pod)]
+//! #[repr(C, packed)]
+//! struct Header {
+//!     version: u8,
+//!     length: BeU16,
+//!     id: BeU32,
+//! }
+//!
+//! let bytes = [1, 0, 7, 0x12, 0x34, 0x56, 0x78, 0xFF];
+//! let (header, rest) = Header::overlay(&bytes).unwrap();
+//! assert_eq!(header.length.get(), 7);
+//! assert_eq!(header.id.get(), 0x12345678);
+//! assert_eq!(rest, &[0xFF]);
+//! ```
+//!
+//! For code ported from C socket programs, ```hton()``` and ```ntoh()``` convert integers (or slices and arrays of those)
+//! in place between host and network order, as ```htons()```, ```htonl()```, ```ntohs()``` and ```ntohl()``` do for values:
+//!
+//! ```
+//! use type2network::endian::{hton, htons, ntoh, ntohl};
+//!
+//! #[repr(C)]
+//! struct Header {
+//!     length: u16,
+//!     ports: [u16; 2],
+//! }
+//!
+//! let mut header = Header { length: 7, ports: [80, 443] };
+//! hton(&mut header.length);
+//! hton(&mut header.ports);
+//! assert_eq!(header.length, htons(7));
+//! assert_eq!(header.length.to_ne_bytes(), [0, 7]);
+//! assert_eq!(header.ports[1].to_ne_bytes(), [0x01, 0xBB]);
+//!
+//! ntoh(&mut header.ports[..]);
+//! assert_eq!(header.ports, [80, 443]);
+//! assert_eq!(ntohl(u32::from_ne_bytes([0, 0, 0, 1])), 1);
+//! ```
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use crate::byte_order::SwapBytes;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A type which can be read in place from any bytes.
+///
+/// # Safety
+///
+/// The type must have an alignment of 1, no padding, and any bit pattern must be a valid value. It's implemented by
+/// ```#[network(pod)]``` structs, whose layout is checked at compile time.
+pub unsafe trait Overlay: Sized {
+    /// Returns a reference to the value laid over the first bytes, and the bytes left, or ```None``` if there are
+    /// not enough bytes.
+    fn overlay(bytes: &[u8]) -> Option<(&Self, &[u8])> {
+        if bytes.len() < std::mem::size_of::<Self>() {
+            return None;
+        }
+
+        let (head, rest) = bytes.split_at(std::mem::size_of::<Self>());
+
+        // SAFETY: the size is checked, the alignment is 1 and any bytes are a valid value
+        let value = unsafe { &*head.as_ptr().cast::<Self>() };
+        Some((value, rest))
+    }
+
+    /// Returns a mutable reference to the value laid over the first bytes, and the bytes left, e.g. to patch
+    /// a header in place.
+    fn overlay_mut(bytes: &mut [u8]) -> Option<(&mut Self, &mut [u8])> {
+        if bytes.len() < std::mem::size_of::<Self>() {
+            return None;
+        }
+
+        let (head, rest) = bytes.split_at_mut(std::mem::size_of::<Self>());
+
+        // SAFETY: the size is checked, the alignment is 1 and any bytes are a valid value
+        let value = unsafe { &mut *head.as_mut_ptr().cast::<Self>() };
+        Some((value, rest))
+    }
+}
+
+// SAFETY: a byte has an alignment of 1, and any value is valid
+unsafe impl Overlay for u8 {}
+
+// SAFETY: arrays have no padding and the alignment of their elements
+unsafe impl<T: Overlay, const N: usize> Overlay for [T; N] {}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_be {
+    ($name:ident, $t:ty, $size:literal) => {
+        #[doc = concat!("A `", stringify!($t), "` stored as its big-endian bytes.")]
+        #[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $name([u8; $size]);
+
+        impl $name {
+            /// Creates a value from its native representation.
+            pub const fn new(value: $t) -> Self {
+                Self(value.to_be_bytes())
+            }
+
+            /// Returns the value.
+            pub const fn get(&self) -> $t {
+                <$t>::from_be_bytes(self.0)
+            }
+
+            /// Sets the value.
+            pub fn set(&mut self, value: $t) {
+                self.0 = value.to_be_bytes();
+            }
+
+            /// Returns the bytes, in wire order.
+            pub const fn as_bytes(&self) -> &[u8; $size] {
+                &self.0
+            }
+        }
+
+        impl From<$t> for $name {
+            fn from(value: $t) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for $t {
+            fn from(value: $name) -> Self {
+                value.get()
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:?}", self.get())
+            }
+        }
+
+        // SAFETY: a byte array with an alignment of 1, and any value is valid
+        unsafe impl Overlay for $name {}
+
+        impl ToNetworkOrder for $name {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                buffer.extend_from_slice(&self.0);
+                Ok($size)
+            }
+
+            fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                writer.write_all(&self.0)?;
+                Ok($size)
+            }
+        }
+
+        impl<'a> FromNetworkOrder<'a> for $name {
+            fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+                buffer.read_exact(&mut self.0)
+            }
+        }
+    };
+}
+
+impl_be!(BeU16, u16, 2);
+impl_be!(BeU32, u32, 4);
+impl_be!(BeU64, u64, 8);
+
+/// Converts an integer, or a slice or an array of integers, from host to network order in place. Nothing is done on
+/// big-endian hosts.
+pub fn hton<T: SwapBytes + ?Sized>(value: &mut T) {
+    if cfg!(target_endian = "little") {
+        value.swap_bytes();
+    }
+}
+
+/// Converts an integer, or a slice or an array of integers, from network to host order in place. Nothing is done on
+/// big-endian hosts.
+pub fn ntoh<T: SwapBytes + ?Sized>(value: &mut T) {
+    hton(value)
+}
+
+/// Converts a ```u16``` from host to network order, as the C function of the same name.
+pub const fn htons(value: u16) -> u16 {
+    value.to_be()
+}
+
+/// Converts a ```u32``` from host to network order, as the C function of the same name.
+pub const fn htonl(value: u32) -> u32 {
+    value.to_be()
+}
+
+/// Converts a ```u16``` from network to host order, as the C function of the same name.
+pub const fn ntohs(value: u16) -> u16 {
+    u16::from_be(value)
+}
+
+/// Converts a ```u32``` from network to host order, as the C function of the same name.
+pub const fn ntohl(value: u32) -> u32 {
+    u32::from_be(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn be() {
+        let mut v = BeU32::new(0x12345678);
+        assert_eq!(v.as_bytes(), &[0x12, 0x34, 0x56, 0x78]);
+        v.set(1);
+        assert_eq!(u32::from(v), 1);
+        assert_eq!(format!("{:?}", BeU16::new(7)), "7");
+
+        to_network_test(BeU64::new(0x0102), 8, &[0, 0, 0, 0, 0, 0, 1, 2]);
+        from_network_test(None, BeU16::new(0x1234), &vec![0x12, 0x34]);
+
+        let mut bytes = [0u8, 1, 2];
+        let (v, rest) = BeU16::overlay_mut(&mut bytes).unwrap();
+        v.set(0xFFFF);
+        assert_eq!(rest, &[2]);
+        assert_eq!(bytes, [0xFF, 0xFF, 2]);
+
+        assert!(<[BeU16; 2]>::overlay(&bytes).is_none());
+        assert_eq!(<[BeU16; 1]>::overlay(&bytes).unwrap().0[0].get(), 0xFFFF);
+    }
+
+    #[test]
+    fn hton_ntoh() {
+        let mut values = [0x1234_u16, 0x5678];
+        hton(&mut values[..]);
+        assert_eq!(values.map(u16::to_ne_bytes), [[0x12, 0x34], [0x56, 0x78]]);
+        ntoh(&mut values);
+        assert_eq!(values, [0x1234, 0x5678]);
+
+        let mut value = -2_i64;
+        hton(&mut value);
+        assert_eq!(value.to_ne_bytes(), (-2_i64).to_be_bytes());
+
+        assert_eq!(htonl(0x01020304).to_ne_bytes(), [1, 2, 3, 4]);
+        assert_eq!(ntohs(htons(0xABCD)), 0xABCD);
+    }
+}
@@ -0,0 +1,302 @@
+//! Encryption of a whole message, with a cipher provided by the application.
+//!
+//! An ```EncryptedEnvelope<T, C>``` is sent as a nonce, the length of the ciphertext (a big-endian ```u32```), the
+//! ciphertext and the authentication tag. The ```T``` value is serialized in the buffer, and sealed in place by the
+//! ```C``` cipher, which implements the ```Cipher``` trait over any AEAD (AES-GCM, ChaCha20-Poly1305...). When
+//! deserialized, the tag is verified before the ```T``` value is read from the decrypted bytes. The nonce and the
+//! length are authenticated as associated data.
+//!
+//! ```
+//! use std::cell::Cell;
+//! use std::io::{Cursor, Error, ErrorKind};
+//! use type2network::envelope::{Cipher, EncryptedEnvelope};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//!
+//! // not a real cipher: a XOR with the key, and the sum of the bytes as tag
+//! #[derive(Default)]
+//! struct Toy {
+//!     key: u8,
+//!     counter: Cell<u8>,
+//! }
+//!
+//! impl Toy {
+//!     fn tag(aad: &[u8], data: &[u8]) -> u8 {
+//!         aad.iter().chain(data).fold(0u8, |t, b| t.wrapping_add(*b))
+//!     }
+//! }
+//!
+//! impl Cipher for Toy {
+//!     const NONCE_LEN: usize = 1;
+//!     const TAG_LEN: usize = 1;
+//!
+//!     fn generate_nonce(&self, nonce: &mut [u8]) -> std::io::Result<()> {
+//!         self.counter.set(self.counter.get() + 1);
+//!         nonce[0] = self.counter.get();
+//!         Ok(())
+//!     }
+//!
+//!     fn seal(&self, nonce: &[u8], aad: &[u8], data: &mut [u8], tag: &mut [u8]) -> std::io::Result<()> {
+//!         data.iter_mut().for_each(|b| *b ^= self.key ^ nonce[0]);
+//!         tag[0] = Self::tag(aad, data);
+//!         Ok(())
+//!     }
+//!
+//!     fn open(&self, nonce: &[u8], aad: &[u8], data: &mut [u8], tag: &[u8]) -> std::io::Result<()> {
+//!         if tag[0] != Self::tag(aad, data) {
+//!             return Err(Error::new(ErrorKind::InvalidData, "authentication failed"));
+//!         }
+//!         data.iter_mut().for_each(|b| *b ^= self.key ^ nonce[0]);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let cipher = Toy { key: 0x55, counter: Cell::new(0) };
+//! let sealed = EncryptedEnvelope::new(0x1234_u16, cipher);
+//! let mut buffer = Vec::new();
+//! assert_eq!(sealed.serialize_to(&mut buffer).unwrap(), 8);
+//!
+//! let mut opened = EncryptedEnvelope::<u16, _>::new(0, Toy { key: 0x55, ..Toy::default() });
+//! opened.deserialize_from(&mut Cursor::new(buffer.as_slice())).unwrap();
+//! assert_eq!(*opened, 0x1234);
+//!
+//! // a byte of the ciphertext is modified
+//! buffer[5] ^= 1;
+//! assert!(opened.deserialize_from(&mut Cursor::new(buffer.as_slice())).is_err());
+//! ```
+//!
+//! As ```serialize_to()``` takes ```&self```, a cipher generating nonces from a counter keeps it in a ```Cell``` or an
+//! atomic. The decrypted bytes are kept in a scratch buffer, wiped when dropped with the ```zeroize``` feature.
+use std::io::{Cursor, Error, ErrorKind, Read};
+use std::ops::{Deref, DerefMut};
+
+use crate::secret::Scratch;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Size of the length of the ciphertext.
+const LENGTH_SIZE: usize = 4;
+
+/// An authenticated encryption algorithm, with its key, sealing the messages in place.
+pub trait Cipher {
+    /// Length of the nonces.
+    const NONCE_LEN: usize;
+
+    /// Length of the authentication tags.
+    const TAG_LEN: usize;
+
+    /// Writes the nonce of the next message, which should never be used twice with the same key.
+    fn generate_nonce(&self, nonce: &mut [u8]) -> std::io::Result<()>;
+
+    /// Encrypts ```data``` in place, and writes the tag authenticating it with ```aad```.
+    fn seal(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &mut [u8],
+    ) -> std::io::Result<()>;
+
+    /// Decrypts ```data``` in place, or returns an ```InvalidData``` error if ```tag``` doesn't authenticate it with
+    /// ```aad```.
+    fn open(&self, nonce: &[u8], aad: &[u8], data: &mut [u8], tag: &[u8]) -> std::io::Result<()>;
+}
+
+/// A ```T``` value encrypted on the wire by the ```C``` cipher.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EncryptedEnvelope<T, C> {
+    value: T,
+    cipher: C,
+}
+
+impl<T, C> EncryptedEnvelope<T, C> {
+    /// Creates an envelope sealing ```value``` with ```cipher```. To deserialize a message, the value is overwritten.
+    pub fn new(value: T, cipher: C) -> Self {
+        Self { value, cipher }
+    }
+
+    /// Returns the cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, C> Deref for EncryptedEnvelope<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, C> DerefMut for EncryptedEnvelope<T, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: ToNetworkOrder, C: Cipher> ToNetworkOrder for EncryptedEnvelope<T, C> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        // the nonce and the length, which is only known once the value is serialized
+        let start = buffer.len();
+        buffer.resize(start + C::NONCE_LEN + LENGTH_SIZE, 0);
+        self.cipher
+            .generate_nonce(&mut buffer[start..start + C::NONCE_LEN])?;
+
+        let data = buffer.len();
+        let result = self.value.serialize_to(buffer).and_then(|length| {
+            let length = u32::try_from(length).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("encrypted message of {} bytes is too long", length),
+                )
+            })?;
+            buffer[data - LENGTH_SIZE..data].copy_from_slice(&length.to_be_bytes());
+
+            // the value is sealed in place, and followed by the tag
+            let end = buffer.len();
+            buffer.resize(end + C::TAG_LEN, 0);
+            let (head, tag) = buffer.split_at_mut(end);
+            let (aad, plaintext) = head[start..].split_at_mut(C::NONCE_LEN + LENGTH_SIZE);
+            self.cipher.seal(&aad[..C::NONCE_LEN], aad, plaintext, tag)
+        });
+
+        // the plaintext is not left in the buffer
+        if let Err(e) = result {
+            crate::secret::wipe_bytes(&mut buffer[start..]);
+            buffer.truncate(start);
+            return Err(e);
+        }
+
+        Ok(buffer.len() - start)
+    }
+}
+
+impl<'a, T, C> FromNetworkOrder<'a> for EncryptedEnvelope<T, C>
+where
+    T: for<'b> FromNetworkOrder<'b>,
+    C: Cipher,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let aad = take(buffer, C::NONCE_LEN + LENGTH_SIZE)?;
+        let (nonce, length) = aad.split_at(C::NONCE_LEN);
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+
+        let mut data = Scratch(take(buffer, length)?.to_vec());
+        let tag = take(buffer, C::TAG_LEN)?;
+        self.cipher.open(nonce, aad, &mut data, tag)?;
+
+        // the value should use all the decrypted bytes
+        let mut plaintext = Cursor::new(data.as_slice());
+        self.value.deserialize_from(&mut plaintext)?;
+        if plaintext.position() as usize != data.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} bytes left after the encrypted message",
+                    data.len() - plaintext.position() as usize
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// the next bytes of the buffer
+fn take<'a>(buffer: &mut Cursor<&'a [u8]>, length: usize) -> std::io::Result<&'a [u8]> {
+    let bytes = *buffer.get_ref();
+    let position = (buffer.position() as usize).min(bytes.len());
+
+    match bytes.get(position..position + length) {
+        Some(taken) => {
+            buffer.set_position((position + length) as u64);
+            Ok(taken)
+        }
+        None => {
+            // consume the bytes left, as read_exact() does
+            buffer.read_to_end(&mut Vec::new())?;
+            Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "encrypted message too short",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a XOR with the nonce, and the XOR of all bytes as tag
+    struct Xor;
+
+    impl Cipher for Xor {
+        const NONCE_LEN: usize = 2;
+        const TAG_LEN: usize = 1;
+
+        fn generate_nonce(&self, nonce: &mut [u8]) -> std::io::Result<()> {
+            nonce.copy_from_slice(&[0xA0, 0x0B]);
+            Ok(())
+        }
+
+        fn seal(
+            &self,
+            nonce: &[u8],
+            aad: &[u8],
+            data: &mut [u8],
+            tag: &mut [u8],
+        ) -> std::io::Result<()> {
+            data.iter_mut().for_each(|b| *b ^= nonce[0]);
+            tag[0] = aad.iter().chain(data.iter()).fold(0, |t, b| t ^ b);
+            Ok(())
+        }
+
+        fn open(
+            &self,
+            nonce: &[u8],
+            aad: &[u8],
+            data: &mut [u8],
+            tag: &[u8],
+        ) -> std::io::Result<()> {
+            if tag[0] != aad.iter().chain(data.iter()).fold(0, |t, b| t ^ b) {
+                return Err(Error::new(ErrorKind::InvalidData, "bad tag"));
+            }
+            data.iter_mut().for_each(|b| *b ^= nonce[0]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn envelope() {
+        let mut buffer = vec![0xFF];
+        let sealed = EncryptedEnvelope::new(0x0102_u16, Xor);
+        assert_eq!(sealed.serialize_to(&mut buffer).unwrap(), 9);
+        assert_eq!(buffer, &[0xFF, 0xA0, 0x0B, 0, 0, 0, 2, 0xA1, 0xA2, 0xAA]);
+
+        let mut opened = EncryptedEnvelope::new(0_u16, Xor);
+        let mut cursor = Cursor::new(&buffer[1..]);
+        opened.deserialize_from(&mut cursor).unwrap();
+        assert_eq!(*opened, 0x0102);
+
+        // a byte of the ciphertext is modified
+        buffer[8] ^= 1;
+        let mut cursor = Cursor::new(&buffer[1..]);
+        let e = opened.deserialize_from(&mut cursor).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        buffer[8] ^= 1;
+
+        // the value doesn't use all the bytes
+        let mut opened = EncryptedEnvelope::new(0_u8, Xor);
+        let mut cursor = Cursor::new(&buffer[1..]);
+        assert!(opened.deserialize_from(&mut cursor).is_err());
+
+        // bytes are missing
+        let mut cursor = Cursor::new(&buffer[1..9]);
+        let e = opened.deserialize_from(&mut cursor).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+}
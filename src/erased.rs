@@ -0,0 +1,107 @@
+//! Object-safe serialization, for heterogeneous collections of values.
+//!
+//! ```ErasedToNetworkOrder``` is implemented for every ```ToNetworkOrder``` type, and only has object-safe methods,
+//! so it can be used as a trait object whatever the methods of ```ToNetworkOrder``` become. The boxed trait
+//! objects implement ```ToNetworkOrder```, so a ```Vec<Box<dyn ErasedToNetworkOrder>>``` is serialized as any
+//! vector, each element being serialized in turn:
+//!
+//! ```
+//! use type2network::erased::ErasedToNetworkOrder;
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! struct Option1 {
+//!     code: u8,
+//!     value: u16,
+//! }
+//!
+//! let options: Vec<Box<dyn ErasedToNetworkOrder>> = vec![
+//!     Box::new(Option1 { code: 1, value: 0x1234 }),
+//!     Box::new(0xFF_u8),
+//!     Box::new("end"),
+//! ];
+//!
+//! let mut buffer = Vec::new();
+//! assert_eq!(options.serialize_to(&mut buffer).unwrap(), 7);
+//! assert_eq!(buffer, &[1, 0x12, 0x34, 0xFF, b'e', b'n', b'd']);
+//! ```
+//!
+//! The ```Send``` and ```Send + Sync``` trait objects are supported as well, e.g. to move the collection to another
+//! thread.
+use std::io::Write;
+
+use crate::ToNetworkOrder;
+
+/// The object-safe counterpart of ```ToNetworkOrder```, implemented for all the ```ToNetworkOrder``` types.
+pub trait ErasedToNetworkOrder {
+    /// Serializes the value at the end of the buffer, as ```ToNetworkOrder::serialize_to()```.
+    fn erased_serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize>;
+
+    /// Serializes the value to the writer, and returns the number of bytes written.
+    fn erased_write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize>;
+}
+
+impl<T: ToNetworkOrder> ErasedToNetworkOrder for T {
+    fn erased_serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.serialize_to(buffer)
+    }
+
+    fn erased_write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        self.write_to(writer)
+    }
+}
+
+// the trait objects, with or without the auto traits, are serialized as the values they hold
+macro_rules! impl_erased {
+    ($($bounds:tt)*) => {
+        impl ToNetworkOrder for Box<dyn ErasedToNetworkOrder $($bounds)* + '_> {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                (**self).erased_serialize_to(buffer)
+            }
+
+            fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+                (**self).erased_write_to(writer)
+            }
+        }
+
+        impl ToNetworkOrder for &(dyn ErasedToNetworkOrder $($bounds)* + '_) {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                (**self).erased_serialize_to(buffer)
+            }
+
+            fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+                (**self).erased_write_to(writer)
+            }
+        }
+    };
+}
+
+impl_erased!();
+impl_erased!(+ Send);
+impl_erased!(+ Send + Sync);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::Secret;
+
+    #[test]
+    fn erased() {
+        let values: Vec<Box<dyn ErasedToNetworkOrder + Send + Sync>> =
+            vec![Box::new(0x1234_u16), Box::new(vec![1u8, 2]), Box::new(())];
+        let mut buffer = vec![0xFF];
+        assert_eq!(values.serialize_to(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer, &[0xFF, 0x12, 0x34, 1, 2]);
+
+        let value: &dyn ErasedToNetworkOrder = &0x56_u8;
+        let mut writer = Vec::new();
+        assert_eq!(value.erased_write_to(&mut writer).unwrap(), 1);
+        assert_eq!(writer, &[0x56]);
+
+        // the secrets behind a trait object are seen when serialized
+        let secret: Box<dyn ErasedToNetworkOrder> = Box::new(Secret::new(1u8));
+        let (_, seen) = crate::secret::tracking_secrets(|| secret.serialize_to(&mut Vec::new()));
+        assert!(seen);
+    }
+}
@@ -0,0 +1,253 @@
+//! Helpers around the [`std::io::Error`] errors returned by the traits.
+use std::fmt;
+use std::io::Error;
+
+/// The error returned when a field of a derived struct or enum can't be deserialized. It's found inside
+/// the [`std::io::Error`] returned, which keeps the kind of the original error.
+///
+/// When the field is itself a derived type, the path is extended so that it leads to the failing
+/// field (e.g. ```Response.answer.rd_length```).
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FieldError, FromNetworkOrder};
+/// use type2network_derive::FromNetwork;
+///
+/// #[derive(Default, FromNetwork)]
+/// struct Answer {
+///     name: u16,
+///     rd_length: u16,
+/// }
+///
+/// #[derive(Default, FromNetwork)]
+/// struct Response {
+///     id: u16,
+///     answer: Answer,
+/// }
+///
+/// let b = vec![0x12, 0x34, 0, 1, 0];
+/// let mut buffer = Cursor::new(b.as_slice());
+/// let e = Response::default().deserialize_from(&mut buffer).unwrap_err();
+///
+/// assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+/// let field_error = FieldError::from_io(&e).unwrap();
+/// assert_eq!(field_error.path(), "Response.answer.rd_length");
+/// assert!(e.to_string().starts_with("Response.answer.rd_length: "));
+/// ```
+///
+/// The error of the failing field is kept as it is, so the errors of ```with_fn``` functions can be told apart
+/// by their type with ```downcast_ref()```, e.g. a timeout from a validation failure:
+///
+/// ```
+/// use std::io::{Cursor, Error, ErrorKind};
+/// use type2network::{FieldError, FromNetworkOrder};
+/// use type2network_derive::FromNetwork;
+///
+/// #[derive(Debug)]
+/// struct BadVersion(u8);
+///
+/// impl std::fmt::Display for BadVersion {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "bad version {}", self.0)
+///     }
+/// }
+///
+/// impl std::error::Error for BadVersion {}
+///
+/// fn check(header: &mut Header) -> std::io::Result<()> {
+///     Err(Error::new(ErrorKind::InvalidData, BadVersion(header.version)))
+/// }
+///
+/// #[derive(Default, FromNetwork)]
+/// struct Header {
+///     #[network(from(with_fn(check)))]
+///     version: u8,
+/// }
+///
+/// let mut header = Header { version: 7 };
+/// let e = header.deserialize_from(&mut Cursor::new(&[][..])).unwrap_err();
+/// let field_error = FieldError::from_io(&e).unwrap();
+/// assert_eq!(field_error.downcast_ref::<BadVersion>().unwrap().0, 7);
+/// ```
+#[derive(Debug)]
+pub struct FieldError {
+    type_name: &'static str,
+
+    // outermost field first
+    fields: Vec<&'static str>,
+
+    source: Error,
+}
+
+impl FieldError {
+    /// Returns the ```FieldError``` found in ```e```, if any.
+    pub fn from_io(e: &Error) -> Option<&FieldError> {
+        e.get_ref()
+            .and_then(|inner| inner.downcast_ref::<FieldError>())
+    }
+
+    /// Returns the path of the failing field, starting with the outermost type name.
+    pub fn path(&self) -> String {
+        let mut path = self.type_name.to_string();
+        for field in &self.fields {
+            path.push('.');
+            path.push_str(field);
+        }
+        path
+    }
+
+    /// Returns the name of the outermost type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the field names, from the outermost one to the failing one.
+    pub fn fields(&self) -> &[&'static str] {
+        &self.fields
+    }
+
+    /// Returns the error of the failing field.
+    pub fn error(&self) -> &Error {
+        &self.source
+    }
+
+    /// Returns the error of the failing field, e.g. to get back the error given to ```std::io::Error::new()```
+    /// with ```std::io::Error::downcast()```.
+    pub fn into_error(self) -> Error {
+        self.source
+    }
+
+    /// Returns the first error of type ```E``` found in the error of the failing field or in its sources.
+    pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        let mut error = self
+            .source
+            .get_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static));
+
+        while let Some(e) = error {
+            if let Some(found) = e.downcast_ref::<E>() {
+                return Some(found);
+            }
+            error = e.source();
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path(), self.source)
+    }
+}
+
+impl std::error::Error for FieldError {
+    // std::io::Error::source() skips the error it wraps, which is returned instead with the
+    // error-source feature so it's found when walking the chain of sources
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        #[cfg(feature = "error-source")]
+        if let Some(inner) = self.source.get_ref() {
+            return Some(inner);
+        }
+
+        Some(&self.source)
+    }
+}
+
+// used by the derive macros to add the field to the path of the error
+#[doc(hidden)]
+pub fn field_context(e: Error, type_name: &'static str, field: &'static str) -> Error {
+    let kind = e.kind();
+
+    // the field is a derived type: prepend the field to its path
+    if FieldError::from_io(&e).is_some() {
+        let mut inner = e
+            .into_inner()
+            .and_then(|i| i.downcast::<FieldError>().ok())
+            .expect("FieldError checked above");
+        inner.type_name = type_name;
+        inner.fields.insert(0, field);
+        return Error::new(kind, *inner);
+    }
+
+    Error::new(
+        kind,
+        FieldError {
+            type_name,
+            fields: vec![field],
+            source: e,
+        },
+    )
+}
+
+/// Wrapper used to log an [`std::io::Error`] with ```defmt```, as ```defmt::Format``` can't be implemented
+/// on a foreign type. The error kind and message are logged.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{DefmtError, FromNetworkOrder};
+///
+/// let b = vec![0x12];
+/// let mut buffer = Cursor::new(b.as_slice());
+/// if let Err(e) = 0u16.deserialize_from(&mut buffer) {
+///     // on an embedded target: defmt::error!("parse failure: {}", DefmtError(&e));
+///     let _ = DefmtError(&e);
+/// }
+/// ```
+#[cfg(feature = "defmt")]
+#[derive(Debug, Clone, Copy)]
+pub struct DefmtError<'a>(pub &'a std::io::Error);
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for DefmtError<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "{} ({})",
+            defmt::Display2Format(self.0),
+            defmt::Debug2Format(&self.0.kind())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn field_error() {
+        let e = Error::new(ErrorKind::InvalidData, "bad value");
+        let e = field_context(e, "Inner", "x");
+        let e = field_context(e, "Outer", "inner");
+
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        let fe = FieldError::from_io(&e).unwrap();
+        assert_eq!(fe.path(), "Outer.inner.x");
+        assert_eq!(fe.fields(), &["inner", "x"]);
+        assert_eq!(fe.error().to_string(), "bad value");
+        assert_eq!(e.to_string(), "Outer.inner.x: bad value");
+
+        assert!(FieldError::from_io(&Error::from(ErrorKind::UnexpectedEof)).is_none());
+    }
+
+    #[test]
+    fn field_error_source() {
+        let e = Error::new(ErrorKind::TimedOut, Error::from(ErrorKind::WouldBlock));
+        let e = field_context(e, "Outer", "x");
+        let fe = FieldError::from_io(&e).unwrap();
+
+        // the wrapped error is kept, not its message
+        let inner = fe.downcast_ref::<Error>().unwrap();
+        assert_eq!(inner.kind(), ErrorKind::WouldBlock);
+        assert!(fe.downcast_ref::<std::fmt::Error>().is_none());
+
+        #[cfg(feature = "error-source")]
+        assert!(std::error::Error::source(fe)
+            .unwrap()
+            .downcast_ref::<Error>()
+            .is_some_and(|e| e.kind() == ErrorKind::WouldBlock));
+    }
+}
@@ -0,0 +1,169 @@
+//! Floats sent with a sortable encoding.
+//!
+//! ```f32``` and ```f64``` values are sent as their IEEE 754 bits in big-endian order, converted with
+//! ```to_bits()```/```from_bits()``` and ```to_be_bytes()```/```from_be_bytes()``` only (no ```unsafe``` code nor
+//! ```transmute```), so all values, including the NaN payloads, round-trip.
+//!
+//! As these bytes don't compare as the values do (negative values are sorted after positive ones, in reverse order),
+//! ```TotalOrder<T>``` sends the total-order bit pattern instead: the sign bit is flipped for positive values and all
+//! bits for negative ones. The lexicographic order of the bytes is then the order of ```f64::total_cmp()```, which is
+//! required by key encodings or protocols comparing raw bytes.
+//!
+//! # Example
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network::float::TotalOrder;
+//!
+//! let mut small: Vec<u8> = Vec::new();
+//! let mut large: Vec<u8> = Vec::new();
+//! assert_eq!(TotalOrder(-2.5f64).serialize_to(&mut small).unwrap(), 8);
+//! TotalOrder(1.0f64).serialize_to(&mut large).unwrap();
+//! assert!(small < large);
+//!
+//! let mut cursor = Cursor::new(small.as_slice());
+//! assert_eq!(TotalOrder::<f64>::deserialize_new(&mut cursor).unwrap(), TotalOrder(-2.5));
+//! ```
+use std::cmp::Ordering;
+use std::io::Cursor;
+
+use crate::codec;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A float sent as its total-order bit pattern, so its bytes sort as ```total_cmp()``` does. It's compared
+/// using ```total_cmp()``` as well, so it implements ```Eq``` and ```Ord```.
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TotalOrder<T>(pub T);
+
+// helper macro for boiler plate definitions
+macro_rules! impl_total_order {
+    ($t:ty, $bits:ty, $put:ident, $get:ident) => {
+        impl TotalOrder<$t> {
+            /// Returns the total-order bit pattern of the value, as sent on the wire.
+            pub fn to_sortable_bits(self) -> $bits {
+                const SIGN: $bits = 1 << (<$bits>::BITS - 1);
+
+                let bits = self.0.to_bits();
+                if bits & SIGN == 0 {
+                    bits ^ SIGN
+                } else {
+                    !bits
+                }
+            }
+
+            /// Returns the value from its total-order bit pattern.
+            pub fn from_sortable_bits(bits: $bits) -> Self {
+                const SIGN: $bits = 1 << (<$bits>::BITS - 1);
+
+                let bits = if bits & SIGN == 0 { !bits } else { bits ^ SIGN };
+                Self(<$t>::from_bits(bits))
+            }
+        }
+
+        impl ToNetworkOrder for TotalOrder<$t> {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                codec::$put(buffer, self.to_sortable_bits());
+                Ok(std::mem::size_of::<$bits>())
+            }
+
+            fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                self.to_sortable_bits().write_to(writer)
+            }
+        }
+
+        impl<'a> FromNetworkOrder<'a> for TotalOrder<$t> {
+            fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+                *self = Self::from_sortable_bits(codec::$get(buffer)?);
+                Ok(())
+            }
+        }
+
+        impl PartialEq for TotalOrder<$t> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for TotalOrder<$t> {}
+
+        impl PartialOrd for TotalOrder<$t> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for TotalOrder<$t> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl From<$t> for TotalOrder<$t> {
+            fn from(value: $t) -> Self {
+                Self(value)
+            }
+        }
+    };
+}
+
+impl_total_order!(f32, u32, put_u32, get_u32);
+impl_total_order!(f64, u64, put_u64, get_u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn total_order() {
+        let values = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            2.0,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+
+        // the bytes are sorted as the values
+        let encoded: Vec<Vec<u8>> = values
+            .iter()
+            .map(|v| {
+                let mut buffer = Vec::new();
+                TotalOrder(*v).serialize_to(&mut buffer).unwrap();
+                buffer
+            })
+            .collect();
+        assert!(encoded.windows(2).all(|w| w[0] < w[1]));
+
+        // and read back, bit for bit
+        for (v, bytes) in values.iter().zip(&encoded) {
+            let mut cursor = Cursor::new(bytes.as_slice());
+            let read = TotalOrder::<f64>::deserialize_new(&mut cursor).unwrap();
+            assert_eq!(read.0.to_bits(), v.to_bits());
+        }
+
+        let mut buffer = Vec::new();
+        TotalOrder(0.0f32).serialize_to(&mut buffer).unwrap();
+        TotalOrder(-1.0f32).serialize_to(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x80, 0x00, 0x00, 0x00, 0x40, 0x7F, 0xFF, 0xFF]);
+        assert!(TotalOrder(-0.0f32) < TotalOrder(0.0));
+    }
+
+    #[test]
+    fn nan_payload() {
+        // the payload of a NaN is kept by the IEEE 754 encoding
+        let nan = f64::from_bits(0x7FF8_0000_0000_1234);
+        let mut buffer = Vec::new();
+        nan.serialize_to(&mut buffer).unwrap();
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(
+            f64::deserialize_new(&mut cursor).unwrap().to_bits(),
+            0x7FF8_0000_0000_1234
+        );
+    }
+}
@@ -0,0 +1,800 @@
+//! Length-prefixed framing of messages sent over a byte stream (e.g. a TCP connection).
+//!
+//! Each frame is made of the length of the message, as a big-endian ```u32```, followed by the serialized
+//! message. A frame must be fully consumed when deserializing the message.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::framing::{FrameReader, FrameWriter};
+//!
+//! let mut writer = FrameWriter::new(Vec::new());
+//! assert_eq!(writer.write(&0x1234_u16).unwrap(), 6);
+//! assert_eq!(writer.write(&"hello").unwrap(), 9);
+//!
+//! let bytes = writer.into_inner();
+//! assert_eq!(&bytes[..6], &[0, 0, 0, 2, 0x12, 0x34]);
+//!
+//! let mut reader = FrameReader::new(Cursor::new(bytes));
+//! assert_eq!(reader.read::<u16>().unwrap(), 0x1234);
+//! assert_eq!(reader.read_frame().unwrap(), b"hello");
+//! ```
+//!
+//! Without a ```Read``` implementation, e.g. in a custom event loop (```mio```, ```io_uring```), the bytes received
+//! can be given in chunks of any size to a ```Decoder```, which returns the messages once their frames are complete:
+//!
+//! ```
+//! use type2network::framing::Decoder;
+//!
+//! let mut decoder = Decoder::<u16>::new();
+//! assert_eq!(decoder.feed(&[0, 0]).unwrap(), None);
+//! assert_eq!(decoder.needed(), 2);
+//! assert_eq!(decoder.feed(&[0, 2, 0x12]).unwrap(), None);
+//! assert_eq!(decoder.needed(), 1);
+//!
+//! // the next frame is kept until the message is taken
+//! assert_eq!(decoder.feed(&[0x34, 0, 0, 0, 2, 0x56, 0x78]).unwrap(), Some(0x1234));
+//! assert_eq!(decoder.decode().unwrap(), Some(0x5678));
+//! assert_eq!(decoder.needed(), 4);
+//! ```
+//!
+//! Other protocols encode the length prefix differently. The ```FrameWriter```, ```FrameReader``` and ```Decoder```
+//! use the ```FrameFormat``` given to ```with_format()```, which selects the byte order of the prefix and whether it
+//! counts its own 4 bytes. ```FrameFormat::NineP``` is the ```size[4]``` of the 9P protocol (Plan 9 file system),
+//! little-endian and including itself:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::framing::{FrameFormat, FrameReader, FrameWriter};
+//!
+//! let mut writer = FrameWriter::new(Vec::new()).with_format(FrameFormat::NineP);
+//! assert_eq!(writer.write(&0x1234_u16).unwrap(), 6);
+//!
+//! let bytes = writer.into_inner();
+//! assert_eq!(bytes, &[6, 0, 0, 0, 0x12, 0x34]);
+//!
+//! let mut reader = FrameReader::new(Cursor::new(bytes)).with_format(FrameFormat::NineP);
+//! assert_eq!(reader.read::<u16>().unwrap(), 0x1234);
+//! ```
+//!
+//! The prefix of these frames is always 4 bytes long, so the ```Decoder``` knows when it's complete and a size counting
+//! the prefix itself can be decoded. Frames prefixed by any other length encoding (a ```len::WireLen```, e.g. a
+//! ```len::Varint```) are written by ```write_frame_with()``` and read by ```read_frame_with()```:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::framing::{read_frame_with, write_frame_with};
+//! use type2network::len::Varint;
+//!
+//! let mut bytes = Vec::new();
+//! assert_eq!(write_frame_with::<Varint, _, _>(&mut bytes, &"hello").unwrap(), 6);
+//! assert_eq!(bytes[0], 5);
+//!
+//! let mut reader = Cursor::new(bytes);
+//! assert_eq!(read_frame_with::<Varint, _>(&mut reader, 1024).unwrap(), b"hello");
+//! ```
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+use crate::byte_order::ByteOrder;
+use crate::len::WireLen;
+use crate::secret::{wipe_bytes, Scratch};
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Size of the length prefix of a frame.
+pub const FRAME_HEADER_SIZE: usize = 4;
+
+/// How the length prefix of the frames is encoded, always on 4 bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameFormat {
+    /// The length of the message as a big-endian ```u32```.
+    #[default]
+    Standard,
+
+    /// The size of the frame, including the prefix, as a little-endian ```u32``` (9P).
+    NineP,
+
+    /// Any byte order, the prefix counting its own bytes if ```inclusive``` is set.
+    Custom {
+        /// Byte order of the prefix.
+        byte_order: ByteOrder,
+
+        /// Whether the prefix counts its own bytes.
+        inclusive: bool,
+    },
+}
+
+impl FrameFormat {
+    /// Returns the byte order of the prefix.
+    pub fn byte_order(self) -> ByteOrder {
+        match self {
+            FrameFormat::Standard => ByteOrder::Big,
+            FrameFormat::NineP => ByteOrder::Little,
+            FrameFormat::Custom { byte_order, .. } => byte_order,
+        }
+    }
+
+    /// Returns ```true``` if the prefix counts its own bytes.
+    pub fn inclusive(self) -> bool {
+        match self {
+            FrameFormat::Standard => false,
+            FrameFormat::NineP => true,
+            FrameFormat::Custom { inclusive, .. } => inclusive,
+        }
+    }
+
+    // the prefix of a message of length bytes
+    fn encode(self, length: usize) -> std::io::Result<[u8; FRAME_HEADER_SIZE]> {
+        let size = if self.inclusive() {
+            length.checked_add(FRAME_HEADER_SIZE)
+        } else {
+            Some(length)
+        };
+        let prefix = size
+            .and_then(|size| u32::try_from(size).ok())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("frame of {} bytes is too long", length),
+                )
+            })?;
+
+        Ok(match self.byte_order() {
+            ByteOrder::Big => prefix.to_be_bytes(),
+            ByteOrder::Little => prefix.to_le_bytes(),
+        })
+    }
+
+    // the length of the message following the prefix
+    pub(crate) fn decode(self, prefix: [u8; FRAME_HEADER_SIZE]) -> std::io::Result<usize> {
+        let size = match self.byte_order() {
+            ByteOrder::Big => u32::from_be_bytes(prefix),
+            ByteOrder::Little => u32::from_le_bytes(prefix),
+        } as usize;
+
+        if !self.inclusive() {
+            return Ok(size);
+        }
+        size.checked_sub(FRAME_HEADER_SIZE).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("frame size {} shorter than its length prefix", size),
+            )
+        })
+    }
+}
+
+/// Serializes the message as a frame, and returns the number of bytes written, including the length prefix.
+pub fn write_frame<W: Write, T: ToNetworkOrder>(writer: &mut W, msg: &T) -> std::io::Result<usize> {
+    write_frame_as(writer, msg, FrameFormat::Standard)
+}
+
+// write_frame() with any length prefix
+fn write_frame_as<W: Write, T: ToNetworkOrder>(
+    writer: &mut W,
+    msg: &T,
+    format: FrameFormat,
+) -> std::io::Result<usize> {
+    // the length is only known once serialized, and the message starts after it
+    let mut buffer = vec![0u8; FRAME_HEADER_SIZE];
+    let length = crate::align::with_origin(FRAME_HEADER_SIZE, || msg.serialize_to(&mut buffer))?;
+    buffer[..FRAME_HEADER_SIZE].copy_from_slice(&format.encode(length)?);
+
+    writer.write_all(&buffer)?;
+    Ok(buffer.len())
+}
+
+/// Reads a frame, and returns the message bytes without the length prefix.
+pub fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let length = read_prefix(reader, FrameFormat::Standard)?;
+    read_body(reader, length)
+}
+
+/// Serializes the message as a frame whose length prefix is encoded as ```L```, and returns the number of bytes
+/// written, including the prefix.
+pub fn write_frame_with<L, W, T>(writer: &mut W, msg: &T) -> std::io::Result<usize>
+where
+    L: WireLen,
+    W: Write,
+    T: ToNetworkOrder + ?Sized,
+{
+    // the length is only known once serialized
+    let mut buffer = Vec::new();
+    let length = crate::align::with_origin(0, || msg.serialize_to(&mut buffer))?;
+    let prefix = L::write_len_to(length, writer)?;
+
+    writer.write_all(&buffer)?;
+    Ok(prefix + length)
+}
+
+/// Reads a frame whose length prefix is encoded as ```L```, and returns the message bytes without the prefix. Frames
+/// longer than ```max``` bytes are rejected with an ```InvalidData``` error before being read.
+pub fn read_frame_with<L: WireLen, R: Read>(
+    reader: &mut R,
+    max: usize,
+) -> std::io::Result<Vec<u8>> {
+    let length = L::read_len_from(reader)?;
+    if length > max {
+        return Err(frame_too_long(length, max));
+    }
+    read_body(reader, length)
+}
+
+// the length of the next frame
+fn read_prefix<R: Read>(reader: &mut R, format: FrameFormat) -> std::io::Result<usize> {
+    let mut prefix = [0u8; FRAME_HEADER_SIZE];
+    reader.read_exact(&mut prefix)?;
+    format.decode(prefix)
+}
+
+// the bytes of a frame whose prefix is read
+fn read_body<R: Read>(reader: &mut R, length: usize) -> std::io::Result<Vec<u8>> {
+    let mut frame = Vec::new();
+    reader.take(length as u64).read_to_end(&mut frame)?;
+
+    if frame.len() != length {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "frame of {} bytes truncated to {} bytes",
+                length,
+                frame.len()
+            ),
+        ));
+    }
+
+    Ok(frame)
+}
+
+// the error returned for a frame longer than the maximum size
+pub(crate) fn frame_too_long(length: usize, max: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "frame of {} bytes exceeds the maximum of {} bytes",
+            length, max
+        ),
+    )
+}
+
+// the maximum size of the frames read, and whether longer frames are skipped or rejected
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct FrameLimit {
+    pub(crate) max: Option<usize>,
+    pub(crate) skip: bool,
+}
+
+impl FrameLimit {
+    // true if a frame of length bytes is read, false if it's skipped
+    pub(crate) fn accept(&self, length: usize) -> std::io::Result<bool> {
+        match self.max {
+            Some(max) if length > max && self.skip => Ok(false),
+            Some(max) if length > max => Err(frame_too_long(length, max)),
+            _ => Ok(true),
+        }
+    }
+}
+
+/// Deserializes a message from the bytes of a frame. An error is returned if the message doesn't use
+/// all the bytes of the frame.
+pub fn decode_frame<T>(frame: &[u8]) -> std::io::Result<T>
+where
+    T: for<'a> FromNetworkOrder<'a> + Default,
+{
+    let mut buffer = Cursor::new(frame);
+    let msg = T::deserialize_new(&mut buffer)?;
+
+    let left = frame.len() - buffer.position() as usize;
+    if left != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} bytes left in frame after the message", left),
+        ));
+    }
+
+    Ok(msg)
+}
+
+/// Writes messages as frames to the inner writer.
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    inner: W,
+    format: FrameFormat,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Creates a frame writer on top of ```inner```.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            format: FrameFormat::default(),
+        }
+    }
+
+    /// Encodes the length prefix as defined by ```format```.
+    pub fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Writes the message as a frame, and returns the number of bytes written.
+    pub fn write<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize> {
+        write_frame_as(&mut self.inner, msg, self.format)
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads frames from the inner reader.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    inner: R,
+    format: FrameFormat,
+    limit: FrameLimit,
+
+    // bytes of the frames read or skipped
+    position: u64,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Creates a frame reader on top of ```inner```.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            format: FrameFormat::default(),
+            limit: FrameLimit::default(),
+            position: 0,
+        }
+    }
+
+    /// Decodes the length prefix as defined by ```format```.
+    pub fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Rejects the frames longer than ```max``` bytes with an ```InvalidData``` error, before reading them.
+    pub fn with_max_frame_size(mut self, max: usize) -> Self {
+        self.limit.max = Some(max);
+        self
+    }
+
+    /// Skips the frames longer than the maximum size instead of rejecting them: their bytes are read and dropped,
+    /// and the next frame is returned. The frames skipped are reported to the observer (see [`crate::observer`]),
+    /// with their offset in the stream.
+    pub fn skip_oversized(mut self) -> Self {
+        self.limit.skip = true;
+        self
+    }
+
+    /// Reads a frame and returns its bytes, without the length prefix.
+    pub fn read_frame(&mut self) -> std::io::Result<Vec<u8>> {
+        loop {
+            let length = read_prefix(&mut self.inner, self.format)?;
+            self.position += FRAME_HEADER_SIZE as u64;
+
+            if self.limit.accept(length)? {
+                let frame = read_body(&mut self.inner, length)?;
+                self.position += length as u64;
+                return Ok(frame);
+            }
+
+            crate::observer::report(self.position, length, "FrameReader");
+            self.position += length as u64;
+
+            let skipped = std::io::copy(
+                &mut (&mut self.inner).take(length as u64),
+                &mut std::io::sink(),
+            )?;
+            if skipped != length as u64 {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "end of file in a frame skipped",
+                ));
+            }
+        }
+    }
+
+    /// Reads a frame and deserializes the message it contains.
+    pub fn read<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        decode_frame(&self.read_frame()?)
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Decodes the messages of frames received in chunks of any size, without doing any I/O. The bytes are buffered
+/// until a frame is complete.
+///
+/// With a maximum frame size, a corrupted length prefix can't make the decoder buffer an unbounded number of bytes:
+/// the frame is rejected, and the bytes buffered are dropped as the stream can't be decoded anymore. With
+/// ```skip_oversized()```, the bytes of the frame are dropped as they arrive instead, and the next frame is decoded.
+#[derive(Debug)]
+pub struct Decoder<T> {
+    // bytes received, those from start not decoded yet and starting with a length prefix
+    buffer: Scratch,
+    start: usize,
+
+    format: FrameFormat,
+    limit: FrameLimit,
+
+    // bytes of a skipped frame not received yet
+    skipping: usize,
+
+    // bytes consumed since the decoder was created
+    consumed: u64,
+
+    message: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Decoder<T> {
+    fn default() -> Self {
+        Self {
+            buffer: Scratch(Vec::new()),
+            start: 0,
+            format: FrameFormat::default(),
+            limit: FrameLimit::default(),
+            skipping: 0,
+            consumed: 0,
+            message: PhantomData,
+        }
+    }
+}
+
+impl<T> Decoder<T>
+where
+    T: for<'a> FromNetworkOrder<'a> + Default,
+{
+    /// Creates a decoder without any byte buffered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the length prefix as defined by ```format```.
+    pub fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Rejects the frames longer than ```max``` bytes with an ```InvalidData``` error, as soon as their prefix is
+    /// received.
+    pub fn with_max_frame_size(mut self, max: usize) -> Self {
+        self.limit.max = Some(max);
+        self
+    }
+
+    /// Skips the frames longer than the maximum size instead of rejecting them. The frames skipped are reported to
+    /// the observer (see [`crate::observer`]), with their offset in the bytes fed.
+    pub fn skip_oversized(mut self) -> Self {
+        self.limit.skip = true;
+        self
+    }
+
+    /// Buffers the bytes received, and returns the next message if its frame is complete. As a chunk can end
+    /// several frames, the messages left are returned by ```decode()```.
+    pub fn feed(&mut self, bytes: &[u8]) -> std::io::Result<Option<T>> {
+        self.buffer.extend_from_slice(bytes);
+        self.decode()
+    }
+
+    /// Returns the next message if its frame is complete in the bytes already buffered. The frame is consumed even
+    /// if the message can't be deserialized, so the next frames can still be decoded.
+    pub fn decode(&mut self) -> std::io::Result<Option<T>> {
+        let msg = self.next_message();
+
+        // the bytes consumed are dropped at once, when they're at least half of those buffered so a chunk of
+        // small frames isn't moved for each of them
+        if self.start * 2 >= self.buffer.len() {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        msg
+    }
+
+    // decodes the next frame, the oversized ones being skipped in turn
+    fn next_message(&mut self) -> std::io::Result<Option<T>> {
+        loop {
+            // the bytes of a skipped frame are dropped as they arrive
+            if self.skipping > 0 {
+                let n = self.skipping.min(self.buffered());
+                self.consume(n);
+                self.skipping -= n;
+                if self.skipping > 0 {
+                    return Ok(None);
+                }
+            }
+
+            // the stream can't be decoded anymore after an invalid prefix
+            let length = match self.frame_length() {
+                None => return Ok(None),
+                Some(Ok(length)) => length,
+                Some(Err(e)) => {
+                    self.consume(self.buffered());
+                    return Err(e);
+                }
+            };
+
+            match self.limit.accept(length) {
+                Ok(true) => (),
+                Ok(false) => {
+                    self.consume(FRAME_HEADER_SIZE);
+                    crate::observer::report(self.consumed, length, std::any::type_name::<T>());
+                    self.skipping = length;
+                    continue;
+                }
+                Err(e) => {
+                    self.consume(self.buffered());
+                    return Err(e);
+                }
+            }
+
+            let end = FRAME_HEADER_SIZE + length;
+            if self.buffered() < end {
+                return Ok(None);
+            }
+
+            let msg = decode_frame(&self.pending()[FRAME_HEADER_SIZE..end]);
+            self.consume(end);
+
+            return msg.map(Some);
+        }
+    }
+
+    /// Returns the number of bytes still needed to complete the length prefix, or the frame once the prefix is
+    /// known. It's 0 if a complete frame is buffered.
+    pub fn needed(&self) -> usize {
+        if self.skipping > 0 {
+            return self.skipping + FRAME_HEADER_SIZE;
+        }
+
+        let expected = match self.frame_length() {
+            Some(Ok(length)) => FRAME_HEADER_SIZE + length,
+            _ => FRAME_HEADER_SIZE,
+        };
+        expected.saturating_sub(self.buffered())
+    }
+
+    /// Returns the number of bytes buffered and not decoded yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    // the bytes buffered and not decoded yet
+    fn pending(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    // consumes the first n bytes not decoded yet, which are wiped and dropped by decode()
+    fn consume(&mut self, n: usize) {
+        wipe_bytes(&mut self.buffer[self.start..self.start + n]);
+        self.start += n;
+        self.consumed += n as u64;
+    }
+
+    // the length of the next frame, once its prefix is buffered
+    fn frame_length(&self) -> Option<std::io::Result<usize>> {
+        let prefix: [u8; FRAME_HEADER_SIZE] =
+            self.pending().get(..FRAME_HEADER_SIZE)?.try_into().ok()?;
+        Some(self.format.decode(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framing() {
+        let mut buffer = Vec::new();
+        assert_eq!(write_frame(&mut buffer, &0x12345678_u32).unwrap(), 8);
+        assert_eq!(write_frame(&mut buffer, &Vec::<u8>::new()).unwrap(), 4);
+        assert_eq!(buffer, &[0, 0, 0, 4, 0x12, 0x34, 0x56, 0x78, 0, 0, 0, 0]);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(read_frame(&mut cursor).unwrap(), &[0x12, 0x34, 0x56, 0x78]);
+        assert!(read_frame(&mut cursor).unwrap().is_empty());
+        assert_eq!(
+            read_frame(&mut cursor).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        // truncated frame
+        let mut cursor = Cursor::new([0u8, 0, 0, 3, 1, 2].as_slice());
+        assert!(read_frame(&mut cursor).is_err());
+
+        // bytes left after the message
+        assert_eq!(decode_frame::<u16>(&[0x12, 0x34]).unwrap(), 0x1234);
+        assert!(decode_frame::<u16>(&[0x12, 0x34, 0x56]).is_err());
+        assert!(decode_frame::<u16>(&[0x12]).is_err());
+    }
+
+    #[test]
+    fn decoder() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &0x12345678_u32).unwrap();
+        write_frame(&mut buffer, &0x1234_u16).unwrap();
+        write_frame(&mut buffer, &0x9ABCDEF0_u32).unwrap();
+
+        // byte by byte
+        let mut decoder = Decoder::<u32>::new();
+        let mut messages = Vec::new();
+        for b in &buffer {
+            if let Ok(Some(msg)) = decoder.feed(&[*b]) {
+                messages.push(msg);
+            }
+        }
+        assert_eq!(messages, &[0x12345678, 0x9ABCDEF0]);
+        assert_eq!(decoder.buffered(), 0);
+
+        // all at once: the frame too short for a u32 is an error, but the next one is decoded
+        let mut decoder = Decoder::<u32>::new();
+        assert_eq!(decoder.feed(&buffer).unwrap(), Some(0x12345678));
+        assert!(decoder.decode().is_err());
+        assert_eq!(decoder.decode().unwrap(), Some(0x9ABCDEF0));
+        assert_eq!(decoder.decode().unwrap(), None);
+        assert_eq!(decoder.needed(), FRAME_HEADER_SIZE);
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &0x1234_u16).unwrap();
+        write_frame(&mut buffer, &vec![0u8; 100]).unwrap();
+        write_frame(&mut buffer, &0x5678_u16).unwrap();
+
+        // rejected
+        let mut reader = FrameReader::new(Cursor::new(buffer.as_slice())).with_max_frame_size(10);
+        assert_eq!(reader.read::<u16>().unwrap(), 0x1234);
+        assert_eq!(
+            reader.read_frame().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        let mut decoder = Decoder::<u16>::new().with_max_frame_size(10);
+        assert_eq!(decoder.feed(&buffer[..10]).unwrap(), Some(0x1234));
+        assert!(decoder.decode().is_err());
+        assert_eq!(decoder.buffered(), 0);
+
+        // skipped
+        let mut reader = FrameReader::new(Cursor::new(buffer.as_slice()))
+            .with_max_frame_size(10)
+            .skip_oversized();
+        assert_eq!(reader.read::<u16>().unwrap(), 0x1234);
+        assert_eq!(reader.read::<u16>().unwrap(), 0x5678);
+
+        let mut decoder = Decoder::<u16>::new()
+            .with_max_frame_size(10)
+            .skip_oversized();
+        let mut messages = Vec::new();
+        for chunk in buffer.chunks(7) {
+            messages.extend(decoder.feed(chunk).unwrap());
+            assert!(decoder.buffered() <= 10);
+        }
+        assert_eq!(messages, &[0x1234, 0x5678]);
+
+        // the frames skipped are reported
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let observer = {
+            let seen = seen.clone();
+            move |ignored: &crate::observer::Ignored| {
+                seen.borrow_mut().push((ignored.offset, ignored.length))
+            }
+        };
+        crate::observer::with_observer(observer, || {
+            let mut reader = FrameReader::new(Cursor::new(buffer.as_slice()))
+                .with_max_frame_size(10)
+                .skip_oversized();
+            assert_eq!(reader.read::<u16>().unwrap(), 0x1234);
+            assert_eq!(reader.read::<u16>().unwrap(), 0x5678);
+
+            let mut decoder = Decoder::<u16>::new()
+                .with_max_frame_size(10)
+                .skip_oversized();
+            for chunk in buffer.chunks(7) {
+                decoder.feed(chunk).unwrap();
+            }
+        });
+        assert_eq!(*seen.borrow(), &[(10, 100), (10, 100)]);
+
+        // many frames skipped from a single chunk, without recursing
+        let frames = [0u8, 0, 0, 1, 0xFF].repeat(2_000_000);
+        let mut decoder = Decoder::<Vec<u8>>::new()
+            .with_max_frame_size(0)
+            .skip_oversized();
+        assert_eq!(decoder.feed(&frames).unwrap(), None);
+        assert_eq!(decoder.buffered(), 0);
+        assert_eq!(decoder.feed(&[0, 0, 0, 0]).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn nine_p() {
+        let msg = (100u8, 0x2000u32);
+        let mut writer = FrameWriter::new(Vec::new()).with_format(FrameFormat::NineP);
+        assert_eq!(writer.write(&msg).unwrap(), 9);
+        let bytes = writer.into_inner();
+        assert_eq!(bytes, &[9, 0, 0, 0, 100, 0, 0, 0x20, 0]);
+
+        let mut reader =
+            FrameReader::new(Cursor::new(bytes.as_slice())).with_format(FrameFormat::NineP);
+        assert_eq!(reader.read::<(u8, u32)>().unwrap(), msg);
+
+        let mut decoder = Decoder::<(u8, u32)>::new().with_format(FrameFormat::NineP);
+        assert_eq!(decoder.feed(&bytes[..4]).unwrap(), None);
+        assert_eq!(decoder.needed(), 5);
+        assert_eq!(decoder.feed(&bytes[4..]).unwrap(), Some(msg));
+
+        // a size shorter than the prefix itself
+        let mut decoder = Decoder::<u8>::new().with_format(FrameFormat::NineP);
+        let e = decoder.feed(&[3, 0, 0, 0, 1]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(decoder.buffered(), 0);
+
+        let format = FrameFormat::Custom {
+            byte_order: ByteOrder::Big,
+            inclusive: true,
+        };
+        let mut writer = FrameWriter::new(Vec::new()).with_format(format);
+        writer.write(&1u8).unwrap();
+        assert_eq!(writer.get_ref(), &[0, 0, 0, 5, 1]);
+        assert_eq!(format.decode([0, 0, 0, 4]).unwrap(), 0);
+        assert!(FrameFormat::NineP.decode([3, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn wire_len() {
+        use crate::len::Varint;
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            write_frame_with::<u16, _, _>(&mut buffer, &0x1234_u16).unwrap(),
+            4
+        );
+        assert_eq!(
+            write_frame_with::<Varint, _, _>(&mut buffer, &vec![0u8; 200]).unwrap(),
+            202
+        );
+        assert_eq!(&buffer[..6], &[0, 2, 0x12, 0x34, 0xC8, 0x01]);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(
+            read_frame_with::<u16, _>(&mut cursor, 10).unwrap(),
+            &[0x12, 0x34]
+        );
+        assert_eq!(
+            read_frame_with::<Varint, _>(&mut cursor, 200)
+                .unwrap()
+                .len(),
+            200
+        );
+        assert_eq!(
+            read_frame_with::<Varint, _>(&mut cursor, 200)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        // the frame is rejected once its prefix is read
+        let mut cursor = Cursor::new(&buffer[4..]);
+        let e = read_frame_with::<Varint, _>(&mut cursor, 100).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(cursor.position(), 2);
+    }
+}
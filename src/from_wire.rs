@@ -0,0 +1,148 @@
+//! Borrowed or owned deserialization of the same message definition, selected by the caller.
+//!
+//! A message generic over a ```Mode``` uses the types of this mode for its variable length fields. With ```Borrow```,
+//! these fields borrow their bytes from the buffer (zero-copy), and the message can't outlive it. With ```Own```,
+//! the bytes are copied and the message is independent of the buffer. The ```FromWire``` trait expresses both
+//! cases with its ```Out<'a>``` output type, which depends or not on the buffer lifetime ```'a```:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::FromNetworkOrder;
+//! use type2network::from_wire::{Borrow, FromWire, Mode, Own};
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Message<'a, M: Mode> {
+//!     id: u16,
+//!     name: M::Bytes<'a, u8>,
+//! }
+//!
+//! // the output borrows from the buffer
+//! impl FromWire for Message<'static, Borrow> {
+//!     type Out<'a> = Message<'a, Borrow>;
+//!
+//!     fn from_wire<'a>(buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<Self::Out<'a>> {
+//!         Message::deserialize_new(buffer)
+//!     }
+//! }
+//!
+//! // the output doesn't depend on the buffer lifetime: the fields are moved to a 'static message
+//! impl FromWire for Message<'static, Own> {
+//!     type Out<'a> = Message<'static, Own>;
+//!
+//!     fn from_wire(buffer: &mut Cursor<&[u8]>) -> std::io::Result<Self::Out<'static>> {
+//!         let msg = Message::<Own>::deserialize_new(buffer)?;
+//!         Ok(Message { id: msg.id, name: msg.name })
+//!     }
+//! }
+//!
+//! let bytes = vec![0x12, 0x34, 5, b'h', b'e', b'l', b'l', b'o'];
+//!
+//! // the name points into bytes
+//! let borrowed = Message::<Borrow>::from_bytes(&bytes).unwrap();
+//! assert_eq!(borrowed.name.as_bytes(), b"hello");
+//!
+//! // the name is copied
+//! let owned = Message::<Own>::from_bytes(&bytes).unwrap();
+//! drop(bytes);
+//! assert_eq!(owned.name.as_slice(), b"hello");
+//! ```
+use std::fmt;
+use std::io::Cursor;
+
+use crate::len::WireLen;
+use crate::opaque::{Opaque, OpaqueRef};
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A type deserialized into ```Out<'a>```, which can borrow from the buffer for ```'a```.
+pub trait FromWire {
+    /// The type deserialized from a buffer living for ```'a```.
+    type Out<'a>;
+
+    /// Deserializes a value from the buffer.
+    fn from_wire<'a>(buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<Self::Out<'a>>;
+
+    /// Deserializes a value from the start of ```bytes```.
+    fn from_bytes(bytes: &[u8]) -> std::io::Result<Self::Out<'_>> {
+        Self::from_wire(&mut Cursor::new(bytes))
+    }
+}
+
+/// Selects the types of the variable length fields of a message: borrowed with ```Borrow```, owned with ```Own```.
+/// Both have the same wire format. The ```Default``` and ```Debug``` supertraits allow messages generic over a mode
+/// to derive these traits.
+pub trait Mode: Default + fmt::Debug {
+    /// Bytes preceded by their length, encoded as defined by ```L```.
+    type Bytes<'a, L: WireLen + 'a>: FromNetworkOrder<'a>
+        + ToNetworkOrder
+        + Default
+        + AsRef<[u8]>
+        + fmt::Debug;
+}
+
+/// Variable length fields borrow their bytes from the buffer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Borrow;
+
+impl Mode for Borrow {
+    type Bytes<'a, L: WireLen + 'a> = OpaqueRef<'a, L>;
+}
+
+/// Variable length fields own their bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Own;
+
+impl Mode for Own {
+    type Bytes<'a, L: WireLen + 'a> = Opaque<L>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // without the derive, to check the trait alone
+    #[derive(Debug, Default)]
+    struct Name<'a, M: Mode>(M::Bytes<'a, u16>);
+
+    impl FromWire for Name<'static, Borrow> {
+        type Out<'a> = Name<'a, Borrow>;
+
+        fn from_wire<'a>(buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<Self::Out<'a>> {
+            let mut bytes = OpaqueRef::default();
+            bytes.deserialize_from(buffer)?;
+            Ok(Name(bytes))
+        }
+    }
+
+    impl FromWire for Name<'static, Own> {
+        type Out<'a> = Name<'static, Own>;
+
+        fn from_wire(buffer: &mut Cursor<&[u8]>) -> std::io::Result<Self::Out<'static>> {
+            let mut bytes = Opaque::default();
+            bytes.deserialize_from(buffer)?;
+            Ok(Name(bytes))
+        }
+    }
+
+    #[test]
+    fn from_wire() {
+        let b = vec![0, 2, b'a', b'b', b'c'];
+
+        let mut buffer = Cursor::new(b.as_slice());
+        let borrowed = Name::<Borrow>::from_wire(&mut buffer).unwrap();
+        assert_eq!(borrowed.0.as_bytes(), b"ab");
+        assert_eq!(buffer.position(), 4);
+
+        let owned = Name::<Own>::from_bytes(&b.clone()).unwrap();
+        assert_eq!(owned.0.as_ref(), borrowed.0.as_ref());
+
+        // same wire format
+        let mut v1 = Vec::new();
+        let mut v2 = Vec::new();
+        borrowed.0.serialize_to(&mut v1).unwrap();
+        owned.0.serialize_to(&mut v2).unwrap();
+        assert_eq!(v1, v2);
+
+        assert!(Name::<Borrow>::from_bytes(&b[..3]).is_err());
+    }
+}
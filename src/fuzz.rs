@@ -0,0 +1,175 @@
+//! Dictionaries for AFL and libFuzzer, built from the definitions of the messages, so the fuzzers find the magic
+//! values and discriminants of a protocol without guessing them.
+//!
+//! The ```#[network(fuzz)]``` container attribute of ```FromNetwork``` implements ```FuzzTokens```, whose tokens are
+//! the bytes of the ```#[network(magic = value)]``` and ```#[network(expect = value)]``` fields of a struct, or of
+//! the discriminants of an enum (except the fallback variant). A ```Dictionary``` collects the tokens of the
+//! messages, along with typical lengths, and gives the dictionary file:
+//!
+//! ```
+//! use byteorder::ReadBytesExt;
+//! use type2network::fuzz::Dictionary;
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! #[network(fuzz)]
+//! struct Header {
+//!     #[network(magic = 0xCAFE)]
+//!     magic: u16,
+//!     kind: u8,
+//! }
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! #[network(fuzz, derive_tryfrom)]
+//! #[repr(u8)]
+//! enum Kind {
+//!     #[default]
+//!     Hello = 1,
+//!     Bye = 0x22,
+//! }
+//!
+//! let dict = Dictionary::new().add::<Header>().add::<Kind>();
+//! assert_eq!(
+//!     dict.to_string(),
+//!     "Header_magic=\"\\xCA\\xFE\"\nKind_Hello=\"\\x01\"\nKind_Bye=\"\\x22\"\n"
+//! );
+//! ```
+//!
+//! The file is usually written by a test or an example run when the messages change, e.g.
+//! ```dict.write_to(std::fs::File::create("fuzz/protocol.dict")?)```, and given to the fuzzer
+//! (```-dict=fuzz/protocol.dict``` for libFuzzer and cargo-fuzz, ```-x fuzz/protocol.dict``` for AFL).
+use std::fmt;
+use std::io::Write;
+
+use crate::ToNetworkOrder;
+
+/// Types giving the tokens of a fuzzing dictionary, implemented by the ```#[network(fuzz)]``` attribute.
+pub trait FuzzTokens {
+    /// Returns the tokens, with their names (letters, digits and ```_```).
+    fn fuzz_tokens() -> Vec<(&'static str, Vec<u8>)>;
+}
+
+/// Returns the bytes of a value, or no bytes if it can't be serialized. It's called by the derived code.
+pub fn token<T: ToNetworkOrder>(value: T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if value.serialize_to(&mut bytes).is_err() {
+        bytes.clear();
+    }
+    bytes
+}
+
+/// The entries of an AFL or libFuzzer dictionary.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl Dictionary {
+    /// Creates an empty dictionary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the tokens of ```T```.
+    pub fn add<T: FuzzTokens>(mut self) -> Self {
+        for (name, bytes) in T::fuzz_tokens() {
+            self.push(name.to_string(), bytes);
+        }
+        self
+    }
+
+    /// Adds a token. The bytes already in the dictionary, or empty, are not added again.
+    pub fn token(mut self, name: &str, bytes: &[u8]) -> Self {
+        self.push(name.to_string(), bytes.to_vec());
+        self
+    }
+
+    /// Adds typical lengths as big-endian ```u8```, ```u16``` and ```u32```: 0, 1 and the values around the limits of
+    /// the smaller integers, which often reveal truncation or overflow bugs.
+    pub fn with_lengths(mut self) -> Self {
+        for length in [0u32, 1, 0x7F, 0x80, 0xFF] {
+            self.push(format!("length_u8_{}", length), vec![length as u8]);
+        }
+        for length in [0u32, 1, 0xFF, 0x100, 0x7FFF, 0x8000, 0xFFFF] {
+            self.push(
+                format!("length_u16_{}", length),
+                (length as u16).to_be_bytes().to_vec(),
+            );
+        }
+        for length in [0u32, 1, 0xFFFF, 0x1_0000, 0x7FFF_FFFF, 0xFFFF_FFFF] {
+            self.push(
+                format!("length_u32_{}", length),
+                length.to_be_bytes().to_vec(),
+            );
+        }
+        self
+    }
+
+    /// Returns the entries, with their names.
+    pub fn entries(&self) -> &[(String, Vec<u8>)] {
+        &self.entries
+    }
+
+    /// Writes the dictionary file.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        write!(writer, "{}", self)
+    }
+
+    fn push(&mut self, name: String, bytes: Vec<u8>) {
+        if !bytes.is_empty() && !self.entries.iter().any(|(_, b)| *b == bytes) {
+            self.entries.push((name, bytes));
+        }
+    }
+}
+
+/// The dictionary file: one ```name="bytes"``` line per entry, the bytes being escaped as ```\xNN``` unless they're
+/// printable ASCII.
+impl fmt::Display for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, bytes) in &self.entries {
+            write!(f, "{}=\"", name)?;
+            for b in bytes {
+                match b {
+                    b'"' | b'\\' => write!(f, "\\x{:02X}", b)?,
+                    0x20..=0x7E => write!(f, "{}", *b as char)?,
+                    _ => write!(f, "\\x{:02X}", b)?,
+                }
+            }
+            writeln!(f, "\"")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tokens;
+
+    impl FuzzTokens for Tokens {
+        fn fuzz_tokens() -> Vec<(&'static str, Vec<u8>)> {
+            vec![("magic", token(0x4142_u16)), ("quote", token(b'"'))]
+        }
+    }
+
+    #[test]
+    fn dictionary() {
+        assert_eq!(token(0x0102_u16), &[1, 2]);
+
+        let dict = Dictionary::new()
+            .add::<Tokens>()
+            .token("again", b"AB")
+            .token("empty", &[]);
+        assert_eq!(dict.to_string(), "magic=\"AB\"\nquote=\"\\x22\"\n");
+
+        let dict = Dictionary::new().with_lengths();
+        assert_eq!(dict.entries().len(), 18);
+        assert_eq!(dict.entries()[0], ("length_u8_0".to_string(), vec![0]));
+
+        let mut file = Vec::new();
+        dict.write_to(&mut file).unwrap();
+        assert!(file.starts_with(b"length_u8_0=\"\\x00\"\n"));
+    }
+}
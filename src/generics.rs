@@ -26,6 +26,13 @@ impl<T: ToNetworkOrder> ToNetworkOrder for Option<T> {
             self.as_ref().unwrap().serialize_to(buffer)
         }
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        match self {
+            Some(value) => value.write_to(writer),
+            None => Ok(0),
+        }
+    }
 }
 
 impl<'a, T: FromNetworkOrder<'a>> FromNetworkOrder<'a> for Option<T> {
@@ -79,6 +86,10 @@ impl<T: ToNetworkOrder, const N: usize> ToNetworkOrder for [T; N] {
 
         Ok(length)
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        crate::size::write_items(self, writer)
+    }
 }
 
 impl<'a, T: FromNetworkOrder<'a>, const N: usize> FromNetworkOrder<'a> for [T; N] {
@@ -130,41 +141,54 @@ where
 
         Ok(length)
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        crate::size::write_items(self, writer)
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for Vec<T>
 where
     T: Default + FromNetworkOrder<'a>,
 {
-    /// To be able to use this method on a vector, should be defined with the [`std::vec::Vec::with_capacity`]
-    /// method to be usable.
+    /// With the ```vec-capacity``` feature (enabled by default but deprecated), the vector should be defined
+    /// with the [`std::vec::Vec::with_capacity`] method: ```capacity()``` elements are read.
     ///
-    /// # Example    
+    /// Without this feature, the elements already in the vector are deserialized in place, as for arrays.
+    /// To select how the number of elements is known, use the ```#[network(count = "expr")]``` field
+    /// attribute, or the [`crate::vec::CountPrefixed`] or [`crate::vec::UntilEof`] wrappers.
+    ///
+    /// # Example
     /// ```
     /// use std::io::Cursor;
     /// use type2network::FromNetworkOrder;
     ///
     /// let b = vec![0x12, 0x34, 0x56, 0x78];
     /// let mut buffer = Cursor::new(b.as_slice());
+    /// # #[cfg(feature = "vec-capacity")]
     /// let mut v: Vec<u16> = Vec::<u16>::with_capacity(2);
+    /// # #[cfg(not(feature = "vec-capacity"))]
+    /// # let mut v: Vec<u16> = vec![0; 2];
     /// assert!(v.deserialize_from(&mut buffer).is_ok());
     /// assert_eq!(v, &[0x1234_u16, 0x5678]);
     /// ```
     fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
-        // println!("inside Vec, buffer={:?}", buffer);
-        // for item in self {
-        //     item.deserialize_from(buffer)?;
-        // }
-        // Ok(())
-
         // the vector should be defined with the with_capacity() method to be usable with this
-        let length = self.capacity();
+        #[cfg(feature = "vec-capacity")]
+        {
+            let length = self.capacity();
+            crate::budget::take(length)?;
+
+            for _ in 0..length {
+                self.push(T::deserialize_new(buffer)?);
+            }
+        }
 
-        for _ in 0..length {
-            let mut u: T = T::default();
-            u.deserialize_from(buffer)?;
-            self.push(u);
+        #[cfg(not(feature = "vec-capacity"))]
+        for item in self {
+            item.deserialize_from(buffer)?;
         }
+
         Ok(())
     }
 }
@@ -183,7 +207,7 @@ where
     /// let bytes = Cow::from(b"\xFE\xFF");
     /// assert_eq!(bytes.serialize_to(&mut buffer).unwrap(), 2);
     /// assert_eq!(buffer, &[0xFE, 0xFF]);
-    /// 
+    ///
     /// let mut buffer: Vec<u8> = Vec::new();
     /// let bytes = Cow::from(b"\xFE\xFF".to_vec());
     /// assert_eq!(bytes.serialize_to(&mut buffer).unwrap(), 2);
@@ -196,6 +220,13 @@ where
             Cow::Owned(own) => own.serialize_to(buffer),
         }
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        match self {
+            Cow::Borrowed(bor) => bor.write_to(writer),
+            Cow::Owned(own) => own.write_to(writer),
+        }
+    }
 }
 
 impl<T> ToNetworkOrder for Box<T>
@@ -215,6 +246,10 @@ where
         use std::ops::Deref;
         self.deref().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        self.as_ref().write_to(writer)
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for Box<T>
@@ -253,6 +288,10 @@ impl ToNetworkOrder for Box<dyn ToNetworkOrder> {
         use std::ops::Deref;
         self.deref().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        self.as_ref().write_to(writer)
+    }
 }
 
 impl<'a> FromNetworkOrder<'a> for Box<dyn FromNetworkOrder<'a>> {
@@ -278,6 +317,10 @@ impl<T> ToNetworkOrder for PhantomData<T> {
     fn serialize_to(&self, _: &mut Vec<u8>) -> std::io::Result<usize> {
         Ok(0)
     }
+
+    fn write_to(&self, _: &mut dyn Write) -> std::io::Result<usize> {
+        Ok(0)
+    }
 }
 
 impl<'a, T> FromNetworkOrder<'a> for PhantomData<T> {
@@ -330,7 +373,10 @@ mod tests {
             x: u16,
             y: u16,
         }
+        #[cfg(feature = "vec-capacity")]
         let w = Vec::<Point>::with_capacity(3);
+        #[cfg(not(feature = "vec-capacity"))]
+        let w = (0..3).map(|_| Point::default()).collect();
 
         from_network_test(
             //Some(vec![[Some(0_u16); 2]; 3]),
@@ -0,0 +1,191 @@
+//! Interoperability tests against a C implementation of the same protocol (```interop``` feature), to validate
+//! the Rust definitions of messages against legacy C code.
+//!
+//! The C encoding and decoding functions of a message, usually made available by ```bindgen```, must have the
+//! following signatures:
+//!
+//! ```c
+//! // writes the value to out, returns the number of bytes written or a negative value on error
+//! ssize_t encode_header(const struct header *value, uint8_t *out, size_t out_len);
+//!
+//! // reads the value from bytes, returns the number of bytes read or a negative value on error
+//! ssize_t decode_header(const uint8_t *bytes, size_t len, struct header *value);
+//! ```
+//!
+//! ```assert_interop()``` then checks that the Rust and C values give the same bytes, and that each side reads
+//! back the bytes of the other one:
+//!
+//! ```
+//! use type2network::interop::{assert_interop, CCodec};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Header {
+//!     id: u16,
+//!     flags: u16,
+//! }
+//!
+//! // the struct generated by bindgen
+//! #[allow(non_camel_case_types)]
+//! #[repr(C)]
+//! #[derive(Default)]
+//! struct header {
+//!     id: u16,
+//!     flags: u16,
+//! }
+//!
+//! // stand-ins for the C functions
+//! unsafe extern "C" fn encode_header(value: *const header, out: *mut u8, out_len: usize) -> isize {
+//!     if out_len < 4 {
+//!         return -1;
+//!     }
+//!     let value = &*value;
+//!     let out = std::slice::from_raw_parts_mut(out, 4);
+//!     out[..2].copy_from_slice(&value.id.to_be_bytes());
+//!     out[2..].copy_from_slice(&value.flags.to_be_bytes());
+//!     4
+//! }
+//!
+//! unsafe extern "C" fn decode_header(bytes: *const u8, len: usize, value: *mut header) -> isize {
+//!     if len < 4 {
+//!         return -1;
+//!     }
+//!     let bytes = std::slice::from_raw_parts(bytes, 4);
+//!     (*value).id = u16::from_be_bytes([bytes[0], bytes[1]]);
+//!     (*value).flags = u16::from_be_bytes([bytes[2], bytes[3]]);
+//!     4
+//! }
+//!
+//! // SAFETY: the functions follow the contract of CCodec
+//! let codec = unsafe { CCodec::new(encode_header, decode_header) };
+//! assert_interop(
+//!     &Header { id: 0x1234, flags: 0x8180 },
+//!     &header { id: 0x1234, flags: 0x8180 },
+//!     &codec,
+//! );
+//! ```
+use std::fmt::Debug;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::{convert_from_bytes, convert_to_vec, FromNetworkOrder, ToNetworkOrder};
+
+/// The C function writing a ```C``` value to a buffer: ```ssize_t encode(const C *value, uint8_t *out, size_t out_len)```.
+pub type CEncodeFn<C> =
+    unsafe extern "C" fn(value: *const C, out: *mut u8, out_len: usize) -> isize;
+
+/// The C function reading a ```C``` value from a buffer: ```ssize_t decode(const uint8_t *bytes, size_t len, C *value)```.
+pub type CDecodeFn<C> = unsafe extern "C" fn(bytes: *const u8, len: usize, value: *mut C) -> isize;
+
+/// The C encoding and decoding functions of a message.
+pub struct CCodec<C> {
+    encode: CEncodeFn<C>,
+    decode: CDecodeFn<C>,
+    capacity: usize,
+}
+
+impl<C: Default> CCodec<C> {
+    /// The size of the buffer given to the encoding function, unless set by ```with_capacity()```.
+    pub const DEFAULT_CAPACITY: usize = 65536;
+
+    /// Creates the codec from the C functions.
+    ///
+    /// # Safety
+    ///
+    /// The encoding function must only write to the ```out_len``` bytes of ```out``` and return at most ```out_len```,
+    /// and the decoding function must only read the ```len``` bytes of ```bytes``` and return at most ```len```.
+    /// Both must accept any valid ```C``` value and any bytes.
+    pub unsafe fn new(encode: CEncodeFn<C>, decode: CDecodeFn<C>) -> Self {
+        Self {
+            encode,
+            decode,
+            capacity: Self::DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Sets the size of the buffer given to the encoding function, which should hold the largest message encoded.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Encodes the value with the C function.
+    pub fn encode(&self, value: &C) -> Result<Vec<u8>> {
+        let mut out = vec![0u8; self.capacity];
+
+        // SAFETY: the function writes at most out.len() bytes, as required by new()
+        let written = unsafe { (self.encode)(value, out.as_mut_ptr(), out.len()) };
+        out.truncate(c_length("encoding", written, self.capacity)?);
+        Ok(out)
+    }
+
+    /// Decodes a value with the C function, returning it along with the number of bytes read.
+    pub fn decode(&self, bytes: &[u8]) -> Result<(C, usize)> {
+        let mut value = C::default();
+
+        // SAFETY: the function reads at most bytes.len() bytes, as required by new()
+        let read = unsafe { (self.decode)(bytes.as_ptr(), bytes.len(), &mut value) };
+        let read = c_length("decoding", read, bytes.len())?;
+        Ok((value, read))
+    }
+}
+
+// the value returned by a C function is the number of bytes processed, or an error if negative
+fn c_length(what: &str, ret: isize, max: usize) -> Result<usize> {
+    match usize::try_from(ret) {
+        Ok(length) if length <= max => Ok(length),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("C {} function returned {}", what, ret),
+        )),
+    }
+}
+
+/// Checks that a Rust value and its C counterpart are compatible, and panics otherwise:
+///
+/// * both give the same bytes
+/// * the Rust value is read back from the C bytes
+/// * the C decoding function reads all the Rust bytes, and its value is encoded again with the same bytes
+pub fn assert_interop<T, C>(value: &T, c_value: &C, codec: &CCodec<C>)
+where
+    T: ToNetworkOrder + for<'a> FromNetworkOrder<'a> + Default + PartialEq + Debug,
+    C: Default,
+{
+    let type_name = std::any::type_name::<T>();
+
+    let rust_bytes = convert_to_vec(type_name, value);
+    let c_bytes = codec
+        .encode(c_value)
+        .unwrap_or_else(|e| panic!("unable to encode a {} in C: {}", type_name, e));
+    assert_eq!(
+        rust_bytes, c_bytes,
+        "{}: Rust (left) and C (right) bytes differ",
+        type_name
+    );
+
+    let rust_value: T = convert_from_bytes(type_name, &c_bytes)
+        .unwrap_or_else(|e| panic!("unable to read the C bytes of a {}: {}", type_name, e));
+    assert_eq!(
+        &rust_value, value,
+        "{}: value read from the C bytes differs",
+        type_name
+    );
+
+    let (c_value, read) = codec
+        .decode(&rust_bytes)
+        .unwrap_or_else(|e| panic!("unable to decode the bytes of a {} in C: {}", type_name, e));
+    assert_eq!(
+        read,
+        rust_bytes.len(),
+        "{}: C decoding function didn't read all bytes",
+        type_name
+    );
+    let c_bytes = codec
+        .encode(&c_value)
+        .unwrap_or_else(|e| panic!("unable to encode a {} in C: {}", type_name, e));
+    assert_eq!(
+        rust_bytes, c_bytes,
+        "{}: Rust bytes (left) differ once decoded and encoded in C (right)",
+        type_name
+    );
+}
@@ -0,0 +1,186 @@
+//! A field whose deserialization is deferred until it's accessed.
+//!
+//! When deserialized, a ```Lazy<'a, T>``` only keeps the bytes left in the buffer, so it must be the last
+//! field of a message. The ```T``` value is deserialized from these bytes on first access. This speeds up
+//! servers routing messages based on their headers, which rarely touch the bodies:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::FromNetworkOrder;
+//! use type2network::lazy::Lazy;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Body {
+//!     x: u16,
+//!     y: u16,
+//! }
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Message<'a> {
+//!     route: u8,
+//!     body: Lazy<'a, Body>,
+//! }
+//!
+//! let bytes = [1, 0x12, 0x34, 0x56, 0x78];
+//! let mut msg = Message::default();
+//! msg.deserialize_from(&mut Cursor::new(bytes.as_slice())).unwrap();
+//!
+//! assert_eq!(msg.route, 1);
+//! assert_eq!(msg.body.raw(), &[0x12, 0x34, 0x56, 0x78]);
+//! assert!(!msg.body.is_parsed());
+//!
+//! // deserialized now
+//! assert_eq!(msg.body.get().unwrap().y, 0x5678);
+//! assert!(msg.body.is_parsed());
+//! ```
+use std::cell::OnceCell;
+use std::fmt;
+use std::io::Cursor;
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A value deserialized on first access, from the bytes captured when the field was deserialized.
+pub struct Lazy<'a, T> {
+    // bytes captured when deserialized
+    raw: Option<&'a [u8]>,
+
+    // the value, once deserialized or when given by from_value()
+    value: OnceCell<T>,
+}
+
+impl<'a, T> Lazy<'a, T> {
+    /// Creates a lazy field holding an already known value, e.g. to serialize a message.
+    pub fn from_value(value: T) -> Self {
+        Self {
+            raw: None,
+            value: OnceCell::from(value),
+        }
+    }
+
+    /// Returns the bytes captured when deserialized, or an empty slice.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw.unwrap_or_default()
+    }
+
+    /// True if the value is already deserialized.
+    pub fn is_parsed(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Returns the value, deserializing it on first access. On error, the value will be deserialized
+    /// again on the next access.
+    pub fn get(&self) -> std::io::Result<&T>
+    where
+        T: FromNetworkOrder<'a> + Default,
+    {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let mut buffer = Cursor::new(self.raw());
+        let value = T::deserialize_new(&mut buffer)?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    /// Returns the value, deserializing it if not already done.
+    pub fn into_inner(self) -> std::io::Result<T>
+    where
+        T: FromNetworkOrder<'a> + Default,
+    {
+        match self.value.into_inner() {
+            Some(value) => Ok(value),
+            None => T::deserialize_new(&mut Cursor::new(self.raw.unwrap_or_default())),
+        }
+    }
+}
+
+impl<T> Default for Lazy<'_, T> {
+    fn default() -> Self {
+        Self {
+            raw: None,
+            value: OnceCell::new(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Lazy<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy")
+            .field("raw", &self.raw)
+            .field("value", &self.value.get())
+            .finish()
+    }
+}
+
+/// The captured bytes are written verbatim. Without them, the value given by ```from_value()``` is serialized.
+impl<T: ToNetworkOrder> ToNetworkOrder for Lazy<'_, T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        match (self.raw, self.value.get()) {
+            (Some(raw), _) => {
+                buffer.extend_from_slice(raw);
+                Ok(raw.len())
+            }
+            (None, Some(value)) => value.serialize_to(buffer),
+            (None, None) => Ok(0),
+        }
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        match (self.raw, self.value.get()) {
+            (Some(raw), _) => {
+                writer.write_all(raw)?;
+                Ok(raw.len())
+            }
+            (None, Some(value)) => value.write_to(writer),
+            (None, None) => Ok(0),
+        }
+    }
+}
+
+impl<'a, T> FromNetworkOrder<'a> for Lazy<'a, T> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let data = *buffer.get_ref();
+        let position = (buffer.position() as usize).min(data.len());
+
+        self.raw = Some(&data[position..]);
+        self.value = OnceCell::new();
+        buffer.set_position(data.len() as u64);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lazy() {
+        let b = vec![0xFF, 0x12, 0x34];
+        let mut buffer = Cursor::new(b.as_slice());
+        buffer.set_position(1);
+
+        let mut l: Lazy<u16> = Lazy::default();
+        l.deserialize_from(&mut buffer).unwrap();
+        assert_eq!(buffer.position(), 3);
+        assert_eq!(l.raw(), &[0x12, 0x34]);
+        assert_eq!(*l.get().unwrap(), 0x1234);
+
+        let mut v = Vec::new();
+        assert_eq!(l.serialize_to(&mut v).unwrap(), 2);
+        assert_eq!(v, &[0x12, 0x34]);
+
+        // not enough bytes
+        let mut l: Lazy<u32> = Lazy::default();
+        l.deserialize_from(&mut buffer).unwrap();
+        assert!(l.get().is_err());
+        assert!(!l.is_parsed());
+
+        let l = Lazy::from_value(0xABCD_u16);
+        let mut v = Vec::new();
+        assert_eq!(l.serialize_to(&mut v).unwrap(), 2);
+        assert_eq!(v, &[0xAB, 0xCD]);
+        assert_eq!(l.into_inner().unwrap(), 0xABCD);
+    }
+}
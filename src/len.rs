@@ -0,0 +1,467 @@
+//! Length prefix encodings used by length-prefixed wrappers (e.g. [`crate::vec::CountPrefixed`]), and variable-length
+//! integers.
+//!
+//! The ```WireLen``` trait is implemented for ```u8```, ```u16```, ```u32``` (big-endian), ```Varint```
+//! (LEB128) and ```Fixed<N>``` (a length known in advance, not sent). Custom length encodings can be used by
+//! implementing this trait, including for the prefix of frames (see ```framing::write_frame_with()```).
+//!
+//! # Example
+//! ```
+//! use std::io::{Cursor, Error, ErrorKind};
+//! use type2network::ToNetworkOrder;
+//! use type2network::len::WireLen;
+//! use type2network::vec::CountPrefixed;
+//!
+//! // a length sent as a nibble-swapped byte
+//! struct Nibble;
+//!
+//! impl WireLen for Nibble {
+//!     fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+//!         let len = u8::try_from(len).map_err(|_| Error::new(ErrorKind::InvalidData, "too long"))?;
+//!         buffer.push(len.rotate_left(4));
+//!         Ok(1)
+//!     }
+//!
+//!     fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+//!         let mut len = 0u8;
+//!         std::io::Read::read_exact(buffer, std::slice::from_mut(&mut len))?;
+//!         Ok(len.rotate_left(4) as usize)
+//!     }
+//! }
+//!
+//! let v: CountPrefixed<Nibble, u8> = vec![0; 18].into();
+//! let mut buffer: Vec<u8> = Vec::new();
+//! assert_eq!(v.serialize_to(&mut buffer).unwrap(), 19);
+//! assert_eq!(buffer[0], 0x21);
+//! ```
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+
+use crate::codec;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// How a length (a number of elements or of bytes) is encoded on the wire.
+pub trait WireLen {
+    /// Writes ```len``` to the buffer, and returns the number of bytes written.
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize>;
+
+    /// Reads a length from the buffer.
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize>;
+
+    /// Writes ```len``` to the writer, and returns the number of bytes written. By default, the length is
+    /// written to a buffer first.
+    fn write_len_to(len: usize, writer: &mut dyn Write) -> std::io::Result<usize> {
+        let mut buffer = Vec::new();
+        let length = Self::write_len(len, &mut buffer)?;
+        writer.write_all(&buffer)?;
+        Ok(length)
+    }
+
+    /// Reads a length from the reader, without reading past it (e.g. the length prefix of a frame). By default,
+    /// the bytes are read one at a time until ```read_len()``` can decode them.
+    fn read_len_from(reader: &mut dyn Read) -> std::io::Result<usize> {
+        let mut prefix = Vec::new();
+        loop {
+            match Self::read_len(&mut Cursor::new(prefix.as_slice())) {
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                    if prefix.len() == MAX_PREFIX_SIZE {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("length prefix longer than {} bytes", MAX_PREFIX_SIZE),
+                        ));
+                    }
+
+                    let mut byte = 0u8;
+                    reader.read_exact(std::slice::from_mut(&mut byte))?;
+                    prefix.push(byte);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+// the longest length prefix read by read_len_from()
+const MAX_PREFIX_SIZE: usize = 16;
+
+// error returned when the length doesn't fit in the prefix
+fn too_long(len: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("length {} doesn't fit in the length prefix", len),
+    )
+}
+
+impl WireLen for u8 {
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let len = u8::try_from(len).map_err(|_| too_long(len))?;
+        codec::put_u8(buffer, len);
+        Ok(1)
+    }
+
+    fn write_len_to(len: usize, writer: &mut dyn Write) -> std::io::Result<usize> {
+        u8::try_from(len)
+            .map_err(|_| too_long(len))?
+            .write_to(writer)
+    }
+
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        Ok(codec::get_u8(buffer)? as usize)
+    }
+
+    fn read_len_from(reader: &mut dyn Read) -> std::io::Result<usize> {
+        let mut bytes = [0u8; 1];
+        reader.read_exact(&mut bytes)?;
+        Ok(u8::from_be_bytes(bytes) as usize)
+    }
+}
+
+impl WireLen for u16 {
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let len = u16::try_from(len).map_err(|_| too_long(len))?;
+        codec::put_u16(buffer, len);
+        Ok(2)
+    }
+
+    fn write_len_to(len: usize, writer: &mut dyn Write) -> std::io::Result<usize> {
+        u16::try_from(len)
+            .map_err(|_| too_long(len))?
+            .write_to(writer)
+    }
+
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        Ok(codec::get_u16(buffer)? as usize)
+    }
+
+    fn read_len_from(reader: &mut dyn Read) -> std::io::Result<usize> {
+        let mut bytes = [0u8; 2];
+        reader.read_exact(&mut bytes)?;
+        Ok(u16::from_be_bytes(bytes) as usize)
+    }
+}
+
+impl WireLen for u32 {
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let len = u32::try_from(len).map_err(|_| too_long(len))?;
+        codec::put_u32(buffer, len);
+        Ok(4)
+    }
+
+    fn write_len_to(len: usize, writer: &mut dyn Write) -> std::io::Result<usize> {
+        u32::try_from(len)
+            .map_err(|_| too_long(len))?
+            .write_to(writer)
+    }
+
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        usize::try_from(codec::get_u32(buffer)?)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "length doesn't fit in usize"))
+    }
+
+    fn read_len_from(reader: &mut dyn Read) -> std::io::Result<usize> {
+        let mut bytes = [0u8; 4];
+        reader.read_exact(&mut bytes)?;
+        usize::try_from(u32::from_be_bytes(bytes))
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "length doesn't fit in usize"))
+    }
+}
+
+/// A length known in advance, which is not sent on the wire: serializing fails if the length is not ```N```.
+///
+/// # Example
+/// ```
+/// use type2network::ToNetworkOrder;
+/// use type2network::len::Fixed;
+/// use type2network::opaque::Opaque;
+///
+/// let v: Opaque<Fixed<2>> = vec![1, 2].into();
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 2);
+/// assert_eq!(buffer, &[1, 2]);
+///
+/// let v: Opaque<Fixed<2>> = vec![1, 2, 3].into();
+/// assert!(v.serialize_to(&mut buffer).is_err());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Fixed<const N: usize>;
+
+impl<const N: usize> WireLen for Fixed<N> {
+    fn write_len(len: usize, _buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        if len != N {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("length {} instead of the fixed length {}", len, N),
+            ));
+        }
+        Ok(0)
+    }
+
+    fn write_len_to(len: usize, _writer: &mut dyn Write) -> std::io::Result<usize> {
+        Self::write_len(len, &mut Vec::new())
+    }
+
+    fn read_len(_buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        Ok(N)
+    }
+}
+
+/// An unsigned integer encoded as LEB128: 7 bits per byte, least significant group first, the high bit
+/// being set on all bytes but the last one.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::len::Varint;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(Varint(300).serialize_to(&mut buffer).unwrap(), 2);
+/// assert_eq!(buffer, &[0xAC, 0x02]);
+///
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// let mut v = Varint::default();
+/// assert!(v.deserialize_from(&mut cursor).is_ok());
+/// assert_eq!(v, Varint(300));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Varint(pub u64);
+
+impl Varint {
+    // the bytes of the value, at most 10 for a u64, and their number
+    fn encode(&self) -> ([u8; 10], usize) {
+        let mut bytes = [0u8; 10];
+        let mut value = self.0;
+        let mut length = 0usize;
+
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                bytes[length] = byte;
+                return (bytes, length + 1);
+            }
+            bytes[length] = byte | 0x80;
+            length += 1;
+        }
+    }
+}
+
+impl ToNetworkOrder for Varint {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let (bytes, length) = self.encode();
+        buffer.extend_from_slice(&bytes[..length]);
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        let (bytes, length) = self.encode();
+        writer.write_all(&bytes[..length])?;
+        Ok(length)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Varint {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let mut value = 0u64;
+
+        // at most 10 bytes for a u64
+        for shift in (0..64).step_by(7) {
+            let byte = codec::get_u8(buffer)?;
+            let bits = (byte & 0x7F) as u64;
+
+            // the last byte can only hold the highest bit
+            if shift == 63 && bits > 1 {
+                break;
+            }
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                // a last group of zeros is only sent for the value 0
+                crate::canonical::check(byte != 0 || shift == 0, "overlong varint")?;
+                self.0 = value;
+                return Ok(());
+            }
+        }
+
+        Err(Error::new(ErrorKind::InvalidData, "varint overflows u64"))
+    }
+}
+
+impl WireLen for Varint {
+    fn write_len(len: usize, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        Varint(len as u64).serialize_to(buffer)
+    }
+
+    fn write_len_to(len: usize, writer: &mut dyn Write) -> std::io::Result<usize> {
+        Varint(len as u64).write_to(writer)
+    }
+
+    fn read_len(buffer: &mut Cursor<&[u8]>) -> std::io::Result<usize> {
+        let mut v = Varint::default();
+        v.deserialize_from(buffer)?;
+        usize::try_from(v.0)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "length doesn't fit in usize"))
+    }
+}
+
+/// A signed integer zigzag encoded (0, -1, 1, -2... are mapped to 0, 1, 2, 3...) then sent as a ```Varint```,
+/// so small negative values are as compact as small positive ones.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::len::Zigzag;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(Zigzag(-2i32).serialize_to(&mut buffer).unwrap(), 1);
+/// assert_eq!(Zigzag(150i16).serialize_to(&mut buffer).unwrap(), 2);
+/// assert_eq!(buffer, &[0x03, 0xAC, 0x02]);
+///
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// assert_eq!(Zigzag::<i32>::deserialize_new(&mut cursor).unwrap(), Zigzag(-2));
+/// assert_eq!(Zigzag::<i16>::deserialize_new(&mut cursor).unwrap(), Zigzag(150));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Zigzag<T>(pub T);
+
+// helper macro for boiler plate definitions
+macro_rules! impl_zigzag {
+    ($($t:ty),*) => {
+        $(
+            impl ToNetworkOrder for Zigzag<$t> {
+                fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                    let n = self.0 as i64;
+                    Varint(((n << 1) ^ (n >> 63)) as u64).serialize_to(buffer)
+                }
+
+                fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+                    let n = self.0 as i64;
+                    Varint(((n << 1) ^ (n >> 63)) as u64).write_to(writer)
+                }
+            }
+
+            impl<'a> FromNetworkOrder<'a> for Zigzag<$t> {
+                fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+                    let mut v = Varint::default();
+                    v.deserialize_from(buffer)?;
+
+                    let n = ((v.0 >> 1) as i64) ^ -((v.0 & 1) as i64);
+                    self.0 = <$t>::try_from(n).map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("zigzag value {} doesn't fit in {}", n, stringify!($t)),
+                        )
+                    })?;
+                    Ok(())
+                }
+            }
+
+            impl From<$t> for Zigzag<$t> {
+                fn from(value: $t) -> Self {
+                    Self(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_zigzag!(i8, i16, i32, i64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn varint() {
+        to_network_test(Varint(0), 1, &[0x00]);
+        to_network_test(Varint(127), 1, &[0x7F]);
+        to_network_test(Varint(128), 2, &[0x80, 0x01]);
+        to_network_test(
+            Varint(u64::MAX),
+            10,
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+        );
+
+        from_network_test(None, Varint(128), &vec![0x80, 0x01]);
+        from_network_test(
+            None,
+            Varint(u64::MAX),
+            &vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+        );
+
+        // overflow
+        let b = vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Varint::default().deserialize_from(&mut buffer).is_err());
+
+        // truncated
+        let b = vec![0x80];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Varint::default().deserialize_from(&mut buffer).is_err());
+
+        // overlong, only rejected in canonical mode
+        let b = vec![0x80, 0x80, 0x00];
+        from_network_test(None, Varint(0), &b);
+        crate::canonical::with_canonical(|| {
+            from_network_test(None, Varint(0), &vec![0x00]);
+            let e = Varint::deserialize_new(&mut Cursor::new(b.as_slice())).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+        });
+    }
+
+    #[test]
+    fn zigzag() {
+        to_network_test(Zigzag(0i8), 1, &[0x00]);
+        to_network_test(Zigzag(-1i16), 1, &[0x01]);
+        to_network_test(Zigzag(1i32), 1, &[0x02]);
+        to_network_test(Zigzag(-64i64), 1, &[0x7F]);
+        to_network_test(Zigzag(64i64), 2, &[0x80, 0x01]);
+        to_network_test(
+            Zigzag(i64::MIN),
+            10,
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+        );
+
+        from_network_test(None, Zigzag(i8::MIN), &vec![0xFF, 0x01]);
+        from_network_test(
+            None,
+            Zigzag(i64::MAX),
+            &vec![0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+        );
+
+        // 128 doesn't fit in a i8
+        let b = vec![0x80, 0x02];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Zigzag::<i8>::default()
+            .deserialize_from(&mut buffer)
+            .is_err());
+    }
+
+    #[test]
+    fn wire_len() {
+        let mut buffer: Vec<u8> = Vec::new();
+        assert_eq!(u16::write_len(0x1234, &mut buffer).unwrap(), 2);
+        assert_eq!(Varint::write_len(300, &mut buffer).unwrap(), 2);
+        assert!(u8::write_len(256, &mut buffer).is_err());
+        assert_eq!(buffer, &[0x12, 0x34, 0xAC, 0x02]);
+
+        let mut cursor = Cursor::new(buffer.as_slice());
+        assert_eq!(u16::read_len(&mut cursor).unwrap(), 0x1234);
+        assert_eq!(Varint::read_len(&mut cursor).unwrap(), 300);
+
+        // from a reader, without reading past the length
+        let mut reader = &buffer[..];
+        assert_eq!(u16::read_len_from(&mut reader).unwrap(), 0x1234);
+        assert_eq!(Fixed::<3>::read_len_from(&mut reader).unwrap(), 3);
+        assert_eq!(Varint::read_len_from(&mut reader).unwrap(), 300);
+        assert!(reader.is_empty());
+
+        let mut reader = &[0x80u8][..];
+        let e = Varint::read_len_from(&mut reader).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+}
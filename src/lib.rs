@@ -18,8 +18,15 @@
 //! }
 //! ```
 //!
-//! It's extensively using the [```byteorder```](https://docs.rs/byteorder/latest/byteorder) crate in order to convert integers or floats
-//! to a bigendian buffer of ```u8```.
+//! The ```FromNetworkOrder``` trait also provides a ```deserialize_new()``` method building a new value from the buffer, for ```Default``` types.
+//! The ```ToNetworkOrder``` trait also provides a ```serialize_to_bytes()``` method to serialize directly into a ```bytes::BytesMut``` buffer.
+//! Its ```write_to()``` method serializes to any ```std::io::Write``` sink (e.g. a ```TcpStream``` wrapped in a ```BufWriter```, or a reusable buffer): the primitive
+//! types, the strings, the collections, the wrappers and the derived structs and enums write their bytes directly, while the signed or encrypted values
+//! and the types only implementing ```serialize_to()``` go through a buffer kept per thread. The sink being a trait object, it can also be called on a ```dyn ToNetworkOrder```.
+//!
+//! Integers and floats are converted to a bigendian buffer of ```u8``` by the functions of the ```codec``` module (e.g. ```codec::put_u16()```
+//! and ```codec::get_u16()```), which hand-written implementations can also use.
+//! The ```wire``` module exposes ```const fn``` helpers (e.g. ```wire::encode_u32()```) to build big-endian byte arrays at compile time.
 //! It is compatible with other attributes like those provided by [```serde```](https://crates.io/crates/serde) crate.
 //!
 //! ## How to use it ?
@@ -32,25 +39,130 @@
 //!
 //! The ```ToNetworkOrder``` trait is supported for all structs or enums containing supported primary types (see below for a list of supported types).
 //!
-//! The ```FromNetworkOrder``` trait is only supported for C-like unit-only enums or those having a fallback variant.
-//! For the ```ToNetworkOrder``` trait on C-like enums, it needs to be ```Copy, Clone```.
-//!
-//! ## The ```#[from_network]``` field attribute
-//! In addition it's possible to add a field attribute on a struct's field for the ```FromNetworkOrder``` trait:
-//!
-//! * ```#[from_network(ignore)]``` : the field is not deserialized.
-//! * ```#[from_network(debug)]``` : a ```dbg!(self.field_name)``` statement is inserted after the field is being deserialized.
-//! * ```#[from_network(with_fn(func))]``` : the function ```func(&mut self) -> std::io::Result<()>``` is called for that field.
-//! * ```#[from_network(with_code(block))]``` : the ```code``` block is injected before the field is being deserialized.
-//!
-//!
-//! ## The ```#[from_network]``` enum attribute
+//! The ```FromNetworkOrder``` trait is supported for C-like unit-only enums, those having a fallback variant, and enums with data.
+//! For the ```ToNetworkOrder``` trait on C-like enums, the discriminant is extracted using a ```match``` so the enum doesn't need to be ```Copy```.
+//! The discriminants are checked against the ```repr``` when deriving: a compile error is reported on each variant whose discriminant doesn't fit in it.
+//! They are written as constants of the ```repr``` type rather than with ```as``` casts, so a discriminant which can't be checked when deriving (e.g. a constant)
+//! is still rejected by the compiler instead of being truncated on the wire.
+//!
+//! ## The ```#[network]``` field attribute
+//! In addition it's possible to add a field attribute on a struct's field. Options related to a single direction are
+//! grouped under the ```from``` (```FromNetworkOrder``` trait) and ```to``` (```ToNetworkOrder``` trait) sub-keys:
+//!
+//! * ```#[network(from(ignore))]``` : the field is not deserialized.
+//! * ```#[network(from(debug))]``` : a ```dbg!(&self.field_name)``` statement is inserted after the field is being deserialized. It's only compiled with ```debug_assertions``` (i.e. not in release builds).
+//! * ```#[network(from(debug = "hex"))]``` : same as ```debug``` but the value is printed in hexadecimal.
+//! * ```#[network(from(trace))]``` : the field value is logged with ```log::trace!()``` after the field is being deserialized. Requires the ```log``` feature.
+//! * ```#[network(from(with_fn(func)))]``` : the function ```func(&mut self) -> std::io::Result<()>``` is called for that field.
+//! * ```#[network(from(with_code(block)))]``` : the ```code``` block is injected before the field is being deserialized. The block can use the ```buffer``` variable (the cursor being read) and the ```ctx``` variable (a ```DeserializationContext```).
+//! * ```#[network(from(with_parser(parser)))]``` : with the ```nom``` feature, the field is read by the nom parser ```parser``` (```FnMut(&[u8]) -> IResult<&[u8], T, E>```) from the bytes left, which are then moved after the bytes consumed.
+//! * ```#[network(from(mask = "expr", bit = N))]``` : the field is only deserialized if the bit ```N``` of the mask ```expr``` is set, otherwise it keeps its current value. This is
+//!   used to merge partial updates of "field mask" protocols into an existing value (e.g. ```mask = "self.field_mask", bit = 3```). The serialization is not affected.
+//! * ```#[network(to(ignore))]``` : the field is not serialized.
+//! * ```#[network(to(borrow))]``` : with ```#[network(vectored)]```, the bytes of the field (```AsRef<[u8]>```, e.g. ```Bytes``` or ```Vec<u8>```) are borrowed
+//!   by ```ToVectored``` instead of being copied.
+//! * ```#[network(skip)]``` : the field is neither serialized nor deserialized (e.g. a cache, a timestamp or a socket handle only meaningful at runtime).
+//!   It can't be combined with other options, except ```order```. Its type doesn't need to implement ```Default```: the derived ```from_wire_parts(extra, buffer)```
+//!   constructor deserializes a new value, the skipped fields being given by the ```extra``` tuple in declaration order (e.g. ```Session::from_wire_parts((handle,), &mut buffer)```).
+//! * ```#[network(order = N)]``` : the field is at position ```N``` (starting from 0) on the wire, whatever its declaration order. If used, it must be set on all fields.
+//! * ```#[network(header)]``` : the field can be referred to by its name in the expressions of the next fields (e.g. ```count = "header.qd_count"```).
+//! * ```#[network(count = "expr")]``` : for a ```Vec<T>``` field, exactly ```expr``` elements are deserialized. The expression can use ```self``` or header fields.
+//! * ```#[network(byte_order)]``` : the field (e.g. a ```byte_order::Magic32```) gives the byte order of the integer and float fields (or arrays of those)
+//!   following it on the wire, which are read and written in this order. It's also given to ```with_code``` blocks as ```ctx.byte_order```.
+//! * ```#[network(utf8 = "strict" | "lossy" | "bytes")]``` : the string field (```String```, ```heapless::String<N>```, ```ArrayString<N>``` or ```text::Utf8Text```) is read
+//!   from the remaining bytes, or from ```count``` bytes if given. Invalid UTF-8 is an error (```strict```), replaced by U+FFFD (```lossy```), or kept as bytes
+//!   in a ```text::Utf8Text``` (```bytes```).
+//! * ```#[network(char_as = "u32" | "utf8")]``` : a ```char``` field is sent as its 4-byte scalar value (```u32```, the default) or as its
+//!   UTF-8 bytes (```utf8```, 1 to 4 bytes), as does the ```text::Utf8Char``` wrapper.
+//! * ```#[network(magic = value)]``` : the field is a magic number or a fixed tag. Its value is checked when deserializing (an ```InvalidData``` error is returned
+//!   if it doesn't match), and set by ```#[derive(WireDefault)]```. On the first field on the wire, ```FromNetwork``` also implements ```resync::WireMagic```,
+//!   so ```resync::Resync``` can find the messages in a stream after an error by scanning for the magic.
+//! * ```#[network(align_from_parent = N)]``` : zero bytes are added before the field, and skipped when deserializing, so it starts at a multiple of ```N``` bytes
+//!   from the start of the outermost message (NetBIOS or SMB style), even in a nested struct. See the ```align``` module.
+//! * ```#[network(pad_fill = value)]```, ```#[network(pad_verify)]``` : next to ```align_from_parent``` on a field or ```pad_block``` on a struct, the padding
+//!   bytes are ```value``` instead of 0, and are checked when deserializing with ```pad_verify``` (an ```InvalidData``` error is returned if one is different).
+//! * ```#[network(expect = value)]``` : the value set by ```#[derive(WireDefault)]``` (e.g. a version number), not checked when deserializing.
+//! * ```#[network(compute = "expr")]``` : the value of the field (e.g. a length or a checksum) is computed by the builder generated with ```#[network(builder)]```.
+//!   The expression can use ```self```, the message being built.
+//!
+//! Several options can be combined: ```#[network(from(ignore), to(ignore))]```.
+//!
+//! Field attributes can also be set on tuple struct fields, except ```header``` (the count expressions refer to them as ```self.0```, ```self.1```, ...).
+//! On the fields of enum variants, only ```skip```, ```from(ignore)```, ```from(debug)```, ```from(trace)```, ```to(ignore)``` and ```count``` are supported. The
+//! count expression can use the previous fields of the variant, which are bound by reference (e.g. ```count = "*len"```).
+//!
+//! When a field of a derived type can't be deserialized, the error returned keeps its kind but wraps a ```FieldError``` giving the path of the failing field,
+//! e.g. ```Response.answer.rd_length``` (use ```FieldError::from_io(&e)``` to get it). The error message is prefixed with this path.
+//! The error of the failing field is kept: ```FieldError::downcast_ref::<E>()``` finds the error given by a ```with_fn``` function by its type.
+//! With the ```error-source``` feature, ```FieldError::source()``` returns this error directly, so it's also found when walking the sources.
+//!
+//! The ```#[from_network(...)]``` and ```#[deser(...)]``` forms are deprecated aliases of ```#[network(from(...))]``` and emit a warning.
+//!
+//!
+//! ## The ```#[network]``` enum attribute
 //! Two types of enums are supported for the ```FromNetworkOrder``` trait:
 //!
-//! * C-like enums: in that case, the `TryFrom` trait must be defined, and ```#[from_network(TryFrom)]``` must be added as an outer attribute
+//! * C-like enums: in that case, the `TryFrom` trait must be defined, and ```#[network(from(TryFrom))]``` must be added as an outer attribute
 //! * C-like enums having in addition a catch all fallback value (refer to the `num_enum` crate). In that case, the `From` trait must be defined
 //!
-//! and ```#[from_network(From)]``` must be added as an outer attribute
+//! and ```#[network(from(From))]``` must be added as an outer attribute
+//!
+//! If no ```#[network(from(...))]``` attribute is given and the enum has a fallback variant, the conversion is generated and unknown values
+//! are kept in the fallback variant, so they round-trip. The fallback variant is the one marked with ```#[network(fallback)]```
+//! (or ```#[num_enum(catch_all)]```), or for a ```#[non_exhaustive]``` enum, the only variant having a single unnamed field.
+//!
+//! For unit-only enums, ```#[network(derive_tryfrom)]``` generates the ```TryFrom<repr>``` implementation (the error being the unknown value)
+//! and implies ```#[network(from(TryFrom))]```. Duplicate discriminants are rejected by the compiler.
+//! With ```#[network(values)]```, ```ToNetwork``` also generates the ```ALL``` constant holding all the variants, and the ```wire_value()```
+//! and ```from_wire_value()``` const functions converting a variant to its ```repr``` value and back (```None``` for an unknown value),
+//! e.g. for protocol registries, fuzzing dictionaries or exhaustive conformance tests.
+//!
+//! For enums with data and no ```#[network(from(...))]``` attribute or fallback variant, the tag isn't on the wire: the fields of the
+//! current variant are deserialized in place (like for ```Option<T>```). Such enums can be generic (e.g. ```enum Payload<T> { Raw(Vec<u8>), Typed(T) }```):
+//! the ```ToNetworkOrder``` or ```FromNetworkOrder<'a>``` bounds are added to the type parameters used in the variants.
+//!
+//! ## The ```#[network]``` container attribute
+//! The following options can be set on a struct or an enum:
+//!
+//! * ```#[network(tag = N)]``` : the ```TYPE_TAG``` constant of the ```NetworkMessage``` trait, derived using ```#[derive(NetworkMessage)]```.
+//!   This trait combines ```ToNetworkOrder``` and ```FromNetworkOrder``` with the ```TYPE_TAG``` and ```NAME``` constants, giving
+//!   registries or codecs a single bound to require.
+//!
+//! * ```#[network(skip_all_if = "cond")]``` : the whole type is neither serialized nor deserialized if ```cond``` is true.
+//!   ```cond``` is either a configuration predicate evaluated at compile time (e.g. ```feature = "ext"```, ```target_endian = "big"```,
+//!   ```not(feature = "ext")``` or ```miri```: any ```key = "value"```, ```not```, ```any``` or ```all``` list, or option without a value)
+//!   or an expression evaluated at runtime which can use ```self```.
+//!
+//! * ```#[network(pod)]``` : on ```#[repr(C, packed)]``` structs having only ```u8```, ```endian::BeU16```, ```endian::BeU32```, ```endian::BeU64```
+//!   or arrays of those fields, the struct is serialized and deserialized with a single copy of its bytes. The layout is checked at compile time,
+//!   and field attributes are not supported. This fast path is meant for simple fixed headers, and generates ```unsafe``` code.
+//!   ```FromNetwork``` also implements the ```endian::Overlay``` trait, so the struct can be read in place from a buffer with ```overlay()```.
+//! * ```#[network(buf)]``` : on structs, ```FromNetwork``` also implements the ```buf::FromBuf``` trait, to deserialize the struct from a ```bytes::Buf```
+//!   made of several chunks without copying them first. ```with_code``` is not supported by this trait. ```ToNetwork``` also implements
+//!   the ```buf::ToBufMut``` trait, to serialize the struct into a ```bytes::BufMut```.
+//! * ```#[network(convert)]``` : ```FromNetwork``` also implements ```TryFrom<&[u8]>```, which fails if some bytes are left after the value, and
+//!   ```ToNetwork``` implements ```From<&T> for Vec<u8>```, which panics if the value can't be serialized.
+//! * ```#[network(builder)]``` : on structs with named fields, ```ToNetwork``` also generates a ```TBuilder``` struct returned by ```T::builder()```,
+//!   with a setter per payload field. Its ```build()``` method sets the fields not given to their default value (or their ```magic``` or ```expect``` value),
+//!   then calls the generated ```sync_wire_fields()``` method: the counters referred to by a ```count = "self.n"``` expression are set to the length
+//!   of their vector, and the ```compute``` fields are computed in declaration order. Both return an ```InvalidData``` error if a vector has more
+//!   elements than its counter can hold.
+//! * ```#[network(vectored)]``` : on structs, ```ToNetwork``` also implements the ```vectored::ToVectored``` trait, which serializes the struct as a list
+//!   of ```IoSlice``` for vectored writes: the fields marked with ```#[network(to(borrow))]``` are referenced, the other ones copied in a small buffer.
+//! * ```#[network(pad_block = N)]``` : on structs, zero bytes are added after the last field so the message is a multiple of ```N``` bytes
+//!   (e.g. before a block cipher, or for fixed-size cells), and skipped when deserializing. See the ```align``` module.
+//! * ```#[network(zerocopy)]``` : with the ```zerocopy``` feature, a struct implementing the ```zerocopy``` traits is serialized and deserialized as its bytes.
+//!   Its fields must be stored in network order (e.g. ```zerocopy::byteorder::U32<NetworkEndian>```), which is checked at compile time by the
+//!   ```zerocopy::NetworkLayout``` trait. Field attributes are not supported.
+//! * ```#[network(digest = "sha256")]``` : with the ```digest``` feature, ```ToNetwork``` also generates a ```wire_digest()``` method returning the
+//!   SHA-256 digest of the serialized value as a ```[u8; 32]```, which panics if the value can't be serialized, and a ```try_wire_digest()``` method
+//!   returning the error instead. See the ```digest``` module.
+//! * ```#[network(fuzz)]``` : ```FromNetwork``` also implements ```fuzz::FuzzTokens```, giving the bytes of the magic and expected values of a struct,
+//!   or of the discriminants of an enum, as the tokens of a fuzzing dictionary. See the ```fuzz``` module.
+//!
+//! * ```#[network(hexdebug)]``` : on structs deriving ```ToNetwork```, generates a ```fmt_wire()``` method returning the value of each serialized field
+//!   alongside its bytes in hex, one field per line, for protocol documentation and debugging sessions. The fields must implement ```Debug```.
+//!   The bytes of the fields holding a ```secret::Secret<T>```, however deep, are redacted, as is their ```Debug``` output.
 //!
 //! Refer to [integration test](https://github.com/dandyvica/type2network/blob/main/tests/integration_tests.rs) for examples.
 //!
@@ -60,7 +172,7 @@
 //!
 //! | Type    | ```ToNetwork``` | ```FromNetwork``` |
 //! | -------- | ------- |------- |
-//! | ```all integers & floats```  |yes    |yes|
+//! | ```all integers & floats``` (```i128``` and ```u128``` with the default ```int128``` feature) |yes    |yes|
 //! | ```char``` | yes     |yes|
 //! | ```&[u8]``` | yes     |no|
 //! | ```&str``` | yes     |no|
@@ -69,17 +181,219 @@
 //! | ```Vec<T>``` | yes     |yes|
 //! | ```Box<T>``` | yes     |yes|
 //! | ```PhantomData<T>``` | yes     |yes|
+//! | ```(A, B)``` | yes     |yes|
+//! | ```HashMap<K, V>``` | yes     |yes|
+//! | ```BTreeMap<K, V>``` | yes     |yes|
 //! | ```()``` | yes     |yes|
 //! | ```Cell<T>``` | yes     |yes|
 //! | ```OnceCell<T>``` | yes     |yes|
 //! | ```RefCell<T>``` | yes     |yes|
 //! | ```Box<dyn ToNetworkOrder>``` | yes     |no|
+//! | ```Box<dyn erased::ErasedToNetworkOrder>``` | yes     |no|
 //! | ```Box<dyn FromNetworkOrder<'a>>``` | no     |yes|
 //! | ```Ipv4Addr``` | yes     |yes|
 //! | ```Ipv6Addr``` | yes     |yes|
+//! | ```OsString```, ```PathBuf``` | yes     |yes|
+//! | ```&OsStr```, ```&Path``` | yes     |no|
 //! | ```Either<L,R>``` | yes     |no|
 //! | ```Bytes``` | yes     |no|
 //! | ```BytesMut``` | no     |yes|
+//! | ```SmallVec<[T; N]>``` (```smallvec``` feature) | yes     |yes|
+//! | ```heapless::Vec<T, N>``` (```heapless``` feature) | yes     |yes|
+//! | ```heapless::String<N>``` (```heapless``` feature) | yes     |yes|
+//! | ```ArrayVec<T, N>``` (```arrayvec``` feature) | yes     |yes|
+//! | ```ArrayString<N>``` (```arrayvec``` feature) | yes     |yes|
+//! | ```Decimal``` (```rust_decimal``` feature) | yes     |yes|
+//! | ```U256```, ```H256``` (```primitive-types``` feature) | yes     |yes|
+//! | ```ethnum::U256```, ```ethnum::I256``` (```ethnum``` feature) | yes     |yes|
+//!
+//! By default, deserializing a ```Vec<T>``` reads ```capacity()``` elements, so the vector should be created with ```Vec::with_capacity()```.
+//! This behaviour is deprecated and enabled by the ```vec-capacity``` default feature. Without it, the elements already in the vector are deserialized
+//! in place, as for arrays. The number of elements can be explicitly given by:
+//!
+//! * the ```#[network(count = "expr")]``` field attribute: exactly ```expr``` elements are read
+//! * the ```vec::CountPrefixed<L, T>``` wrapper: the number of elements is sent first, encoded as defined by ```L``` (e.g. ```CountPrefixed<u16, T>```)
+//! * the ```vec::UntilEof<T>``` wrapper: elements are read until the end of the buffer
+//! * the ```vec::Rle<L, T>``` wrapper: the elements are sent as runs of equal elements, each run being its length followed by the element
+//!   (```vec::Rle<L, T, MAX>``` deserializes at most ```MAX``` elements, ```vec::DEFAULT_MAX_RLE_LEN``` by default)
+//! * the ```vec::Terminated<M, T>``` wrapper: the elements are followed by an end marker defined by ```M``` (e.g. ```der::EndOfContents``` for
+//!   the BER indefinite length), and are read until the marker is found
+//! * the ```text::HexString<L>``` and ```text::Base64Bytes<L>``` wrappers: the bytes are sent as hexadecimal or base64 text, preceded by the length of
+//!   the text encoded as ```L``` (```len::Fixed<N>``` for a fixed length)
+//!
+//! Maps are sent as a sequence of (key, value) pairs, and are deserialized until the end of the buffer, unless the number of pairs is given by
+//! the ```#[network(count = "expr")]``` field attribute. A duplicate key is an error. The pairs of a ```BTreeMap``` are sent in the order of their keys,
+//! and those of a ```HashMap``` are sorted by the serialized bytes of their keys. C-like enums can be used as keys (e.g. option tables keyed by
+//! option codes): they are sent as their ```repr```, and their derived ```Ord``` follows the discriminants.
+//!
+//! Bytes not understood by this node (e.g. extension payloads) can be passed through without interpretation: ```opaque::Opaque<L>``` is preceded by
+//! its length encoded as ```L```, and ```opaque::Raw``` is written verbatim, its length being given by the ```#[network(count = "expr")]``` attribute.
+//!
+//! Protocols starting with a textual preamble before a binary body can use ```text::TextLine```, a line terminated by CRLF, and
+//! ```text::TextHeaders```, ```Name: value``` lines terminated by an empty line, as fields of the derived structs.
+//!
+//! ASN.1 structures encoded with DER (e.g. SNMP messages) can be modeled with ```der::DerTlv<TAG, T>```, a value preceded by its tag and
+//! its length, which is a ```der::DerLength``` in the short or long form. ```DerLength``` also implements ```len::WireLen```.
+//!
+//! SSH and the protocols derived from it (SFTP, SSH agent) can use ```ssh::SshString```, bytes preceded by their length as a ```u32```, and
+//! ```ssh::Mpint```, a signed integer of any size whose shortest two's complement encoding is checked when deserializing.
+//!
+//! Heterogeneous lists of values can be built with ```erased::ErasedToNetworkOrder```, an object-safe trait implemented for all the ```ToNetworkOrder```
+//! types: a ```Vec<Box<dyn ErasedToNetworkOrder>>``` is serialized as any vector, each value being serialized in turn.
+//!
+//! A message generic over a ```from_wire::Mode``` can be deserialized from the same definition either borrowing its variable length
+//! fields from the buffer (```from_wire::Borrow```, zero-copy) or owning them (```from_wire::Own```), as selected by the caller through
+//! the ```from_wire::FromWire``` trait: its ```Out<'a>``` output type borrows from a buffer living for ```'a```, or is ```'static```.
+//! ```opaque::OpaqueRef<'a, L>``` is the borrowed counterpart of ```opaque::Opaque<L>```.
+//!
+//! Without the derive, externally tagged values can be composed with ```tagged::Tagged<TAG, T>```: the ```TAG``` constant is sent as a big-endian ```u16```
+//! before the ```T``` value, and checked when deserializing. ```tagged::peek_tag()``` returns the tag of the next value without consuming it,
+//! so a dispatcher can select the type to deserialize.
+//!
+//! ```#[derive(WireDefault)]``` implements ```Default``` for a struct, the fields having a ```magic``` or ```expect``` attribute being set to
+//! this value and the other ones to their default value, so ```T::default()``` is a valid empty message. The arrays are built element by element,
+//! so structs generic over their length (```struct Block<const N: usize> { data: [u8; N] }```) are supported, as with ```#[network(builder)]```.
+//!
+//! For hot paths inspecting headers without allocating or copying, ```#[derive(NetworkView)]``` generates a read-only view struct over a ```&[u8]```
+//! (```HeaderView<'v>``` for a ```Header``` struct), with an accessor per field reading the field at its offset computed at compile time.
+//! The fields must implement the ```view::FixedSize``` trait (integers, floats, ```endian::BeU16```... and arrays of those), except the last one
+//! which can be a ```Vec<u8>``` or a ```&[u8]```: its accessor returns the bytes left.
+//!
+//! A ```lazy::Lazy<'a, T>``` field keeps the bytes left in the buffer when deserialized, and only deserializes the ```T``` value on first access
+//! with ```get()```. It must be the last field of a message, and speeds up servers routing messages based on headers which rarely touch the bodies.
+//!
+//! A field whose value is only known once the message is serialized (e.g. a length or a checksum) can be declared as a
+//! ```placeholder::Placeholder<T>```: a zeroed slot is serialized, and recorded when serialized by ```placeholder::serialize_with_slots()```, so it can be patched afterwards with ```Slots::patch()```.
+//!
+//! The size of a value on the wire is given by ```size::wire_size_of()```, e.g. to compute a length prefix before serializing the value.
+//! The ```size::CountingWriter``` counts the bytes written to an inner writer, or discards them when created with ```sink()```.
+//!
+//! Length prefixes are encoded using the ```len::WireLen``` trait, implemented for ```u8```, ```u16```, ```u32``` and ```len::Varint``` (LEB128).
+//! Its ```read_len_from()``` method reads a length from any ```std::io::Read```, e.g. the prefix of a frame.
+//! Signed integers can be sent as a ```len::Zigzag<T>```, zigzag encoded then sent as a ```Varint```.
+//! Floats are sent as their IEEE 754 bits, or as a ```float::TotalOrder<T>``` using the total-order bit pattern, so the bytes of the values
+//! sort as the values do (e.g. for key encodings).
+//! Implement it to use a custom length encoding.
+//!
+//! When deserializing a ```Vec<T>``` of structs having only integer, float, char or array of those fields, each element is built
+//! directly from the wire: default values are not first written to be immediately overwritten.
+//!
+//! For large payloads, the ```uninit``` module copies bytes without zero-filling the destination first: ```read_uninit()``` fills a
+//! ```&mut [MaybeUninit<u8>]```, ```read_to_spare()``` writes to the spare capacity of a ```Vec<u8>```, and the ```uninit::Bulk``` field type
+//! reads all the remaining bytes of the buffer this way.
+//!
+//! Other vector types (e.g. ```SmallVec``` or ```heapless::Vec```) deserialize the elements already present in place, and can be used with the ```#[network(count = "expr")]```
+//! field attribute (any type implementing the ```vec::Push``` trait). For bounded vectors, an error is returned if the count exceeds the capacity.
+//! ```Vec<T, A>``` with a custom allocator is not supported until the allocator API is stabilized.
+//!
+//! Bounded strings (e.g. ```heapless::String<N>``` or ```ArrayString<N>```) are deserialized from the remaining bytes of the buffer.
+//! Use the ```#[network(utf8 = "mode")]``` field attribute to choose what happens with invalid UTF-8.
+//! ```OsString``` and ```PathBuf``` are preceded by their length as a big-endian ```u32```. On Unix, their bytes are sent as is; on other
+//! platforms they're sent as UTF-8, an error being returned for strings which are not valid Unicode.
+//!
+//! As an ```Instant``` can't be sent, ```time::WireInstant``` sends the time elapsed since an epoch chosen by the user as a ```u64``` number of
+//! microseconds. Created with ```WireInstant::new(epoch)```, it captures the elapsed time when serialized, e.g. to measure latencies.
+//! The ```heapless``` types let the same derived packet definitions use bounded, allocation-free fields, but the crate itself still requires ```std```:
+//! ```no_std``` support is not provided yet, and is deferred to a separate change. The ```ToNetworkOrder``` and ```FromNetworkOrder``` signatures
+//! (and the code generated by the derive macros) use ```std::io::Cursor```, ```std::io::Write``` and ```std::io::Result```, which have no ```core``` or
+//! ```alloc``` counterpart: supporting firmware needs an I/O abstraction of the crate's own, replacing them in the public API, with the modules
+//! using sockets, threads or the file system behind a ```std``` feature.
+//!
+//! For handshake-heavy protocols, a ```session::Session<S>``` holds the current state of a protocol and the messages allowed in each state,
+//! identified by their ```NetworkMessage``` tag: ```send()``` and ```receive()``` return an error if the message is not allowed in the current
+//! state, and otherwise move to the next state.
+//!
+//! The ```socket::UdpSocketExt``` trait adds ```send_msg_to()```, ```send_msg()```, ```recv_msg_from()``` and ```recv_msg()``` to ```std::net::UdpSocket```:
+//! each message is sent in a single datagram, and an error is returned if it doesn't fit in a datagram.
+//!
+//! For one-shot exchanges, ```query::query()``` sends a request and receives the response over a connected ```UdpSocket``` or a ```TcpStream```
+//! (any ```query::Transport```), with a timeout limiting the whole exchange. With the ```async``` feature, ```query::query_async()``` does the same over
+//! a ```futures::io``` byte stream using frames, rejecting the responses longer than a maximum frame size.
+//!
+//! Messages can be sent over a byte stream as frames, using the ```framing``` module: each frame is the length of the message as a big-endian ```u32```,
+//! followed by the message. ```FrameWriter``` and ```FrameReader``` write and read frames on top of any ```std::io::Write``` or ```std::io::Read```,
+//! and the ```socket::TcpStreamExt``` trait adds ```write_framed()``` and ```read_framed()``` to ```std::net::TcpStream```.
+//! For custom event loops (```mio```, ```io_uring```...), ```framing::Decoder``` is fed with the bytes received in chunks of any size
+//! and returns the messages once their frames are complete.
+//! Other length prefixes are selected by ```with_format()``` and a ```framing::FrameFormat```, giving the byte order of the prefix and whether it
+//! counts its own bytes (e.g. ```FrameFormat::NineP```, the little-endian ```size[4]``` of 9P including itself).
+//! Frames prefixed by any ```len::WireLen``` (e.g. a ```len::Varint```) are written by ```framing::write_frame_with()``` and read by ```framing::read_frame_with()```.
+//! ```FrameReader```, ```Decoder``` and ```MessageStream``` reject the frames longer than the size given to ```with_max_frame_size()```, so a corrupted
+//! length prefix can't make a server buffer an unbounded number of bytes. With ```skip_oversized()```, they skip these frames instead.
+//! With the ```async``` feature, ```stream::MessageStream``` reads frames (of any ```FrameFormat```) from a ```futures::io::AsyncRead``` and implements ```futures::Stream```:
+//!
+//! ```rust,ignore
+//! let mut messages = MessageStream::<Message, _>::new(connection);
+//! while let Some(msg) = messages.next().await {
+//!     println!("{:?}", msg?);
+//! }
+//! ```
+//!
+//! A ```stream::CancelToken``` given with ```with_cancel()``` stops a read waiting for a frame sent too slowly, with a ```TimedOut``` error, and ends the stream.
+//! It's checked while the frames are read, the messages being deserialized from complete frames.
+//!
+//! With the ```diagnose``` feature, the derived code records the offsets of the fields deserialized, and ```diagnose::diagnose::<T>(bytes)``` returns
+//! a report of how far the deserialization went: the fields deserialized with their offsets, the failing field and the error, and a hexdump
+//! of the bytes left. It's intended for test failures and bug reports from production captures.
+//!
+//! Within ```budget::with_budget(n, f)```, at most ```n``` elements are deserialized by the collections (```vec::CountPrefixed```, ```vec::UntilEof```,
+//! ```vec::Rle```, ```vec::Terminated```, maps and ```#[network(count = "expr")]``` fields): the count is taken from the budget before the elements are read, and an
+//! ```InvalidData``` error is returned once it's exhausted. Every deserialization loop being bounded, parsers can be checked with proof harnesses
+//! (e.g. Kani, the budget being kept in a static under ```cfg(kani)```) or fed untrusted bytes.
+//!
+//! Serializing always gives a single encoding for a value, but some types accept other encodings when deserializing (e.g. a ```len::Varint``` with
+//! trailing zero groups, ```BTreeMap``` pairs not sorted by key, uppercase hex digits or adjacent runs of a ```vec::Rle```). Within ```canonical::with_canonical(f)```, those are rejected with an ```InvalidData```
+//! error, as required when the bytes feed signatures or consensus algorithms.
+//!
+//! Within ```observer::with_observer(observer, f)```, the bytes not understood are reported to the observer with their offset, length and the type being
+//! deserialized: the unknown values read by the ```#[network(fallback)]``` variants, the bytes skipped by ```resync::Resync```, the oversized frames skipped, the padding
+//! not verified and the bytes passed through by ```opaque::Opaque```, ```opaque::OpaqueRef``` or ```opaque::Raw```. Operators can then detect
+//! new protocol extensions appearing in the wild.
+//!
+//! The ```#[network(fuzz)]``` attribute of ```FromNetwork``` gives the magic values and the enum discriminants of a type as fuzzing tokens, which
+//! ```fuzz::Dictionary``` collects along with typical lengths to write an AFL or libFuzzer dictionary file.
+//!
+//! With the ```debug-codegen``` feature, the code generated by ```ToNetwork``` and ```FromNetwork``` is formatted with ```prettyplease``` and written to
+//! ```$OUT_DIR/type2network/<crate>/<source file>/<Type>.<Derive>.rs```, so the exact implementations can be inspected, or diffed across crate upgrades when auditing
+//! wire compatibility. The types of the same name in a source file are numbered (```<Type>.2.<Derive>.rs```, ...). ```OUT_DIR``` is only set for crates
+//! having a build script, so the crate deriving the types needs one (an empty ```build.rs``` is enough): nothing is written otherwise.
+//!
+//! With the ```interop``` feature, ```interop::assert_interop()``` validates a derived type against a legacy C implementation of the protocol:
+//! a Rust value and its C counterpart (e.g. generated by ```bindgen```) must give the same bytes, the C encoding and decoding functions being
+//! called through FFI, and each side must read back the bytes of the other one.
+//!
+//! With the ```versions``` feature, ```versions::VersionMatrix``` checks sample packets of each version of a long-lived protocol: every packet must be read
+//! by the struct of its version and serialized again with the same bytes, and its upgrade to the struct of the latest version must give the expected value.
+//!
+//! With the ```nom``` feature, ```nom::parse::<T>()``` is a nom parser deserializing a ```T```, so derived types can be used within nom combinators,
+//! and the ```#[network(from(with_parser(parser)))]``` field attribute reads a field with a nom parser, so existing parsers can be kept for some fields.
+//!
+//! With the ```compat``` feature, the derives accept a subset of the ```deku``` and ```binrw``` attributes (```#[deku(count = "len")]```,
+//! ```#[br(ignore)]```...), so existing protocol definitions can be ported by replacing their derives first. The ```compat``` module gives the
+//! equivalent ```#[network(...)]``` attributes.
+//!
+//! With the ```rayon``` feature, ```batch::par_serialize_all()``` serializes a slice of messages in parallel, one buffer per message, and
+//! ```batch::par_serialize_concat()``` concatenates these buffers in the order of the messages.
+//!
+//! The 128-bit integers are supported with the ```int128``` default feature. It can be disabled for targets or FFI contexts where they are
+//! mis-handled: ```Ipv6Addr``` is then read and written from its octets.
+//!
+//! To ease porting C socket programs, ```endian::hton()``` and ```endian::ntoh()``` convert integers, or slices and arrays of integers (e.g. the
+//! fields of a ```#[repr(C)]``` struct laid over a buffer), in place between host and network order. ```htons()```, ```htonl()```, ```ntohs()```
+//! and ```ntohl()``` are also provided.
+//!
+//! With the ```defmt``` feature, ```DeserializationContext``` and ```len::Varint``` implement ```defmt::Format```. As errors are
+//! ```std::io::Error```, they can be logged by wrapping them: ```defmt::error!("{}", DefmtError(&e))```.
+//!
+//! With the ```zeroize``` feature, the values of ```secret::Secret<T>``` are zeroized when dropped (```T``` implements ```secret::Wipe```
+//! whether the feature is enabled or not), as are the scratch buffers holding received bytes: datagrams and frames read from sockets or streams,
+//! and the text of ```HexString``` and ```Base64Bytes```, and the bytes decrypted by ```envelope::EncryptedEnvelope```.
+//!
+//! An ```envelope::EncryptedEnvelope<T, C>``` encrypts a whole message with an authenticated cipher provided by the application through
+//! the ```envelope::Cipher``` trait: the nonce is sent first, the serialized ```T``` is sealed in place, and the authentication tag is verified
+//! before the message is deserialized.
+//! A ```signed::Signed<T, S>``` is followed by the signature of its bytes, made and checked by the application through the ```signed::Signer```
+//! and ```signed::Verifier``` traits (e.g. for control-plane messages or firmware updates).
 //!
 //! ## Examples
 //!
@@ -91,7 +405,7 @@
 //!     y: u16,
 //!
 //!     // last field is not deserialized
-//!     #[from_network(ignore)]
+//!     #[network(from(ignore))]
 //!     z: u16,
 //! }
 //!
@@ -106,7 +420,7 @@
 //!     y: u16,
 //!
 //!     // last field is not deserialized
-//!     #[from_network(with_fn(update))]
+//!     #[network(from(with_fn(update)))]
 //!     z: u16,
 //! }
 //!
@@ -117,12 +431,16 @@
 //!     y: u16,
 //!
 //!     // last field is not deserialized
-//!     #[from_network(with_code(self.z = 0xFFFF;))]
+//!     #[network(from(with_code(self.z = 0xFFFF;)))]
 //!     z: u16,
 //! }
 //! ```
 //!
 //!
+//! ## Wire format stability
+//! The wire format of a given type never changes between minor versions of the crate. This is checked by a compatibility corpus:
+//! the serialized bytes of a matrix of types are compared to the golden bytes generated by previous versions (```tests/golden```).
+//!
 //! ## Full examples
 //! Two examples can be found in the ```examples``` directory:
 //!
@@ -146,21 +464,294 @@
 pub trait ToNetworkOrder {
     /// Returns the number of bytes copied or an [`std::io::Error`] error if any.
     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize>;
+
+    /// Same as [`ToNetworkOrder::serialize_to`] but appends data to a [`bytes::BytesMut`] buffer, as used by
+    /// `tokio` codecs. When the buffer is not shared, its allocation is reused and no data is copied.
+    ///
+    /// # Example
+    /// ```
+    /// use bytes::BytesMut;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer = BytesMut::from(&[0xAA_u8][..]);
+    /// assert_eq!(0x1234_u16.serialize_to_bytes(&mut buffer).unwrap(), 2);
+    /// assert_eq!(&buffer[..], &[0xAA, 0x12, 0x34]);
+    /// ```
+    fn serialize_to_bytes(&self, buffer: &mut bytes::BytesMut) -> std::io::Result<usize> {
+        // BytesMut <-> Vec<u8> conversions are zero-copy for a unique buffer
+        let mut vec: Vec<u8> = std::mem::take(buffer).into();
+        let length = self.serialize_to(&mut vec);
+        *buffer = bytes::BytesMut::from(bytes::Bytes::from(vec));
+
+        length
+    }
+
+    /// Same as [`ToNetworkOrder::serialize_to`] but writes to any [`std::io::Write`] sink (e.g. a ```TcpStream```, a
+    /// ```BufWriter``` or a hasher), and returns the number of bytes written. The sink is a trait object, so the
+    /// method is also available on ```dyn ToNetworkOrder```.
+    ///
+    /// The types of this crate and the derived structs and enums write their bytes directly to the sink, except
+    /// those needing all their bytes first (e.g. signed or encrypted values): these, and the types only implementing
+    /// ```serialize_to()```, are serialized into a buffer kept per thread, then written at once. As the values are
+    /// written in turn, an unbuffered sink should be wrapped in a ```BufWriter```.
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::BufWriter;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut writer = BufWriter::new(Vec::new());
+    /// assert_eq!(0x1234_u16.write_to(&mut writer).unwrap(), 2);
+    /// assert_eq!(vec![0xAA_u8; 3].write_to(&mut writer).unwrap(), 3);
+    /// assert_eq!(writer.into_inner().unwrap(), &[0x12, 0x34, 0xAA, 0xAA, 0xAA]);
+    ///
+    /// let value: Box<dyn ToNetworkOrder> = Box::new(0x56_u8);
+    /// let mut writer = Vec::new();
+    /// assert_eq!(value.write_to(&mut writer).unwrap(), 1);
+    /// ```
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        size::write_through_scratch(self, writer)
+    }
 }
 
 /// Copy data from a network-order buffer to structured data.
 pub trait FromNetworkOrder<'a> {
     /// Copy data from a network-order buffer to structured data.
     fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()>;
+
+    /// Build a new value from a network-order buffer. By default, the [`Default`] value is deserialized
+    /// in place, but derived structs having only primitive fields build the value directly, without
+    /// first writing default values which are immediately overwritten. It's also used to deserialize
+    /// the elements of collections, e.g. a [`Vec<T>`].
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::Cursor;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let mut buffer = Cursor::new([0x12, 0x34, 0x56].as_slice());
+    /// assert_eq!(u16::deserialize_new(&mut buffer).unwrap(), 0x1234);
+    /// assert_eq!(u8::deserialize_new(&mut buffer).unwrap(), 0x56);
+    /// ```
+    fn deserialize_new(buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<Self>
+    where
+        Self: Sized + Default,
+    {
+        let mut value = Self::default();
+        value.deserialize_from(buffer)?;
+        Ok(value)
+    }
+}
+
+/// A message combining both directions with type-level metadata, so that registries, dispatchers or codecs
+/// can require a single bound. It's derived using ```#[derive(NetworkMessage)]``` and the ```#[network(tag = N)]```
+/// attribute.
+///
+/// # Example
+/// ```
+/// use type2network::{FromNetworkOrder, NetworkMessage, ToNetworkOrder};
+/// use type2network_derive::{FromNetwork, NetworkMessage, ToNetwork};
+///
+/// #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork, NetworkMessage)]
+/// #[network(tag = 0x01)]
+/// struct Ping {
+///     id: u16,
+/// }
+///
+/// assert_eq!(Ping::TYPE_TAG, 0x01);
+/// assert_eq!(Ping::NAME, "Ping");
+///
+/// let bytes = Ping { id: 0x1234 }.to_bytes().unwrap();
+/// assert_eq!(bytes, &[0x12, 0x34]);
+/// assert_eq!(Ping::from_bytes(&bytes).unwrap(), Ping { id: 0x1234 });
+/// ```
+pub trait NetworkMessage: ToNetworkOrder + for<'a> FromNetworkOrder<'a> {
+    /// Tag identifying the message type (e.g. a message type or an opcode).
+    const TYPE_TAG: u32;
+
+    /// Name of the message type.
+    const NAME: &'static str;
+
+    /// Serializes the message to a new buffer.
+    fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize_to(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Deserializes a message from the default value.
+    fn from_bytes(bytes: &[u8]) -> std::io::Result<Self>
+    where
+        Self: Sized + Default,
+    {
+        let mut buffer = std::io::Cursor::new(bytes);
+        Self::deserialize_new(&mut buffer)
+    }
 }
 
+// the generated code refers to type2network::..., which must also resolve in the tests of this crate
+extern crate self as type2network;
+
 // all definitions of serialize_to()/deserialize_from() for standard types
 mod additional;
 mod cell;
 mod generics;
+mod map;
 mod net;
+mod path;
 mod primitive;
 
+pub mod align;
+pub mod budget;
+pub mod buf;
+pub mod byte_order;
+pub mod canonical;
+pub mod codec;
+pub mod der;
+pub mod endian;
+pub mod envelope;
+pub mod erased;
+pub mod float;
+pub mod framing;
+pub mod from_wire;
+pub mod fuzz;
+pub mod lazy;
+pub mod len;
+pub mod observer;
+pub mod opaque;
+pub mod placeholder;
+pub mod query;
+pub mod resync;
+pub mod secret;
+pub mod session;
+pub mod signed;
+pub mod size;
+pub mod socket;
+pub mod ssh;
+pub mod tagged;
+pub mod text;
+pub mod time;
+pub mod uninit;
+pub mod vec;
+pub mod vectored;
+pub mod view;
+pub mod wire;
+
+#[cfg(feature = "zerocopy")]
+pub mod zerocopy;
+
+mod context;
+pub use context::DeserializationContext;
+
+#[cfg(feature = "rayon")]
+pub mod batch;
+
+#[cfg(feature = "async")]
+pub mod stream;
+
+mod error;
+#[cfg(feature = "defmt")]
+pub use error::DefmtError;
+pub use error::FieldError;
+
+// used by the generated code to add the field path to errors
+#[doc(hidden)]
+pub use error::field_context;
+
+#[cfg(feature = "diagnose")]
+pub mod diagnose;
+
+#[cfg(feature = "digest")]
+pub mod digest;
+
+#[cfg(feature = "interop")]
+pub mod interop;
+
+#[cfg(feature = "versions")]
+pub mod versions;
+
+#[cfg(feature = "nom")]
+pub mod nom;
+
+#[cfg(feature = "compat")]
+pub mod compat;
+
+// called by the generated code once a field is deserialized, does nothing without the diagnose feature
+#[doc(hidden)]
+#[inline(always)]
+#[allow(unused_variables)]
+pub fn record_field(type_name: &'static str, field: &'static str, start: u64, end: u64) {
+    #[cfg(feature = "diagnose")]
+    diagnose::record(type_name, field, start, end);
+}
+
+// called by the fmt_wire() method generated for #[network(hexdebug)]: one line per field with its value and its bytes
+#[doc(hidden)]
+pub fn fmt_wire_field<T: std::fmt::Debug + ToNetworkOrder + ?Sized>(
+    out: &mut String,
+    name: &str,
+    width: usize,
+    value: &T,
+) {
+    use std::fmt::Write;
+
+    let mut bytes = Vec::new();
+    let hex = match secret::tracking_secrets(|| value.serialize_to(&mut bytes)) {
+        (Ok(_), true) => String::from("<redacted>"),
+        (Ok(_), false) => bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" "),
+        (Err(e), _) => format!("<{}>", e),
+    };
+    secret::wipe_bytes(&mut bytes);
+
+    let value = format!("{:?}", value);
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:<24}  {}",
+        name,
+        value,
+        hex,
+        width = width
+    );
+}
+
+// called by the TryFrom<&[u8]> impl generated for #[network(convert)]: all bytes must be consumed
+#[doc(hidden)]
+pub fn convert_from_bytes<'a, T: FromNetworkOrder<'a> + Default>(
+    type_name: &str,
+    bytes: &'a [u8],
+) -> std::io::Result<T> {
+    let mut buffer = std::io::Cursor::new(bytes);
+    let value = T::deserialize_new(&mut buffer)?;
+
+    let left = bytes.len() - buffer.position() as usize;
+    if left != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} trailing bytes after a {}", left, type_name),
+        ));
+    }
+    Ok(value)
+}
+
+// called by the From<&T> for Vec<u8> impl generated for #[network(convert)]
+#[doc(hidden)]
+pub fn convert_to_vec<T: ToNetworkOrder + ?Sized>(type_name: &str, value: &T) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Err(e) = value.serialize_to(&mut buffer) {
+        panic!("unable to serialize a {}: {}", type_name, e);
+    }
+    buffer
+}
+
+// used by the code generated for #[network(from(trace))]
+#[doc(hidden)]
+#[cfg(feature = "log")]
+pub use log;
+
 #[cfg(test)]
 pub mod test_helpers {
     use super::*;
@@ -179,11 +770,7 @@ pub mod test_helpers {
         T: FromNetworkOrder<'a> + Default + std::fmt::Debug + std::cmp::PartialEq,
     {
         let mut buffer = Cursor::new(buf.as_slice());
-        let mut v: T = if def.is_none() {
-            T::default()
-        } else {
-            def.unwrap()
-        };
+        let mut v: T = def.unwrap_or_default();
         assert!(v.deserialize_from(&mut buffer).is_ok());
         assert_eq!(v, val);
     }
@@ -0,0 +1,255 @@
+//! Maps are sent as a sequence of (key, value) pairs, e.g. option tables keyed by option codes
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::io::{Cursor, Error, ErrorKind};
+
+use crate::vec::Push;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+impl<A, B> ToNetworkOrder for (A, B)
+where
+    A: ToNetworkOrder,
+    B: ToNetworkOrder,
+{
+    /// ```
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert_eq!((0x12_u8, 0x3456_u16).serialize_to(&mut buffer).unwrap(), 3);
+    /// assert_eq!(buffer, &[0x12, 0x34, 0x56]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        Ok(self.0.serialize_to(buffer)? + self.1.serialize_to(buffer)?)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::align::track_writes(writer, |writer| {
+            Ok(self.0.write_to(writer)? + self.1.write_to(writer)?)
+        })
+    }
+}
+
+impl<'a, A, B> FromNetworkOrder<'a> for (A, B)
+where
+    A: FromNetworkOrder<'a>,
+    B: FromNetworkOrder<'a>,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0.deserialize_from(buffer)?;
+        self.1.deserialize_from(buffer)
+    }
+}
+
+// a key found twice when deserializing a map
+fn duplicate_key() -> Error {
+    Error::new(ErrorKind::InvalidData, "duplicate key in map")
+}
+
+// without the #[network(count = "expr")] attribute, pairs are read until the end of the buffer
+fn read_until_eof<'a, K, V, M>(map: &mut M, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()>
+where
+    K: Default + FromNetworkOrder<'a>,
+    V: Default + FromNetworkOrder<'a>,
+    M: Push<(K, V)>,
+{
+    map.clear();
+    while (buffer.position() as usize) < buffer.get_ref().len() {
+        crate::budget::take(1)?;
+        map.try_push(<(K, V)>::deserialize_new(buffer)?)?;
+    }
+    Ok(())
+}
+
+impl<K, V, S> ToNetworkOrder for HashMap<K, V, S>
+where
+    K: ToNetworkOrder,
+    V: ToNetworkOrder,
+{
+    /// The pairs are sorted by the serialized bytes of their keys, so the same map is always sent the
+    /// same way (e.g. a C-like enum key is sorted by its discriminant).
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let map = HashMap::from([(2_u8, 0xFF_u8), (1, 0xEE)]);
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert_eq!(map.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(buffer, &[1, 0xEE, 2, 0xFF]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+        for (key, v) in sorted_pairs(self)? {
+            buffer.extend_from_slice(&key);
+            length += key.len() + v.serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let pairs = sorted_pairs(self)?;
+        crate::align::track_writes(writer, |writer| {
+            let mut length = 0usize;
+            for (key, v) in pairs {
+                writer.write_all(&key)?;
+                length += key.len() + v.write_to(writer)?;
+            }
+            Ok(length)
+        })
+    }
+}
+
+// the values of the map along with the serialized bytes of their keys, sorted by these bytes
+fn sorted_pairs<K: ToNetworkOrder, V, S>(
+    map: &HashMap<K, V, S>,
+) -> std::io::Result<Vec<(Vec<u8>, &V)>> {
+    let mut pairs = Vec::with_capacity(map.len());
+    for (k, v) in map {
+        let mut key = Vec::new();
+        k.serialize_to(&mut key)?;
+        pairs.push((key, v));
+    }
+    pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    Ok(pairs)
+}
+
+impl<'a, K, V, S> FromNetworkOrder<'a> for HashMap<K, V, S>
+where
+    K: Default + Eq + Hash + FromNetworkOrder<'a>,
+    V: Default + FromNetworkOrder<'a>,
+    S: BuildHasher,
+{
+    /// Pairs are read until the end of the buffer, unless their number is given by the
+    /// ```#[network(count = "expr")]``` field attribute. A duplicate key is an error.
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        read_until_eof(self, buffer)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Push<(K, V)> for HashMap<K, V, S> {
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+
+    fn try_push(&mut self, item: (K, V)) -> std::io::Result<()> {
+        match self.insert(item.0, item.1) {
+            None => Ok(()),
+            Some(_) => Err(duplicate_key()),
+        }
+    }
+}
+
+impl<K, V> ToNetworkOrder for BTreeMap<K, V>
+where
+    K: ToNetworkOrder,
+    V: ToNetworkOrder,
+{
+    /// The pairs are sent in the order of their keys. For a C-like enum key, the derived ```Ord``` follows
+    /// the discriminants, so the order is the one of the wire values.
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let map = BTreeMap::from([(2_u8, 0xFF_u8), (1, 0xEE)]);
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert_eq!(map.serialize_to(&mut buffer).unwrap(), 4);
+    /// assert_eq!(buffer, &[1, 0xEE, 2, 0xFF]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+
+        for (k, v) in self {
+            length += k.serialize_to(buffer)? + v.serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::align::track_writes(writer, |writer| {
+            let mut length = 0usize;
+            for (k, v) in self {
+                length += k.write_to(writer)? + v.write_to(writer)?;
+            }
+            Ok(length)
+        })
+    }
+}
+
+impl<'a, K, V> FromNetworkOrder<'a> for BTreeMap<K, V>
+where
+    K: Default + Ord + FromNetworkOrder<'a>,
+    V: Default + FromNetworkOrder<'a>,
+{
+    /// Pairs are read until the end of the buffer, unless their number is given by the
+    /// ```#[network(count = "expr")]``` field attribute. A duplicate key is an error.
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        read_until_eof(self, buffer)
+    }
+}
+
+impl<K: Ord, V> Push<(K, V)> for BTreeMap<K, V> {
+    fn clear(&mut self) {
+        BTreeMap::clear(self)
+    }
+
+    fn try_push(&mut self, item: (K, V)) -> std::io::Result<()> {
+        // the pairs are serialized in the order of their keys
+        if let Some((last, _)) = self.last_key_value() {
+            crate::canonical::check(item.0 >= *last, "map keys not in increasing order")?;
+        }
+
+        match self.insert(item.0, item.1) {
+            None => Ok(()),
+            Some(_) => Err(duplicate_key()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn maps() {
+        to_network_test((1u8, 2u16), 3, &[1, 0, 2]);
+        from_network_test(None, (1u8, 2u16), &vec![1, 0, 2]);
+
+        let map = HashMap::from([(0x0102_u16, 3_u8), (0x0001, 4), (0x0100, 5)]);
+        to_network_test(map.clone(), 9, &[0, 1, 4, 1, 0, 5, 1, 2, 3]);
+        from_network_test(None, map, &vec![1, 2, 3, 0, 1, 4, 1, 0, 5]);
+
+        let map = BTreeMap::from([(2_u8, 'a'), (1, 'b')]);
+        to_network_test(map.clone(), 10, &[1, 0, 0, 0, 0x62, 2, 0, 0, 0, 0x61]);
+        from_network_test(
+            Some(BTreeMap::from([(9, 'z')])),
+            map,
+            &vec![2, 0, 0, 0, 0x61, 1, 0, 0, 0, 0x62],
+        );
+
+        // duplicate key, truncated pair
+        let b = vec![1, 2, 1, 3];
+        let mut map = HashMap::<u8, u8>::new();
+        assert!(map
+            .deserialize_from(&mut Cursor::new(b.as_slice()))
+            .is_err());
+        assert!(map.deserialize_from(&mut Cursor::new(&b[..3])).is_err());
+
+        // keys not in order, only rejected in canonical mode
+        let b = vec![2, 0, 1, 0];
+        let mut map = BTreeMap::<u8, u8>::new();
+        assert!(map.deserialize_from(&mut Cursor::new(b.as_slice())).is_ok());
+        crate::canonical::with_canonical(|| {
+            let e = map
+                .deserialize_from(&mut Cursor::new(b.as_slice()))
+                .unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+            assert!(map
+                .deserialize_from(&mut Cursor::new(&[1, 0, 2, 0][..]))
+                .is_ok());
+        });
+    }
+}
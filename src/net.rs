@@ -17,6 +17,12 @@ impl ToNetworkOrder for Ipv4Addr {
     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
         self.octets().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let octets = self.octets();
+        writer.write_all(&octets)?;
+        Ok(octets.len())
+    }
 }
 
 impl<'a> FromNetworkOrder<'a> for Ipv4Addr {
@@ -55,6 +61,12 @@ impl ToNetworkOrder for Ipv6Addr {
     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
         self.octets().serialize_to(buffer)
     }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let octets = self.octets();
+        writer.write_all(&octets)?;
+        Ok(octets.len())
+    }
 }
 
 impl<'a> FromNetworkOrder<'a> for Ipv6Addr {
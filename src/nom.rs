@@ -0,0 +1,123 @@
+//! Interoperability with the [```nom```](https://crates.io/crates/nom) parser combinators (```nom``` feature).
+//!
+//! A ```FromNetworkOrder``` type can be used as a nom parser with ```parse::<T>```, and a nom parser can read a field
+//! of a derived struct with the ```#[network(from(with_parser(parser)))]``` field attribute, so the derive and
+//! combinators can be mixed:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use nom::bytes::complete::take_until;
+//! use nom::{IResult, Parser};
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! // a NUL terminated string
+//! fn c_string(input: &[u8]) -> IResult<&[u8], String> {
+//!     let (input, s) = take_until(&b"\0"[..]).parse(input)?;
+//!     Ok((&input[1..], String::from_utf8_lossy(s).into_owned()))
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, FromNetwork)]
+//! struct Request {
+//!     id: u16,
+//!     #[network(from(with_parser(c_string)))]
+//!     name: String,
+//!     flags: u8,
+//! }
+//!
+//! let bytes = [0x12, 0x34, b'a', b'b', 0, 0xFF];
+//!
+//! // the derived type read by the parser
+//! let (input, request) = type2network::nom::parse::<Request>(&bytes).unwrap();
+//! assert!(input.is_empty());
+//! assert_eq!(request.name, "ab");
+//! assert_eq!(request.flags, 0xFF);
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+
+use ::nom::error::{ErrorKind as NomErrorKind, ParseError};
+use ::nom::{Err, IResult};
+
+use crate::FromNetworkOrder;
+
+/// Deserializes a ```T``` from the input, as a nom parser. A buffer too short is reported as a nom ```Eof```
+/// error, other errors as a ```Verify``` one.
+pub fn parse<'a, T>(input: &'a [u8]) -> IResult<&'a [u8], T>
+where
+    T: FromNetworkOrder<'a> + Default,
+{
+    parse_with_error(input)
+}
+
+/// Same as ```parse()```, for any nom error type.
+pub fn parse_with_error<'a, T, E>(input: &'a [u8]) -> IResult<&'a [u8], T, E>
+where
+    T: FromNetworkOrder<'a> + Default,
+    E: ParseError<&'a [u8]>,
+{
+    let mut buffer = Cursor::new(input);
+
+    match T::deserialize_new(&mut buffer) {
+        Ok(value) => Ok((&input[buffer.position() as usize..], value)),
+        Err(e) => {
+            let kind = match e.kind() {
+                ErrorKind::UnexpectedEof => NomErrorKind::Eof,
+                _ => NomErrorKind::Verify,
+            };
+            Err(Err::Error(E::from_error_kind(input, kind)))
+        }
+    }
+}
+
+/// Runs a nom parser on the bytes left in the buffer, and moves the buffer after the bytes consumed.
+/// It's called by the code generated for ```#[network(from(with_parser(parser)))]```. Parser errors are
+/// returned as ```InvalidData``` errors, or ```UnexpectedEof``` errors if more input is needed.
+pub fn run_parser<'a, O, E, P>(buffer: &mut Cursor<&'a [u8]>, mut parser: P) -> std::io::Result<O>
+where
+    P: FnMut(&'a [u8]) -> IResult<&'a [u8], O, E>,
+    E: std::fmt::Debug,
+{
+    let bytes: &'a [u8] = buffer.get_ref();
+    let position = (buffer.position() as usize).min(bytes.len());
+    let input = &bytes[position..];
+
+    match parser(input) {
+        Ok((rest, value)) => {
+            buffer.set_position((bytes.len() - rest.len()) as u64);
+            Ok(value)
+        }
+        Err(Err::Incomplete(_)) => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "parser needs more input",
+        )),
+        Err(Err::Error(e)) | Err(Err::Failure(e)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("parser error: {:?}", e),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::nom::number::complete::be_u16;
+
+    #[test]
+    fn nom() {
+        // a type2network type used by nom
+        let (rest, v) = parse::<u32>(&[0, 0, 1, 0, 9]).unwrap();
+        assert_eq!((rest, v), (&[9u8][..], 256));
+        assert!(matches!(
+            parse::<u32>(&[0, 1]),
+            Err(Err::Error(e)) if e.code == NomErrorKind::Eof
+        ));
+
+        // a nom parser used by type2network
+        let bytes = [0x12, 0x34, 0x56];
+        let mut buffer = Cursor::new(&bytes[..]);
+        assert_eq!(run_parser(&mut buffer, be_u16::<_, ()>).unwrap(), 0x1234);
+        assert_eq!(buffer.position(), 2);
+        let e = run_parser(&mut buffer, be_u16::<_, ()>).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,133 @@
+//! Observer of the bytes not understood while deserializing, so operators can detect new protocol extensions
+//! appearing in the wild.
+//!
+//! Within ```with_observer()```, the observer is called with an ```Ignored``` value (the offset and length of the
+//! bytes, and the type being deserialized) when:
+//!
+//! * an enum with a ```#[network(fallback)]``` variant reads an unknown value, the bytes being those of the value
+//! * ```resync::Resync``` skips bytes not starting a valid message
+//! * ```FrameReader```, ```Decoder``` or ```MessageStream``` skip an oversized frame, the bytes being those of its payload
+//! * the padding of ```#[network(pad_block)]``` or ```#[network(align_from_parent)]``` is skipped unverified
+//! * ```opaque::Opaque```, ```opaque::OpaqueRef``` or ```opaque::Raw``` pass bytes through
+//!
+//! ```
+//! use byteorder::ReadBytesExt;
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use type2network::observer::{with_observer, Ignored};
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, PartialEq, FromNetwork)]
+//! #[repr(u8)]
+//! enum Kind {
+//!     #[default]
+//!     Data = 1,
+//!     #[network(fallback)]
+//!     Unknown(u8),
+//! }
+//!
+//! let seen = Rc::new(RefCell::new(Vec::new()));
+//! let observer = {
+//!     let seen = seen.clone();
+//!     move |ignored: &Ignored| seen.borrow_mut().push(*ignored)
+//! };
+//!
+//! let bytes = [1u8, 7];
+//! let kinds = with_observer(observer, || {
+//!     let mut buffer = std::io::Cursor::new(&bytes[..]);
+//!     (Kind::deserialize_new(&mut buffer), Kind::deserialize_new(&mut buffer))
+//! });
+//! assert_eq!(kinds.1.unwrap(), Kind::Unknown(7));
+//! assert_eq!(
+//!     *seen.borrow(),
+//!     &[Ignored { offset: 1, length: 1, container: "Kind" }]
+//! );
+//! ```
+//!
+//! The offsets are those of the buffer being deserialized, which is a frame or a sub-buffer for some types.
+use std::cell::RefCell;
+
+/// Bytes not understood while deserializing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ignored {
+    /// Position of the bytes in the buffer.
+    pub offset: u64,
+
+    /// Number of bytes.
+    pub length: usize,
+
+    /// Name of the type being deserialized.
+    pub container: &'static str,
+}
+
+type Observer = Box<dyn Fn(&Ignored)>;
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Observer>> = const { RefCell::new(None) };
+}
+
+// restores the enclosing observer, even if the closure panics
+struct Restore(Option<Observer>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        OBSERVER.with(|o| *o.borrow_mut() = previous);
+    }
+}
+
+/// Runs ```f``` with ```observer``` called for the bytes not understood. It replaces the enclosing observer, if any.
+pub fn with_observer<R>(observer: impl Fn(&Ignored) + 'static, f: impl FnOnce() -> R) -> R {
+    let previous = OBSERVER.with(|o| o.borrow_mut().replace(Box::new(observer)));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Calls the current observer, if any, with the ```length``` bytes at ```offset``` not understood by
+/// ```container```. It's called by the derived code and the implementations skipping bytes, and can be called by
+/// hand-written implementations.
+pub fn report(offset: u64, length: usize, container: &'static str) {
+    // the observer is taken while called, so it can deserialize values itself without being called again
+    let restore = Restore(OBSERVER.with(|o| o.borrow_mut().take()));
+
+    if let Some(observer) = &restore.0 {
+        observer(&Ignored {
+            offset,
+            length,
+            container,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn observer() {
+        // no observer
+        report(0, 1, "test");
+
+        let count = Rc::new(RefCell::new(0));
+        let observer = {
+            let count = count.clone();
+            move |ignored: &Ignored| {
+                assert_eq!(ignored.container, "test");
+                *count.borrow_mut() += ignored.length;
+
+                // not called again when reporting from the observer
+                report(0, 100, "test");
+            }
+        };
+
+        with_observer(observer, || {
+            report(0, 1, "test");
+            report(3, 2, "test");
+        });
+        report(0, 4, "test");
+        assert_eq!(*count.borrow(), 3);
+    }
+}
@@ -0,0 +1,356 @@
+//! Already-serialized bytes, passed through without interpretation (e.g. extension payloads not understood
+//! by this node).
+//!
+//! * ```Opaque<L>```: the bytes are preceded by their length, encoded as defined by ```L``` (see [`crate::len::WireLen`])
+//! * ```OpaqueRef<'a, L>```: same as ```Opaque<L>```, but borrowing the bytes from the buffer when deserialized
+//! * ```Raw```: the bytes are written verbatim; their number is given by the ```#[network(count = "expr")]```
+//!   attribute when deserializing
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network::opaque::{Opaque, Raw};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Extension {
+//!     kind: u8,
+//!     length: u8,
+//!     #[network(count = "self.length")]
+//!     data: Raw,
+//!     trailer: Opaque<u16>,
+//! }
+//!
+//! let bytes = [7, 2, 0xAA, 0xBB, 0, 1, 0xCC];
+//! let mut ext = Extension::default();
+//! ext.deserialize_from(&mut Cursor::new(bytes.as_slice())).unwrap();
+//! assert_eq!(ext.data.as_slice(), &[0xAA, 0xBB]);
+//! assert_eq!(ext.trailer.as_slice(), &[0xCC]);
+//!
+//! let mut buffer: Vec<u8> = Vec::new();
+//! assert_eq!(ext.serialize_to(&mut buffer).unwrap(), 7);
+//! assert_eq!(buffer, bytes);
+//! ```
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::len::WireLen;
+use crate::uninit::read_to_spare;
+use crate::vec::Push;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Bytes sent verbatim, preceded by their length encoded as defined by ```L``` (e.g. ```u16```).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Opaque<L> {
+    bytes: Vec<u8>,
+    length: PhantomData<L>,
+}
+
+impl<L> Opaque<L> {
+    /// Returns the inner bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<L> Default for Opaque<L> {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L> From<Vec<u8>> for Opaque<L> {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            length: PhantomData,
+        }
+    }
+}
+
+// the length encoding is not relevant
+impl<L> fmt::Debug for Opaque<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Opaque").field(&self.bytes).finish()
+    }
+}
+
+impl<L> AsRef<[u8]> for Opaque<L> {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<L> Deref for Opaque<L> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl<L> DerefMut for Opaque<L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bytes
+    }
+}
+
+impl<L: WireLen> ToNetworkOrder for Opaque<L> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let length = L::write_len(self.bytes.len(), buffer)?;
+        buffer.extend_from_slice(&self.bytes);
+        Ok(length + self.bytes.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let length = L::write_len_to(self.bytes.len(), writer)?;
+        writer.write_all(&self.bytes)?;
+        Ok(length + self.bytes.len())
+    }
+}
+
+impl<'a, L: WireLen> FromNetworkOrder<'a> for Opaque<L> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let length = L::read_len(buffer)?;
+        self.bytes.clear();
+        read_to_spare(buffer, &mut self.bytes, length)?;
+        report(buffer, length, "Opaque");
+        Ok(())
+    }
+}
+
+/// Bytes sent verbatim, preceded by their length encoded as defined by ```L```, and borrowed from the
+/// buffer when deserialized.
+pub struct OpaqueRef<'a, L> {
+    bytes: &'a [u8],
+    length: PhantomData<L>,
+}
+
+impl<'a, L> OpaqueRef<'a, L> {
+    /// Returns the bytes, with the lifetime of the buffer.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<L> Default for OpaqueRef<'_, L> {
+    fn default() -> Self {
+        Self {
+            bytes: &[],
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L> Clone for OpaqueRef<'_, L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L> Copy for OpaqueRef<'_, L> {}
+
+impl<L> PartialEq for OpaqueRef<'_, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<L> Eq for OpaqueRef<'_, L> {}
+
+impl<L> fmt::Debug for OpaqueRef<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OpaqueRef").field(&self.bytes).finish()
+    }
+}
+
+impl<'a, L> From<&'a [u8]> for OpaqueRef<'a, L> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L> AsRef<[u8]> for OpaqueRef<'_, L> {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl<L> Deref for OpaqueRef<'_, L> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.bytes
+    }
+}
+
+impl<L: WireLen> ToNetworkOrder for OpaqueRef<'_, L> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let length = L::write_len(self.bytes.len(), buffer)?;
+        buffer.extend_from_slice(self.bytes);
+        Ok(length + self.bytes.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let length = L::write_len_to(self.bytes.len(), writer)?;
+        writer.write_all(self.bytes)?;
+        Ok(length + self.bytes.len())
+    }
+}
+
+impl<'a, L: WireLen> FromNetworkOrder<'a> for OpaqueRef<'a, L> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let length = L::read_len(buffer)?;
+
+        let data = *buffer.get_ref();
+        let start = (buffer.position() as usize).min(data.len());
+        let end = start.checked_add(length).filter(|end| *end <= data.len());
+
+        match end {
+            Some(end) => {
+                self.bytes = &data[start..end];
+                buffer.set_position(end as u64);
+                report(buffer, length, "OpaqueRef");
+                Ok(())
+            }
+            None => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{} bytes expected, only {} left",
+                    length,
+                    data.len() - start
+                ),
+            )),
+        }
+    }
+}
+
+/// Bytes sent verbatim. When deserialized, as many bytes as already held are read, unless the number
+/// of bytes is given by the ```#[network(count = "expr")]``` attribute.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Raw(pub Vec<u8>);
+
+impl From<Vec<u8>> for Raw {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Deref for Raw {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Raw {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Push<u8> for Raw {
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    fn try_push(&mut self, item: u8) -> std::io::Result<()> {
+        self.0.push(item);
+        Ok(())
+    }
+
+    fn pass_through(&self) -> Option<&'static str> {
+        Some("Raw")
+    }
+}
+
+impl ToNetworkOrder for Raw {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&self.0);
+        Ok(self.0.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&self.0)?;
+        Ok(self.0.len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Raw {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let length = self.0.len();
+        self.0.clear();
+        read_to_spare(buffer, &mut self.0, length)?;
+        report(buffer, length, "Raw");
+        Ok(())
+    }
+}
+
+// the bytes just read are passed through, reported to the observer as not understood
+fn report(buffer: &Cursor<&[u8]>, length: usize, container: &'static str) {
+    if length > 0 {
+        crate::observer::report(buffer.position() - length as u64, length, container);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::len::Varint;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn opaque() {
+        to_network_test(Opaque::<u8>::from(vec![1, 2]), 3, &[2, 1, 2]);
+        to_network_test(Opaque::<Varint>::default(), 1, &[0]);
+        assert!(Opaque::<u8>::from(vec![0; 256])
+            .serialize_to(&mut Vec::new())
+            .is_err());
+
+        from_network_test(
+            Some(Opaque::<u16>::from(vec![0xFF; 5])),
+            Opaque::from(vec![1, 2]),
+            &vec![0, 2, 1, 2],
+        );
+
+        // truncated
+        let b = vec![0, 3, 1, 2];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Opaque::<u16>::default()
+            .deserialize_from(&mut buffer)
+            .is_err());
+    }
+
+    #[test]
+    fn opaque_ref() {
+        let b = vec![0, 2, 1, 2, 3];
+        let mut buffer = Cursor::new(b.as_slice());
+        let mut o = OpaqueRef::<u16>::default();
+        o.deserialize_from(&mut buffer).unwrap();
+        assert_eq!(o.as_bytes(), &[1, 2]);
+        assert_eq!(buffer.position(), 4);
+
+        // borrowed from the buffer
+        assert_eq!(o.as_bytes().as_ptr(), b[2..].as_ptr());
+        to_network_test(o, 4, &[0, 2, 1, 2]);
+
+        // truncated
+        assert!(o.deserialize_from(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn raw() {
+        to_network_test(Raw(vec![1, 2, 3]), 3, &[1, 2, 3]);
+        from_network_test(Some(Raw(vec![0; 2])), Raw(vec![1, 2]), &vec![1, 2, 3]);
+        from_network_test(None, Raw::default(), &vec![1, 2, 3]);
+    }
+}
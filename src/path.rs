@@ -0,0 +1,186 @@
+//! Serialization and Deserialization for OS strings and paths.
+//!
+//! The bytes are preceded by their length as a big-endian ```u32```. On Unix, the bytes of the OS string are sent
+//! as is, so any path is carried without loss. On other platforms, OS strings are sent as UTF-8: those which are not
+//! valid Unicode can't be serialized, and received bytes which are not valid UTF-8 are an ```InvalidData``` error.
+use std::ffi::{OsStr, OsString};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use crate::len::WireLen;
+use crate::uninit::read_to_spare;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+// the bytes sent for an OS string
+#[cfg(unix)]
+fn os_bytes(s: &OsStr) -> std::io::Result<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_bytes(s: &OsStr) -> std::io::Result<&[u8]> {
+    s.to_str().map(str::as_bytes).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is not valid Unicode", s),
+        )
+    })
+}
+
+// the OS string received
+#[cfg(unix)]
+fn os_string(bytes: Vec<u8>) -> std::io::Result<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: Vec<u8>) -> std::io::Result<OsString> {
+    String::from_utf8(bytes)
+        .map(OsString::from)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn serialize_os_str(s: &OsStr, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+    let bytes = os_bytes(s)?;
+    let length = u32::write_len(bytes.len(), buffer)?;
+    buffer.extend_from_slice(bytes);
+    Ok(length + bytes.len())
+}
+
+fn write_os_str(s: &OsStr, writer: &mut dyn Write) -> std::io::Result<usize> {
+    let bytes = os_bytes(s)?;
+    let length = u32::write_len_to(bytes.len(), writer)?;
+    writer.write_all(bytes)?;
+    Ok(length + bytes.len())
+}
+
+fn deserialize_os_string(buffer: &mut Cursor<&[u8]>) -> std::io::Result<OsString> {
+    let length = u32::read_len(buffer)?;
+    let mut bytes = Vec::new();
+    read_to_spare(buffer, &mut bytes, length)?;
+    os_string(bytes)
+}
+
+impl ToNetworkOrder for OsStr {
+    /// Example:
+    /// ```
+    /// use std::ffi::OsStr;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer = Vec::new();
+    /// assert_eq!(OsStr::new("東京").serialize_to(&mut buffer).unwrap(), 10);
+    /// assert_eq!(buffer, [0, 0, 0, 6, 230, 157, 177, 228, 186, 172]);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        serialize_os_str(self, buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        write_os_str(self, writer)
+    }
+}
+
+impl ToNetworkOrder for OsString {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        serialize_os_str(self, buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        write_os_str(self, writer)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for OsString {
+    /// Example:
+    /// ```
+    /// use std::ffi::OsString;
+    /// use std::io::Cursor;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = [0, 0, 0, 2, b'o', b'k'];
+    /// let s = OsString::deserialize_new(&mut Cursor::new(b.as_slice())).unwrap();
+    /// assert_eq!(s, "ok");
+    ///
+    /// // truncated
+    /// let b = [0, 0, 0, 3, b'o', b'k'];
+    /// assert!(OsString::deserialize_new(&mut Cursor::new(b.as_slice())).is_err());
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = deserialize_os_string(buffer)?;
+        Ok(())
+    }
+}
+
+impl ToNetworkOrder for Path {
+    /// Example:
+    /// ```
+    /// use std::path::Path;
+    /// use type2network::ToNetworkOrder;
+    ///
+    /// let mut buffer = Vec::new();
+    /// assert_eq!(Path::new("/tmp").serialize_to(&mut buffer).unwrap(), 8);
+    /// assert_eq!(buffer, [0, 0, 0, 4, b'/', b't', b'm', b'p']);
+    /// ```
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        serialize_os_str(self.as_os_str(), buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        write_os_str(self.as_os_str(), writer)
+    }
+}
+
+impl ToNetworkOrder for PathBuf {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        serialize_os_str(self.as_os_str(), buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        write_os_str(self.as_os_str(), writer)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for PathBuf {
+    /// Example:
+    /// ```
+    /// use std::io::Cursor;
+    /// use std::path::PathBuf;
+    /// use type2network::FromNetworkOrder;
+    ///
+    /// let b = [0, 0, 0, 4, b'/', b't', b'm', b'p'];
+    /// let path = PathBuf::deserialize_new(&mut Cursor::new(b.as_slice())).unwrap();
+    /// assert_eq!(path, PathBuf::from("/tmp"));
+    /// ```
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = PathBuf::from(deserialize_os_string(buffer)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn path() {
+        let path = PathBuf::from("dir/file.txt");
+        let mut bytes = vec![0, 0, 0, 12];
+        bytes.extend_from_slice(b"dir/file.txt");
+        to_network_test(path.clone(), 16, &bytes);
+        from_network_test(None, path, &bytes);
+    }
+
+    // bytes which are not UTF-8 are kept as is
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let s = OsStr::from_bytes(b"caf\xE9").to_os_string();
+        to_network_test(s.clone(), 8, &[0, 0, 0, 4, b'c', b'a', b'f', 0xE9]);
+        from_network_test(None, s, &vec![0, 0, 0, 4, b'c', b'a', b'f', 0xE9]);
+    }
+}
@@ -0,0 +1,237 @@
+//! A field whose value is only known after the message is serialized (e.g. a length or a checksum).
+//!
+//! A ```Placeholder<T>``` serializes a zeroed slot of the size of ```T```. When the message is serialized by
+//! ```serialize_with_slots()```, the slots are recorded in the returned ```Slots```, and patched with the final values
+//! once the whole message is serialized:
+//!
+//! ```
+//! use type2network::ToNetworkOrder;
+//! use type2network::placeholder::{serialize_with_slots, Placeholder};
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! struct Message {
+//!     id: u8,
+//!     length: Placeholder<u16>,
+//!     data: Vec<u8>,
+//! }
+//!
+//! let msg = Message {
+//!     id: 1,
+//!     length: Placeholder::default(),
+//!     data: vec![0xAA; 3],
+//! };
+//!
+//! let mut buffer: Vec<u8> = Vec::new();
+//! let (length, slots) = serialize_with_slots(&msg, &mut buffer).unwrap();
+//! assert_eq!(length, 6);
+//! assert_eq!(buffer, &[1, 0, 0, 0xAA, 0xAA, 0xAA]);
+//!
+//! // the length of the data following the length field
+//! let length = buffer.len() - slots.offset(&msg.length).unwrap() - 2;
+//! slots.patch(&mut buffer, &msg.length, &(length as u16)).unwrap();
+//! assert_eq!(buffer, &[1, 0, 3, 0xAA, 0xAA, 0xAA]);
+//! ```
+//!
+//! The placeholder itself isn't modified, so a message can be serialized by several threads at once (e.g. by
+//! ```batch::par_serialize_all()```). The offsets are those in the buffer given to ```serialize_with_slots()```, so
+//! placeholders inside values serialized into a buffer of their own (e.g. signed or encrypted) can't be patched.
+use std::cell::RefCell;
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+use std::ops::Range;
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+thread_local! {
+    // the slots serialized within serialize_with_slots()
+    static SLOTS: RefCell<Option<Vec<Slot>>> = const { RefCell::new(None) };
+}
+
+// restores the slots of the enclosing serialize_with_slots(), even if the serialization panics
+struct Restore(Option<Vec<Slot>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        SLOTS.with(|s| *s.borrow_mut() = self.0.take())
+    }
+}
+
+// a placeholder, identified by its address, and the bytes of its slot
+#[derive(Debug, Clone)]
+struct Slot {
+    id: usize,
+    bytes: Range<usize>,
+}
+
+/// Serializes ```value``` to ```buffer```, and returns the number of bytes written with the slots of the
+/// placeholders serialized.
+pub fn serialize_with_slots<T>(value: &T, buffer: &mut Vec<u8>) -> std::io::Result<(usize, Slots)>
+where
+    T: ToNetworkOrder + ?Sized,
+{
+    let _restore = Restore(SLOTS.with(|s| s.replace(Some(Vec::new()))));
+    let length = value.serialize_to(buffer)?;
+    let slots = SLOTS.with(|s| s.borrow_mut().take()).unwrap_or_default();
+
+    Ok((length, Slots(slots)))
+}
+
+/// The slots of the placeholders serialized by ```serialize_with_slots()```.
+#[derive(Debug, Default, Clone)]
+pub struct Slots(Vec<Slot>);
+
+impl Slots {
+    /// Returns the offset of the slot of ```placeholder``` in the buffer, if it was serialized.
+    pub fn offset<T>(&self, placeholder: &Placeholder<T>) -> Option<usize> {
+        self.slot(placeholder).map(|bytes| bytes.start)
+    }
+
+    /// Overwrites the slot of ```placeholder``` in ```buffer``` with ```value```. An error is returned if the
+    /// placeholder was not serialized, or if ```value``` has not the size of the slot.
+    pub fn patch<T: ToNetworkOrder>(
+        &self,
+        buffer: &mut [u8],
+        placeholder: &Placeholder<T>,
+        value: &T,
+    ) -> std::io::Result<()> {
+        let slot = self
+            .slot(placeholder)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "placeholder was not serialized"))?;
+
+        let mut bytes: Vec<u8> = Vec::new();
+        value.serialize_to(&mut bytes)?;
+
+        if bytes.len() != slot.len() || slot.end > buffer.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "patched value doesn't fit in the placeholder slot",
+            ));
+        }
+
+        buffer[slot].copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    // the bytes of the last slot of the placeholder
+    fn slot<T>(&self, placeholder: &Placeholder<T>) -> Option<Range<usize>> {
+        let id = placeholder.id();
+        self.0
+            .iter()
+            .rev()
+            .find(|slot| slot.id == id)
+            .map(|slot| slot.bytes.clone())
+    }
+}
+
+/// A value serialized as a zeroed slot, to be patched after the serialization. When deserialized,
+/// the value is read as a ```T```.
+#[derive(Debug, Default, Clone)]
+pub struct Placeholder<T> {
+    value: T,
+}
+
+impl<T> Placeholder<T> {
+    /// Creates a placeholder holding ```value```, which is only used when deserializing.
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// Returns the deserialized value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    // placeholders are told apart by their address
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+}
+
+impl<T: PartialEq> PartialEq for Placeholder<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: ToNetworkOrder> ToNetworkOrder for Placeholder<T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let offset = buffer.len();
+        let length = self.value.serialize_to(buffer)?;
+
+        // zero the slot
+        buffer[offset..].fill(0);
+        SLOTS.with(|s| {
+            if let Some(slots) = s.borrow_mut().as_mut() {
+                slots.push(Slot {
+                    id: self.id(),
+                    bytes: offset..offset + length,
+                });
+            }
+        });
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        // the slot is written without being recorded, the bytes going to an unknown buffer
+        let length = crate::size::wire_size_of(&self.value)?;
+        std::io::copy(&mut std::io::repeat(0).take(length as u64), writer)?;
+        Ok(length)
+    }
+}
+
+impl<'a, T: FromNetworkOrder<'a>> FromNetworkOrder<'a> for Placeholder<T> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.value.deserialize_from(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::from_network_test;
+
+    #[test]
+    fn placeholder() {
+        let p = Placeholder::new(0xFFFF_u16);
+        assert!(Slots::default().patch(&mut [0; 2], &p, &1).is_err());
+
+        let mut buffer = vec![0xAA];
+        let (length, slots) = serialize_with_slots(&p, &mut buffer).unwrap();
+        assert_eq!(length, 2);
+        assert_eq!(buffer, &[0xAA, 0, 0]);
+        assert_eq!(slots.offset(&p), Some(1));
+
+        slots.patch(&mut buffer, &p, &0x1234).unwrap();
+        assert_eq!(buffer, &[0xAA, 0x12, 0x34]);
+
+        // slot outside the buffer
+        assert!(slots.patch(&mut buffer[..2], &p, &0x1234).is_err());
+
+        // another placeholder, and the slots are only recorded within serialize_with_slots()
+        let q = Placeholder::new(0u16);
+        assert_eq!(slots.offset(&q), None);
+        q.serialize_to(&mut buffer).unwrap();
+        assert!(SLOTS.with(|s| s.borrow().is_none()));
+
+        // written without the slot
+        let mut bytes = Vec::new();
+        assert_eq!(p.write_to(&mut bytes).unwrap(), 2);
+        assert_eq!(bytes, &[0, 0]);
+
+        from_network_test(None, Placeholder::new(0x1234_u16), &vec![0x12, 0x34]);
+    }
+
+    #[test]
+    fn sync() {
+        fn is_sync<T: Sync>() {}
+        is_sync::<Placeholder<u16>>();
+
+        #[cfg(feature = "rayon")]
+        {
+            let messages = vec![(1u8, Placeholder::new(0u16)); 4];
+            let buffers = crate::batch::par_serialize_all(&messages).unwrap();
+            assert!(buffers.iter().all(|b| b == &[1, 0, 0]));
+        }
+    }
+}
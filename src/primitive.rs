@@ -1,17 +1,22 @@
 //! All functions/trait to convert DNS structures to network order back & forth for
 //! primitive types.
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Write;
 
+use crate::codec;
 use crate::{FromNetworkOrder, ToNetworkOrder};
 
 // helper macro for boiler plate definitions
 //#[macro_export]
 macro_rules! impl_primitive {
-    ($t:ty, $fw:path, $fr:path) => {
+    ($t:ty, $put:ident, $get:ident) => {
         impl ToNetworkOrder for $t {
             fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
-                $fw(buffer, *self as $t)?;
+                codec::$put(buffer, *self);
+                Ok(std::mem::size_of::<$t>())
+            }
+
+            fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+                writer.write_all(&self.to_be_bytes())?;
                 Ok(std::mem::size_of::<$t>())
             }
         }
@@ -21,7 +26,7 @@ macro_rules! impl_primitive {
                 &mut self,
                 buffer: &mut std::io::Cursor<&'a [u8]>,
             ) -> std::io::Result<()> {
-                *self = $fr(buffer)?;
+                *self = codec::$get(buffer)?;
                 Ok(())
             }
         }
@@ -29,65 +34,28 @@ macro_rules! impl_primitive {
 }
 
 // signed
-impl_primitive!(i8, WriteBytesExt::write_i8, ReadBytesExt::read_i8);
-impl_primitive!(
-    i16,
-    WriteBytesExt::write_i16::<BigEndian>,
-    ReadBytesExt::read_i16::<BigEndian>
-);
-impl_primitive!(
-    i32,
-    WriteBytesExt::write_i32::<BigEndian>,
-    ReadBytesExt::read_i32::<BigEndian>
-);
-impl_primitive!(
-    i64,
-    WriteBytesExt::write_i64::<BigEndian>,
-    ReadBytesExt::read_i64::<BigEndian>
-);
-impl_primitive!(
-    i128,
-    WriteBytesExt::write_i128::<BigEndian>,
-    ReadBytesExt::read_i128::<BigEndian>
-);
+impl_primitive!(i8, put_i8, get_i8);
+impl_primitive!(i16, put_i16, get_i16);
+impl_primitive!(i32, put_i32, get_i32);
+impl_primitive!(i64, put_i64, get_i64);
+#[cfg(feature = "int128")]
+impl_primitive!(i128, put_i128, get_i128);
 
 // unsigned
-impl_primitive!(u8, WriteBytesExt::write_u8, ReadBytesExt::read_u8);
-impl_primitive!(
-    u16,
-    WriteBytesExt::write_u16::<BigEndian>,
-    ReadBytesExt::read_u16::<BigEndian>
-);
-impl_primitive!(
-    u32,
-    WriteBytesExt::write_u32::<BigEndian>,
-    ReadBytesExt::read_u32::<BigEndian>
-);
-impl_primitive!(
-    u64,
-    WriteBytesExt::write_u64::<BigEndian>,
-    ReadBytesExt::read_u64::<BigEndian>
-);
-impl_primitive!(
-    u128,
-    WriteBytesExt::write_u128::<BigEndian>,
-    ReadBytesExt::read_u128::<BigEndian>
-);
+impl_primitive!(u8, put_u8, get_u8);
+impl_primitive!(u16, put_u16, get_u16);
+impl_primitive!(u32, put_u32, get_u32);
+impl_primitive!(u64, put_u64, get_u64);
+#[cfg(feature = "int128")]
+impl_primitive!(u128, put_u128, get_u128);
 
 // // floats
-impl_primitive!(
-    f32,
-    WriteBytesExt::write_f32::<BigEndian>,
-    ReadBytesExt::read_f32::<BigEndian>
-);
-impl_primitive!(
-    f64,
-    WriteBytesExt::write_f64::<BigEndian>,
-    ReadBytesExt::read_f64::<BigEndian>
-);
+impl_primitive!(f32, put_f32, get_f32);
+impl_primitive!(f64, put_f64, get_f64);
 
 impl ToNetworkOrder for char {
-    /// ```char``` is serialized as 4 bytes.
+    /// ```char``` is serialized as 4 bytes. Use the ```#[network(char_as = "utf8")]``` field attribute
+    /// or ```text::Utf8Char``` to send its UTF-8 bytes instead.
     /// Example:
     /// ```
     /// use type2network::ToNetworkOrder;
@@ -106,6 +74,10 @@ impl ToNetworkOrder for char {
         //println!("u={} buffer={:?}", u, buffer);
         Ok(std::mem::size_of::<char>())
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        (*self as u32).write_to(writer)
+    }
 }
 
 impl<'a> FromNetworkOrder<'a> for char {
@@ -124,7 +96,12 @@ impl<'a> FromNetworkOrder<'a> for char {
         // convert first to u32
         let mut u = 0_u32;
         u.deserialize_from(buffer)?;
-        *self = char::from_u32(u).unwrap();
+        *self = char::from_u32(u).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{:#x} is not a Unicode scalar value", u),
+            )
+        })?;
 
         Ok(())
     }
@@ -143,6 +120,11 @@ impl ToNetworkOrder for [u8] {
         _ = buffer.write(self.as_ref())?;
         Ok(self.len())
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        writer.write_all(self)?;
+        Ok(self.len())
+    }
 }
 
 // can't implement this
@@ -165,6 +147,11 @@ impl ToNetworkOrder for &str {
         _ = buffer.write(self.as_bytes())?;
         Ok(self.len())
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
 }
 
 // a void implementation to ease integration test
@@ -187,12 +174,21 @@ impl ToNetworkOrder for String {
         _ = buffer.write(self.as_bytes())?;
         Ok(self.len())
     }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
 }
 
 impl ToNetworkOrder for () {
     fn serialize_to(&self, _buffer: &mut Vec<u8>) -> std::io::Result<usize> {
         Ok(0)
     }
+
+    fn write_to(&self, _writer: &mut dyn Write) -> std::io::Result<usize> {
+        Ok(0)
+    }
 }
 
 // impl<'a> FromNetworkOrder<'a> for String {
@@ -267,4 +263,38 @@ mod tests {
         // char
         from_network_test(None, '💯', &vec![0, 1, 244, 175]);
     }
+
+    // explicit vectors, as some toolchains mis-handle 128-bit shifts
+    #[cfg(feature = "int128")]
+    #[test]
+    fn int128() {
+        let bytes: Vec<u8> = (1..=16).collect();
+        let vectors: [(u128, Vec<u8>); 4] = [
+            (0, vec![0; 16]),
+            (1, [vec![0; 15], vec![1]].concat()),
+            (0x0102030405060708090A0B0C0D0E0F10, bytes.clone()),
+            (u128::MAX, vec![0xFF; 16]),
+        ];
+        for (value, bytes) in vectors {
+            to_network_test(value, 16, &bytes);
+            from_network_test(None, value, &bytes);
+        }
+
+        let vectors: [(i128, Vec<u8>); 4] = [
+            (-1, vec![0xFF; 16]),
+            (i128::MIN, [vec![0x80], vec![0; 15]].concat()),
+            (i128::MAX, [vec![0x7F], vec![0xFF; 15]].concat()),
+            (
+                -0x0102030405060708090A0B0C0D0E0F10,
+                vec![
+                    0xFE, 0xFD, 0xFC, 0xFB, 0xFA, 0xF9, 0xF8, 0xF7, 0xF6, 0xF5, 0xF4, 0xF3, 0xF2,
+                    0xF1, 0xF0, 0xF0,
+                ],
+            ),
+        ];
+        for (value, bytes) in vectors {
+            to_network_test(value, 16, &bytes);
+            from_network_test(None, value, &bytes);
+        }
+    }
 }
@@ -0,0 +1,335 @@
+//! One-shot request/response exchanges: the request is serialized and sent, then the response is received
+//! and deserialized.
+//!
+//! ```no_run
+//! use std::net::UdpSocket;
+//! use std::time::Duration;
+//! use type2network::query::query;
+//!
+//! let mut socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+//! socket.connect("127.0.0.1:9999").unwrap();
+//!
+//! let response: u32 = query(&mut socket, &0x1234_u16, Duration::from_secs(2)).unwrap();
+//! ```
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::framing::{decode_frame, read_frame, write_frame};
+use crate::secret::Scratch;
+use crate::socket::{TcpStreamExt, UdpSocketExt};
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A connected transport able to send and receive whole messages.
+///
+/// It's implemented for a connected ```UdpSocket``` (a message per datagram) and for ```TcpStream```
+/// (a message per frame).
+pub trait Transport {
+    /// Sends a message.
+    fn send_msg<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize>;
+
+    /// Receives a message.
+    fn recv_msg<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default;
+
+    /// Returns the current read and write timeouts.
+    fn timeouts(&self) -> std::io::Result<(Option<Duration>, Option<Duration>)>;
+
+    /// Sets the read and write timeouts.
+    fn set_timeouts(
+        &mut self,
+        read: Option<Duration>,
+        write: Option<Duration>,
+    ) -> std::io::Result<()>;
+
+    /// Sends a message before ```deadline```. By default, the timeouts are set to the time left, which bounds the
+    /// whole sending for the transports sending a message in a single write (e.g. a datagram).
+    fn send_msg_before<T: ToNetworkOrder>(
+        &mut self,
+        msg: &T,
+        deadline: Instant,
+    ) -> std::io::Result<usize> {
+        let left = time_left(deadline)?;
+        self.set_timeouts(Some(left), Some(left))?;
+        self.send_msg(msg)
+    }
+
+    /// Receives a message before ```deadline```. By default, the timeouts are set to the time left, which bounds
+    /// the whole reception for the transports receiving a message in a single read (e.g. a datagram).
+    fn recv_msg_before<T>(&mut self, deadline: Instant) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        let left = time_left(deadline)?;
+        self.set_timeouts(Some(left), Some(left))?;
+        self.recv_msg()
+    }
+}
+
+// the time left before the deadline, or a TimedOut error once it's exceeded
+fn time_left(deadline: Instant) -> std::io::Result<Duration> {
+    deadline
+        .checked_duration_since(Instant::now())
+        .filter(|left| !left.is_zero())
+        .ok_or_else(|| Error::new(ErrorKind::TimedOut, "deadline exceeded"))
+}
+
+// a TCP stream whose reads and writes are limited to the time left before the deadline, as a frame can take
+// several of them
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream
+            .set_read_timeout(Some(time_left(self.deadline)?))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream
+            .set_write_timeout(Some(time_left(self.deadline)?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for UdpSocket {
+    fn send_msg<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize> {
+        UdpSocketExt::send_msg(self, msg)
+    }
+
+    fn recv_msg<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        UdpSocketExt::recv_msg(self)
+    }
+
+    fn timeouts(&self) -> std::io::Result<(Option<Duration>, Option<Duration>)> {
+        Ok((self.read_timeout()?, self.write_timeout()?))
+    }
+
+    fn set_timeouts(
+        &mut self,
+        read: Option<Duration>,
+        write: Option<Duration>,
+    ) -> std::io::Result<()> {
+        self.set_read_timeout(read)?;
+        self.set_write_timeout(write)
+    }
+}
+
+impl Transport for TcpStream {
+    fn send_msg<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize> {
+        self.write_framed(msg)
+    }
+
+    fn recv_msg<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        self.read_framed()
+    }
+
+    fn timeouts(&self) -> std::io::Result<(Option<Duration>, Option<Duration>)> {
+        Ok((self.read_timeout()?, self.write_timeout()?))
+    }
+
+    fn set_timeouts(
+        &mut self,
+        read: Option<Duration>,
+        write: Option<Duration>,
+    ) -> std::io::Result<()> {
+        self.set_read_timeout(read)?;
+        self.set_write_timeout(write)
+    }
+
+    fn send_msg_before<T: ToNetworkOrder>(
+        &mut self,
+        msg: &T,
+        deadline: Instant,
+    ) -> std::io::Result<usize> {
+        let stream = &*self;
+        write_frame(&mut Deadline { stream, deadline }, msg)
+    }
+
+    fn recv_msg_before<T>(&mut self, deadline: Instant) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        let stream = &*self;
+        decode_frame(&Scratch(read_frame(&mut Deadline { stream, deadline })?))
+    }
+}
+
+/// Sends the request and waits for the response. The whole exchange is limited to ```timeout```, however many
+/// reads and writes it takes, after which an error of kind ```ErrorKind::TimedOut``` is returned. The previous
+/// timeouts of the transport are restored afterwards.
+pub fn query<Req, Resp, T>(transport: &mut T, req: &Req, timeout: Duration) -> std::io::Result<Resp>
+where
+    Req: ToNetworkOrder,
+    Resp: for<'a> FromNetworkOrder<'a> + Default,
+    T: Transport,
+{
+    let deadline = Instant::now() + timeout;
+    let (read, write) = transport.timeouts()?;
+
+    let result = transport
+        .send_msg_before(req, deadline)
+        .and_then(|_| transport.recv_msg_before(deadline))
+        .map_err(|e| match e.kind() {
+            // a timeout is reported as WouldBlock on Unix platforms
+            ErrorKind::WouldBlock => std::io::Error::new(ErrorKind::TimedOut, e),
+            _ => e,
+        });
+
+    transport.set_timeouts(read, write)?;
+    result
+}
+
+/// Same as ```query()``` over an asynchronous byte stream (```async``` feature): the request and the
+/// response are sent as frames (see the [`crate::framing`] module). A response frame longer than
+/// ```max_frame_size``` bytes is rejected with an ```InvalidData``` error before being read.
+///
+/// As timers depend on the async runtime, there's no timeout: the future is given an overall deadline by
+/// wrapping it in the timeout of the runtime (e.g. ```tokio::time::timeout()```), which drops it once expired
+/// whatever it's waiting for.
+///
+/// ```
+/// use futures::{executor::block_on, io::Cursor};
+/// use type2network::query::query_async;
+///
+/// // the request frame overwrites the first 6 bytes, the response follows
+/// let mut stream = Cursor::new(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0x56, 0x78]);
+/// let response: u16 = block_on(query_async(&mut stream, &0x1234_u16, 1024)).unwrap();
+/// assert_eq!(response, 0x5678);
+/// assert_eq!(&stream.get_ref()[..6], &[0, 0, 0, 2, 0x12, 0x34]);
+/// ```
+#[cfg(feature = "async")]
+pub async fn query_async<Req, Resp, S>(
+    stream: &mut S,
+    req: &Req,
+    max_frame_size: usize,
+) -> std::io::Result<Resp>
+where
+    Req: ToNetworkOrder,
+    Resp: for<'a> FromNetworkOrder<'a> + Default,
+    S: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+{
+    use futures_util::io::AsyncWriteExt;
+    use futures_util::StreamExt;
+
+    use crate::stream::MessageStream;
+
+    let mut buffer = Vec::new();
+    write_frame(&mut buffer, req)?;
+    stream.write_all(&buffer).await?;
+    stream.flush().await?;
+
+    // the stream reads the response frame, and nothing after it
+    let mut responses = MessageStream::<Resp, _>::new(stream).with_max_frame_size(max_frame_size);
+    responses.next().await.unwrap_or_else(|| {
+        Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "connection closed before the response",
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn udp_query() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(silent.local_addr().unwrap()).unwrap();
+
+        // no response
+        let err = query::<_, u8, _>(&mut client, &1_u8, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(client.read_timeout().unwrap(), None);
+
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+
+        // the server answers the double of the request
+        let handle = std::thread::spawn(move || {
+            let (req, addr): (u16, _) = server.recv_msg_from().unwrap();
+            server.send_msg_to(&(req as u32 * 2), addr).unwrap();
+        });
+
+        let resp: u32 = query(&mut client, &0x1234_u16, Duration::from_secs(5)).unwrap();
+        assert_eq!(resp, 0x2468);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn tcp_query() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let req: u8 = stream.read_framed().unwrap();
+            stream.write_framed(&[req; 3]).unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        let resp: [u8; 3] = query(&mut client, &7_u8, Duration::from_secs(5)).unwrap();
+        assert_eq!(resp, [7, 7, 7]);
+        handle.join().unwrap();
+
+        // a response sent byte by byte, each byte before the timeout of a single read
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _: u8 = stream.read_framed().unwrap();
+            for b in [0u8, 0, 0, 8, 1, 2, 3, 4, 5, 6, 7, 8] {
+                if stream.write_all(&[b]).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(30));
+            }
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        let start = Instant::now();
+        let err = query::<_, u64, _>(&mut client, &7_u8, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_millis(300));
+        assert_eq!(client.read_timeout().unwrap(), None);
+
+        drop(client);
+        handle.join().unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_query() {
+        use futures::{executor::block_on, io::Cursor};
+
+        // the response frame is too long
+        let mut stream = Cursor::new(vec![0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let err = block_on(query_async::<_, u16, _>(&mut stream, &0x1234_u16, 1024)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // no response
+        let mut stream = Cursor::new(vec![0; 6]);
+        let err = block_on(query_async::<_, u16, _>(&mut stream, &0x1234_u16, 1024)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
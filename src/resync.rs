@@ -0,0 +1,214 @@
+//! Resynchronization of a byte stream on the magic of its messages.
+//!
+//! On lossy serial links, or when a capture starts in the middle of a stream, the bytes don't start at a message
+//! boundary. When the messages start with a magic, the parsing can resume at its next occurrence after an error.
+//! ```FromNetwork``` implements the ```WireMagic``` trait for the structs whose first field on the wire has the
+//! ```#[network(magic = value)]``` attribute, and ```Resync``` iterates over the messages found in a buffer:
+//!
+//! ```
+//! use type2network::resync::Resync;
+//! use type2network::FromNetworkOrder;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, PartialEq, FromNetwork)]
+//! struct Sample {
+//!     #[network(magic = 0xA55A)]
+//!     sync: u16,
+//!     value: u16,
+//! }
+//!
+//! // garbage, a sample, a truncated sample and a sample
+//! let bytes = [0x12, 0xA5, 0x5A, 0x00, 0x01, 0xA5, 0x5A, 0xA5, 0x5A, 0x00, 0x02];
+//! let mut samples = Resync::<Sample>::new(&bytes);
+//!
+//! let values: Vec<u16> = samples.by_ref().map(|s| s.value).collect();
+//! assert_eq!(values, &[1, 0xA55A]);
+//! assert_eq!(samples.skipped(), 3);
+//! ```
+//!
+//! In the example above, the truncated sample is read with the first bytes of the next one. As a magic can also be
+//! found inside a message, the messages should be checked (e.g. with a checksum) when this matters.
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+use crate::FromNetworkOrder;
+
+/// Types starting with a magic on the wire.
+pub trait WireMagic {
+    /// Returns the bytes of the magic.
+    fn wire_magic() -> Vec<u8>;
+}
+
+/// Returns the position of the first occurrence of ```magic``` in ```bytes```.
+pub fn find_magic(bytes: &[u8], magic: &[u8]) -> Option<usize> {
+    if magic.is_empty() {
+        return Some(0);
+    }
+    bytes.windows(magic.len()).position(|w| w == magic)
+}
+
+/// Moves the buffer to the next occurrence of ```magic```, after the current position, and returns the number of
+/// bytes skipped. It's called after an error, with the buffer at the position where the message started.
+///
+/// If the magic isn't found, the last bytes which are the start of a magic are kept, so the bytes received next
+/// can complete it.
+pub fn resync(buffer: &mut Cursor<&[u8]>, magic: &[u8]) -> usize {
+    let bytes = *buffer.get_ref();
+    let start = (buffer.position() as usize).min(bytes.len());
+
+    // the magic at the current position is the one of the message which failed
+    let from = (start + 1).min(bytes.len());
+    let next = match find_magic(&bytes[from..], magic) {
+        Some(offset) => from + offset,
+        None => bytes.len() - magic_start(&bytes[from..], magic),
+    };
+
+    buffer.set_position(next as u64);
+    next - start
+}
+
+// the number of bytes at the end which are the start of the magic
+fn magic_start(bytes: &[u8], magic: &[u8]) -> usize {
+    (1..magic.len().min(bytes.len() + 1))
+        .rev()
+        .find(|n| magic.starts_with(&bytes[bytes.len() - n..]))
+        .unwrap_or(0)
+}
+
+/// An iterator over the messages of a buffer, which skips the bytes not starting a valid message.
+#[derive(Debug)]
+pub struct Resync<'a, T> {
+    buffer: Cursor<&'a [u8]>,
+    magic: Vec<u8>,
+
+    // bytes not part of a message
+    skipped: usize,
+
+    message: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> Resync<'a, T>
+where
+    T: FromNetworkOrder<'a> + Default,
+{
+    /// Iterates over the messages found in ```bytes```, using the magic of ```T```.
+    pub fn new(bytes: &'a [u8]) -> Self
+    where
+        T: WireMagic,
+    {
+        Self::with_magic(bytes, &T::wire_magic())
+    }
+
+    /// Iterates over the messages found in ```bytes```, starting with ```magic```.
+    pub fn with_magic(bytes: &'a [u8], magic: &[u8]) -> Self {
+        Self {
+            buffer: Cursor::new(bytes),
+            magic: magic.to_vec(),
+            skipped: 0,
+            message: PhantomData,
+        }
+    }
+
+    /// Returns the number of bytes skipped so far, which were not part of a message.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    // counts the bytes skipped, and reports them to the observer
+    fn skip(&mut self, offset: usize, length: usize) {
+        if length > 0 {
+            self.skipped += length;
+            crate::observer::report(offset as u64, length, std::any::type_name::<T>());
+        }
+    }
+
+    /// Returns the position of the next byte to read. The bytes from there are those left once the iteration is
+    /// over, e.g. to be completed with the next bytes received.
+    pub fn offset(&self) -> usize {
+        self.buffer.position() as usize
+    }
+}
+
+impl<'a, T> Iterator for Resync<'a, T>
+where
+    T: FromNetworkOrder<'a> + Default,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let bytes = *self.buffer.get_ref();
+
+        loop {
+            let start = self.offset();
+
+            // align on the next magic
+            match find_magic(&bytes[start..], &self.magic) {
+                Some(offset) => {
+                    self.skip(start, offset);
+                    self.buffer.set_position((start + offset) as u64);
+                }
+                None => {
+                    let end = bytes.len() - magic_start(&bytes[start..], &self.magic);
+                    self.skip(start, end - start);
+                    self.buffer.set_position(end as u64);
+                    return None;
+                }
+            }
+
+            let start = self.offset();
+            match T::deserialize_new(&mut self.buffer) {
+                Ok(msg) => return Some(msg),
+                Err(_) => {
+                    self.buffer.set_position(start as u64);
+                    let skipped = resync(&mut self.buffer, &self.magic);
+                    self.skip(start, skipped);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resync_magic() {
+        assert_eq!(find_magic(&[1, 2, 3, 2, 3], &[2, 3]), Some(1));
+        assert_eq!(find_magic(&[1, 2], &[2, 3]), None);
+
+        // the magic of the failed message is skipped
+        let bytes = [0xCA, 0xFE, 1, 0xCA, 0xFE, 2, 0xCA];
+        let mut buffer = Cursor::new(&bytes[..]);
+        assert_eq!(resync(&mut buffer, &[0xCA, 0xFE]), 3);
+        assert_eq!(resync(&mut buffer, &[0xCA, 0xFE]), 3);
+
+        // the last byte could start a magic
+        assert_eq!(buffer.position(), 6);
+        assert_eq!(resync(&mut buffer, &[0xCA, 0xFE]), 1);
+        assert_eq!(buffer.position(), 7);
+
+        // only messages starting with the magic are returned
+        let bytes = [0, 0xCA, 0xFE, 0, 1, 0xFF, 0xCA, 0xFE, 0];
+        let mut messages = Resync::<(u16, u16)>::with_magic(&bytes, &[0xCA, 0xFE]);
+        let skipped = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let observer = {
+            let skipped = skipped.clone();
+            move |ignored: &crate::observer::Ignored| {
+                skipped.borrow_mut().push((ignored.offset, ignored.length))
+            }
+        };
+        crate::observer::with_observer(observer, || {
+            assert_eq!(messages.next(), Some((0xCAFE, 1)));
+            assert_eq!(messages.next(), None);
+        });
+        assert_eq!(messages.skipped(), 5);
+        assert_eq!(*skipped.borrow(), &[(0, 1), (5, 1), (6, 3)]);
+        assert_eq!(messages.offset(), 9);
+
+        // a magic can be completed by the next bytes
+        let mut messages = Resync::<(u16, u16)>::with_magic(&[0, 0xCA], &[0xCA, 0xFE]);
+        assert_eq!(messages.next(), None);
+        assert_eq!(messages.offset(), 1);
+    }
+}
@@ -0,0 +1,319 @@
+//! Secret values (e.g. authentication tokens or digests) which should not leak into logs.
+//!
+//! A ```Secret<T>``` is serialized and deserialized as ```T```, but:
+//!
+//! * its ```Debug``` output is redacted, as well as its bytes in the output of ```#[network(hexdebug)]```: the bytes of
+//!   a field are redacted if a secret is serialized with them, whatever the wrappers holding it (e.g. a tuple, a
+//!   ```der::DerTlv``` or a derived enum)
+//! * the equality of secrets made of bytes is computed in constant time, so that comparing a received token with the
+//!   expected one doesn't leak how many bytes match
+//!
+//! With the ```zeroize``` feature, the value of a ```Secret<T>``` is zeroized when dropped by its [`Wipe`] impl, ```T```
+//! implementing ```Wipe``` with or without the feature. So are the scratch buffers holding received bytes while deserializing (e.g. the
+//! datagrams received by [`crate::socket::UdpSocketExt`]).
+//!
+//! ```
+//! use type2network::secret::Secret;
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(Debug, ToNetwork)]
+//! #[network(hexdebug)]
+//! struct Login {
+//!     user: u16,
+//!     token: Secret<[u8; 4]>,
+//! }
+//!
+//! let login = Login { user: 1, token: Secret::new([1, 2, 3, 4]) };
+//! assert_eq!(format!("{:?}", login), "Login { user: 1, token: Secret(<redacted>) }");
+//! assert!(login.fmt_wire().lines().last().unwrap().ends_with("<redacted>"));
+//! assert!(login.token == Secret::new([1, 2, 3, 4]));
+//! ```
+use std::cell::Cell;
+use std::fmt;
+use std::io::Cursor;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A value which can be wiped from memory, as required by ```Secret<T>```. The bound is the same whether the
+/// ```zeroize``` feature is enabled or not: only ```wipe()``` depends on it, zeroizing the value with the feature and
+/// doing nothing otherwise.
+///
+/// It's implemented for the integers, ```bool```, ```char```, ```String```, and the arrays, slices, ```Vec```, ```Box```
+/// and ```Option``` of those. Other types implement it by wiping their fields, or with an empty impl:
+///
+/// ```
+/// use type2network::secret::{Secret, Wipe};
+///
+/// struct Token {
+///     id: u16,
+///     key: [u8; 16],
+/// }
+///
+/// impl Wipe for Token {
+///     fn wipe(&mut self) {
+///         self.id.wipe();
+///         self.key.wipe();
+///     }
+/// }
+///
+/// let token = Secret::new(Token { id: 1, key: [0xAA; 16] });
+/// assert_eq!(token.expose().id, 1);
+/// ```
+pub trait Wipe {
+    /// Overwrites the value with zeroes, without being optimized away. It does nothing by default.
+    fn wipe(&mut self) {}
+}
+
+// the types zeroized as a whole by the zeroize crate
+macro_rules! impl_wipe {
+    ($($t:ty),*) => {
+        $(
+            impl Wipe for $t {
+                fn wipe(&mut self) {
+                    #[cfg(feature = "zeroize")]
+                    zeroize::Zeroize::zeroize(self);
+                }
+            }
+        )*
+    };
+}
+
+impl_wipe!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, char, String, str);
+
+impl<T: Wipe> Wipe for [T] {
+    fn wipe(&mut self) {
+        self.iter_mut().for_each(Wipe::wipe);
+    }
+}
+
+impl<T: Wipe, const N: usize> Wipe for [T; N] {
+    fn wipe(&mut self) {
+        self.as_mut_slice().wipe();
+    }
+}
+
+impl<T: Wipe> Wipe for Vec<T> {
+    // the elements are wiped and dropped, then the whole capacity is zeroized
+    fn wipe(&mut self) {
+        self.as_mut_slice().wipe();
+        self.clear();
+
+        #[cfg(feature = "zeroize")]
+        {
+            let spare = self.spare_capacity_mut();
+
+            // SAFETY: the spare capacity is memory not holding any value, which can be overwritten byte per byte
+            let bytes = unsafe {
+                std::slice::from_raw_parts_mut(
+                    spare.as_mut_ptr().cast::<std::mem::MaybeUninit<u8>>(),
+                    std::mem::size_of_val(spare),
+                )
+            };
+            zeroize::Zeroize::zeroize(bytes);
+        }
+    }
+}
+
+impl<T: Wipe + ?Sized> Wipe for Box<T> {
+    fn wipe(&mut self) {
+        (**self).wipe();
+    }
+}
+
+impl<T: Wipe> Wipe for Option<T> {
+    fn wipe(&mut self) {
+        if let Some(value) = self {
+            value.wipe();
+        }
+    }
+}
+
+/// A value sent as is, but redacted in ```Debug``` output and compared in constant time.
+#[derive(Default, Clone)]
+pub struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    /// Wraps the secret value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns a reference to the secret value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the secret value.
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    /// Returns the secret value, which is not zeroized anymore.
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+
+        // SAFETY: the value is moved out once, and the secret is not dropped
+        unsafe { std::ptr::read(&this.0) }
+    }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl<T: Wipe> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Wipe> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+/// Compares 2 byte slices in a time only depending on their lengths.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    // no early exit: all bytes are compared
+    let diff = a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+impl<T: Wipe + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<T: Wipe + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: Wipe + ToNetworkOrder> ToNetworkOrder for Secret<T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        SERIALIZED.with(|serialized| serialized.set(true));
+        self.0.serialize_to(buffer)
+    }
+}
+
+thread_local! {
+    // set when a secret is serialized
+    static SERIALIZED: Cell<bool> = const { Cell::new(false) };
+}
+
+// restores the enclosing flag, set as well if a secret was serialized meanwhile
+struct Restore(bool);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0;
+        SERIALIZED.with(|serialized| serialized.set(previous || serialized.get()));
+    }
+}
+
+// runs f, and tells whether a secret was serialized by f, however deep in the value: the wrappers don't need to
+// know about secrets for their bytes to be redacted or wiped
+pub(crate) fn tracking_secrets<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    let _restore = Restore(SERIALIZED.with(|serialized| serialized.replace(false)));
+    let result = f();
+    (result, SERIALIZED.with(Cell::get))
+}
+
+impl<'a, T: Wipe + FromNetworkOrder<'a>> FromNetworkOrder<'a> for Secret<T> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0.deserialize_from(buffer)
+    }
+}
+
+// a buffer of received bytes, zeroized when dropped with the zeroize feature
+#[derive(Debug)]
+pub(crate) struct Scratch(pub(crate) Vec<u8>);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl Deref for Scratch {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Scratch {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+// zeroizes bytes which are going to be reused
+pub(crate) fn wipe_bytes(bytes: &mut [u8]) {
+    bytes.wipe();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::to_network_test;
+
+    #[test]
+    fn secret() {
+        to_network_test(Secret::new(vec![1u8, 2]), 2, &[1, 2]);
+
+        let mut buffer = Cursor::new(&[0x12, 0x34][..]);
+        let s = Secret::<[u8; 2]>::deserialize_new(&mut buffer).unwrap();
+        assert_eq!(s.expose(), &[0x12, 0x34]);
+        assert_eq!(format!("{:?}", s), "Secret(<redacted>)");
+
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"tok"));
+        assert!(Secret::new(vec![1u8]) != Secret::new(vec![1u8, 2]));
+
+        let secret = |value: &dyn crate::erased::ErasedToNetworkOrder| {
+            tracking_secrets(|| value.erased_serialize_to(&mut Vec::new())).1
+        };
+        assert!(secret(&Some(s.clone())));
+        assert!(secret(&(1u8, vec![s.clone()])));
+        assert!(!secret(&vec![Some(1u8)]));
+
+        // the enclosing call sees the secret as well
+        let (inner, outer) = tracking_secrets(|| secret(&s));
+        assert!(inner && outer);
+        assert!(!tracking_secrets(|| ()).1);
+
+        let s = Secret::new(String::from("token"));
+        assert_eq!(s.into_inner(), "token");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn wipe_scratch() {
+        let mut scratch = Scratch(vec![1u8, 2, 3]);
+        scratch.0.wipe();
+        assert!(scratch.is_empty());
+
+        let mut bytes = [1u8, 2, 3];
+        wipe_bytes(&mut bytes);
+        assert_eq!(bytes, [0, 0, 0]);
+
+        let mut nested = vec![vec![1u8, 2], vec![3]];
+        nested.wipe();
+        assert!(nested.is_empty());
+
+        let mut text = Some(Box::new(String::from("token")));
+        text.wipe();
+        assert_eq!(text.as_deref().map(String::as_str), Some(""));
+    }
+}
@@ -0,0 +1,250 @@
+//! A protocol state machine, enforcing which messages can be sent or received in each state.
+//!
+//! The states are defined by the user, and the transitions by the ```TYPE_TAG``` of the messages
+//! (see [`crate::NetworkMessage`]). Sending or receiving a message not allowed in the current state is an
+//! error, and the state is left unchanged:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::session::Session;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, NetworkMessage, ToNetwork};
+//!
+//! #[derive(Debug, Default, ToNetwork, FromNetwork, NetworkMessage)]
+//! #[network(tag = 1)]
+//! struct Hello {
+//!     version: u8,
+//! }
+//!
+//! #[derive(Debug, Default, ToNetwork, FromNetwork, NetworkMessage)]
+//! #[network(tag = 2)]
+//! struct Welcome {
+//!     id: u16,
+//! }
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum State {
+//!     Init,
+//!     HelloSent,
+//!     Established,
+//! }
+//!
+//! let mut session = Session::new(State::Init)
+//!     .allow_send::<Hello>(State::Init, State::HelloSent)
+//!     .allow_receive::<Welcome>(State::HelloSent, State::Established);
+//!
+//! // a Welcome message is not expected before sending Hello
+//! assert!(session.receive::<Welcome>(&mut Cursor::new([0, 1].as_slice())).is_err());
+//!
+//! let mut buffer = Vec::new();
+//! session.send(&Hello { version: 1 }, &mut buffer).unwrap();
+//! assert_eq!(session.state(), State::HelloSent);
+//!
+//! let welcome: Welcome = session.receive(&mut Cursor::new([0, 1].as_slice())).unwrap();
+//! assert_eq!(welcome.id, 1);
+//! assert_eq!(session.state(), State::Established);
+//! ```
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind};
+
+use crate::NetworkMessage;
+
+/// Whether a message is sent or received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Send,
+    Receive,
+}
+
+// a message allowed in a state, and the state following it
+#[derive(Debug, Clone)]
+struct Transition<S> {
+    from: S,
+    direction: Direction,
+    tag: u32,
+    name: &'static str,
+    to: S,
+}
+
+/// The current state of a protocol session, and the transitions allowed between states.
+#[derive(Debug, Clone)]
+pub struct Session<S> {
+    state: S,
+    transitions: Vec<Transition<S>>,
+}
+
+impl<S: Copy + PartialEq + fmt::Debug> Session<S> {
+    /// Creates a session in the ```initial``` state, without any transition.
+    pub fn new(initial: S) -> Self {
+        Self {
+            state: initial,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Allows sending a ```T``` message in the ```from``` state, moving the session to the ```to``` state.
+    pub fn allow_send<T: NetworkMessage>(self, from: S, to: S) -> Self {
+        self.allow::<T>(from, Direction::Send, to)
+    }
+
+    /// Allows receiving a ```T``` message in the ```from``` state, moving the session to the ```to``` state.
+    pub fn allow_receive<T: NetworkMessage>(self, from: S, to: S) -> Self {
+        self.allow::<T>(from, Direction::Receive, to)
+    }
+
+    // a transition already defined for the same state, direction and tag is replaced
+    fn allow<T: NetworkMessage>(mut self, from: S, direction: Direction, to: S) -> Self {
+        self.transitions
+            .retain(|t| !(t.from == from && t.direction == direction && t.tag == T::TYPE_TAG));
+        self.transitions.push(Transition {
+            from,
+            direction,
+            tag: T::TYPE_TAG,
+            name: T::NAME,
+            to,
+        });
+        self
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Forces the current state (e.g. to reset the session after a connection is lost).
+    pub fn set_state(&mut self, state: S) {
+        self.state = state;
+    }
+
+    /// Returns the names of the messages allowed in the current state for the direction.
+    pub fn expected(&self, direction: Direction) -> Vec<&'static str> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from == self.state && t.direction == direction)
+            .map(|t| t.name)
+            .collect()
+    }
+
+    // the next state, or an error if the message is not allowed in the current state
+    fn next<T: NetworkMessage>(&self, direction: Direction) -> std::io::Result<S> {
+        self.transitions
+            .iter()
+            .find(|t| t.from == self.state && t.direction == direction && t.tag == T::TYPE_TAG)
+            .map(|t| t.to)
+            .ok_or_else(|| {
+                let kind = match direction {
+                    Direction::Send => ErrorKind::InvalidInput,
+                    Direction::Receive => ErrorKind::InvalidData,
+                };
+                Error::new(
+                    kind,
+                    format!(
+                        "{} (tag {}) can't be {} in state {:?}, expected one of {:?}",
+                        T::NAME,
+                        T::TYPE_TAG,
+                        match direction {
+                            Direction::Send => "sent",
+                            Direction::Receive => "received",
+                        },
+                        self.state,
+                        self.expected(direction)
+                    ),
+                )
+            })
+    }
+
+    /// Serializes the message if allowed in the current state, and moves to the next state.
+    pub fn send<T: NetworkMessage>(
+        &mut self,
+        msg: &T,
+        buffer: &mut Vec<u8>,
+    ) -> std::io::Result<usize> {
+        let next = self.next::<T>(Direction::Send)?;
+        let length = msg.serialize_to(buffer)?;
+        self.state = next;
+        Ok(length)
+    }
+
+    /// Deserializes a message if allowed in the current state, and moves to the next state. If the
+    /// deserialization fails, the state is left unchanged.
+    pub fn receive<T>(&mut self, buffer: &mut Cursor<&[u8]>) -> std::io::Result<T>
+    where
+        T: NetworkMessage + Default,
+    {
+        let next = self.next::<T>(Direction::Receive)?;
+        let msg = T::deserialize_new(buffer)?;
+        self.state = next;
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FromNetworkOrder, ToNetworkOrder};
+
+    // a message without the derive
+    #[derive(Debug, Default)]
+    struct Ping(u8);
+
+    impl ToNetworkOrder for Ping {
+        fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+            self.0.serialize_to(buffer)
+        }
+    }
+
+    impl<'a> FromNetworkOrder<'a> for Ping {
+        fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+            self.0.deserialize_from(buffer)
+        }
+    }
+
+    impl NetworkMessage for Ping {
+        const TYPE_TAG: u32 = 7;
+        const NAME: &'static str = "Ping";
+    }
+
+    #[test]
+    fn session() {
+        let mut session = Session::new(0)
+            .allow_send::<Ping>(0, 1)
+            .allow_receive::<Ping>(1, 0)
+            .allow_receive::<Ping>(1, 2);
+
+        assert_eq!(session.expected(Direction::Send), &["Ping"]);
+        assert!(session.expected(Direction::Receive).is_empty());
+
+        let e = session
+            .receive::<Ping>(&mut Cursor::new([1].as_slice()))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            e.to_string(),
+            "Ping (tag 7) can't be received in state 0, expected one of []"
+        );
+
+        let mut buffer = Vec::new();
+        assert_eq!(session.send(&Ping(1), &mut buffer).unwrap(), 1);
+        assert_eq!(session.state(), 1);
+        assert_eq!(
+            session.send(&Ping(1), &mut buffer).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        // truncated: no transition
+        assert!(session
+            .receive::<Ping>(&mut Cursor::new([].as_slice()))
+            .is_err());
+        assert_eq!(session.state(), 1);
+
+        // the last transition defined replaces the previous one
+        assert_eq!(
+            session
+                .receive::<Ping>(&mut Cursor::new([1].as_slice()))
+                .unwrap()
+                .0,
+            1
+        );
+        assert_eq!(session.state(), 2);
+    }
+}
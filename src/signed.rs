@@ -0,0 +1,206 @@
+//! Messages followed by a signature, with the algorithm provided by the application.
+//!
+//! A ```Signed<T, S>``` is sent as the ```T``` value followed by the signature of its bytes, made by ```S```
+//! implementing the ```Signer``` trait (e.g. an Ed25519 private key). When deserialized, the signature is checked by
+//! ```S``` implementing the ```Verifier``` trait (e.g. the public key), and an error is returned if it doesn't match:
+//!
+//! ```
+//! use std::io::{Cursor, Error, ErrorKind};
+//! use type2network::signed::{Algorithm, Signed, Signer, Verifier};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//!
+//! // not a real signature: the sum of the bytes and the key
+//! struct Toy(u8);
+//!
+//! impl Algorithm for Toy {
+//!     const SIGNATURE_LEN: usize = 1;
+//! }
+//!
+//! impl Signer for Toy {
+//!     fn sign(&self, bytes: &[u8], signature: &mut [u8]) -> std::io::Result<()> {
+//!         signature[0] = bytes.iter().fold(self.0, |s, b| s.wrapping_add(*b));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl Verifier for Toy {
+//!     fn verify(&self, bytes: &[u8], signature: &[u8]) -> std::io::Result<()> {
+//!         if signature[0] != bytes.iter().fold(self.0, |s, b| s.wrapping_add(*b)) {
+//!             return Err(Error::new(ErrorKind::InvalidData, "bad signature"));
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut buffer = Vec::new();
+//! Signed::new(0x0102_u16, Toy(7)).serialize_to(&mut buffer).unwrap();
+//! assert_eq!(buffer, &[1, 2, 10]);
+//!
+//! let mut update = Signed::new(0_u16, Toy(7));
+//! update.deserialize_from(&mut Cursor::new(buffer.as_slice())).unwrap();
+//! assert_eq!(*update, 0x0102);
+//!
+//! // another key
+//! let mut update = Signed::new(0_u16, Toy(8));
+//! assert!(update.deserialize_from(&mut Cursor::new(buffer.as_slice())).is_err());
+//! ```
+//!
+//! The ```T``` value is deserialized before its signature is checked, so it must be discarded if an error is
+//! returned.
+use std::io::{Cursor, Error, ErrorKind};
+use std::ops::{Deref, DerefMut};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A signature algorithm, giving the length of the signatures.
+pub trait Algorithm {
+    /// Length of the signatures.
+    const SIGNATURE_LEN: usize;
+}
+
+/// Signs the messages, e.g. with a private key.
+pub trait Signer: Algorithm {
+    /// Writes the signature of ```bytes```.
+    fn sign(&self, bytes: &[u8], signature: &mut [u8]) -> std::io::Result<()>;
+}
+
+/// Checks the signatures of the messages, e.g. with a public key.
+pub trait Verifier: Algorithm {
+    /// Returns an ```InvalidData``` error if ```signature``` is not the one of ```bytes```.
+    fn verify(&self, bytes: &[u8], signature: &[u8]) -> std::io::Result<()>;
+}
+
+/// A ```T``` value followed by its signature on the wire.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Signed<T, S> {
+    value: T,
+    key: S,
+}
+
+impl<T, S> Signed<T, S> {
+    /// Creates a value signed or verified with ```key```. To deserialize a message, the value is overwritten.
+    pub fn new(value: T, key: S) -> Self {
+        Self { value, key }
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> &S {
+        &self.key
+    }
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, S> Deref for Signed<T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, S> DerefMut for Signed<T, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: ToNetworkOrder, S: Signer> ToNetworkOrder for Signed<T, S> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let start = buffer.len();
+        self.value.serialize_to(buffer)?;
+
+        // the signature of the bytes of the value
+        let end = buffer.len();
+        buffer.resize(end + S::SIGNATURE_LEN, 0);
+        let (bytes, signature) = buffer.split_at_mut(end);
+        if let Err(e) = self.key.sign(&bytes[start..], signature) {
+            buffer.truncate(start);
+            return Err(e);
+        }
+
+        Ok(buffer.len() - start)
+    }
+}
+
+impl<'a, T: FromNetworkOrder<'a>, S: Verifier> FromNetworkOrder<'a> for Signed<T, S> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let start = buffer.position() as usize;
+        self.value.deserialize_from(buffer)?;
+        let end = buffer.position() as usize;
+
+        let bytes = *buffer.get_ref();
+        let Some(signature) = bytes.get(end..end + S::SIGNATURE_LEN) else {
+            buffer.set_position(bytes.len() as u64);
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("signature of {} bytes expected", S::SIGNATURE_LEN),
+            ));
+        };
+        buffer.set_position((end + S::SIGNATURE_LEN) as u64);
+
+        self.key.verify(&bytes[start..end], signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the XOR of the bytes, repeated
+    struct Xor;
+
+    impl Algorithm for Xor {
+        const SIGNATURE_LEN: usize = 2;
+    }
+
+    impl Signer for Xor {
+        fn sign(&self, bytes: &[u8], signature: &mut [u8]) -> std::io::Result<()> {
+            signature.fill(bytes.iter().fold(0, |s, b| s ^ b));
+            Ok(())
+        }
+    }
+
+    impl Verifier for Xor {
+        fn verify(&self, bytes: &[u8], signature: &[u8]) -> std::io::Result<()> {
+            let mut expected = [0u8; 2];
+            self.sign(bytes, &mut expected)?;
+            if signature != expected {
+                return Err(Error::new(ErrorKind::InvalidData, "bad signature"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn signed() {
+        let mut buffer = vec![0xFF];
+        assert_eq!(
+            Signed::new((1u8, 2u8), Xor)
+                .serialize_to(&mut buffer)
+                .unwrap(),
+            4
+        );
+        assert_eq!(buffer, &[0xFF, 1, 2, 3, 3]);
+
+        // the signature is computed over the value only
+        let mut signed = Signed::new((0u8, 0u8), Xor);
+        let mut cursor = Cursor::new(&buffer[1..]);
+        signed.deserialize_from(&mut cursor).unwrap();
+        assert_eq!(signed.into_inner(), (1, 2));
+
+        let mut signed = Signed::new((0u8, 0u8), Xor);
+        let e = signed
+            .deserialize_from(&mut Cursor::new(&[1u8, 2, 3, 0][..]))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+        let e = signed
+            .deserialize_from(&mut Cursor::new(&[1u8, 2, 3][..]))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,178 @@
+//! Computing the number of bytes a value takes on the wire, e.g. to fill a length prefix before serializing.
+//!
+//! ```
+//! use type2network::size::{wire_size_of, CountingWriter};
+//! use type2network::framing::write_frame;
+//!
+//! assert_eq!(wire_size_of(&"hello").unwrap(), 5);
+//! assert_eq!(wire_size_of(&vec![0x1234_u16; 3]).unwrap(), 6);
+//!
+//! // bytes are counted, not kept
+//! let mut counter = CountingWriter::sink();
+//! write_frame(&mut counter, &0x12345678_u32).unwrap();
+//! assert_eq!(counter.count(), 8);
+//! ```
+use std::cell::RefCell;
+use std::io::{Sink, Write};
+
+use crate::secret::{tracking_secrets, wipe_bytes};
+use crate::ToNetworkOrder;
+
+// scratch buffers bigger than this are not kept between calls
+const MAX_SCRATCH_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    // reused by write_to() to not allocate on each call
+    static SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A writer counting the bytes written to the inner writer. By default, the bytes are discarded
+/// (see ```sink()```) and nothing is allocated.
+#[derive(Debug, Default)]
+pub struct CountingWriter<W = Sink> {
+    inner: W,
+    count: usize,
+}
+
+impl CountingWriter {
+    /// Creates a counting writer discarding the bytes.
+    pub fn sink() -> Self {
+        Self::default()
+    }
+}
+
+impl<W> CountingWriter<W> {
+    /// Creates a counting writer on top of ```inner```.
+    pub fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Returns the number of bytes ```value``` takes when serialized, for types whose size depends on their
+/// value (e.g. vectors or strings).
+///
+/// The value is written with ```write_to()``` to a ```CountingWriter::sink()```, so the bytes are counted and
+/// not kept (only the types without their own ```write_to()``` go through the scratch buffer kept per thread).
+/// The value is counted as a message of its own, its alignments starting from its first byte. Errors are those
+/// of ```write_to()```.
+pub fn wire_size_of<T: ToNetworkOrder + ?Sized>(value: &T) -> std::io::Result<usize> {
+    let mut counter = CountingWriter::sink();
+    crate::align::new_message(&mut counter, |writer| value.write_to(writer))?;
+    Ok(counter.count())
+}
+
+// the default ToNetworkOrder::write_to(): the value is serialized into the scratch buffer, then written at once.
+// The bytes are zeroized afterwards if a secret was serialized
+pub(crate) fn write_through_scratch<T>(value: &T, writer: &mut dyn Write) -> std::io::Result<usize>
+where
+    T: ToNetworkOrder + ?Sized,
+{
+    with_scratch(|buffer| {
+        let (length, secret) =
+            tracking_secrets(|| crate::align::with_scratch_origin(|| value.serialize_to(buffer)));
+        let result = length.and_then(|length| writer.write_all(buffer).map(|_| length));
+
+        if secret {
+            wipe_bytes(buffer);
+        }
+        result
+    })
+}
+
+// the write_to() of the collections: the items are written in turn
+pub(crate) fn write_items<'a, T>(
+    items: impl IntoIterator<Item = &'a T>,
+    writer: &mut dyn Write,
+) -> std::io::Result<usize>
+where
+    T: ToNetworkOrder + ?Sized + 'a,
+{
+    crate::align::track_writes(writer, |writer| {
+        let mut length = 0usize;
+        for item in items {
+            length += item.write_to(writer)?;
+        }
+        Ok(length)
+    })
+}
+
+// runs f with the scratch buffer emptied, or with a new buffer if it's already in use (e.g. when computing the
+// size of a value while serializing)
+fn with_scratch<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut buffer) => {
+            buffer.clear();
+            let result = f(&mut buffer);
+
+            buffer.clear();
+            if buffer.capacity() > MAX_SCRATCH_CAPACITY {
+                buffer.shrink_to(MAX_SCRATCH_CAPACITY);
+            }
+            result
+        }
+        Err(_) => f(&mut Vec::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // computes the size of its inner value while serializing
+    struct Nested(Vec<u16>);
+
+    impl ToNetworkOrder for Nested {
+        fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+            let size = wire_size_of(&self.0)? as u8;
+            Ok(size.serialize_to(buffer)? + self.0.serialize_to(buffer)?)
+        }
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(wire_size_of(&0u64).unwrap(), 8);
+        assert_eq!(wire_size_of(&vec![0u32; 100_000]).unwrap(), 400_000);
+        // counted without being serialized into the scratch buffer
+        assert_eq!(SCRATCH.with(|s| s.borrow().capacity()), 0);
+
+        assert_eq!(wire_size_of(&Nested(vec![1, 2])).unwrap(), 5);
+        let mut buffer = Vec::new();
+        Nested(vec![1, 2]).serialize_to(&mut buffer).unwrap();
+        assert_eq!(buffer, &[4, 0, 1, 0, 2]);
+
+        // written at once, the scratch buffer being in use while serializing Nested
+        let mut counter = CountingWriter::new(Vec::new());
+        assert_eq!(
+            write_through_scratch(&Nested(vec![1, 2]), &mut counter).unwrap(),
+            5
+        );
+        assert_eq!(counter.into_inner(), &[4, 0, 1, 0, 2]);
+
+        let mut counter = CountingWriter::new(Vec::new());
+        counter.write_all(&[1, 2, 3]).unwrap();
+        assert_eq!(counter.count(), 3);
+        assert_eq!(counter.into_inner(), &[1, 2, 3]);
+    }
+}
@@ -0,0 +1,195 @@
+//! Extension traits to send and receive messages over the standard library sockets:
+//!
+//! * ```UdpSocketExt```: a message per datagram
+//! * ```TcpStreamExt```: a message per frame (see the [`crate::framing`] module)
+//!
+//! ```no_run
+//! use std::net::UdpSocket;
+//! use type2network::socket::UdpSocketExt;
+//!
+//! let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+//! socket.send_msg_to(&0x1234_u16, "127.0.0.1:9999").unwrap();
+//! let reply: u16 = socket.recv_msg().unwrap();
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+
+use crate::framing::{decode_frame, read_frame, write_frame};
+use crate::secret::Scratch;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Largest UDP payload, for IPv4 (65535 - 8 bytes UDP header - 20 bytes IP header).
+pub const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Sending and receiving messages as UDP datagrams. Each message is sent in a single datagram.
+pub trait UdpSocketExt {
+    /// Serializes the message and sends it to ```addr```. Returns the number of bytes sent. An error is
+    /// returned if the message doesn't fit in a datagram.
+    fn send_msg_to<T: ToNetworkOrder, A: ToSocketAddrs>(
+        &self,
+        msg: &T,
+        addr: A,
+    ) -> std::io::Result<usize>;
+
+    /// Serializes the message and sends it to the connected peer.
+    fn send_msg<T: ToNetworkOrder>(&self, msg: &T) -> std::io::Result<usize>;
+
+    /// Receives a datagram and deserializes the message it contains, along with the sender address.
+    fn recv_msg_from<T>(&self) -> std::io::Result<(T, SocketAddr)>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default;
+
+    /// Receives a datagram from the connected peer and deserializes the message it contains.
+    fn recv_msg<T>(&self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default;
+}
+
+// serialize the message in a buffer fitting in a datagram
+fn datagram<T: ToNetworkOrder>(msg: &T) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    msg.serialize_to(&mut buffer)?;
+
+    if buffer.len() > MAX_DATAGRAM_SIZE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "message of {} bytes doesn't fit in a datagram (max {} bytes)",
+                buffer.len(),
+                MAX_DATAGRAM_SIZE
+            ),
+        ));
+    }
+
+    Ok(buffer)
+}
+
+// all bytes of the datagram must have been sent
+fn check_sent(sent: usize, expected: usize) -> std::io::Result<usize> {
+    if sent != expected {
+        return Err(Error::new(
+            ErrorKind::WriteZero,
+            format!(
+                "only {} bytes of a {} bytes datagram were sent",
+                sent, expected
+            ),
+        ));
+    }
+    Ok(sent)
+}
+
+impl UdpSocketExt for UdpSocket {
+    fn send_msg_to<T: ToNetworkOrder, A: ToSocketAddrs>(
+        &self,
+        msg: &T,
+        addr: A,
+    ) -> std::io::Result<usize> {
+        let buffer = datagram(msg)?;
+        check_sent(self.send_to(&buffer, addr)?, buffer.len())
+    }
+
+    fn send_msg<T: ToNetworkOrder>(&self, msg: &T) -> std::io::Result<usize> {
+        let buffer = datagram(msg)?;
+        check_sent(self.send(&buffer)?, buffer.len())
+    }
+
+    fn recv_msg_from<T>(&self) -> std::io::Result<(T, SocketAddr)>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        let mut buffer = Scratch(vec![0u8; MAX_DATAGRAM_SIZE]);
+        let (received, addr) = self.recv_from(&mut buffer)?;
+
+        let mut cursor = Cursor::new(&buffer[..received]);
+        Ok((T::deserialize_new(&mut cursor)?, addr))
+    }
+
+    fn recv_msg<T>(&self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        let mut buffer = Scratch(vec![0u8; MAX_DATAGRAM_SIZE]);
+        let received = self.recv(&mut buffer)?;
+
+        let mut cursor = Cursor::new(&buffer[..received]);
+        T::deserialize_new(&mut cursor)
+    }
+}
+
+/// Sending and receiving messages as length-prefixed frames over a TCP connection.
+pub trait TcpStreamExt {
+    /// Serializes the message and writes it as a frame. Returns the number of bytes written, including
+    /// the length prefix.
+    fn write_framed<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize>;
+
+    /// Reads a frame and deserializes the message it contains.
+    fn read_framed<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default;
+}
+
+impl TcpStreamExt for TcpStream {
+    fn write_framed<T: ToNetworkOrder>(&mut self, msg: &T) -> std::io::Result<usize> {
+        write_frame(self, msg)
+    }
+
+    fn read_framed<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'a> FromNetworkOrder<'a> + Default,
+    {
+        decode_frame(&Scratch(read_frame(self)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        assert_eq!(
+            client
+                .send_msg_to(&[0x1234_u16, 0x5678], server.local_addr().unwrap())
+                .unwrap(),
+            4
+        );
+        let (msg, addr): ([u16; 2], _) = server.recv_msg_from().unwrap();
+        assert_eq!(msg, [0x1234, 0x5678]);
+        assert_eq!(addr, client.local_addr().unwrap());
+
+        server.connect(addr).unwrap();
+        server.send_msg(&0xAB_u8).unwrap();
+        assert_eq!(client.recv_msg::<u8>().unwrap(), 0xAB);
+
+        // too large
+        let big = vec![0u8; MAX_DATAGRAM_SIZE + 1];
+        assert!(server.send_msg(&big).is_err());
+    }
+
+    #[test]
+    fn tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // echo server
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let msg: [u32; 2] = stream.read_framed().unwrap();
+            stream.write_framed(&msg).unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        assert_eq!(client.write_framed(&[0x1234_u32, 0x56789ABC]).unwrap(), 12);
+        let msg: [u32; 2] = client.read_framed().unwrap();
+        assert_eq!(msg, [0x1234, 0x56789ABC]);
+
+        server.join().unwrap();
+
+        // connection closed
+        assert!(client.read_framed::<u8>().is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! The ```string``` and ```mpint``` types of the SSH protocol (RFC 4251), also used by SFTP and the SSH agent protocol.
+//!
+//! * ```SshString```: bytes preceded by their length as a big-endian ```u32```. The bytes can be text (e.g. a user
+//!   name) or binary data (e.g. a key blob)
+//! * ```Mpint```: a signed integer of any size, as its two's complement bytes in a ```SshString```. The shortest
+//!   encoding is required: a positive number starts with a ```0x00``` byte only if its first byte is above
+//!   ```0x7F```, and zero has no byte. This is checked when deserializing
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::ssh::{Mpint, SshString};
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct DhReply {
+//!     kind: u8,
+//!     host_key: SshString,
+//!     f: Mpint,
+//! }
+//!
+//! let reply = DhReply {
+//!     kind: 31,
+//!     host_key: SshString::from("ssh-ed25519"),
+//!     f: Mpint::from_unsigned(&[0x80, 0x01]),
+//! };
+//!
+//! let mut buffer = Vec::new();
+//! assert_eq!(reply.serialize_to(&mut buffer).unwrap(), 23);
+//! assert_eq!(&buffer[16..], b"\x00\x00\x00\x03\x00\x80\x01");
+//!
+//! let decoded = DhReply::deserialize_new(&mut Cursor::new(buffer.as_slice())).unwrap();
+//! assert_eq!(decoded.host_key.as_str(), Some("ssh-ed25519"));
+//! assert_eq!(decoded.f.to_unsigned(), Some(&[0x80, 0x01][..]));
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+use std::ops::{Deref, DerefMut};
+
+use crate::len::WireLen;
+use crate::uninit::read_to_spare;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// Bytes preceded by their length as a big-endian ```u32```.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SshString(pub Vec<u8>);
+
+impl SshString {
+    /// Returns the bytes as a ```&str```, or ```None``` if they're not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// Returns the inner bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for SshString {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for SshString {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<&str> for SshString {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for SshString {
+    fn from(s: String) -> Self {
+        Self(s.into_bytes())
+    }
+}
+
+impl AsRef<[u8]> for SshString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Deref for SshString {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SshString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl ToNetworkOrder for SshString {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let length = u32::write_len(self.0.len(), buffer)?;
+        buffer.extend_from_slice(&self.0);
+        Ok(length + self.0.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let length = u32::write_len_to(self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+        Ok(length + self.0.len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for SshString {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let length = u32::read_len(buffer)?;
+        self.0.clear();
+        read_to_spare(buffer, &mut self.0, length)
+    }
+}
+
+/// A signed integer of any size, sent as its shortest two's complement bytes preceded by their length.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Mpint(Vec<u8>);
+
+impl Mpint {
+    /// Creates a positive number from its big-endian bytes (e.g. a Diffie-Hellman public value). The leading zeros are
+    /// removed, and a ```0x00``` byte is added if the first byte is above ```0x7F```.
+    pub fn from_unsigned(bytes: &[u8]) -> Self {
+        let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[first..];
+
+        let mut mpint = Vec::with_capacity(bytes.len() + 1);
+        if bytes.first().is_some_and(|b| *b > 0x7F) {
+            mpint.push(0);
+        }
+        mpint.extend_from_slice(bytes);
+        Self(mpint)
+    }
+
+    /// Returns the two's complement bytes, as sent.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns ```true``` if the number is negative.
+    pub fn is_negative(&self) -> bool {
+        self.0.first().is_some_and(|b| *b > 0x7F)
+    }
+
+    /// Returns the big-endian bytes of a positive number without leading zeros, or ```None``` if it's negative.
+    pub fn to_unsigned(&self) -> Option<&[u8]> {
+        match self.0.as_slice() {
+            [0, rest @ ..] => Some(rest),
+            _ if self.is_negative() => None,
+            bytes => Some(bytes),
+        }
+    }
+
+    // checks the bytes follow the SSH rules
+    fn check(bytes: &[u8]) -> std::io::Result<()> {
+        match bytes {
+            [0x00] | [0x00, 0x00..=0x7F, ..] | [0xFF, 0x80..=0xFF, ..] => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("mpint with an unnecessary leading byte 0x{:02X}", bytes[0]),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<i64> for Mpint {
+    fn from(value: i64) -> Self {
+        if value == 0 {
+            return Self::default();
+        }
+
+        // the leading bytes only repeating the sign bit are removed
+        let mut bytes = &value.to_be_bytes()[..];
+        while let [first @ (0x00 | 0xFF), second, ..] = bytes {
+            if (first ^ second) & 0x80 != 0 {
+                break;
+            }
+            bytes = &bytes[1..];
+        }
+
+        Self(bytes.to_vec())
+    }
+}
+
+impl ToNetworkOrder for Mpint {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let length = u32::write_len(self.0.len(), buffer)?;
+        buffer.extend_from_slice(&self.0);
+        Ok(length + self.0.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let length = u32::write_len_to(self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+        Ok(length + self.0.len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Mpint {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let length = u32::read_len(buffer)?;
+        let mut bytes = Vec::new();
+        read_to_spare(buffer, &mut bytes, length)?;
+        Self::check(&bytes)?;
+        self.0 = bytes;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn ssh_string() {
+        to_network_test(SshString::from("abc"), 7, &[0, 0, 0, 3, b'a', b'b', b'c']);
+        to_network_test(SshString::default(), 4, &[0, 0, 0, 0]);
+        from_network_test(None, SshString::from("ab"), &vec![0, 0, 0, 2, b'a', b'b']);
+
+        let mut cursor = Cursor::new(&[0u8, 0, 0, 3, b'a'][..]);
+        assert!(SshString::deserialize_new(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn mpint() {
+        // examples of RFC 4251
+        to_network_test(Mpint::from(0), 4, &[0, 0, 0, 0]);
+        to_network_test(
+            Mpint::from_unsigned(&[0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]),
+            12,
+            &[0, 0, 0, 8, 0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7],
+        );
+        to_network_test(Mpint::from(0x80), 6, &[0, 0, 0, 2, 0x00, 0x80]);
+        to_network_test(Mpint::from(-1234), 6, &[0, 0, 0, 2, 0xfb, 0x2e]);
+        to_network_test(
+            Mpint::from(-0xdeadbeef),
+            9,
+            &[0, 0, 0, 5, 0xff, 0x21, 0x52, 0x41, 0x11],
+        );
+        assert_eq!(Mpint::from(-1).as_bytes(), &[0xFF]);
+        assert_eq!(Mpint::from(-128).as_bytes(), &[0x80]);
+        assert_eq!(Mpint::from(127).as_bytes(), &[0x7F]);
+        assert_eq!(Mpint::from_unsigned(&[0, 0]).as_bytes(), &[] as &[u8]);
+
+        from_network_test(None, Mpint::from(-1234), &vec![0, 0, 0, 2, 0xfb, 0x2e]);
+        assert!(Mpint::from(-1234).is_negative());
+        assert_eq!(Mpint::from(-1234).to_unsigned(), None);
+        assert_eq!(Mpint::from(0x80).to_unsigned(), Some(&[0x80][..]));
+
+        // leading bytes not needed
+        for bytes in [
+            &[0u8, 0, 0, 1, 0x00][..],
+            &[0, 0, 0, 2, 0x00, 0x7F],
+            &[0, 0, 0, 2, 0xFF, 0x80],
+        ] {
+            let e = Mpint::deserialize_new(&mut Cursor::new(bytes)).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! Asynchronous reading of framed messages (```async``` feature).
+//!
+//! A ```MessageStream``` reads the frames (see the [`crate::framing`] module) of any ```futures::io::AsyncRead```
+//! and yields the deserialized messages as a ```futures::Stream```:
+//!
+//! ```
+//! use futures::{executor::block_on, io::Cursor, StreamExt};
+//! use type2network::stream::MessageStream;
+//!
+//! let bytes = vec![0, 0, 0, 2, 0x12, 0x34, 0, 0, 0, 2, 0x56, 0x78];
+//! let stream = MessageStream::<u16, _>::new(Cursor::new(bytes));
+//! let messages: Vec<u16> = block_on(stream.map(|msg| msg.unwrap()).collect());
+//! assert_eq!(messages, &[0x1234, 0x5678]);
+//! ```
+//!
+//! With ```tokio```, a ```TcpStream``` can be used through the ```compat()``` adapter of the ```tokio-util``` crate.
+//! The length prefix is decoded as defined by the ```FrameFormat``` given to ```with_format()```, and the bytes of a
+//! frame are buffered as they are received, never more than announced by its prefix.
+//!
+//! As the stream waits for the whole frame, a peer sending a frame byte by byte (or not at all) could block it
+//! forever. A ```CancelToken``` given to the stream stops the read when cancelled, from a timer of any runtime or
+//! from another thread: the frame partially read is then discarded and a ```TimedOut``` error returned, or the stream
+//! ends if it's cancelled between two frames. As the reader may be left in the middle of a frame, a cancelled stream
+//! is over and then always returns ```None```. A token can be replaced before each frame with ```set_cancel()```, to
+//! give each frame its own deadline.
+//!
+//! The token is checked while the frame is read: the messages are only deserialized once their frame is complete,
+//! so ```FromNetworkOrder::deserialize_from()``` never waits for the peer and doesn't need a deadline of its own.
+//!
+//!
+//! ```
+//! use std::time::Duration;
+//! use futures::{executor::block_on, StreamExt};
+//! use futures::io::AsyncRead;
+//! use std::pin::Pin;
+//! use std::task::{Context, Poll};
+//! use type2network::stream::{CancelToken, MessageStream};
+//!
+//! // a peer sending a single byte, and then nothing
+//! struct Slow(bool);
+//!
+//! impl AsyncRead for Slow {
+//!     fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+//!         if std::mem::replace(&mut self.0, true) {
+//!             return Poll::Pending;
+//!         }
+//!         buf[0] = 0;
+//!         Poll::Ready(Ok(1))
+//!     }
+//! }
+//!
+//! let token = CancelToken::new();
+//! let mut stream = MessageStream::<u16, _>::new(Slow(false)).with_cancel(token.clone());
+//!
+//! // the deadline
+//! std::thread::spawn(move || {
+//!     std::thread::sleep(Duration::from_millis(10));
+//!     token.cancel();
+//! });
+//!
+//! let e = block_on(stream.next()).unwrap().unwrap_err();
+//! assert_eq!(e.kind(), std::io::ErrorKind::TimedOut);
+//! ```
+use std::io::{Error, ErrorKind};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::{ready, FusedStream, Stream};
+use futures_io::AsyncRead;
+use futures_util::task::AtomicWaker;
+
+use crate::framing::{decode_frame, FrameFormat, FrameLimit, FRAME_HEADER_SIZE};
+use crate::secret::wipe_bytes;
+use crate::FromNetworkOrder;
+
+// the frame bytes are read in chunks of this size at most, so the buffer only grows with the bytes received
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// A stream of messages read from length-prefixed frames. The stream ends when the reader reaches the end
+/// of file between two frames. An end of file in the middle of a frame is an error, as is an I/O error: the
+/// stream then ends, its reader being left in the middle of a frame.
+#[derive(Debug)]
+pub struct MessageStream<T, R> {
+    reader: R,
+
+    // the length prefix being read
+    prefix: [u8; FRAME_HEADER_SIZE],
+    filled: usize,
+
+    // bytes of the frame received so far, once its length is known
+    buffer: Vec<u8>,
+    length: Option<usize>,
+
+    format: FrameFormat,
+
+    // stops the read when cancelled
+    cancel: Option<CancelToken>,
+
+    // set once cancelled or when the next frame can't be found, the stream being over
+    done: bool,
+
+    // the maximum size of the frames, and the bytes of a skipped frame not read yet
+    limit: FrameLimit,
+    skipping: usize,
+
+    // bytes of the frames read or skipped
+    position: u64,
+
+    message: PhantomData<fn() -> T>,
+}
+
+/// A token stopping the reads of a ```MessageStream```. Its clones share the same state, so one of them can be
+/// cancelled from a timer or another thread, waking up the task reading the stream.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl CancelToken {
+    /// Creates a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token, and wakes up the task reading the stream.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Release);
+        self.0.waker.wake();
+    }
+
+    /// Returns ```true``` once the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+}
+
+impl<T, R> MessageStream<T, R> {
+    /// Creates a stream of messages read from ```reader```.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            prefix: [0; FRAME_HEADER_SIZE],
+            filled: 0,
+            buffer: Vec::new(),
+            length: None,
+            format: FrameFormat::default(),
+            cancel: None,
+            done: false,
+            limit: FrameLimit::default(),
+            skipping: 0,
+            position: 0,
+            message: PhantomData,
+        }
+    }
+
+    /// Decodes the length prefix as defined by ```format```.
+    pub fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Stops reading the stream once ```token``` is cancelled. The stream is then over.
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Rejects the frames longer than ```max``` bytes with an ```InvalidData``` error, before reading them. As the
+    /// stream can't find the next frame, it's then over, unless these frames are skipped.
+    pub fn with_max_frame_size(mut self, max: usize) -> Self {
+        self.limit.max = Some(max);
+        self
+    }
+
+    /// Skips the frames longer than the maximum size instead of rejecting them: their bytes are read and dropped
+    /// as they are received, and the next frame is returned. The frames skipped are reported to the observer (see
+    /// [`crate::observer`]), with their offset in the stream.
+    pub fn skip_oversized(mut self) -> Self {
+        self.limit.skip = true;
+        self
+    }
+
+    /// Replaces the token stopping the reads, e.g. to give the next frame its own deadline once a message is
+    /// received. It doesn't resume a stream already cancelled, which always returns ```None```.
+    pub fn set_cancel(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
+
+    /// Returns the inner reader. Bytes of a partially read frame are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<T, R> MessageStream<T, R> {
+    // drops the bytes of the frame being read, and gets ready for the next prefix
+    fn reset(&mut self) {
+        wipe_bytes(&mut self.buffer);
+        self.buffer.clear();
+        self.length = None;
+        self.filled = 0;
+        self.skipping = 0;
+    }
+
+    // the stream ends after an error it can't recover from: a truncated frame or an I/O error
+    fn fail<M>(&mut self, e: Error) -> Poll<Option<std::io::Result<M>>> {
+        self.done = true;
+        self.reset();
+        Poll::Ready(Some(Err(e)))
+    }
+}
+
+impl<T, R> Stream for MessageStream<T, R>
+where
+    T: for<'a> FromNetworkOrder<'a> + Default,
+    R: AsyncRead + Unpin,
+{
+    type Item = std::io::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        // the waker is registered before checking the token, so a cancel can't be missed
+        if let Some(token) = &this.cancel {
+            token.0.waker.register(cx.waker());
+
+            if token.is_cancelled() {
+                this.done = true;
+
+                // nothing read yet: the stream just ends
+                if this.filled == 0 && this.length.is_none() && this.skipping == 0 {
+                    return Poll::Ready(None);
+                }
+
+                this.reset();
+                return Poll::Ready(Some(Err(Error::new(
+                    ErrorKind::TimedOut,
+                    "read cancelled in the middle of a frame",
+                ))));
+            }
+        }
+
+        // read the prefix
+        while this.length.is_none() {
+            // the bytes of a skipped frame are dropped as they arrive
+            if this.skipping > 0 {
+                let mut chunk = [0u8; READ_CHUNK_SIZE];
+                let wanted = this.skipping.min(READ_CHUNK_SIZE);
+
+                let read = Pin::new(&mut this.reader).poll_read(cx, &mut chunk[..wanted]);
+                match ready!(read) {
+                    Ok(0) => return this.fail(truncated()),
+                    Ok(n) => this.skipping -= n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(e) => return this.fail(e),
+                }
+                continue;
+            }
+
+            if this.filled < FRAME_HEADER_SIZE {
+                let read =
+                    Pin::new(&mut this.reader).poll_read(cx, &mut this.prefix[this.filled..]);
+                match ready!(read) {
+                    // end of file between 2 frames
+                    Ok(0) if this.filled == 0 => return Poll::Ready(None),
+                    Ok(0) => return this.fail(truncated()),
+                    Ok(n) => this.filled += n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(e) => return this.fail(e),
+                }
+                continue;
+            }
+
+            // the stream can't be read anymore after an invalid prefix or a frame rejected
+            let length = match this
+                .format
+                .decode(this.prefix)
+                .and_then(|length| Ok((length, this.limit.accept(length)?)))
+            {
+                Ok((length, true)) => length,
+                Ok((length, false)) => {
+                    this.position += FRAME_HEADER_SIZE as u64;
+                    crate::observer::report(this.position, length, std::any::type_name::<T>());
+                    this.position += length as u64;
+
+                    this.filled = 0;
+                    this.skipping = length;
+                    continue;
+                }
+                Err(e) => return this.fail(e),
+            };
+            this.length = Some(length);
+        }
+
+        // read the frame as its bytes are received, a corrupted prefix can't make the stream allocate them upfront
+        let length = this.length.unwrap_or_default();
+        while this.buffer.len() < length {
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            let wanted = (length - this.buffer.len()).min(READ_CHUNK_SIZE);
+
+            let read = Pin::new(&mut this.reader).poll_read(cx, &mut chunk[..wanted]);
+            match ready!(read) {
+                Ok(0) => return this.fail(truncated()),
+                Ok(n) => {
+                    this.buffer.extend_from_slice(&chunk[..n]);
+                    wipe_bytes(&mut chunk[..n]);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return this.fail(e),
+            }
+        }
+
+        // the frame is read: get ready for the next prefix
+        let msg = decode_frame(&this.buffer);
+        this.position += (FRAME_HEADER_SIZE + length) as u64;
+        this.reset();
+
+        Poll::Ready(Some(msg))
+    }
+}
+
+impl<T, R> FusedStream for MessageStream<T, R>
+where
+    T: for<'a> FromNetworkOrder<'a> + Default,
+    R: AsyncRead + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+// the error returned for an end of file in the middle of a frame
+fn truncated() -> Error {
+    Error::new(
+        ErrorKind::UnexpectedEof,
+        "end of file in the middle of a frame",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, io::Cursor, StreamExt};
+
+    #[test]
+    fn message_stream() {
+        // empty frame, then a truncated one
+        let bytes = vec![0, 0, 0, 0, 0, 0, 0, 4, 0x12];
+        let mut stream = MessageStream::<Vec<u8>, _>::new(Cursor::new(bytes));
+
+        block_on(async {
+            assert!(stream.next().await.unwrap().unwrap().is_empty());
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                ErrorKind::UnexpectedEof
+            );
+
+            // the stream ends after the error
+            assert!(stream.next().await.is_none());
+            assert!(stream.is_terminated());
+        });
+
+        // bytes left in the frame
+        let bytes = vec![0, 0, 0, 3, 0x12, 0x34, 0x56];
+        let mut stream = MessageStream::<u16, _>::new(Cursor::new(bytes));
+        block_on(async {
+            assert!(stream.next().await.unwrap().is_err());
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn frame_format() {
+        let bytes = vec![6, 0, 0, 0, 0x12, 0x34, 3, 0, 0, 0];
+        let mut stream =
+            MessageStream::<u16, _>::new(Cursor::new(bytes)).with_format(FrameFormat::NineP);
+
+        block_on(async {
+            assert_eq!(stream.next().await.unwrap().unwrap(), 0x1234);
+
+            // a size shorter than the prefix itself
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                ErrorKind::InvalidData
+            );
+            assert!(stream.next().await.is_none());
+        });
+
+        // the buffer only grows with the bytes received, whatever the length announced
+        let bytes = vec![0xFF, 0xFF, 0xFF, 0xF0, 1, 2, 3];
+        let mut stream = MessageStream::<Vec<u8>, _>::new(Cursor::new(bytes));
+        block_on(async {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                ErrorKind::UnexpectedEof
+            );
+        });
+        assert!(stream.buffer.capacity() < READ_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let bytes = vec![
+            0, 0, 0, 2, 0x12, 0x34, 0, 0, 0, 3, 1, 2, 3, 0, 0, 0, 2, 0x56, 0x78,
+        ];
+
+        // rejected: the stream is over
+        let mut stream =
+            MessageStream::<u16, _>::new(Cursor::new(bytes.clone())).with_max_frame_size(2);
+        block_on(async {
+            assert_eq!(stream.next().await.unwrap().unwrap(), 0x1234);
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                ErrorKind::InvalidData
+            );
+            assert!(stream.next().await.is_none());
+        });
+
+        // skipped
+        let stream = MessageStream::<u16, _>::new(Cursor::new(bytes))
+            .with_max_frame_size(2)
+            .skip_oversized();
+        let messages: Vec<u16> = block_on(stream.map(|msg| msg.unwrap()).collect());
+        assert_eq!(messages, &[0x1234, 0x5678]);
+    }
+
+    #[test]
+    fn cancel_token() {
+        let token = CancelToken::new();
+        let bytes = vec![0, 0, 0, 2, 0x12, 0x34, 0, 0, 0, 2, 0x56];
+        let mut stream =
+            MessageStream::<u16, _>::new(Cursor::new(bytes)).with_cancel(token.clone());
+
+        block_on(async {
+            assert_eq!(stream.next().await.unwrap().unwrap(), 0x1234);
+
+            // cancelled between two frames: the stream ends, even with a new token
+            token.cancel();
+            assert!(stream.next().await.is_none());
+            assert!(stream.is_terminated());
+            stream.set_cancel(Some(CancelToken::new()));
+            assert!(stream.next().await.is_none());
+        });
+
+        // cancelled in the middle of a frame: the bytes left aren't read as a frame
+        let token = CancelToken::new();
+        let bytes = vec![0, 0, 0, 2, 0x12, 0, 0, 0, 2, 0x56, 0x78];
+        let mut stream =
+            MessageStream::<u16, _>::new(Stalled(Cursor::new(bytes), 5)).with_cancel(token.clone());
+
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        assert!(stream.poll_next_unpin(&mut cx).is_pending());
+        token.cancel();
+
+        block_on(async {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                ErrorKind::TimedOut
+            );
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    // a reader stalling after some bytes
+    struct Stalled(Cursor<Vec<u8>>, usize);
+
+    impl AsyncRead for Stalled {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let left = self.1 - self.0.position() as usize;
+            if left == 0 {
+                return Poll::Pending;
+            }
+            let n = buf.len().min(left);
+            Pin::new(&mut self.0).poll_read(cx, &mut buf[..n])
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Externally tagged values, without the derive.
+//!
+//! A ```Tagged<TAG, T>``` is sent as ```TAG``` (a big-endian ```u16```) followed by the ```T``` value. When deserialized,
+//! the tag is checked. A dispatcher can read the tag of the next value with ```peek_tag()``` to select the type to deserialize:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network::tagged::{peek_tag, Tagged};
+//!
+//! type Ping = Tagged<1, u32>;
+//! type Pong = Tagged<2, u16>;
+//!
+//! let mut buffer: Vec<u8> = Vec::new();
+//! Pong::from(0x1234).serialize_to(&mut buffer).unwrap();
+//! assert_eq!(buffer, &[0, 2, 0x12, 0x34]);
+//!
+//! let mut cursor = Cursor::new(buffer.as_slice());
+//! match peek_tag(&cursor).unwrap() {
+//!     Ping::TAG => unreachable!(),
+//!     Pong::TAG => assert_eq!(*Pong::deserialize_new(&mut cursor).unwrap(), 0x1234),
+//!     _ => unreachable!(),
+//! }
+//!
+//! // wrong tag
+//! assert!(Ping::deserialize_new(&mut Cursor::new(&[0, 2, 0, 0, 0, 0][..])).is_err());
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+use std::ops::{Deref, DerefMut};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A ```T``` value preceded by ```TAG``` on the wire.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tagged<const TAG: u16, T>(pub T);
+
+impl<const TAG: u16, T> Tagged<TAG, T> {
+    /// The tag sent before the value.
+    pub const TAG: u16 = TAG;
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<const TAG: u16, T> From<T> for Tagged<TAG, T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<const TAG: u16, T> Deref for Tagged<TAG, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const TAG: u16, T> DerefMut for Tagged<TAG, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const TAG: u16, T: ToNetworkOrder> ToNetworkOrder for Tagged<TAG, T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        Ok(TAG.serialize_to(buffer)? + self.0.serialize_to(buffer)?)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::align::track_writes(writer, |writer| {
+            Ok(TAG.write_to(writer)? + self.0.write_to(writer)?)
+        })
+    }
+}
+
+impl<'a, const TAG: u16, T: FromNetworkOrder<'a>> FromNetworkOrder<'a> for Tagged<TAG, T> {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let mut tag = 0u16;
+        tag.deserialize_from(buffer)?;
+
+        if tag != TAG {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("tag {} found, {} expected", tag, TAG),
+            ));
+        }
+
+        self.0.deserialize_from(buffer)
+    }
+}
+
+/// Returns the tag at the current position of the buffer, without moving it.
+pub fn peek_tag(buffer: &Cursor<&[u8]>) -> std::io::Result<u16> {
+    let data = *buffer.get_ref();
+    let position = (buffer.position() as usize).min(data.len());
+
+    match data[position..] {
+        [hi, lo, ..] => Ok(u16::from_be_bytes([hi, lo])),
+        _ => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "not enough bytes to read a tag",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn tagged() {
+        to_network_test(Tagged::<0x0102, u8>(3), 3, &[1, 2, 3]);
+        from_network_test(None, Tagged::<0x0102, u8>(3), &vec![1, 2, 3]);
+
+        // nested tags
+        let t: Tagged<1, Tagged<2, u8>> = Tagged(Tagged(3));
+        to_network_test(t, 5, &[0, 1, 0, 2, 3]);
+
+        let mut buffer = Cursor::new([0u8, 1, 0].as_slice());
+        assert_eq!(peek_tag(&buffer).unwrap(), 1);
+        buffer.set_position(2);
+        assert!(peek_tag(&buffer).is_err());
+        buffer.set_position(10);
+        assert!(peek_tag(&buffer).is_err());
+    }
+}
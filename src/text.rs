@@ -0,0 +1,788 @@
+//! Binary data sent as ASCII text, for protocols mixing text and binary data (e.g. digests in SIP or HTTP
+//! authentication parameters).
+//!
+//! * ```HexString<L>```: the bytes are sent as lowercase hexadecimal digits (uppercase digits are also accepted, unless in
+//!   canonical mode)
+//! * ```Base64Bytes<L>```: the bytes are sent as standard base64 with padding
+//!
+//! and for textual preambles followed by binary data (e.g. the handshakes of custom protocols):
+//!
+//! * ```TextLine```: a line terminated by CRLF
+//! * ```TextHeaders```: ```Name: value``` lines terminated by an empty line
+//!
+//! The text is preceded by its length in bytes, encoded as defined by ```L``` (see [`crate::len::WireLen`]). Use
+//! [`crate::len::Fixed`] for a text whose length is known in advance:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network::len::Fixed;
+//! use type2network::text::{Base64Bytes, HexString};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct Auth {
+//!     nonce: HexString<Fixed<4>>,
+//!     token: Base64Bytes<u8>,
+//! }
+//!
+//! let auth = Auth {
+//!     nonce: vec![0xCA, 0xFE].into(),
+//!     token: b"hi!".to_vec().into(),
+//! };
+//!
+//! let mut buffer: Vec<u8> = Vec::new();
+//! assert_eq!(auth.serialize_to(&mut buffer).unwrap(), 9);
+//! assert_eq!(buffer, b"cafe\x04aGkh");
+//!
+//! let a = Auth::deserialize_new(&mut Cursor::new(buffer.as_slice())).unwrap();
+//! assert_eq!(a, auth);
+//! ```
+//!
+//! Strings are read with the ```#[network(utf8 = "mode")]``` field attribute, which selects what to do with invalid UTF-8
+//! (see [`Utf8Mode`]). The bytes read are the remaining ones, or the number given by ```#[network(count = "expr")]```:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::FromNetworkOrder;
+//! use type2network::text::Utf8Text;
+//! use type2network_derive::FromNetwork;
+//!
+//! #[derive(Debug, Default, FromNetwork)]
+//! struct Greeting {
+//!     length: u8,
+//!     #[network(utf8 = "lossy", count = "self.length")]
+//!     name: String,
+//!     #[network(utf8 = "bytes")]
+//!     comment: Utf8Text,
+//! }
+//!
+//! let b = b"\x03ab\xFF\xFE\xFF";
+//! let g = Greeting::deserialize_new(&mut Cursor::new(b.as_slice())).unwrap();
+//! assert_eq!(g.name, "ab\u{FFFD}");
+//! assert_eq!(g.comment, Utf8Text::Bytes(vec![0xFE, 0xFF]));
+//! ```
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind, Write};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::len::WireLen;
+use crate::secret::Scratch;
+use crate::uninit::read_to_spare;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// error returned for an invalid text
+fn invalid(encoding: &str, text: &[u8]) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "invalid {} text {:?}",
+            encoding,
+            String::from_utf8_lossy(text)
+        ),
+    )
+}
+
+fn hex_encode(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|b| {
+            [
+                HEX_DIGITS[(b >> 4) as usize],
+                HEX_DIGITS[(b & 0x0F) as usize],
+            ]
+        })
+        .collect()
+}
+
+fn hex_decode(text: &[u8]) -> std::io::Result<Vec<u8>> {
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+
+    if !text.len().is_multiple_of(2) {
+        return Err(invalid("hex", text));
+    }
+    crate::canonical::check(
+        !text.iter().any(u8::is_ascii_uppercase),
+        "uppercase hex digits",
+    )?;
+
+    text.chunks_exact(2)
+        .map(|pair| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(invalid("hex", text)),
+        })
+        .collect()
+}
+
+fn base64_encode(bytes: &[u8]) -> Vec<u8> {
+    let mut text = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+
+        // a chunk of k bytes gives k + 1 digits, completed with padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64_DIGITS[(n >> (18 - 6 * i) & 0x3F) as usize]);
+            } else {
+                text.push(b'=');
+            }
+        }
+    }
+
+    text
+}
+
+fn base64_decode(text: &[u8]) -> std::io::Result<Vec<u8>> {
+    let digit = |c: u8| BASE64_DIGITS.iter().position(|d| *d == c).map(|d| d as u32);
+
+    if !text.len().is_multiple_of(4) {
+        return Err(invalid("base64", text));
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let chunks = text.len() / 4;
+
+    for (index, chunk) in text.chunks_exact(4).enumerate() {
+        // padding is only allowed at the end of the last chunk
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && index != chunks - 1) {
+            return Err(invalid("base64", text));
+        }
+
+        let mut n = 0u32;
+        for (i, c) in chunk[..4 - padding].iter().enumerate() {
+            n |= digit(*c).ok_or_else(|| invalid("base64", text))? << (18 - 6 * i);
+        }
+
+        // the bits of the last digit beyond the last byte are zero when encoded
+        let unused = (1u32 << (8 * padding)) - 1;
+        crate::canonical::check(n & unused == 0, "non-zero trailing bits in base64")?;
+
+        bytes.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+
+    Ok(bytes)
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_text {
+    ($name:ident, $encode:ident, $decode:ident, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Clone, PartialEq, Eq)]
+        pub struct $name<L> {
+            bytes: Vec<u8>,
+            length: PhantomData<L>,
+        }
+
+        impl<L> $name<L> {
+            /// Returns the inner bytes.
+            pub fn into_inner(self) -> Vec<u8> {
+                self.bytes
+            }
+        }
+
+        impl<L> Default for $name<L> {
+            fn default() -> Self {
+                Self {
+                    bytes: Vec::new(),
+                    length: PhantomData,
+                }
+            }
+        }
+
+        impl<L> From<Vec<u8>> for $name<L> {
+            fn from(bytes: Vec<u8>) -> Self {
+                Self {
+                    bytes,
+                    length: PhantomData,
+                }
+            }
+        }
+
+        // the bytes are shown as text
+        impl<L> fmt::Debug for $name<L> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let text = $encode(&self.bytes);
+                f.debug_tuple(stringify!($name))
+                    .field(&String::from_utf8_lossy(&text))
+                    .finish()
+            }
+        }
+
+        impl<L> AsRef<[u8]> for $name<L> {
+            fn as_ref(&self) -> &[u8] {
+                &self.bytes
+            }
+        }
+
+        impl<L> Deref for $name<L> {
+            type Target = Vec<u8>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.bytes
+            }
+        }
+
+        impl<L> DerefMut for $name<L> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.bytes
+            }
+        }
+
+        impl<L: WireLen> ToNetworkOrder for $name<L> {
+            fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                let text = $encode(&self.bytes);
+                let length = L::write_len(text.len(), buffer)?;
+                buffer.extend_from_slice(&text);
+                Ok(length + text.len())
+            }
+
+            fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+                let text = $encode(&self.bytes);
+                let length = L::write_len_to(text.len(), writer)?;
+                writer.write_all(&text)?;
+                Ok(length + text.len())
+            }
+        }
+
+        impl<'a, L: WireLen> FromNetworkOrder<'a> for $name<L> {
+            fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+                let length = L::read_len(buffer)?;
+                let mut text = Scratch(Vec::new());
+                read_to_spare(buffer, &mut text, length)?;
+                self.bytes = $decode(&text)?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_text!(
+    HexString,
+    hex_encode,
+    hex_decode,
+    "Bytes sent as hexadecimal digits, preceded by the number of digits encoded as defined by ```L```."
+);
+impl_text!(
+    Base64Bytes,
+    base64_encode,
+    base64_decode,
+    "Bytes sent as base64 text, preceded by the length of the text encoded as defined by ```L```."
+);
+
+/// What to do with invalid UTF-8 when reading a string with the ```#[network(utf8 = "mode")]``` field attribute.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Mode {
+    /// ```utf8 = "strict"```: an ```InvalidData``` error is returned.
+    #[default]
+    Strict,
+
+    /// ```utf8 = "lossy"```: invalid sequences are replaced by U+FFFD.
+    Lossy,
+
+    /// ```utf8 = "bytes"```: the bytes are kept as is in a [`Utf8Text`]. Other string types return an ```InvalidData``` error.
+    Bytes,
+}
+
+// decode the bytes in the strict or lossy mode
+fn decode(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Cow<'_, str>> {
+    match mode {
+        Utf8Mode::Lossy => Ok(String::from_utf8_lossy(bytes)),
+        Utf8Mode::Strict | Utf8Mode::Bytes => std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+    }
+}
+
+/// A string which can be built from bytes which are possibly not UTF-8.
+pub trait FromUtf8: Sized {
+    /// Builds the string from the bytes, invalid UTF-8 being managed as defined by ```mode```.
+    fn from_utf8(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Self>;
+}
+
+impl FromUtf8 for String {
+    fn from_utf8(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Self> {
+        decode(bytes, mode).map(Cow::into_owned)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> FromUtf8 for heapless::String<N> {
+    fn from_utf8(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Self> {
+        let s = decode(bytes, mode)?;
+        heapless::String::try_from(s.as_ref()).map_err(|_| crate::vec::full(N))
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<const N: usize> FromUtf8 for arrayvec::ArrayString<N> {
+    fn from_utf8(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Self> {
+        let s = decode(bytes, mode)?;
+        arrayvec::ArrayString::from(s.as_ref()).map_err(|_| crate::vec::full(N))
+    }
+}
+
+/// A string kept as bytes when it's not valid UTF-8. Deserialized as such from the remaining bytes of the buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Utf8Text {
+    /// Valid UTF-8 (or made valid in the lossy mode).
+    Text(String),
+
+    /// Invalid UTF-8, kept as is.
+    Bytes(Vec<u8>),
+}
+
+impl Utf8Text {
+    /// Returns the string, if it's valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(s) => Some(s),
+            Self::Bytes(_) => None,
+        }
+    }
+
+    /// Returns the bytes sent.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(s) => s.as_bytes(),
+            Self::Bytes(b) => b,
+        }
+    }
+}
+
+impl Default for Utf8Text {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl FromUtf8 for Utf8Text {
+    fn from_utf8(bytes: &[u8], mode: Utf8Mode) -> std::io::Result<Self> {
+        match (mode, std::str::from_utf8(bytes)) {
+            (_, Ok(s)) => Ok(Self::Text(s.to_string())),
+            (Utf8Mode::Bytes, Err(_)) => Ok(Self::Bytes(bytes.to_vec())),
+            (_, Err(_)) => decode(bytes, mode).map(|s| Self::Text(s.into_owned())),
+        }
+    }
+}
+
+impl ToNetworkOrder for Utf8Text {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(self.as_bytes());
+        Ok(self.as_bytes().len())
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.as_bytes().len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Utf8Text {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        *self = read_utf8(buffer, None, Utf8Mode::Bytes)?;
+        Ok(())
+    }
+}
+
+/// A ```char``` sent as its UTF-8 bytes (1 to 4), instead of the 4 bytes of its scalar value. This is how ```char```
+/// fields are sent with the ```#[network(char_as = "utf8")]``` field attribute.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::text::Utf8Char;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(Utf8Char('é').serialize_to(&mut buffer).unwrap(), 2);
+/// assert_eq!(buffer, [0xC3, 0xA9]);
+///
+/// let c = Utf8Char::deserialize_new(&mut Cursor::new(buffer.as_slice())).unwrap();
+/// assert_eq!(c.0, 'é');
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Utf8Char(pub char);
+
+impl ToNetworkOrder for Utf8Char {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut bytes = [0u8; 4];
+        let s = self.0.encode_utf8(&mut bytes);
+        buffer.extend_from_slice(s.as_bytes());
+        Ok(s.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        let mut bytes = [0u8; 4];
+        let s = self.0.encode_utf8(&mut bytes);
+        writer.write_all(s.as_bytes())?;
+        Ok(s.len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Utf8Char {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let first = u8::deserialize_new(buffer)?;
+
+        // the number of bytes is given by the leading bits of the first one
+        let width = match first.leading_ones() {
+            0 => 1,
+            n @ 2..=4 => n as usize,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid UTF-8 first byte {:#04x}", first),
+                ))
+            }
+        };
+
+        let mut bytes = [first, 0, 0, 0];
+        std::io::Read::read_exact(buffer, &mut bytes[1..width])?;
+
+        let s = std::str::from_utf8(&bytes[..width])
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        // a valid UTF-8 sequence of this width is a single char
+        self.0 = s.chars().next().unwrap_or_default();
+        Ok(())
+    }
+}
+
+impl From<char> for Utf8Char {
+    fn from(c: char) -> Self {
+        Self(c)
+    }
+}
+
+/// A line of text terminated by CRLF, for the textual preambles of binary protocols (e.g. handshakes). The terminator
+/// isn't part of the string. A line terminated by a single LF is also accepted when deserializing.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::text::TextLine;
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(TextLine::from("HELLO 1.0").serialize_to(&mut buffer).unwrap(), 11);
+/// assert_eq!(buffer, b"HELLO 1.0\r\n");
+///
+/// let line = TextLine::deserialize_new(&mut Cursor::new(&b"OK\nbinary"[..])).unwrap();
+/// assert_eq!(line.as_str(), "OK");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct TextLine(pub String);
+
+impl TextLine {
+    /// Returns the line, without its terminator.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for TextLine {
+    fn from(s: &str) -> Self {
+        Self(s.to_string())
+    }
+}
+
+impl ToNetworkOrder for TextLine {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        write_line(&self.0, buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        write_line(&self.0, writer)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for TextLine {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0 = read_line(buffer)?.to_string();
+        Ok(())
+    }
+}
+
+/// A block of ```Name: value``` lines terminated by an empty line, as the headers of HTTP, SIP or of custom handshakes
+/// preceding a binary body. The lines are terminated by CRLF (or a single LF when deserializing), and the spaces
+/// around the values are not kept.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::text::{TextHeaders, TextLine};
+/// use type2network_derive::{FromNetwork, ToNetwork};
+///
+/// #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+/// struct Handshake {
+///     request: TextLine,
+///     headers: TextHeaders,
+///     version: u16,
+/// }
+///
+/// let bytes = b"CONNECT relay\r\nHost: example.org\r\nContent-Type: raw\r\n\r\n\x00\x02";
+/// let h = Handshake::deserialize_new(&mut Cursor::new(&bytes[..])).unwrap();
+/// assert_eq!(h.headers.get("content-type"), Some("raw"));
+/// assert_eq!(h.version, 2);
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// h.serialize_to(&mut buffer).unwrap();
+/// assert_eq!(buffer, bytes);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextHeaders(pub Vec<(String, String)>);
+
+impl TextHeaders {
+    /// Returns the value of the first header named ```name```, which is case-insensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Adds a header.
+    pub fn push(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_string(), value.to_string()));
+    }
+}
+
+impl ToNetworkOrder for TextHeaders {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.write_to(buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        let mut length = 0;
+        for (name, value) in &self.0 {
+            if name.is_empty() || name.contains(':') {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid header name {:?}", name),
+                ));
+            }
+            length += write_line(&format!("{}: {}", name, value), writer)?;
+        }
+        Ok(length + write_line("", writer)?)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for TextHeaders {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0.clear();
+
+        loop {
+            let line = read_line(buffer)?;
+            if line.is_empty() {
+                return Ok(());
+            }
+
+            let (name, value) = line.split_once(':').ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("header line {:?} without a colon", line),
+                )
+            })?;
+            crate::budget::take(1)?;
+            self.push(name.trim(), value.trim());
+        }
+    }
+}
+
+// writes the line followed by CRLF, which it can't contain
+fn write_line(line: &str, buffer: &mut dyn Write) -> std::io::Result<usize> {
+    if line.contains(['\r', '\n']) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("line {:?} contains a line terminator", line),
+        ));
+    }
+
+    buffer.write_all(line.as_bytes())?;
+    buffer.write_all(b"\r\n")?;
+    Ok(line.len() + 2)
+}
+
+// reads the next line, without its CRLF or LF terminator
+fn read_line<'a>(buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<&'a str> {
+    let bytes = *buffer.get_ref();
+    let position = (buffer.position() as usize).min(bytes.len());
+    let remaining = &bytes[position..];
+
+    let end = remaining
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "line without terminator"))?;
+    let line = remaining[..end]
+        .strip_suffix(b"\r")
+        .unwrap_or(&remaining[..end]);
+    let line = std::str::from_utf8(line).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    buffer.set_position((position + end + 1) as u64);
+    Ok(line)
+}
+
+/// Reads a string of ```length``` bytes, or the remaining ones if ```None```. Called by the code generated for the
+/// ```#[network(utf8 = "mode")]``` field attribute.
+pub fn read_utf8<T: FromUtf8>(
+    buffer: &mut Cursor<&[u8]>,
+    length: Option<usize>,
+    mode: Utf8Mode,
+) -> std::io::Result<T> {
+    let position = (buffer.position() as usize).min(buffer.get_ref().len());
+    let remaining = &buffer.get_ref()[position..];
+
+    let length = length.unwrap_or(remaining.len());
+    let bytes = remaining.get(..length).ok_or_else(|| {
+        Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "string of {} bytes but only {} left",
+                length,
+                remaining.len()
+            ),
+        )
+    })?;
+
+    let value = T::from_utf8(bytes, mode)?;
+    buffer.set_position((position + length) as u64);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::len::Fixed;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn hex() {
+        let v: HexString<u8> = vec![0x01, 0xAB].into();
+        to_network_test(v.clone(), 5, b"\x0401ab");
+        from_network_test(None, v.clone(), &b"\x0401AB".to_vec());
+        assert_eq!(format!("{:?}", v), "HexString(\"01ab\")");
+
+        for text in [&b"\x03abc"[..], b"\x02xy"] {
+            let mut buffer = Cursor::new(text);
+            assert!(HexString::<u8>::deserialize_new(&mut buffer).is_err());
+        }
+
+        // uppercase digits, only rejected in canonical mode
+        crate::canonical::with_canonical(|| {
+            from_network_test(None, v.clone(), &b"\x0401ab".to_vec());
+            let mut buffer = Cursor::new(&b"\x0401AB"[..]);
+            assert!(HexString::<u8>::deserialize_new(&mut buffer).is_err());
+        });
+    }
+
+    #[test]
+    fn base64() {
+        for (bytes, text) in [
+            (&b""[..], &b""[..]),
+            (b"f", b"Zg=="),
+            (b"fo", b"Zm8="),
+            (b"foo", b"Zm9v"),
+            (b"foob", b"Zm9vYg=="),
+            (b"\xFF\xFE", b"//4="),
+        ] {
+            assert_eq!(base64_encode(bytes), text);
+            assert_eq!(base64_decode(text).unwrap(), bytes);
+        }
+
+        for text in [&b"Zg="[..], b"Zg=a", b"Z===", b"Zg==Zg==", b"Zm9*"] {
+            assert!(base64_decode(text).is_err());
+        }
+
+        // non-zero trailing bits, only rejected in canonical mode
+        for text in [&b"QR=="[..], b"Zm9="] {
+            assert!(base64_decode(text).is_ok());
+            crate::canonical::with_canonical(|| assert!(base64_decode(text).is_err()));
+        }
+        crate::canonical::with_canonical(|| assert!(base64_decode(b"QQ==").is_ok()));
+
+        let v: Base64Bytes<Fixed<4>> = b"foo".to_vec().into();
+        to_network_test(v.clone(), 4, b"Zm9v");
+        from_network_test(None, v, &b"Zm9v".to_vec());
+    }
+
+    #[test]
+    fn utf8() {
+        let invalid = b"ab\xFF";
+
+        let mut buffer = Cursor::new(&invalid[..]);
+        assert!(read_utf8::<String>(&mut buffer, None, Utf8Mode::Strict).is_err());
+        assert_eq!(buffer.position(), 0);
+        assert!(read_utf8::<String>(&mut buffer, None, Utf8Mode::Bytes).is_err());
+
+        let s: String = read_utf8(&mut buffer, Some(3), Utf8Mode::Lossy).unwrap();
+        assert_eq!(s, "ab\u{FFFD}");
+        assert_eq!(buffer.position(), 3);
+
+        let mut buffer = Cursor::new(&invalid[..]);
+        let s: String = read_utf8(&mut buffer, Some(2), Utf8Mode::Strict).unwrap();
+        assert_eq!(s, "ab");
+        assert!(read_utf8::<String>(&mut buffer, Some(2), Utf8Mode::Lossy).is_err());
+
+        let mut buffer = Cursor::new(&invalid[..]);
+        let t: Utf8Text = read_utf8(&mut buffer, None, Utf8Mode::Bytes).unwrap();
+        assert_eq!(t.as_str(), None);
+        assert_eq!(t.as_bytes(), invalid);
+        to_network_test(t, 3, invalid);
+        from_network_test(
+            None,
+            Utf8Text::Text("東京".to_string()),
+            &"東京".as_bytes().to_vec(),
+        );
+    }
+
+    #[test]
+    fn text_line() {
+        to_network_test(TextLine::from("a b"), 5, b"a b\r\n");
+        from_network_test(None, TextLine::from("a b"), &b"a b\r\n".to_vec());
+
+        let mut buffer = Vec::new();
+        assert!(TextLine::from("a\r\nb").serialize_to(&mut buffer).is_err());
+        assert!(TextLine::deserialize_new(&mut Cursor::new(&b"abc"[..])).is_err());
+        assert!(TextLine::deserialize_new(&mut Cursor::new(&b"\xFF\r\n"[..])).is_err());
+
+        // the spaces around the values are removed
+        let mut headers = TextHeaders::default();
+        headers.push("A", "1");
+        from_network_test(None, headers.clone(), &b"A:  1 \n\n".to_vec());
+        to_network_test(headers, 8, b"A: 1\r\n\r\n");
+
+        for bytes in [&b"A 1\r\n\r\n"[..], b"A: 1\r\n"] {
+            assert!(TextHeaders::deserialize_new(&mut Cursor::new(bytes)).is_err());
+        }
+    }
+
+    #[test]
+    fn utf8_char() {
+        for c in ['a', 'é', '東', '💯'] {
+            let bytes = c.to_string().into_bytes();
+            to_network_test(Utf8Char(c), bytes.len(), &bytes);
+            from_network_test(None, Utf8Char(c), &bytes);
+        }
+
+        for bytes in [
+            &[0x80][..],
+            &[0xFF],
+            &[0xC3],
+            &[0xC3, 0x28],
+            &[0xED, 0xA0, 0x80],
+        ] {
+            let mut buffer = Cursor::new(bytes);
+            assert!(Utf8Char::deserialize_new(&mut buffer).is_err());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Monotonic timestamps, for latency measurements.
+//!
+//! ```Instant``` can't be sent as is, as it's only meaningful in the process which created it. A ```WireInstant``` is
+//! sent as the time elapsed since an epoch chosen by the user (e.g. the start of a session), as a big-endian ```u64```
+//! number of microseconds. When created with [`WireInstant::new`], the elapsed time is captured when serializing:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use std::time::Instant;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network::time::WireInstant;
+//!
+//! let epoch = Instant::now();
+//!
+//! // the probe sent and echoed by the peer
+//! let mut buffer: Vec<u8> = Vec::new();
+//! assert_eq!(WireInstant::new(epoch).serialize_to(&mut buffer).unwrap(), 8);
+//! let sent = WireInstant::deserialize_new(&mut Cursor::new(buffer.as_slice())).unwrap();
+//!
+//! let rtt = epoch.elapsed() - sent.elapsed();
+//! assert!(rtt < std::time::Duration::from_secs(1));
+//! ```
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A point in time sent as the number of microseconds elapsed since an epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WireInstant {
+    // when set, the elapsed time is computed when serializing
+    epoch: Option<Instant>,
+    elapsed: Duration,
+}
+
+impl WireInstant {
+    /// The elapsed time since ```epoch``` is captured when serializing.
+    pub fn new(epoch: Instant) -> Self {
+        Self {
+            epoch: Some(epoch),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// A fixed point in time, ```elapsed``` after the epoch.
+    pub fn from_elapsed(elapsed: Duration) -> Self {
+        Self {
+            epoch: None,
+            elapsed,
+        }
+    }
+
+    /// The point in time ```instant```, relative to ```epoch```. Instants before the epoch are the epoch itself.
+    pub fn at(epoch: Instant, instant: Instant) -> Self {
+        Self::from_elapsed(instant.saturating_duration_since(epoch))
+    }
+
+    /// Returns the time elapsed since the epoch: the one received, or up to now if created with [`WireInstant::new`].
+    pub fn elapsed(&self) -> Duration {
+        match self.epoch {
+            Some(epoch) => epoch.elapsed(),
+            None => self.elapsed,
+        }
+    }
+
+    /// Returns the ```Instant``` corresponding to this point in time, relative to ```epoch```.
+    pub fn to_instant(&self, epoch: Instant) -> Option<Instant> {
+        epoch.checked_add(self.elapsed())
+    }
+}
+
+impl From<Duration> for WireInstant {
+    fn from(elapsed: Duration) -> Self {
+        Self::from_elapsed(elapsed)
+    }
+}
+
+impl ToNetworkOrder for WireInstant {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        // saturates after more than 500,000 years
+        let micros = u64::try_from(self.elapsed().as_micros()).unwrap_or(u64::MAX);
+        micros.serialize_to(buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let micros = u64::try_from(self.elapsed().as_micros()).unwrap_or(u64::MAX);
+        micros.write_to(writer)
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for WireInstant {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let micros = u64::deserialize_new(buffer)?;
+        *self = Self::from_elapsed(Duration::from_micros(micros));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn wire_instant() {
+        let t = WireInstant::from_elapsed(Duration::from_millis(1500));
+        to_network_test(t, 8, &[0, 0, 0, 0, 0, 0x16, 0xE3, 0x60]);
+        from_network_test(None, t, &vec![0, 0, 0, 0, 0, 0x16, 0xE3, 0x60]);
+
+        // sub-microsecond precision is lost
+        let t = WireInstant::from_elapsed(Duration::from_nanos(2_999));
+        to_network_test(t, 8, &[0, 0, 0, 0, 0, 0, 0, 2]);
+
+        let epoch = Instant::now();
+        let later = epoch + Duration::from_secs(3);
+        let t = WireInstant::at(epoch, later);
+        assert_eq!(t.elapsed(), Duration::from_secs(3));
+        assert_eq!(t.to_instant(epoch), Some(later));
+        assert_eq!(WireInstant::at(later, epoch).elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,202 @@
+//! Reading bulk payloads without zero-filling the destination first.
+//!
+//! Deserializing a large ```Vec<u8>``` element by element, or into a zeroed buffer, costs a lot on
+//! multi-megabyte message bodies. The functions of this module copy the bytes straight from the cursor
+//! to uninitialized memory:
+//!
+//! * [`read_uninit`] fills a user-provided ```&mut [MaybeUninit<u8>]```
+//! * [`read_to_spare`] appends to a ```Vec<u8>``` using its spare capacity
+//!
+//! The ```Bulk``` type uses the latter to read all the remaining bytes of the buffer.
+use std::io::{Cursor, Error, ErrorKind};
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+// the bytes not yet read from the cursor
+fn remaining<'a>(buffer: &Cursor<&'a [u8]>) -> &'a [u8] {
+    let data = *buffer.get_ref();
+    let pos = (buffer.position() as usize).min(data.len());
+    &data[pos..]
+}
+
+/// Fills ```dst``` with the next ```dst.len()``` bytes of the buffer, and returns it as initialized.
+/// An error is returned if not enough bytes are left, in which case the cursor is not moved.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use std::mem::MaybeUninit;
+/// use type2network::uninit::read_uninit;
+///
+/// let mut cursor = Cursor::new([1u8, 2, 3, 4].as_slice());
+/// let mut storage = [MaybeUninit::<u8>::uninit(); 3];
+///
+/// let bytes = read_uninit(&mut cursor, &mut storage).unwrap();
+/// assert_eq!(bytes, &[1, 2, 3]);
+/// assert_eq!(cursor.position(), 3);
+/// ```
+pub fn read_uninit<'b>(
+    buffer: &mut Cursor<&[u8]>,
+    dst: &'b mut [MaybeUninit<u8>],
+) -> std::io::Result<&'b mut [u8]> {
+    let src = remaining(buffer);
+    let len = dst.len();
+
+    if src.len() < len {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("{} bytes requested but only {} left", len, src.len()),
+        ));
+    }
+
+    // SAFETY: src has at least len bytes and can't overlap dst which is borrowed mutably. All len
+    // bytes of dst are initialized by the copy, and MaybeUninit<u8> has the same layout as u8
+    let bytes = unsafe {
+        std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr() as *mut u8, len);
+        std::slice::from_raw_parts_mut(dst.as_mut_ptr() as *mut u8, len)
+    };
+
+    buffer.set_position(buffer.position() + len as u64);
+    Ok(bytes)
+}
+
+/// Appends the next ```len``` bytes of the buffer to ```vec```, writing directly to its spare capacity.
+/// An error is returned if not enough bytes are left, in which case neither ```vec``` nor the cursor are
+/// modified.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::uninit::read_to_spare;
+///
+/// let mut cursor = Cursor::new([1u8, 2, 3, 4].as_slice());
+/// let mut v = vec![0xFF];
+///
+/// read_to_spare(&mut cursor, &mut v, 4).unwrap();
+/// assert_eq!(v, &[0xFF, 1, 2, 3, 4]);
+/// assert!(read_to_spare(&mut cursor, &mut v, 1).is_err());
+/// ```
+pub fn read_to_spare(
+    buffer: &mut Cursor<&[u8]>,
+    vec: &mut Vec<u8>,
+    len: usize,
+) -> std::io::Result<()> {
+    // checked first so that nothing is reserved for a truncated buffer
+    let left = remaining(buffer).len();
+    if left < len {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("{} bytes requested but only {} left", len, left),
+        ));
+    }
+
+    vec.reserve(len);
+    let initialized = read_uninit(buffer, &mut vec.spare_capacity_mut()[..len])?.len();
+
+    // SAFETY: the first len bytes of the spare capacity were initialized by read_uninit()
+    unsafe {
+        vec.set_len(vec.len() + initialized);
+    }
+
+    Ok(())
+}
+
+/// A bulk payload made of all the bytes left in the buffer, which are copied without zero-filling.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::uninit::Bulk;
+///
+/// let b = vec![0xAA; 1024];
+/// let mut cursor = Cursor::new(b.as_slice());
+///
+/// let mut payload = Bulk::default();
+/// assert!(payload.deserialize_from(&mut cursor).is_ok());
+/// assert_eq!(payload.len(), 1024);
+///
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(payload.serialize_to(&mut buffer).unwrap(), 1024);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Bulk(pub Vec<u8>);
+
+impl From<Vec<u8>> for Bulk {
+    fn from(v: Vec<u8>) -> Self {
+        Self(v)
+    }
+}
+
+impl Deref for Bulk {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Bulk {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl ToNetworkOrder for Bulk {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        buffer.extend_from_slice(&self.0);
+        Ok(self.0.len())
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        writer.write_all(&self.0)?;
+        Ok(self.0.len())
+    }
+}
+
+impl<'a> FromNetworkOrder<'a> for Bulk {
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0.clear();
+        let len = remaining(buffer).len();
+        read_to_spare(buffer, &mut self.0, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn uninit() {
+        let b = vec![1u8, 2, 3];
+        let mut cursor = Cursor::new(b.as_slice());
+
+        // too short: nothing is read
+        let mut storage = [MaybeUninit::<u8>::uninit(); 4];
+        assert!(read_uninit(&mut cursor, &mut storage).is_err());
+        assert_eq!(cursor.position(), 0);
+
+        let mut v = Vec::new();
+        assert!(read_to_spare(&mut cursor, &mut v, 4).is_err());
+        assert!(v.is_empty());
+
+        assert_eq!(read_uninit(&mut cursor, &mut storage[..1]).unwrap(), &[1]);
+        read_to_spare(&mut cursor, &mut v, 2).unwrap();
+        assert_eq!(v, &[2, 3]);
+
+        // position past the end of the buffer
+        cursor.set_position(10);
+        assert!(read_to_spare(&mut cursor, &mut v, 1).is_err());
+        assert!(read_uninit(&mut cursor, &mut []).unwrap().is_empty());
+    }
+
+    #[test]
+    fn bulk() {
+        to_network_test(Bulk(vec![1, 2, 3]), 3, &[1, 2, 3]);
+        from_network_test(Some(Bulk(vec![0xFF])), Bulk(vec![1, 2, 3]), &vec![1, 2, 3]);
+        from_network_test(None, Bulk::default(), &Vec::new());
+    }
+}
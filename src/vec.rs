@@ -0,0 +1,669 @@
+//! Wrappers around ```Vec<T>``` selecting how the number of elements is known when deserializing.
+//!
+//! * ```CountPrefixed<L, T>```: the number of elements is sent first, using the ```L``` [`crate::len::WireLen`]
+//!   encoding (e.g. ```u16```)
+//! * ```UntilEof<T>```: elements are read until the end of the buffer
+//! * ```Rle<L, T, MAX>```: the elements are run-length encoded, the number of runs being sent first, and at most ```MAX```
+//!   elements are deserialized
+//! * ```Terminated<M, T>```: elements are read until an end marker, defined by ```M``` [`EndMarker`] (e.g. the
+//!   end-of-contents octets of the BER indefinite length)
+//!
+//! For a ```Vec<T>``` field whose number of elements is known from another field, use the
+//! ```#[network(count = "expr")]``` attribute instead.
+use std::io::{Cursor, Error, ErrorKind};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::len::WireLen;
+use crate::{FromNetworkOrder, ToNetworkOrder};
+
+/// A collection to which elements are appended when deserializing a ```#[network(count = "expr")]``` field.
+/// Bounded collections return an error when full.
+pub trait Push<T> {
+    /// Removes all elements.
+    fn clear(&mut self);
+
+    /// Appends an element, or returns an error if the collection is full.
+    fn try_push(&mut self, item: T) -> std::io::Result<()>;
+
+    /// Returns the name reported to the observer for the bytes of the elements, for the collections passing them
+    /// through without interpretation (e.g. ```opaque::Raw```). It's ```None``` by default.
+    fn pass_through(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+impl<T> Push<T> for Vec<T> {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+
+    fn try_push(&mut self, item: T) -> std::io::Result<()> {
+        self.push(item);
+        Ok(())
+    }
+}
+
+// error returned when a bounded collection is full
+#[cfg(any(feature = "heapless", feature = "arrayvec"))]
+pub(crate) fn full(capacity: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("collection is full (capacity {})", capacity),
+    )
+}
+
+/// A ```Vec<T>``` sent on the wire with its number of elements as a prefix, encoded as defined by
+/// ```L``` (e.g. ```u16``` or ```Varint```).
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::vec::CountPrefixed;
+///
+/// let v: CountPrefixed<u8, u16> = vec![0x1234, 0x5678].into();
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 5);
+/// assert_eq!(buffer, &[0x02, 0x12, 0x34, 0x56, 0x78]);
+///
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// let mut w = CountPrefixed::<u8, u16>::default();
+/// assert!(w.deserialize_from(&mut cursor).is_ok());
+/// assert_eq!(w, v);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountPrefixed<L, T> {
+    items: Vec<T>,
+    length: PhantomData<L>,
+}
+
+impl<L, T> CountPrefixed<L, T> {
+    /// Returns the inner vector.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<L, T> Default for CountPrefixed<L, T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L, T> From<Vec<T>> for CountPrefixed<L, T> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            items,
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L, T> Deref for CountPrefixed<L, T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<L, T> DerefMut for CountPrefixed<L, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl<L, T> ToNetworkOrder for CountPrefixed<L, T>
+where
+    L: WireLen,
+    T: ToNetworkOrder,
+{
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = L::write_len(self.items.len(), buffer)?;
+        length += self.items.serialize_to(buffer)?;
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::align::track_writes(writer, |writer| {
+            let length = L::write_len_to(self.items.len(), writer)?;
+            Ok(length + self.items.write_to(writer)?)
+        })
+    }
+}
+
+impl<'a, L, T> FromNetworkOrder<'a> for CountPrefixed<L, T>
+where
+    L: WireLen,
+    T: FromNetworkOrder<'a> + Default,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let count = L::read_len(buffer)?;
+        crate::budget::take(count)?;
+
+        self.items.clear();
+        for _ in 0..count {
+            self.items.push(T::deserialize_new(buffer)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// A ```Vec<T>``` whose elements are read until the end of the buffer. It's serialized as a ```Vec<T>```.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::FromNetworkOrder;
+/// use type2network::vec::UntilEof;
+///
+/// let b = vec![0x12, 0x34, 0x56, 0x78];
+/// let mut buffer = Cursor::new(b.as_slice());
+/// let mut v = UntilEof::<u16>::default();
+/// assert!(v.deserialize_from(&mut buffer).is_ok());
+/// assert_eq!(*v, &[0x1234, 0x5678]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UntilEof<T>(pub Vec<T>);
+
+impl<T> From<Vec<T>> for UntilEof<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self(items)
+    }
+}
+
+impl<T> Deref for UntilEof<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for UntilEof<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: ToNetworkOrder> ToNetworkOrder for UntilEof<T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.0.serialize_to(buffer)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        self.0.write_to(writer)
+    }
+}
+
+impl<'a, T> FromNetworkOrder<'a> for UntilEof<T>
+where
+    T: FromNetworkOrder<'a> + Default,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        self.0.clear();
+
+        while (buffer.position() as usize) < buffer.get_ref().len() {
+            crate::budget::take(1)?;
+            let position = buffer.position();
+            self.0.push(T::deserialize_new(buffer)?);
+
+            // an element which doesn't consume any byte would loop forever
+            if buffer.position() == position {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "element doesn't consume any byte",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Default maximum number of elements of a deserialized ```Rle```.
+pub const DEFAULT_MAX_RLE_LEN: usize = 64 * 1024;
+
+/// A ```Vec<T>``` run-length encoded: the number of runs is sent first, then each run as its number of elements
+/// followed by the repeated element, both counts using the ```L``` [`crate::len::WireLen`] encoding. Suited to sparse
+/// bitmaps or occupancy payloads.
+///
+/// As a few bytes can announce billions of elements, at most ```MAX``` elements are deserialized (```DEFAULT_MAX_RLE_LEN```
+/// by default), a longer vector being rejected with an ```InvalidData``` error before it's expanded.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::vec::Rle;
+///
+/// let v: Rle<u8, u16> = vec![0, 0, 0, 0, 7, 0, 0].into();
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 10);
+/// assert_eq!(buffer, &[3, 4, 0, 0, 1, 0, 7, 2, 0, 0]);
+///
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// let w = Rle::<u8, u16>::deserialize_new(&mut cursor).unwrap();
+/// assert_eq!(*w, &[0, 0, 0, 0, 7, 0, 0]);
+///
+/// // at most 4 elements
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// assert!(Rle::<u8, u16, 4>::deserialize_new(&mut cursor).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rle<L, T, const MAX: usize = DEFAULT_MAX_RLE_LEN> {
+    items: Vec<T>,
+    length: PhantomData<L>,
+}
+
+impl<L, T, const MAX: usize> Rle<L, T, MAX> {
+    /// Returns the inner vector.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<L, T, const MAX: usize> Default for Rle<L, T, MAX> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L, T, const MAX: usize> From<Vec<T>> for Rle<L, T, MAX> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            items,
+            length: PhantomData,
+        }
+    }
+}
+
+impl<L, T, const MAX: usize> Deref for Rle<L, T, MAX> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<L, T, const MAX: usize> DerefMut for Rle<L, T, MAX> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl<L, T, const MAX: usize> ToNetworkOrder for Rle<L, T, MAX>
+where
+    L: WireLen,
+    T: ToNetworkOrder + PartialEq,
+{
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let runs: Vec<&[T]> = self.items.chunk_by(|a, b| a == b).collect();
+
+        let mut length = L::write_len(runs.len(), buffer)?;
+        for run in runs {
+            length += L::write_len(run.len(), buffer)?;
+            length += run[0].serialize_to(buffer)?;
+        }
+
+        Ok(length)
+    }
+
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        crate::align::track_writes(writer, |writer| {
+            let runs = self.items.chunk_by(|a, b| a == b);
+
+            let mut length = L::write_len_to(runs.clone().count(), writer)?;
+            for run in runs {
+                length += L::write_len_to(run.len(), writer)?;
+                length += run[0].write_to(writer)?;
+            }
+
+            Ok(length)
+        })
+    }
+}
+
+impl<'a, L, T, const MAX: usize> FromNetworkOrder<'a> for Rle<L, T, MAX>
+where
+    L: WireLen,
+    T: FromNetworkOrder<'a> + Default + Clone + PartialEq,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let runs = L::read_len(buffer)?;
+        crate::budget::take(runs)?;
+
+        self.items.clear();
+        for _ in 0..runs {
+            let count = L::read_len(buffer)?;
+            if count == 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "empty run"));
+            }
+            crate::budget::take(count - 1)?;
+
+            if count > MAX - self.items.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("run-length encoded vector longer than {} elements", MAX),
+                ));
+            }
+
+            let item = T::deserialize_new(buffer)?;
+            crate::canonical::check(
+                self.items.last() != Some(&item),
+                "adjacent runs of the same element",
+            )?;
+            self.items.extend(std::iter::repeat_n(item, count));
+        }
+
+        Ok(())
+    }
+}
+
+/// The bytes ending a sequence of elements whose number or length is not sent, as in streaming encodings.
+///
+/// An empty marker would be found before any element, and is rejected at compile time:
+///
+/// ```compile_fail
+/// use type2network::vec::{EndMarker, Terminated};
+/// use type2network::ToNetworkOrder;
+///
+/// struct Empty;
+///
+/// impl EndMarker for Empty {
+///     const MARKER: &'static [u8] = &[];
+/// }
+///
+/// let v: Terminated<Empty, u8> = vec![1].into();
+/// v.serialize_to(&mut Vec::new()).unwrap();
+/// ```
+pub trait EndMarker {
+    /// The marker, which can't be empty: ```Terminated``` doesn't compile with an empty marker.
+    const MARKER: &'static [u8];
+}
+
+/// A ```Vec<T>``` followed by the ```M``` end marker. The elements are read until the marker is found where the next
+/// element would start, so the marker bytes can appear inside the elements, but not where an element starts: such
+/// elements couldn't be read back, and ```serialize_to()``` returns an ```InvalidInput``` error.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use type2network::{FromNetworkOrder, ToNetworkOrder};
+/// use type2network::vec::{EndMarker, Terminated};
+///
+/// struct Ff;
+///
+/// impl EndMarker for Ff {
+///     const MARKER: &'static [u8] = &[0xFF, 0xFF];
+/// }
+///
+/// let v: Terminated<Ff, u16> = vec![0x12FF, 0xFF34].into();
+/// let mut buffer: Vec<u8> = Vec::new();
+/// assert_eq!(v.serialize_to(&mut buffer).unwrap(), 6);
+/// assert_eq!(buffer, &[0x12, 0xFF, 0xFF, 0x34, 0xFF, 0xFF]);
+///
+/// let mut cursor = Cursor::new(buffer.as_slice());
+/// let w = Terminated::<Ff, u16>::deserialize_new(&mut cursor).unwrap();
+/// assert_eq!(*w, &[0x12FF, 0xFF34]);
+///
+/// // read back as the marker
+/// let v: Terminated<Ff, u16> = vec![0xFFFF].into();
+/// assert!(v.serialize_to(&mut Vec::new()).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminated<M, T> {
+    items: Vec<T>,
+    marker: PhantomData<M>,
+}
+
+impl<M, T> Terminated<M, T> {
+    /// Returns the inner vector.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<M, T> Default for Terminated<M, T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M, T> From<Vec<T>> for Terminated<M, T> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            items,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M, T> Deref for Terminated<M, T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<M, T> DerefMut for Terminated<M, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl<M: EndMarker, T> Terminated<M, T> {
+    // an empty marker would be found before any element
+    const MARKER_NOT_EMPTY: () = assert!(!M::MARKER.is_empty(), "the end marker can't be empty");
+}
+
+// the default write_to() is kept: the bytes following an element must be known to check it doesn't look like the
+// marker
+impl<M: EndMarker, T: ToNetworkOrder> ToNetworkOrder for Terminated<M, T> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+        let () = Self::MARKER_NOT_EMPTY;
+        let start = buffer.len();
+
+        let mut offsets = Vec::with_capacity(self.items.len());
+        let mut length = 0usize;
+        for item in &self.items {
+            offsets.push(buffer.len());
+            length += item.serialize_to(buffer)?;
+        }
+        buffer.extend_from_slice(M::MARKER);
+
+        // the elements read back as the marker
+        if offsets.iter().any(|&o| buffer[o..].starts_with(M::MARKER)) {
+            buffer.truncate(start);
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "an element starts with the end marker",
+            ));
+        }
+        Ok(length + M::MARKER.len())
+    }
+}
+
+impl<'a, M, T> FromNetworkOrder<'a> for Terminated<M, T>
+where
+    M: EndMarker,
+    T: FromNetworkOrder<'a> + Default,
+{
+    fn deserialize_from(&mut self, buffer: &mut Cursor<&'a [u8]>) -> std::io::Result<()> {
+        let () = Self::MARKER_NOT_EMPTY;
+        self.items.clear();
+
+        loop {
+            let bytes = *buffer.get_ref();
+            let position = (buffer.position() as usize).min(bytes.len());
+            if bytes[position..].starts_with(M::MARKER) {
+                buffer.set_position((position + M::MARKER.len()) as u64);
+                return Ok(());
+            }
+            if position == bytes.len() {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "end marker not found"));
+            }
+
+            crate::budget::take(1)?;
+            self.items.push(T::deserialize_new(buffer)?);
+
+            // an element which doesn't consume any byte would loop forever
+            if buffer.position() as usize == position {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "element doesn't consume any byte",
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::len::Varint;
+    use crate::test_helpers::{from_network_test, to_network_test};
+
+    #[test]
+    fn count_prefixed() {
+        let v: CountPrefixed<u16, u8> = vec![1, 2, 3].into();
+        to_network_test(v.clone(), 5, &[0, 3, 1, 2, 3]);
+        from_network_test(None, v, &vec![0, 3, 1, 2, 3]);
+
+        // too many elements for the prefix
+        let v: CountPrefixed<u8, u8> = vec![0; 256].into();
+        assert!(v.serialize_to(&mut Vec::new()).is_err());
+
+        // varint prefix
+        let v: CountPrefixed<Varint, u8> = vec![0; 128].into();
+        let mut buffer: Vec<u8> = Vec::new();
+        assert_eq!(v.serialize_to(&mut buffer).unwrap(), 130);
+        assert_eq!(&buffer[..2], &[0x80, 0x01]);
+
+        // not enough data
+        let b = vec![3, 1, 2];
+        let mut buffer = Cursor::new(b.as_slice());
+        let mut v = CountPrefixed::<u8, u8>::default();
+        assert!(v.deserialize_from(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn until_eof() {
+        let v: UntilEof<u16> = vec![0x1234, 0x5678].into();
+        to_network_test(v.clone(), 4, &[0x12, 0x34, 0x56, 0x78]);
+        from_network_test(None, v, &vec![0x12, 0x34, 0x56, 0x78]);
+
+        // trailing byte
+        let b = vec![0x12, 0x34, 0x56];
+        let mut buffer = Cursor::new(b.as_slice());
+        let mut v = UntilEof::<u16>::default();
+        assert!(v.deserialize_from(&mut buffer).is_err());
+
+        // zero-sized elements
+        let b = vec![0x12];
+        let mut buffer = Cursor::new(b.as_slice());
+        let mut v = UntilEof::<PhantomData<u8>>::default();
+        assert!(v.deserialize_from(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn rle() {
+        let v: Rle<u8, u8> = vec![1, 1, 1, 2, 1].into();
+        to_network_test(v.clone(), 7, &[3, 3, 1, 1, 2, 1, 1]);
+        from_network_test(None, v, &vec![3, 3, 1, 1, 2, 1, 1]);
+
+        let v: Rle<Varint, u8> = vec![0; 300].into();
+        to_network_test(v.clone(), 4, &[1, 0xAC, 0x02, 0]);
+        from_network_test(None, v, &vec![1, 0xAC, 0x02, 0]);
+
+        to_network_test(Rle::<u16, u8>::default(), 2, &[0, 0]);
+
+        // a run can't be empty
+        let b = vec![1, 0, 5];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Rle::<u8, u8>::deserialize_new(&mut buffer).is_err());
+
+        // runs longer than the prefix can hold
+        let v: Rle<u8, u8> = vec![0; 256].into();
+        assert!(v.serialize_to(&mut Vec::new()).is_err());
+
+        // billions of elements announced in a few bytes
+        let b = vec![2, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0xFF, 0xFF, 0xFF, 0xFF, 1];
+        let mut buffer = Cursor::new(b.as_slice());
+        let e = Rle::<u32, u8>::deserialize_new(&mut buffer).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+        let b = vec![2, 3, 0, 2, 1];
+        from_network_test(None, Rle::<u8, u8, 5>::from(vec![0, 0, 0, 1, 1]), &b);
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Rle::<u8, u8, 4>::deserialize_new(&mut buffer).is_err());
+
+        // adjacent runs of the same element, only rejected in canonical mode
+        let b = vec![2, 1, 7, 2, 7];
+        from_network_test(None, Rle::<u8, u8>::from(vec![7, 7, 7]), &b);
+        crate::canonical::with_canonical(|| {
+            let mut buffer = Cursor::new(b.as_slice());
+            assert!(Rle::<u8, u8>::deserialize_new(&mut buffer).is_err());
+        });
+    }
+
+    #[test]
+    fn terminated() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Zero;
+
+        impl EndMarker for Zero {
+            const MARKER: &'static [u8] = &[0];
+        }
+
+        let v: Terminated<Zero, u8> = vec![1, 2].into();
+        to_network_test(v.clone(), 3, &[1, 2, 0]);
+        from_network_test(None, v, &vec![1, 2, 0, 3]);
+        to_network_test(Terminated::<Zero, u8>::default(), 1, &[0]);
+
+        // the marker is missing
+        let b = vec![1, 2];
+        let mut buffer = Cursor::new(b.as_slice());
+        let e = Terminated::<Zero, u8>::deserialize_new(&mut buffer).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+
+        // elements read back as the marker, alone or with the next ones
+        let v: Terminated<Zero, u8> = vec![1, 0].into();
+        let mut buffer = vec![7];
+        let e = v.serialize_to(&mut buffer).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert_eq!(buffer, &[7]);
+        assert!(v.write_to(&mut Vec::new()).is_err());
+
+        struct Ff;
+
+        impl EndMarker for Ff {
+            const MARKER: &'static [u8] = &[0xFF, 0xFF];
+        }
+
+        let v: Terminated<Ff, u8> = vec![1, 0xFF, 0xFF].into();
+        assert!(v.serialize_to(&mut Vec::new()).is_err());
+        let v: Terminated<Ff, u8> = vec![0xFF, 1, 0xFF].into();
+        assert!(v.serialize_to(&mut Vec::new()).is_err());
+        let v: Terminated<Ff, u8> = vec![0xFF, 1].into();
+        to_network_test(v, 4, &[0xFF, 1, 0xFF, 0xFF]);
+
+        // zero-sized elements
+        let b = vec![0x12];
+        let mut buffer = Cursor::new(b.as_slice());
+        assert!(Terminated::<Zero, PhantomData<u8>>::deserialize_new(&mut buffer).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Vectored serialization, referencing large payloads instead of copying them.
+//!
+//! A ```Vectored``` collects the bytes of a message as a list of segments: the small fields are serialized in a buffer
+//! owned by the ```Vectored```, while the payloads (```Bytes```, ```Vec<u8>```, ```[u8; N]```...) are only borrowed.
+//! Its ```io_slices()``` can then be given to ```write_vectored()``` or to an ```io_uring``` submission, so the payloads
+//! are never copied into the serialization buffer.
+//!
+//! ```#[derive(ToNetwork)]``` implements ```ToVectored``` for a struct having the ```#[network(vectored)]``` attribute.
+//! The fields marked with ```#[network(to(borrow))]``` are borrowed, their type must implement ```AsRef<[u8]>``` and
+//! be serialized as these bytes:
+//!
+//! ```
+//! use bytes::Bytes;
+//! use type2network::ToNetworkOrder;
+//! use type2network::vectored::{ToVectored, Vectored};
+//! use type2network_derive::ToNetwork;
+//!
+//! #[derive(ToNetwork)]
+//! #[network(vectored)]
+//! struct Chunk {
+//!     id: u16,
+//!     length: u32,
+//!     #[network(to(borrow))]
+//!     data: Bytes,
+//!     crc: u16,
+//! }
+//!
+//! let chunk = Chunk { id: 1, length: 4, data: Bytes::from_static(b"data"), crc: 0xFFFF };
+//! let mut out = Vectored::new();
+//! assert_eq!(chunk.serialize_vectored(&mut out).unwrap(), 12);
+//!
+//! // the header, the payload and the trailer
+//! let slices = out.io_slices();
+//! assert_eq!(slices.len(), 3);
+//! assert_eq!(slices[1].as_ptr(), chunk.data.as_ptr());
+//!
+//! let mut sent = Vec::new();
+//! assert_eq!(out.write_to(&mut sent).unwrap(), 12);
+//! assert_eq!(sent, &[0, 1, 0, 0, 0, 4, b'd', b'a', b't', b'a', 0xFF, 0xFF]);
+//! ```
+use std::io::{Error, ErrorKind, IoSlice, Write};
+use std::ops::Range;
+
+use crate::ToNetworkOrder;
+
+/// Types which can be serialized as a list of segments, borrowing their payloads.
+pub trait ToVectored {
+    /// Adds the segments of the value to ```out```, and returns the number of bytes they hold.
+    fn serialize_vectored<'a>(&'a self, out: &mut Vectored<'a>) -> std::io::Result<usize>;
+}
+
+// a part of the message
+#[derive(Debug)]
+enum Segment<'a> {
+    // bytes of the owned buffer
+    Copied(Range<usize>),
+
+    // bytes borrowed from the value serialized
+    Borrowed(&'a [u8]),
+}
+
+/// The bytes of a message, made of copied and borrowed segments.
+#[derive(Debug, Default)]
+pub struct Vectored<'a> {
+    buffer: Vec<u8>,
+
+    // segments before the bytes of the buffer not added yet as a segment
+    segments: Vec<Segment<'a>>,
+    copied: usize,
+}
+
+impl<'a> Vectored<'a> {
+    /// Creates an empty list of segments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the buffer in which small values are copied. The bytes appended are added to the message after
+    /// the segments already there.
+    pub fn buffer(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    /// Serializes the value in the owned buffer, and returns the number of bytes written.
+    pub fn put<T: ToNetworkOrder + ?Sized>(&mut self, value: &T) -> std::io::Result<usize> {
+        value.serialize_to(&mut self.buffer)
+    }
+
+    /// Adds the bytes to the message without copying them, and returns their number.
+    pub fn borrow(&mut self, bytes: &'a [u8]) -> usize {
+        if !bytes.is_empty() {
+            self.flush();
+            self.segments.push(Segment::Borrowed(bytes));
+        }
+        bytes.len()
+    }
+
+    /// Returns the segments of the message, to be given to ```write_vectored()```.
+    pub fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        let copied = (self.copied < self.buffer.len()).then_some(self.copied..self.buffer.len());
+
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Copied(range) => IoSlice::new(&self.buffer[range.clone()]),
+                Segment::Borrowed(bytes) => IoSlice::new(bytes),
+            })
+            .chain(copied.map(|range| IoSlice::new(&self.buffer[range])))
+            .collect()
+    }
+
+    /// Returns the number of bytes of the message.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+            + self
+                .segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Copied(_) => 0,
+                    Segment::Borrowed(bytes) => bytes.len(),
+                })
+                .sum::<usize>()
+    }
+
+    /// Returns ```true``` if the message has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes all the segments with vectored writes, and returns the number of bytes written.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let mut slices = self.io_slices();
+        let mut slices = &mut slices[..];
+
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::WriteZero,
+                        "failed to write whole message",
+                    ))
+                }
+                Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(self.len())
+    }
+
+    // the bytes copied since the last segment become a segment
+    fn flush(&mut self) {
+        if self.copied < self.buffer.len() {
+            self.segments
+                .push(Segment::Copied(self.copied..self.buffer.len()));
+            self.copied = self.buffer.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectored() {
+        let payload = vec![1u8, 2, 3];
+        let mut out = Vectored::new();
+        assert!(out.is_empty());
+
+        assert_eq!(out.put(&0x1234_u16).unwrap(), 2);
+        assert_eq!(out.borrow(&payload), 3);
+        assert_eq!(out.borrow(&[]), 0);
+        assert_eq!(out.borrow(&payload[1..]), 2);
+        out.buffer().push(0xFF);
+
+        let slices = out.io_slices();
+        assert_eq!(slices.len(), 4);
+        assert_eq!(slices[1].as_ptr(), payload.as_ptr());
+        assert_eq!(out.len(), 8);
+
+        // a writer accepting a single byte at a time
+        struct OneByte(Vec<u8>);
+        impl Write for OneByte {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.extend_from_slice(&buf[..1]);
+                Ok(1)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = OneByte(Vec::new());
+        assert_eq!(out.write_to(&mut writer).unwrap(), 8);
+        assert_eq!(writer.0, &[0x12, 0x34, 1, 2, 3, 2, 3, 0xFF]);
+    }
+}
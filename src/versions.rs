@@ -0,0 +1,176 @@
+//! Test matrix of the versions of a protocol (```versions``` feature), for protocols whose messages change over time
+//! while the old versions are still received.
+//!
+//! Each version has its own struct, and a function upgrading its values to the struct of the latest version. Given
+//! sample packets of each version along with the latest values they should be upgraded to, ```VersionMatrix```
+//! checks for every sample that:
+//!
+//! * the struct of its version reads all its bytes
+//! * the value read is serialized again with the same bytes
+//! * the value upgraded is the expected latest value
+//!
+//! ```
+//! use type2network::versions::VersionMatrix;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct HelloV1 {
+//!     id: u16,
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+//! struct HelloV2 {
+//!     id: u16,
+//!     flags: u8,
+//! }
+//!
+//! impl From<HelloV1> for HelloV2 {
+//!     fn from(v1: HelloV1) -> Self {
+//!         Self { id: v1.id, flags: 0 }
+//!     }
+//! }
+//!
+//! VersionMatrix::<HelloV2>::new()
+//!     .version::<HelloV1>("v1", HelloV2::from, [(vec![0x12, 0x34], HelloV2 { id: 0x1234, flags: 0 })])
+//!     .version::<HelloV2>("v2", |v| v, [(vec![0x12, 0x34, 1], HelloV2 { id: 0x1234, flags: 1 })])
+//!     .assert();
+//! ```
+use std::fmt::Debug;
+
+use crate::{convert_from_bytes, FromNetworkOrder, ToNetworkOrder};
+
+// reads the bytes of a sample with the struct of its version, checks they're serialized again the same way, and
+// returns the value upgraded
+type Check<L> = Box<dyn Fn(&[u8]) -> Result<L, String>>;
+
+// a version with its samples
+struct Version<L> {
+    name: &'static str,
+    check: Check<L>,
+    samples: Vec<(Vec<u8>, L)>,
+}
+
+/// The sample packets of the versions of a protocol, whose latest version is read as an ```L``` value.
+pub struct VersionMatrix<L> {
+    versions: Vec<Version<L>>,
+}
+
+impl<L: PartialEq + Debug> Default for VersionMatrix<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: PartialEq + Debug> VersionMatrix<L> {
+    /// Creates a matrix without versions.
+    pub fn new() -> Self {
+        Self {
+            versions: Vec::new(),
+        }
+    }
+
+    /// Adds a version, whose packets are read as ```V``` values then upgraded to the latest version by
+    /// ```upgrade```, with its samples: the bytes of a packet and the latest value expected once upgraded. For the
+    /// latest version, ```upgrade``` is the identity.
+    pub fn version<V>(
+        mut self,
+        name: &'static str,
+        upgrade: impl Fn(V) -> L + 'static,
+        samples: impl IntoIterator<Item = (Vec<u8>, L)>,
+    ) -> Self
+    where
+        V: ToNetworkOrder + for<'a> FromNetworkOrder<'a> + Default + Debug + 'static,
+    {
+        let check = move |bytes: &[u8]| {
+            let type_name = std::any::type_name::<V>();
+            let value: V = convert_from_bytes(type_name, bytes)
+                .map_err(|e| format!("unable to read a {}: {}", type_name, e))?;
+
+            let mut again = Vec::new();
+            value
+                .serialize_to(&mut again)
+                .map_err(|e| format!("unable to serialize {:?}: {}", value, e))?;
+            if again != bytes {
+                return Err(format!(
+                    "{:?} serialized as {:02X?} instead of {:02X?}",
+                    value, again, bytes
+                ));
+            }
+
+            Ok(upgrade(value))
+        };
+
+        self.versions.push(Version {
+            name,
+            check: Box::new(check),
+            samples: samples.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Checks all the samples, and returns the failures, one line per sample failing.
+    pub fn failures(&self) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for version in &self.versions {
+            for (i, (bytes, expected)) in version.samples.iter().enumerate() {
+                let failure = match (version.check)(bytes) {
+                    Ok(latest) if latest == *expected => continue,
+                    Ok(latest) => format!("upgraded to {:?} instead of {:?}", latest, expected),
+                    Err(e) => e,
+                };
+                failures.push(format!(
+                    "version {}, sample #{}: {}",
+                    version.name, i, failure
+                ));
+            }
+        }
+
+        failures
+    }
+
+    /// Checks all the samples, and panics with the list of the failures if any.
+    pub fn assert(&self) {
+        let failures = self.failures();
+        if !failures.is_empty() {
+            panic!(
+                "{} samples failing:\n{}",
+                failures.len(),
+                failures.join("\n")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        // v1 sends the value on a byte, v2 on 2 bytes
+        let matrix = VersionMatrix::<u16>::new()
+            .version::<u8>(
+                "v1",
+                u16::from,
+                [(vec![1], 1), (vec![2], 3), (vec![1, 2], 1)],
+            )
+            .version::<u16>("v2", |v| v, [(vec![0, 1], 1)]);
+
+        let failures = matrix.failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(
+            failures[0],
+            "version v1, sample #1: upgraded to 2 instead of 3"
+        );
+        assert!(failures[1].starts_with("version v1, sample #2: unable to read a u8"));
+
+        let e =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| matrix.assert())).unwrap_err();
+        assert!(e
+            .downcast_ref::<String>()
+            .unwrap()
+            .starts_with("2 samples failing"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Read-only views over received bytes, generated by ```#[derive(NetworkView)]```.
+//!
+//! For a struct ```Header```, the derive generates a ```HeaderView<'v>``` struct holding a ```&'v [u8]```, with an
+//! accessor per field reading the field at its offset. Nothing is allocated or copied besides the value returned,
+//! so hot paths can inspect headers without deserializing them:
+//!
+//! ```
+//! use type2network_derive::NetworkView;
+//!
+//! #[derive(NetworkView)]
+//! struct Header {
+//!     kind: u8,
+//!     id: u32,
+//!     mac: [u8; 6],
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let bytes = [1, 0, 0, 0x12, 0x34, 1, 2, 3, 4, 5, 6, 0xFF, 0xFE];
+//! let view = HeaderView::new(&bytes).unwrap();
+//! assert_eq!(view.kind(), 1);
+//! assert_eq!(view.id(), 0x1234);
+//! assert_eq!(view.mac(), [1, 2, 3, 4, 5, 6]);
+//! assert_eq!(view.payload(), &[0xFF, 0xFE]);
+//! assert_eq!(HeaderView::FIXED_SIZE, 11);
+//!
+//! // not enough bytes for the fixed size fields
+//! assert!(HeaderView::new(&bytes[..10]).is_err());
+//! ```
+//!
+//! The fields must implement the ```FixedSize``` trait, except the last one which can be a ```Vec<u8>``` or a ```&[u8]```:
+//! its accessor returns the bytes left after the other fields.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::endian::{BeU16, BeU32, BeU64};
+
+/// A type with a fixed size on the wire, which can be read at an offset of a view.
+pub trait FixedSize: Sized {
+    /// Number of bytes on the wire.
+    const SIZE: usize;
+
+    /// Reads the value from the first ```SIZE``` bytes. Panics if there are not enough bytes, which the views check
+    /// when created.
+    fn read_at(bytes: &[u8]) -> Self;
+}
+
+// helper macro for boiler plate definitions
+macro_rules! impl_fixed_size {
+    ($($t:ty),*) => {
+        $(
+            impl FixedSize for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn read_at(bytes: &[u8]) -> Self {
+                    let mut value = [0u8; std::mem::size_of::<$t>()];
+                    value.copy_from_slice(&bytes[..Self::SIZE]);
+                    <$t>::from_be_bytes(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_size!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+#[cfg(feature = "int128")]
+impl_fixed_size!(u128, i128);
+
+// these types are built from an integer
+macro_rules! impl_fixed_size_from {
+    ($($t:ty => $int:ty),*) => {
+        $(
+            impl FixedSize for $t {
+                const SIZE: usize = <$int as FixedSize>::SIZE;
+
+                fn read_at(bytes: &[u8]) -> Self {
+                    <$t>::from(<$int>::read_at(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_size_from!(BeU16 => u16, BeU32 => u32, BeU64 => u64, Ipv4Addr => u32, Ipv6Addr => [u8; 16]);
+
+impl<T: FixedSize, const N: usize> FixedSize for [T; N] {
+    const SIZE: usize = N * T::SIZE;
+
+    fn read_at(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::read_at(&bytes[i * T::SIZE..]))
+    }
+}
+
+// called by the generated views when created
+#[doc(hidden)]
+pub fn check_view_size(type_name: &str, bytes: &[u8], size: usize) -> std::io::Result<()> {
+    if bytes.len() < size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "{} bytes are needed for a view of {}, only {} given",
+                size,
+                type_name,
+                bytes.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_size() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        assert_eq!(u16::read_at(&bytes), 0x1234);
+        assert_eq!(i8::read_at(&bytes[4..]), -102);
+        assert_eq!(<[BeU16; 2]>::SIZE, 4);
+        assert_eq!(<[u16; 2]>::read_at(&bytes), [0x1234, 0x5678]);
+        assert_eq!(
+            Ipv4Addr::read_at(&bytes),
+            Ipv4Addr::new(0x12, 0x34, 0x56, 0x78)
+        );
+        assert!(check_view_size("Header", &bytes, 6).is_err());
+    }
+}
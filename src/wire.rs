@@ -0,0 +1,92 @@
+//! `const fn` helpers to convert integers to big-endian byte arrays and back.
+//!
+//! As these functions can be evaluated at compile time, statically-known headers can be baked
+//! into `static` or `const` byte arrays:
+//!
+//! ```
+//! use type2network::wire::{decode_u32_at, encode_u16, encode_u32};
+//!
+//! const MAGIC: [u8; 4] = encode_u32(0xCAFE_BABE);
+//! const VERSION: [u8; 2] = encode_u16(1);
+//!
+//! static HEADER: [u8; 6] = [MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], VERSION[0], VERSION[1]];
+//! assert_eq!(HEADER, [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x01]);
+//!
+//! const DECODED: u32 = decode_u32_at(&HEADER, 0);
+//! assert_eq!(DECODED, 0xCAFE_BABE);
+//! ```
+
+// helper macro for boiler plate definitions
+macro_rules! impl_wire {
+    ($t:ty, $size:literal, $enc:ident, $dec:ident, $dec_at:ident) => {
+        #[doc = concat!("Converts a `", stringify!($t), "` to its big-endian byte representation.")]
+        pub const fn $enc(value: $t) -> [u8; $size] {
+            value.to_be_bytes()
+        }
+
+        #[doc = concat!("Converts a big-endian byte array to a `", stringify!($t), "`.")]
+        pub const fn $dec(bytes: [u8; $size]) -> $t {
+            <$t>::from_be_bytes(bytes)
+        }
+
+        #[doc = concat!("Reads a big-endian `", stringify!($t), "` from `buffer` starting at `offset`.")]
+        ///
+        /// Panics (or fails to compile in a `const` context) if `buffer` is too short.
+        pub const fn $dec_at(buffer: &[u8], offset: usize) -> $t {
+            let mut bytes = [0u8; $size];
+            let mut i = 0;
+
+            // for loops are not allowed in const fn
+            while i < $size {
+                bytes[i] = buffer[offset + i];
+                i += 1;
+            }
+
+            <$t>::from_be_bytes(bytes)
+        }
+    };
+}
+
+// signed
+impl_wire!(i8, 1, encode_i8, decode_i8, decode_i8_at);
+impl_wire!(i16, 2, encode_i16, decode_i16, decode_i16_at);
+impl_wire!(i32, 4, encode_i32, decode_i32, decode_i32_at);
+impl_wire!(i64, 8, encode_i64, decode_i64, decode_i64_at);
+#[cfg(feature = "int128")]
+impl_wire!(i128, 16, encode_i128, decode_i128, decode_i128_at);
+
+// unsigned
+impl_wire!(u8, 1, encode_u8, decode_u8, decode_u8_at);
+impl_wire!(u16, 2, encode_u16, decode_u16, decode_u16_at);
+impl_wire!(u32, 4, encode_u32, decode_u32, decode_u32_at);
+impl_wire!(u64, 8, encode_u64, decode_u64, decode_u64_at);
+#[cfg(feature = "int128")]
+impl_wire!(u128, 16, encode_u128, decode_u128, decode_u128_at);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        const A: [u8; 2] = encode_u16(0x1234);
+        const B: [u8; 4] = encode_i32(-2);
+        const C: [u8; 8] = encode_u64(0x1234567812345678);
+
+        assert_eq!(A, [0x12, 0x34]);
+        assert_eq!(B, [0xFF, 0xFF, 0xFF, 0xFE]);
+        assert_eq!(C, [0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn decode() {
+        const BUF: [u8; 6] = [0xFF, 0x12, 0x34, 0x56, 0x78, 0xFF];
+        const A: u16 = decode_u16([0x12, 0x34]);
+        const B: u32 = decode_u32_at(&BUF, 1);
+        const C: i8 = decode_i8_at(&BUF, 5);
+
+        assert_eq!(A, 0x1234);
+        assert_eq!(B, 0x12345678);
+        assert_eq!(C, -1);
+    }
+}
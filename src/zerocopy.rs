@@ -0,0 +1,105 @@
+//! Interoperability with the [```zerocopy```](https://crates.io/crates/zerocopy) crate (```zerocopy``` feature).
+//!
+//! The traits can't be implemented for all the types implementing ```zerocopy::IntoBytes``` or ```zerocopy::FromBytes```,
+//! as they are already implemented for integers with a different byte order. Instead, a struct deriving the zerocopy
+//! traits and ```#[network(zerocopy)]``` is serialized and deserialized as its bytes, with a single copy:
+//!
+//! ```
+//! use std::io::Cursor;
+//! use type2network::{FromNetworkOrder, ToNetworkOrder};
+//! use type2network_derive::{FromNetwork, ToNetwork};
+//! use zerocopy::byteorder::network_endian::U16;
+//! use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+//!
+//! #[derive(Debug, Default, PartialEq, FromBytes, IntoBytes, Immutable, KnownLayout, ToNetwork, FromNetwork)]
+//! #[network(zerocopy)]
+//! #[repr(C)]
+//! struct UdpHeader {
+//!     src_port: U16,
+//!     dst_port: U16,
+//!     length: U16,
+//!     checksum: U16,
+//! }
+//!
+//! let header = UdpHeader { src_port: U16::new(53), dst_port: U16::new(1024), length: U16::new(8), ..Default::default() };
+//! let mut buffer: Vec<u8> = Vec::new();
+//! assert_eq!(header.serialize_to(&mut buffer).unwrap(), 8);
+//! assert_eq!(buffer, &[0, 53, 4, 0, 0, 8, 0, 0]);
+//!
+//! let mut cursor = Cursor::new(buffer.as_slice());
+//! assert_eq!(UdpHeader::deserialize_new(&mut cursor).unwrap(), header);
+//! ```
+//!
+//! As the bytes of such a struct are sent as they are in memory, its integer fields must be stored in network order.
+//! This is checked at compile time: all fields must implement the ```NetworkLayout``` trait, i.e. be ```u8```, ```i8```,
+//! the big-endian types of ```zerocopy::byteorder``` (e.g. ```U32<NetworkEndian>```), structs deriving ```ToNetwork```
+//! with ```#[network(zerocopy)]```, or arrays of those. A native ```u32``` field is rejected:
+//!
+//! ```compile_fail
+//! use type2network::ToNetworkOrder;
+//! use type2network_derive::ToNetwork;
+//! use zerocopy::{Immutable, IntoBytes};
+//!
+//! #[derive(IntoBytes, Immutable, ToNetwork)]
+//! #[network(zerocopy)]
+//! #[repr(C)]
+//! struct Native {
+//!     id: u32,
+//! }
+//! ```
+use std::io::{Cursor, Error, ErrorKind};
+
+use ::zerocopy::byteorder::{BigEndian, F32, F64, I128, I16, I32, I64, U128, U16, U32, U64};
+use ::zerocopy::{FromBytes, Immutable, IntoBytes};
+
+/// Types whose bytes in memory are their network representation, whatever the host byte order. It's implemented
+/// by ```#[derive(ToNetwork)]``` for structs having the ```#[network(zerocopy)]``` attribute.
+pub trait NetworkLayout {}
+
+impl NetworkLayout for u8 {}
+impl NetworkLayout for i8 {}
+impl<T: NetworkLayout, const N: usize> NetworkLayout for [T; N] {}
+
+impl NetworkLayout for U16<BigEndian> {}
+impl NetworkLayout for U32<BigEndian> {}
+impl NetworkLayout for U64<BigEndian> {}
+impl NetworkLayout for U128<BigEndian> {}
+impl NetworkLayout for I16<BigEndian> {}
+impl NetworkLayout for I32<BigEndian> {}
+impl NetworkLayout for I64<BigEndian> {}
+impl NetworkLayout for I128<BigEndian> {}
+impl NetworkLayout for F32<BigEndian> {}
+impl NetworkLayout for F64<BigEndian> {}
+
+/// Appends the bytes of the value to the buffer, and returns their number.
+pub fn write_bytes<T: IntoBytes + Immutable + ?Sized>(value: &T, buffer: &mut Vec<u8>) -> usize {
+    let bytes = value.as_bytes();
+    buffer.extend_from_slice(bytes);
+    bytes.len()
+}
+
+/// Writes the bytes of the value to the writer, and returns their number.
+pub fn write_bytes_to<T: IntoBytes + Immutable + ?Sized>(
+    value: &T,
+    writer: &mut dyn std::io::Write,
+) -> std::io::Result<usize> {
+    let bytes = value.as_bytes();
+    writer.write_all(bytes)?;
+    Ok(bytes.len())
+}
+
+/// Reads a value from its bytes, or returns an ```UnexpectedEof``` error if the buffer is too short.
+pub fn read_bytes<T: FromBytes>(buffer: &mut Cursor<&[u8]>) -> std::io::Result<T> {
+    let position = (buffer.position() as usize).min(buffer.get_ref().len());
+
+    match T::read_from_prefix(&buffer.get_ref()[position..]) {
+        Ok((value, _)) => {
+            buffer.set_position((position + std::mem::size_of::<T>()) as u64);
+            Ok(value)
+        }
+        Err(_) => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "failed to fill whole buffer",
+        )),
+    }
+}
@@ -0,0 +1,228 @@
+// compatibility corpus: the wire format of a matrix of types is compared to golden bytes
+// generated by previous versions of the crate, so that a change in the derive macros or in
+// the trait implementations never silently alters the wire format.
+//
+// The golden files are found in tests/golden/<version>/<name>.hex. They are never modified once
+// a version is published: a new directory is created for a new version. To create the files
+// for the current version, run:
+//
+// GOLDEN=overwrite cargo test --test golden
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use type2network::len::Varint;
+use type2network::vec::{CountPrefixed, UntilEof};
+use type2network::{FromNetworkOrder, ToNetworkOrder};
+use type2network_derive::{FromNetwork, ToNetwork};
+
+// all versions for which golden bytes are available
+const VERSIONS: &[&str] = &["v0.2"];
+
+// the version for which golden files are created with GOLDEN=overwrite
+const CURRENT: &str = "v0.2";
+
+fn golden_path(version: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(version)
+        .join(format!("{}.hex", name))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Vec<u8> {
+    let s = s.trim();
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// serialize the value and compare to the golden bytes of all versions
+fn check_to<T: ToNetworkOrder>(name: &str, value: &T) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let length = value.serialize_to(&mut buffer).unwrap();
+    assert_eq!(length, buffer.len(), "{}: wrong returned length", name);
+
+    if std::env::var("GOLDEN").as_deref() == Ok("overwrite") {
+        let path = golden_path(CURRENT, name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, format!("{}\n", to_hex(&buffer))).unwrap();
+    }
+
+    for version in VERSIONS {
+        let path = golden_path(version, name);
+        let golden = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: can't read {}: {}", name, path.display(), e));
+        assert_eq!(
+            to_hex(&buffer),
+            golden.trim(),
+            "{}: wire format differs from {}",
+            name,
+            version
+        );
+    }
+
+    buffer
+}
+
+// same as check_to() but also deserialize the golden bytes
+fn check<T>(name: &str, value: &T)
+where
+    T: ToNetworkOrder + for<'a> FromNetworkOrder<'a> + Default + Debug + PartialEq,
+{
+    check_to(name, value);
+
+    for version in VERSIONS {
+        let golden = from_hex(&std::fs::read_to_string(golden_path(version, name)).unwrap());
+        let mut buffer = Cursor::new(golden.as_slice());
+        let mut v = T::default();
+        v.deserialize_from(&mut buffer).unwrap();
+        assert_eq!(&v, value, "{}: wrong value from {}", name, version);
+        assert_eq!(
+            buffer.position() as usize,
+            golden.len(),
+            "{}: bytes left from {}",
+            name,
+            version
+        );
+    }
+}
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+struct Point {
+    x: u16,
+    y: i32,
+}
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+struct Tuple(u8, [u16; 2], char);
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+struct Header {
+    id: u16,
+    count: u8,
+}
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+struct Message {
+    #[network(header)]
+    header: Header,
+    #[network(count = "header.count")]
+    points: Vec<Point>,
+    flags: u8,
+}
+
+// with order, all fields must have it
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+struct Reordered {
+    #[network(order = 1)]
+    a: u8,
+    #[network(order = 0)]
+    b: u16,
+}
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+#[network(derive_tryfrom)]
+#[repr(u16)]
+enum Color {
+    #[default]
+    Black = 0,
+    White = 1,
+    Brown = 0x1234,
+}
+
+#[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+#[repr(u8)]
+enum OpCode {
+    #[default]
+    Query = 0,
+    Status = 2,
+    #[network(fallback)]
+    Reserved(u8),
+}
+
+#[derive(Debug, PartialEq, ToNetwork, FromNetwork)]
+enum Payload<T> {
+    Raw(UntilEof<u8>),
+    Typed(T),
+}
+
+impl<T> Default for Payload<T> {
+    fn default() -> Self {
+        Payload::Raw(UntilEof::default())
+    }
+}
+
+#[test]
+fn primitives() {
+    check("u8", &0xFE_u8);
+    check("i16", &-2_i16);
+    check("u32", &0x12345678_u32);
+    check("i64", &-0x123456789_i64);
+    #[cfg(feature = "int128")]
+    check("u128", &u128::MAX);
+    check("f32", &std::f32::consts::PI);
+    check("f64", &std::f64::consts::E);
+    check("char", &'💯');
+    check("array", &[0x1234_u16, 0x5678, 0x9ABC]);
+}
+
+#[test]
+fn std_types() {
+    check_to("ipv4", &Ipv4Addr::new(192, 168, 1, 1));
+    check_to("ipv6", &Ipv6Addr::LOCALHOST);
+    check_to("option_some", &Some(0x1234_u16));
+    check_to("option_none", &None::<u16>);
+    check_to("vec", &vec![0x1234_u16, 0x5678]);
+    check_to("str", &"I ❤ 東京");
+}
+
+#[test]
+fn wrappers() {
+    check("varint", &Varint(300));
+    check(
+        "count_prefixed",
+        &CountPrefixed::<u16, u32>::from(vec![1, 2, 3]),
+    );
+    check(
+        "count_prefixed_varint",
+        &CountPrefixed::<Varint, u8>::from(vec![0xAA; 130]),
+    );
+    check("until_eof", &UntilEof(vec![0x1234_u16, 0x5678]));
+}
+
+#[test]
+fn structs() {
+    check("struct", &Point { x: 0x1234, y: -1 });
+    check("tuple_struct", &Tuple(0xFF, [0x1234, 0x5678], 'a'));
+    check(
+        "struct_header_count",
+        &Message {
+            header: Header {
+                id: 0xABCD,
+                count: 2,
+            },
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+            flags: 0x80,
+        },
+    );
+    check("struct_order", &Reordered { a: 0x12, b: 0x3456 });
+}
+
+#[test]
+fn enums() {
+    check("enum_unit", &Color::Brown);
+    check("enum_fallback", &OpCode::Status);
+    check("enum_fallback_unknown", &OpCode::Reserved(0x55));
+    check_to(
+        "enum_data_raw",
+        &Payload::<Point>::Raw(vec![1, 2, 3].into()),
+    );
+    check_to("enum_data_typed", &Payload::Typed(Point { x: 1, y: 2 }));
+}
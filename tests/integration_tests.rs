@@ -3,6 +3,7 @@ use num_enum::{FromPrimitive, TryFromPrimitive};
 
 use serde::Serialize;
 // some tests for structs
+use type2network::vec::UntilEof;
 use type2network::{FromNetworkOrder, ToNetworkOrder};
 use type2network_derive::{FromNetwork, ToNetwork};
 
@@ -20,11 +21,7 @@ where
     T: FromNetworkOrder<'a> + Default + std::fmt::Debug + std::cmp::PartialEq,
 {
     let mut buffer = std::io::Cursor::new(buf.as_slice());
-    let mut v: T = if def.is_none() {
-        T::default()
-    } else {
-        def.unwrap()
-    };
+    let mut v: T = def.unwrap_or_default();
     assert!(v.deserialize_from(&mut buffer).is_ok());
     assert_eq!(&v, val);
 }
@@ -52,6 +49,35 @@ fn struct_basic() {
     from_network_test(None, &pt, &vec![0x12, 0x34, 0x56, 0x78]);
 }
 
+#[test]
+fn struct_const_generic() {
+    use type2network_derive::WireDefault;
+
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork, WireDefault)]
+    #[network(builder)]
+    struct Block<const N: usize> {
+        #[network(compute = "N as u8")]
+        length: u8,
+        data: [u8; N],
+    }
+
+    // larger than the arrays implementing Default
+    let block = Block::<40>::builder().build().unwrap();
+    assert_eq!(block.length, 40);
+    assert_eq!(block.data, Block::<40>::default().data);
+
+    let block = Block::<3>::builder().data([1, 2, 3]).build().unwrap();
+    to_network_test(&block, 4, &[3, 1, 2, 3]);
+    from_network_test(None, &block, &vec![3, 1, 2, 3]);
+
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork, WireDefault)]
+    struct Matrix<const R: usize, const C: usize>(u8, [[u8; C]; R]);
+
+    let m = Matrix::<2, 3>(1, [[1, 2, 3], [4, 5, 6]]);
+    to_network_test(&m, 7, &[1, 1, 2, 3, 4, 5, 6]);
+    from_network_test(None, &m, &vec![1, 1, 2, 3, 4, 5, 6]);
+}
+
 #[test]
 fn struct_tuple_basic() {
     #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
@@ -95,7 +121,7 @@ fn struct_lifetime_to() {
     }
 
     let pt = DataLifeTime::<Option<u16>, Vec<Option<u8>>> {
-        x: &"\x01\x02\x03\x04\x05\x06\x07\x08",
+        x: "\x01\x02\x03\x04\x05\x06\x07\x08",
         y: Some(0x090A),
         z: Some(vec![None, Some(0x0B), Some(0x0C)]),
     };
@@ -124,6 +150,7 @@ fn struct_lifetime_from() {
 }
 
 #[test]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 fn struct_rr() {
     // A generic RR structure which could be use with OPT too
     #[derive(Debug, Default, ToNetwork)]
@@ -155,7 +182,8 @@ fn struct_rr() {
 #[test]
 #[allow(dead_code)]
 fn enum_c_like() {
-    #[derive(Copy, Clone, ToNetwork)]
+    // no need for Copy
+    #[derive(ToNetwork)]
     #[repr(u8)]
     enum Boolean {
         True,
@@ -170,7 +198,7 @@ fn enum_c_like() {
 #[allow(dead_code)]
 fn enum_simple() {
     #[derive(Debug, Default, PartialEq, Copy, Clone, TryFromPrimitive, ToNetwork, FromNetwork)]
-    #[from_network(TryFrom)]
+    #[network(from(TryFrom))]
     #[repr(u64)]
     enum Color {
         #[default]
@@ -187,9 +215,10 @@ fn enum_simple() {
 
 #[test]
 #[allow(dead_code)]
+#[allow(clippy::upper_case_acronyms, clippy::derivable_impls)]
 fn enum_opcode() {
     #[derive(Debug, Copy, Clone, PartialEq, ToNetwork, FromNetwork, FromPrimitive)]
-    #[from_network(From)]
+    #[network(from(From))]
     #[repr(u16)]
     pub enum OpCodeReserved {
         Query = 0,  //[RFC1035]
@@ -281,7 +310,7 @@ fn struct_attr_no() {
         y: u16,
 
         // last field is not deserialized
-        #[from_network(ignore)]
+        #[network(from(ignore))]
         z: u16,
     }
 
@@ -307,7 +336,7 @@ fn struct_attr_fn() {
         y: u16,
 
         // last field is not deserialized
-        #[from_network(with_fn(update))]
+        #[network(from(with_fn(update)))]
         z: u16,
     }
 
@@ -327,7 +356,7 @@ fn struct_attr_code() {
         y: u16,
 
         // last field is not deserialized
-        #[from_network(with_code(self.z = 0xFFFF;))]
+        #[network(from(with_code(self.z = 0xFFFF;)))]
         z: u16,
     }
 
@@ -344,24 +373,1663 @@ fn struct_debug() {
     #[allow(unused)]
     #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
     struct PointDebug {
-        #[from_network(debug)]
+        #[network(from(debug))]
         x: u16,
 
-        #[from_network(debug)]
+        #[network(from(debug))]
         y: u16,
     }
 }
 
+#[test]
+fn struct_debug_non_copy() {
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct DataDebug {
+        #[network(from(debug = "hex"))]
+        x: u16,
+
+        #[network(from(debug))]
+        y: Vec<u8>,
+
+        #[network(from(debug = "hex"))]
+        z: [u8; 2],
+    }
+
+    let d = DataDebug {
+        x: 0x1234,
+        y: Vec::new(),
+        z: [0x56, 0x78],
+    };
+    from_network_test(None, &d, &vec![0x12, 0x34, 0x56, 0x78]);
+}
+
 #[test]
 fn struct_serde() {
     #[allow(unused)]
     #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork, Serialize)]
     struct PointDebug {
         #[serde(skip_serializing)]
-        #[from_network(debug)]
+        #[network(from(debug))]
+        x: u16,
+
+        #[network(from(debug))]
+        y: u16,
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn struct_deprecated_attrs() {
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct PointDeprecated {
+        x: u16,
+
+        #[from_network(ignore)]
+        y: u16,
+
+        #[deser(ignore)]
+        z: u16,
+    }
+
+    let pt = PointDeprecated {
+        x: 0x1234,
+        y: 0,
+        z: 0,
+    };
+    from_network_test(None, &pt, &vec![0x12, 0x34, 0x56, 0x78]);
+}
+
+#[test]
+fn struct_attr_to_ignore() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct PointToIgnore {
+        x: u16,
+
+        #[network(to(ignore), from(ignore))]
+        y: u16,
+    }
+
+    let pt = PointToIgnore {
+        x: 0x1234,
+        y: 0x5678,
+    };
+    to_network_test(&pt, 2, &[0x12, 0x34]);
+}
+
+#[test]
+fn struct_attr_skip() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Cached {
+        x: u16,
+
+        #[network(skip)]
+        last_seen: Option<std::time::Instant>,
+
+        y: u16,
+    }
+
+    let pt = Cached {
+        x: 0x1234,
+        last_seen: Some(std::time::Instant::now()),
+        y: 0x5678,
+    };
+    to_network_test(&pt, 4, &[0x12, 0x34, 0x56, 0x78]);
+
+    let b = [0x12, 0x34, 0x56, 0x78];
+    let mut buffer = std::io::Cursor::new(&b[..]);
+    let new = Cached::deserialize_new(&mut buffer).unwrap();
+    assert_eq!(
+        new,
+        Cached {
+            x: 0x1234,
+            last_seen: None,
+            y: 0x5678
+        }
+    );
+
+    // the skipped field keeps its value
+    let mut buffer = std::io::Cursor::new(&b[..]);
+    let mut value = pt;
+    value.deserialize_from(&mut buffer).unwrap();
+    assert!(value.last_seen.is_some());
+}
+
+#[test]
+fn struct_attr_skip_wire_parts() {
+    // not Default: given when deserializing
+    #[derive(Debug, PartialEq)]
+    struct Handle(i32);
+
+    #[derive(Debug, PartialEq, FromNetwork)]
+    struct Session {
+        #[network(skip)]
+        handle: Handle,
+
+        id: u16,
+
+        #[network(skip)]
+        peer: String,
+
+        #[network(count = "self.id")]
+        data: Vec<u8>,
+    }
+
+    let b = [0x00, 0x02, 0xAA, 0xBB];
+    let mut buffer = std::io::Cursor::new(&b[..]);
+    let session = Session::from_wire_parts((Handle(3), String::from("peer")), &mut buffer).unwrap();
+    assert_eq!(
+        session,
+        Session {
+            handle: Handle(3),
+            id: 2,
+            peer: String::from("peer"),
+            data: vec![0xAA, 0xBB],
+        }
+    );
+
+    // tuple structs too
+    #[derive(Debug, FromNetwork)]
+    struct Tagged(u8, #[network(skip)] Handle);
+
+    let mut buffer = std::io::Cursor::new(&b[1..]);
+    let tagged = Tagged::from_wire_parts((Handle(7),), &mut buffer).unwrap();
+    assert_eq!(tagged.0, 2);
+    assert_eq!(tagged.1, Handle(7));
+}
+
+#[test]
+fn struct_attr_utf8() {
+    use type2network::text::Utf8Text;
+
+    #[derive(Debug, Default, FromNetwork)]
+    struct Names {
+        #[network(header)]
+        len: u8,
+        #[network(utf8 = "strict", count = "*len")]
+        strict: String,
+        #[network(utf8 = "lossy", count = "*len")]
+        lossy: String,
+        #[network(utf8 = "bytes")]
+        rest: Utf8Text,
+    }
+
+    let b = [0x02, b'o', b'k', 0xC3, 0x28, 0xFF];
+    let names = Names::deserialize_new(&mut std::io::Cursor::new(&b[..])).unwrap();
+    assert_eq!(names.strict, "ok");
+    assert_eq!(names.lossy, "\u{FFFD}(");
+    assert_eq!(names.rest, Utf8Text::Bytes(vec![0xFF]));
+
+    // invalid UTF-8 in strict mode
+    let b = [0x02, 0xC3, 0x28];
+    let e = Names::deserialize_new(&mut std::io::Cursor::new(&b[..])).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(e.to_string().starts_with("Names.strict"));
+}
+
+#[test]
+fn struct_attr_char_as() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Initials {
+        #[network(char_as = "utf8")]
+        first: char,
+        #[network(char_as = "u32")]
+        last: char,
+        #[network(char_as = "utf8")]
+        emoji: char,
+    }
+
+    let initials = Initials {
+        first: 'É',
+        last: 'a',
+        emoji: '💯',
+    };
+    let bytes = [0xC3, 0x89, 0, 0, 0, 0x61, 0xF0, 0x9F, 0x92, 0xAF];
+    to_network_test(&initials, 10, &bytes);
+    from_network_test(None, &initials, &bytes.to_vec());
+}
+
+#[test]
+fn struct_attr_order() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct PointOrder {
+        #[network(order = 2)]
+        x: u16,
+
+        #[network(order = 0)]
+        y: u16,
+
+        #[network(order = 1)]
+        z: u8,
+    }
+
+    let pt = PointOrder {
+        x: 0x1234,
+        y: 0x5678,
+        z: 0x9A,
+    };
+    to_network_test(&pt, 5, &[0x56, 0x78, 0x9A, 0x12, 0x34]);
+    from_network_test(None, &pt, &vec![0x56, 0x78, 0x9A, 0x12, 0x34]);
+}
+
+#[test]
+fn struct_attr_header_count() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Header {
+        id: u16,
+        qd_count: u16,
+        an_count: u8,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Message {
+        #[network(header)]
+        header: Header,
+
+        #[network(count = "header.qd_count")]
+        questions: Vec<u16>,
+
+        #[network(count = "header.an_count * 2")]
+        answers: Vec<u8>,
+    }
+
+    let msg = Message {
+        header: Header {
+            id: 0x1234,
+            qd_count: 2,
+            an_count: 1,
+        },
+        questions: vec![0x5678, 0x9ABC],
+        answers: vec![0xDE, 0xF0],
+    };
+    let bytes = vec![
+        0x12, 0x34, 0x00, 0x02, 0x01, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
+    ];
+    to_network_test(&msg, 11, &bytes);
+    from_network_test(None, &msg, &bytes);
+
+    // 4 elements are announced
+    let mut m = Message::default();
+    let mut buffer = std::io::Cursor::new(bytes.as_slice());
+    let e = type2network::budget::with_budget(3, || m.deserialize_from(&mut buffer)).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        type2network::FieldError::from_io(&e).unwrap().path(),
+        "Message.answers"
+    );
+}
+
+#[test]
+#[cfg(feature = "log")]
+fn struct_trace() {
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct PointTrace {
+        #[network(from(trace))]
+        x: u16,
+
+        #[network(from(trace))]
+        y: Vec<u8>,
+    }
+
+    let pt = PointTrace {
+        x: 0x1234,
+        y: Vec::new(),
+    };
+    from_network_test(None, &pt, &vec![0x12, 0x34]);
+}
+
+#[test]
+fn struct_attr_code_ctx() {
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct Bounded {
+        len: u8,
+
+        // the buffer and ctx variables are available in the block
+        #[network(from(with_code(
+            assert_eq!(ctx.field_name, "data");
+            assert_eq!(ctx.offset(buffer), 1);
+        )))]
+        #[network(count = "self.len")]
+        data: Vec<u8>,
+    }
+
+    let b = Bounded {
+        len: 2,
+        data: vec![0x12, 0x34],
+    };
+    from_network_test(None, &b, &vec![0x02, 0x12, 0x34]);
+}
+
+#[test]
+fn struct_skip_all_if() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(skip_all_if = "not(debug_assertions)")]
+    struct Extension {
+        x: u16,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(skip_all_if = "feature = \"log\"")]
+    struct WithoutLog {
+        x: u16,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(skip_all_if = "self.x == 0")]
+    struct Runtime {
+        x: u16,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(skip_all_if = "any(target_endian = \"big\", target_pointer_width = \"16\")")]
+    struct BigEndian {
         x: u16,
+    }
+
+    #[cfg(not(feature = "log"))]
+    to_network_test(&WithoutLog { x: 0x1234 }, 2, &[0x12, 0x34]);
+    #[cfg(feature = "log")]
+    to_network_test(&WithoutLog { x: 0x1234 }, 0, &[]);
+
+    let r = Runtime { x: 0 };
+    to_network_test(&r, 0, &[]);
+
+    #[cfg(target_endian = "little")]
+    to_network_test(&BigEndian { x: 0x1234 }, 2, &[0x12, 0x34]);
+
+    #[cfg(debug_assertions)]
+    to_network_test(&Extension { x: 0x1234 }, 2, &[0x12, 0x34]);
+    #[cfg(not(debug_assertions))]
+    to_network_test(&Extension { x: 0x1234 }, 0, &[]);
+}
+
+#[test]
+fn enum_derive_tryfrom() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(from(TryFrom), derive_tryfrom)]
+    #[repr(u16)]
+    enum Code {
+        #[default]
+        Ok = 1,
+        NotFound = 404,
+        Error,
+    }
+
+    assert_eq!(Code::try_from(405), Ok(Code::Error));
+    assert_eq!(Code::try_from(3), Err(3));
+
+    to_network_test(&Code::NotFound, 2, &[0x01, 0x94]);
+    from_network_test(None, &Code::Error, &vec![0x01, 0x95]);
+
+    let b = vec![0x00, 0x00];
+    let mut buffer = std::io::Cursor::new(b.as_slice());
+    let mut c = Code::default();
+    assert!(c.deserialize_from(&mut buffer).is_err());
+
+    // discriminants given by expressions
+    const BASE: u8 = 0x10;
+
+    #[derive(Debug, PartialEq, FromNetwork)]
+    #[network(derive_tryfrom)]
+    #[repr(u8)]
+    enum Flag {
+        Low = BASE,
+        High = BASE << 1,
+        Next,
+    }
+
+    assert_eq!(Flag::try_from(0x10), Ok(Flag::Low));
+    assert_eq!(Flag::try_from(0x20), Ok(Flag::High));
+    assert_eq!(Flag::try_from(0x21), Ok(Flag::Next));
+    assert_eq!(Flag::try_from(0x11), Err(0x11));
+}
+
+#[test]
+fn enum_values() {
+    #[derive(Debug, Clone, Copy, PartialEq, ToNetwork)]
+    #[network(values)]
+    #[repr(u8)]
+    enum OpCode {
+        Query = 0,
+        IQuery,
+        Status,
+        Notify = 4,
+    }
+
+    assert_eq!(
+        OpCode::ALL,
+        &[
+            OpCode::Query,
+            OpCode::IQuery,
+            OpCode::Status,
+            OpCode::Notify
+        ]
+    );
+    assert_eq!(OpCode::Notify.wire_value(), 4);
+    assert_eq!(OpCode::from_wire_value(2), Some(OpCode::Status));
+    assert_eq!(OpCode::from_wire_value(3), None);
+
+    // the table matches what is sent on the wire
+    for op in OpCode::ALL {
+        to_network_test(op, 1, &[op.wire_value()]);
+        assert_eq!(OpCode::from_wire_value(op.wire_value()), Some(*op));
+    }
+}
+
+#[test]
+fn enum_fallback() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[repr(u8)]
+    enum Kind {
+        #[default]
+        A = 1,
+        B = 2,
+
+        #[network(fallback)]
+        Unknown(u8),
+    }
+
+    // unknown values round-trip
+    from_network_test(None, &Kind::B, &vec![2]);
+    from_network_test(None, &Kind::Unknown(55), &vec![55]);
+    to_network_test(&Kind::Unknown(55), 1, &[55]);
+    to_network_test(&Kind::A, 1, &[1]);
+
+    // unknown values are reported to the observer
+    let seen = std::rc::Rc::new(std::cell::Cell::new(None));
+    let observer = {
+        let seen = seen.clone();
+        move |ignored: &type2network::observer::Ignored| seen.set(Some(*ignored))
+    };
+    type2network::observer::with_observer(observer, || {
+        let b = vec![2, 55];
+        let mut buffer = std::io::Cursor::new(b.as_slice());
+        assert_eq!(Kind::deserialize_new(&mut buffer).unwrap(), Kind::B);
+        assert!(seen.get().is_none());
+        assert_eq!(
+            Kind::deserialize_new(&mut buffer).unwrap(),
+            Kind::Unknown(55)
+        );
+    });
+    let ignored = seen.get().unwrap();
+    assert_eq!(
+        (ignored.offset, ignored.length, ignored.container),
+        (1, 1, "Kind")
+    );
+}
+
+#[test]
+fn enum_non_exhaustive() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[non_exhaustive]
+    #[repr(u16)]
+    enum OptionCode {
+        #[default]
+        Pad = 0,
+        Cookie = 10,
+        Padding,
+        Other(u16),
+    }
+
+    from_network_test(None, &OptionCode::Padding, &vec![0, 11]);
+    from_network_test(None, &OptionCode::Other(0x1234), &vec![0x12, 0x34]);
+    to_network_test(&OptionCode::Other(0x1234), 2, &[0x12, 0x34]);
+    to_network_test(&OptionCode::Cookie, 2, &[0, 10]);
+}
+
+#[test]
+fn enum_generic() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Point {
+        x: u8,
+        y: u8,
+    }
+
+    // no bound needed on T, the derive adds them
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork)]
+    enum Payload<T> {
+        Raw(UntilEof<u8>),
+        Typed(T),
+        Named { id: u16, value: T },
+    }
+
+    impl<T> Default for Payload<T> {
+        fn default() -> Self {
+            Payload::Raw(UntilEof::default())
+        }
+    }
+
+    to_network_test(&Payload::<Point>::Raw(vec![1, 2, 3].into()), 3, &[1, 2, 3]);
+    to_network_test(&Payload::Typed(Point { x: 1, y: 2 }), 2, &[1, 2]);
+    to_network_test(
+        &Payload::Named {
+            id: 0x1234,
+            value: 0x56_u8,
+        },
+        3,
+        &[0x12, 0x34, 0x56],
+    );
+
+    // the current variant is deserialized in place
+    from_network_test(
+        Some(Payload::<Point>::Raw(UntilEof::default())),
+        &Payload::Raw(vec![0x12, 0x34].into()),
+        &vec![0x12, 0x34],
+    );
+    from_network_test(
+        Some(Payload::Typed(Point::default())),
+        &Payload::Typed(Point { x: 0x12, y: 0x34 }),
+        &vec![0x12, 0x34],
+    );
+    from_network_test(
+        Some(Payload::Named { id: 0, value: 0u8 }),
+        &Payload::Named {
+            id: 0x1234,
+            value: 0x56,
+        },
+        &vec![0x12, 0x34, 0x56],
+    );
+}
 
-        #[from_network(debug)]
+#[test]
+fn struct_attr_mask() {
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct State {
+        #[network(header)]
+        field_mask: u8,
+        #[network(from(mask = "field_mask", bit = 0))]
+        x: u16,
+        #[network(from(mask = "self.field_mask", bit = 1))]
         y: u16,
+        #[network(from(bit = 2, mask = "self.field_mask", debug))]
+        z: u8,
+    }
+
+    let state = State {
+        field_mask: 0,
+        x: 1,
+        y: 2,
+        z: 3,
+    };
+
+    // only y and z are present
+    from_network_test(
+        Some(state),
+        &State {
+            field_mask: 0b110,
+            x: 1,
+            y: 0x1234,
+            z: 0x56,
+        },
+        &vec![0b110, 0x12, 0x34, 0x56],
+    );
+
+    // nothing present: the current values are kept
+    from_network_test(
+        Some(State {
+            field_mask: 0xFF,
+            x: 1,
+            y: 2,
+            z: 3,
+        }),
+        &State {
+            field_mask: 0,
+            x: 1,
+            y: 2,
+            z: 3,
+        },
+        &vec![0],
+    );
+}
+
+#[test]
+fn tuple_and_variant_field_attrs() {
+    // attributes on tuple struct fields
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Frame(
+        u8,
+        #[network(count = "self.0")] Vec<u16>,
+        #[network(from(ignore), to(ignore))] u32,
+    );
+
+    let frame = Frame(2, vec![0x1234, 0x5678], 0xFFFF);
+    to_network_test(&frame, 5, &[2, 0x12, 0x34, 0x56, 0x78]);
+    from_network_test(
+        None,
+        &Frame(2, vec![0x1234, 0x5678], 0),
+        &vec![2, 0x12, 0x34, 0x56, 0x78],
+    );
+
+    // attributes on enum variant fields: the count expression uses the bindings of the match arm
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork)]
+    enum Record {
+        List {
+            len: u8,
+            #[network(count = "*len")]
+            items: Vec<u8>,
+        },
+        Tagged(#[network(to(ignore), from(ignore))] u8, u16),
+    }
+
+    impl Default for Record {
+        fn default() -> Self {
+            Record::List {
+                len: 0,
+                items: Vec::new(),
+            }
+        }
+    }
+
+    to_network_test(&Record::Tagged(0xAA, 0x1234), 2, &[0x12, 0x34]);
+    from_network_test(
+        Some(Record::Tagged(0xAA, 0)),
+        &Record::Tagged(0xAA, 0x1234),
+        &vec![0x12, 0x34],
+    );
+    from_network_test(
+        None,
+        &Record::List {
+            len: 3,
+            items: vec![1, 2, 3],
+        },
+        &vec![3, 1, 2, 3],
+    );
+}
+
+#[test]
+fn field_error_path() {
+    use type2network::FieldError;
+
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct Point {
+        x: u8,
+        y: u16,
+    }
+
+    #[derive(Debug, PartialEq, FromNetwork)]
+    enum Shape {
+        Line(Point, Point),
+    }
+
+    impl Default for Shape {
+        fn default() -> Self {
+            Shape::Line(Point::default(), Point::default())
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct Drawing {
+        id: u8,
+        #[network(count = "self.id")]
+        points: Vec<Point>,
+        shape: Shape,
+    }
+
+    let path = |b: Vec<u8>| {
+        let mut buffer = std::io::Cursor::new(b.as_slice());
+        let e = Drawing::default()
+            .deserialize_from(&mut buffer)
+            .unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+        FieldError::from_io(&e).unwrap().path()
+    };
+
+    assert_eq!(path(vec![]), "Drawing.id");
+    assert_eq!(path(vec![1, 0xFF]), "Drawing.points.y");
+    assert_eq!(path(vec![0, 1, 0, 2, 3]), "Drawing.shape.Line.1.y");
+}
+
+#[test]
+fn struct_deserialize_new() {
+    // a routing table entry: only primitive fields
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork)]
+    struct Route {
+        #[network(order = 1)]
+        prefix: [u8; 4],
+        #[network(order = 0)]
+        len: u8,
+        #[network(order = 2)]
+        metric: u16,
+    }
+
+    // the default value is never used when building the value
+    impl Default for Route {
+        fn default() -> Self {
+            Route {
+                prefix: [0xFF; 4],
+                len: 0xFF,
+                metric: 0xFFFF,
+            }
+        }
+    }
+
+    let b = vec![24, 192, 168, 1, 0, 0x12, 0x34, 16, 10, 0, 0, 0, 0, 1];
+    let mut buffer = std::io::Cursor::new(b.as_slice());
+    let route = Route::deserialize_new(&mut buffer).unwrap();
+    assert_eq!(
+        route,
+        Route {
+            prefix: [192, 168, 1, 0],
+            len: 24,
+            metric: 0x1234
+        }
+    );
+
+    // same result for a vector of entries
+    from_network_test(
+        None,
+        &UntilEof(vec![
+            route,
+            Route {
+                prefix: [10, 0, 0, 0],
+                len: 16,
+                metric: 1,
+            },
+        ]),
+        &b,
+    );
+}
+
+#[test]
+#[cfg(feature = "smallvec")]
+fn struct_smallvec() {
+    use smallvec::{smallvec, SmallVec};
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Path {
+        #[network(header)]
+        hops: u8,
+        #[network(count = "*hops")]
+        asn: SmallVec<[u32; 4]>,
+    }
+
+    let p = Path {
+        hops: 2,
+        asn: smallvec![0x12345678, 0x9ABCDEF0],
+    };
+    let b = vec![2, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
+    to_network_test(&p, 9, &b);
+    from_network_test(None, &p, &b);
+}
+
+#[test]
+#[cfg(feature = "heapless")]
+fn struct_heapless() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Frame {
+        #[network(header)]
+        len: u8,
+        #[network(count = "*len")]
+        data: heapless::Vec<u16, 2>,
+        name: heapless::String<4>,
+    }
+
+    let f = Frame {
+        len: 2,
+        data: heapless::Vec::from_slice(&[0x1234, 0x5678]).unwrap(),
+        name: heapless::String::try_from("eth0").unwrap(),
+    };
+    let b = vec![2, 0x12, 0x34, 0x56, 0x78, b'e', b't', b'h', b'0'];
+    to_network_test(&f, 9, &b);
+    from_network_test(None, &f, &b);
+
+    // more elements than the capacity
+    let b = vec![3, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+    let mut buffer = std::io::Cursor::new(b.as_slice());
+    assert!(Frame::default().deserialize_from(&mut buffer).is_err());
+}
+
+#[test]
+#[cfg(feature = "arrayvec")]
+fn struct_arrayvec() {
+    use arrayvec::{ArrayString, ArrayVec};
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Frame {
+        #[network(header)]
+        len: u8,
+        #[network(count = "*len")]
+        data: ArrayVec<u16, 2>,
+        name: ArrayString<4>,
+    }
+
+    let f = Frame {
+        len: 2,
+        data: [0x1234, 0x5678].into_iter().collect(),
+        name: ArrayString::from("eth0").unwrap(),
+    };
+    let b = vec![2, 0x12, 0x34, 0x56, 0x78, b'e', b't', b'h', b'0'];
+    to_network_test(&f, 9, &b);
+    from_network_test(None, &f, &b);
+
+    // more elements than the capacity
+    let b = vec![3, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+    let mut buffer = std::io::Cursor::new(b.as_slice());
+    assert!(Frame::default().deserialize_from(&mut buffer).is_err());
+}
+
+#[test]
+fn network_message() {
+    use type2network::NetworkMessage;
+    use type2network_derive::NetworkMessage;
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork, NetworkMessage)]
+    #[network(tag = 1)]
+    struct Ping {
+        id: u16,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork, NetworkMessage)]
+    #[repr(u8)]
+    #[network(tag = 2, derive_tryfrom)]
+    enum Status {
+        #[default]
+        Up = 1,
+        Down = 2,
+    }
+
+    // a single bound for a codec: the tag is sent before the message
+    fn encode<M: NetworkMessage>(msg: &M) -> Vec<u8> {
+        let mut buffer = vec![M::TYPE_TAG as u8];
+        buffer.extend(msg.to_bytes().unwrap());
+        buffer
+    }
+
+    assert_eq!(encode(&Ping { id: 0x1234 }), &[1, 0x12, 0x34]);
+    assert_eq!(encode(&Status::Down), &[2, 2]);
+    assert_eq!(Status::NAME, "Status");
+    assert_eq!(Status::from_bytes(&[1]).unwrap(), Status::Up);
+}
+
+#[test]
+#[cfg(feature = "diagnose")]
+#[allow(dead_code)]
+fn diagnose() {
+    use type2network::diagnose::diagnose;
+
+    #[derive(Debug, Default, FromNetwork)]
+    struct Answer {
+        name: u16,
+        rd_length: u16,
+    }
+
+    #[derive(Debug, Default, FromNetwork)]
+    struct Response {
+        id: u16,
+        answer: Answer,
+        #[network(from(ignore))]
+        ignored: u8,
+    }
+
+    let report = diagnose::<Response>(&[0x12, 0x34, 0, 1, 0]);
+    assert!(!report.is_ok());
+    assert_eq!(report.failing_field().unwrap(), "Response.answer.rd_length");
+    assert_eq!(report.consumed(), 4);
+
+    let fields: Vec<_> = report
+        .fields()
+        .iter()
+        .map(|f| (f.type_name, f.field, f.start, f.end))
+        .collect();
+    assert_eq!(
+        fields,
+        &[("Response", "id", 0, 2), ("Answer", "name", 2, 4)]
+    );
+
+    let report = diagnose::<Response>(&[0x12, 0x34, 0, 1, 0, 2, 0xFF]);
+    assert!(report.is_ok());
+    assert_eq!(report.fields().len(), 4);
+    assert_eq!(report.fields()[3].field, "answer");
+    assert_eq!(report.remaining(), &[0xFF]);
+}
+
+#[test]
+fn struct_attr_hexdebug() {
+    #[derive(Debug, ToNetwork)]
+    #[network(hexdebug)]
+    struct Header {
+        id: u16,
+        flags: [u8; 2],
+        #[network(to(ignore))]
+        _cache: u8,
+        name: String,
+    }
+
+    let h = Header {
+        id: 0x1234,
+        flags: [1, 2],
+        _cache: 0,
+        name: "ab".to_string(),
+    };
+    assert_eq!(
+        h.fmt_wire(),
+        "id     4660                      12 34\n\
+         flags  [1, 2]                    01 02\n\
+         name   \"ab\"                      61 62\n"
+    );
+}
+
+#[test]
+fn struct_attr_hexdebug_secret() {
+    use type2network::der::DerTlv;
+    use type2network::secret::Secret;
+
+    #[derive(Debug, ToNetwork)]
+    #[repr(u8)]
+    enum Credential {
+        #[allow(dead_code)]
+        None = 0,
+        Token(Secret<[u8; 2]>) = 1,
+    }
+
+    #[derive(Debug, ToNetwork)]
+    #[network(hexdebug)]
+    struct Login {
+        a: DerTlv<4, Secret<[u8; 4]>>,
+        b: (u8, Secret<[u8; 4]>),
+        c: Credential,
+        d: u8,
+    }
+
+    let login = Login {
+        a: DerTlv(Secret::new([0xde, 0xad, 0xbe, 0xef])),
+        b: (1, Secret::new([0xca, 0xfe, 0xba, 0xbe])),
+        c: Credential::Token(Secret::new([0x12, 0x34])),
+        d: 5,
+    };
+
+    // the secrets are redacted whatever the wrappers holding them
+    let lines: Vec<_> = login.fmt_wire().lines().map(String::from).collect();
+    assert!(lines[..3].iter().all(|l| l.ends_with("<redacted>")));
+    assert!(lines[3].ends_with("05"));
+}
+
+#[test]
+fn enum_map_keys() {
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(
+        Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ToNetwork, FromNetwork,
+    )]
+    #[network(derive_tryfrom)]
+    #[repr(u8)]
+    enum OptionCode {
+        #[default]
+        Pad = 0,
+        Mss = 2,
+        Sack = 4,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Options {
+        count: u8,
+        #[network(count = "self.count")]
+        table: HashMap<OptionCode, u16>,
+        ordered: BTreeMap<OptionCode, u8>,
+    }
+
+    let opts = Options {
+        count: 2,
+        table: HashMap::from([(OptionCode::Sack, 0x0102), (OptionCode::Mss, 0x05B4)]),
+        ordered: BTreeMap::from([(OptionCode::Sack, 1), (OptionCode::Pad, 2)]),
+    };
+    let bytes = vec![2, 2, 0x05, 0xB4, 4, 0x01, 0x02, 0, 2, 4, 1];
+    to_network_test(&opts, 11, &bytes);
+    from_network_test(None, &opts, &bytes);
+
+    // unknown option code
+    let mut buffer = std::io::Cursor::new([1u8, 3, 0, 0].as_slice());
+    assert!(Options::default().deserialize_from(&mut buffer).is_err());
+}
+
+#[test]
+fn struct_attr_pod() {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, ToNetwork, FromNetwork)]
+    #[network(pod)]
+    #[repr(C, packed)]
+    struct Header {
+        version: u8,
+        flags: u8,
+        id: [u8; 2],
+        mac: [[u8; 3]; 2],
+    }
+
+    let h = Header {
+        version: 1,
+        flags: 2,
+        id: [3, 4],
+        mac: [[5, 6, 7], [8, 9, 10]],
+    };
+    let bytes = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    to_network_test(&h, 10, &bytes);
+    from_network_test(None, &h, &bytes);
+
+    let mut buffer = std::io::Cursor::new(&bytes[..9]);
+    assert!(Header::default().deserialize_from(&mut buffer).is_err());
+}
+
+#[test]
+fn struct_attr_pod_overlay() {
+    use type2network::endian::{BeU16, BeU64, Overlay};
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(pod)]
+    #[repr(C, packed)]
+    struct Header {
+        kind: u8,
+        ports: [BeU16; 2],
+        timestamp: BeU64,
+    }
+
+    let mut bytes = vec![1, 0, 80, 0x1F, 0x90, 0, 0, 0, 0, 0, 0, 0, 9];
+    let (h, rest) = Header::overlay(&bytes).unwrap();
+    let ports = h.ports;
+    assert_eq!(ports[1].get(), 8080);
+    assert_eq!({ h.timestamp }.get(), 9);
+    assert!(rest.is_empty());
+
+    let (h, _) = Header::overlay_mut(&mut bytes).unwrap();
+    h.kind = 2;
+    assert_eq!(bytes[0], 2);
+
+    let h = Header {
+        kind: 2,
+        ports: [BeU16::new(80), BeU16::new(8080)],
+        timestamp: BeU64::new(9),
+    };
+    to_network_test(&h, 13, &bytes);
+    from_network_test(None, &h, &bytes);
+    assert!(Header::overlay(&bytes[1..]).is_none());
+}
+
+#[test]
+fn network_view() {
+    use type2network::endian::BeU16;
+    use type2network_derive::NetworkView;
+
+    #[derive(Debug, ToNetwork, NetworkView)]
+    struct Packet {
+        #[network(order = 1)]
+        kind: u8,
+        #[network(order = 0)]
+        version: BeU16,
+        #[network(order = 2, from(debug))]
+        addr: std::net::Ipv4Addr,
+        #[network(order = 3)]
+        data: Vec<u8>,
+    }
+
+    let bytes = [0, 2, 7, 192, 168, 1, 1, 0xAA];
+    let view = PacketView::new(&bytes).unwrap();
+    assert_eq!(view.version().get(), 2);
+    assert_eq!(view.kind(), 7);
+    assert_eq!(view.addr(), std::net::Ipv4Addr::new(192, 168, 1, 1));
+    assert_eq!(view.data(), &[0xAA]);
+    assert_eq!(view.as_bytes().len(), 8);
+
+    // the view reads the same bytes as serialized
+    let p = Packet {
+        kind: 7,
+        version: BeU16::new(2),
+        addr: view.addr(),
+        data: view.data().to_vec(),
+    };
+    to_network_test(&p, 8, &bytes);
+
+    let e = PacketView::new(&bytes[..6]).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn struct_attr_buf() {
+    use bytes::{Buf, Bytes};
+    use type2network::buf::{from_buf, FromBuf, ToBufMut};
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(buf)]
+    struct Record {
+        #[network(header)]
+        flags: u8,
+        #[network(header)]
+        len: u16,
+        #[network(count = "*len")]
+        values: Vec<u16>,
+        #[network(from(mask = "*flags", bit = 0))]
+        extra: Option<u32>,
+    }
+
+    let bytes = [1, 0, 2, 0x12, 0x34, 0x56, 0x78, 0, 0, 0, 9];
+    let r = Record {
+        flags: 1,
+        len: 2,
+        values: vec![0x1234, 0x5678],
+        extra: Some(9),
+    };
+    to_network_test(&r, 11, &bytes);
+
+    let mut buf = bytes::BytesMut::new();
+    assert_eq!(r.serialize_to_buf(&mut buf).unwrap(), 11);
+    assert_eq!(&buf[..], &bytes);
+
+    // the second value and the extra field are split across chunks
+    let mut chain = Bytes::copy_from_slice(&bytes[..6]).chain(Bytes::copy_from_slice(&bytes[6..]));
+    let mut value = Record {
+        extra: Some(0),
+        ..Default::default()
+    };
+    value.deserialize_from_buf(&mut chain).unwrap();
+    assert_eq!(value, r);
+    assert!(!chain.has_remaining());
+
+    // bit not set: the extra field is not read
+    let mut chain = &[0u8, 0, 1, 0, 1][..];
+    let value: Record = from_buf(&mut chain).unwrap();
+    assert_eq!(value.values, vec![1]);
+    assert_eq!(value.extra, None);
+
+    let mut chain = &bytes[..10];
+    let mut value = Record {
+        extra: Some(0),
+        ..Default::default()
+    };
+    assert!(value.deserialize_from_buf(&mut chain).is_err());
+}
+
+#[test]
+fn struct_attr_convert() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(convert)]
+    struct Point<T>
+    where
+        T: ToNetworkOrder + for<'b> FromNetworkOrder<'b>,
+    {
+        x: T,
+        y: T,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(convert)]
+    struct Pair {
+        first: u8,
+        second: u16,
+    }
+
+    let bytes = [1, 0x12, 0x34];
+    let pair = Pair::try_from(&bytes[..]).unwrap();
+    assert_eq!(
+        pair,
+        Pair {
+            first: 1,
+            second: 0x1234
+        }
+    );
+    assert_eq!(Vec::from(&pair), bytes);
+
+    // all bytes are consumed
+    let e = Pair::try_from(&[1, 2, 3, 4][..]).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(Pair::try_from(&bytes[..2]).is_err());
+
+    let p = Point { x: 1u16, y: 2 };
+    assert_eq!(Vec::<u8>::from(&p), &[0, 1, 0, 2]);
+    assert_eq!(Point::<u16>::try_from(&[0, 1, 0, 2][..]).unwrap(), p);
+}
+
+#[test]
+fn struct_attr_byte_order() {
+    use type2network::byte_order::{ByteOrder, Magic16};
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Record {
+        #[network(byte_order)]
+        magic: Magic16<0xFEFF>,
+        values: [u32; 2],
+        #[network(from(with_code(self.little = ctx.byte_order == ByteOrder::Little;)))]
+        flags: u8,
+        #[network(to(ignore), from(ignore))]
+        little: bool,
+    }
+
+    let big = Record {
+        values: [1, 2],
+        little: false,
+        ..Default::default()
+    };
+    to_network_test(&big, 11, &[0xFE, 0xFF, 0, 0, 0, 1, 0, 0, 0, 2, 0]);
+    from_network_test(
+        None,
+        &big,
+        &[0xFE, 0xFF, 0, 0, 0, 1, 0, 0, 0, 2, 0].to_vec(),
+    );
+
+    let little = Record {
+        magic: Magic16::new(ByteOrder::Little),
+        values: [1, 2],
+        little: true,
+        ..Default::default()
+    };
+    to_network_test(&little, 11, &[0xFF, 0xFE, 1, 0, 0, 0, 2, 0, 0, 0, 0]);
+    from_network_test(
+        None,
+        &little,
+        &[0xFF, 0xFE, 1, 0, 0, 0, 2, 0, 0, 0, 0].to_vec(),
+    );
+}
+
+#[test]
+fn wire_default() {
+    use type2network_derive::WireDefault;
+
+    #[derive(Debug, PartialEq, ToNetwork, FromNetwork, WireDefault)]
+    struct Header {
+        #[network(magic = 0xA1B2C3D4)]
+        magic: u32,
+        #[network(expect = 2)]
+        version: u8,
+        length: u16,
+    }
+
+    let h = Header::default();
+    assert_eq!(h.magic, 0xA1B2C3D4);
+    assert_eq!(h.version, 2);
+    assert_eq!(h.length, 0);
+    to_network_test(&h, 7, &[0xA1, 0xB2, 0xC3, 0xD4, 2, 0, 0]);
+
+    // the version isn't checked
+    from_network_test(
+        None,
+        &Header {
+            magic: 0xA1B2C3D4,
+            version: 3,
+            length: 1,
+        },
+        &vec![0xA1, 0xB2, 0xC3, 0xD4, 3, 0, 1],
+    );
+
+    // but the magic is
+    let bytes = [0xA1, 0xB2, 0xC3, 0xD5, 2, 0, 0];
+    let mut buffer = std::io::Cursor::new(&bytes[..]);
+    let mut h = Header::default();
+    let e = h.deserialize_from(&mut buffer).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn struct_attr_builder() {
+    #[derive(Debug, PartialEq, ToNetwork)]
+    #[network(builder)]
+    struct Record {
+        #[network(magic = 0xCAFE)]
+        magic: u16,
+        #[network(expect = 1)]
+        version: u8,
+        #[network(compute = "self.data.len() as u16")]
+        length: u16,
+        count: u8,
+        #[network(count = "self.count")]
+        items: Vec<u16>,
+        data: Vec<u8>,
+        #[network(compute = "self.data.iter().fold(0, |acc, b| acc ^ b)")]
+        checksum: u8,
+    }
+
+    let r = Record::builder()
+        .items(vec![1, 2])
+        .data(vec![0x0F, 0xF0, 0x01])
+        .build()
+        .unwrap();
+    assert_eq!(r.magic, 0xCAFE);
+    assert_eq!(r.version, 1);
+    assert_eq!(r.length, 3);
+    assert_eq!(r.count, 2);
+    assert_eq!(r.checksum, 0xFE);
+    to_network_test(
+        &r,
+        14,
+        &[0xCA, 0xFE, 1, 0, 3, 2, 0, 1, 0, 2, 0x0F, 0xF0, 0x01, 0xFE],
+    );
+
+    // the fields are synced again after a change
+    let mut r = Record::builder().version(2).build().unwrap();
+    assert_eq!((r.version, r.length, r.count), (2, 0, 0));
+    r.items.push(7);
+    r.sync_wire_fields().unwrap();
+    assert_eq!(r.count, 1);
+
+    // more items than the counter can hold
+    r.items = vec![0; 256];
+    let e = r.sync_wire_fields().unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(Record::builder().items(vec![0; 256]).build().is_err());
+}
+
+#[test]
+#[cfg(feature = "compat")]
+fn struct_attr_compat() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[deku(endian = "big")]
+    struct Deku {
+        length: u8,
+        #[deku(count = "*length")]
+        data: Vec<u16>,
+        #[deku(skip)]
+        cached: u32,
+    }
+
+    let d = Deku {
+        length: 2,
+        data: vec![0x1234, 0x5678],
+        cached: 0,
+    };
+    let b = vec![2, 0x12, 0x34, 0x56, 0x78];
+    to_network_test(&d, 5, &b);
+    from_network_test(None, &d, &b);
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[brw(big)]
+    struct Binrw {
+        count: u16,
+        #[br(count = count as usize + 1)]
+        items: Vec<u8>,
+        #[br(ignore)]
+        version: u8,
+    }
+
+    let r = Binrw {
+        count: 1,
+        items: vec![0xAA, 0xBB],
+        version: 3,
+    };
+    let b = vec![0, 1, 0xAA, 0xBB, 3];
+    to_network_test(&r, 5, &b);
+    let mut cursor = std::io::Cursor::new(&b[..]);
+    let read = Binrw::deserialize_new(&mut cursor).unwrap();
+    assert_eq!(read.items, &[0xAA, 0xBB]);
+    assert_eq!(read.version, 0);
+}
+
+#[test]
+fn struct_attr_vectored() {
+    use type2network::vectored::{ToVectored, Vectored};
+
+    #[derive(ToNetwork)]
+    #[network(vectored)]
+    struct Packet {
+        kind: u8,
+        #[network(to(ignore))]
+        _cached: u32,
+        #[network(to(borrow))]
+        header: [u8; 2],
+        length: u16,
+        #[network(to(borrow))]
+        payload: Vec<u8>,
+    }
+
+    let p = Packet {
+        kind: 1,
+        _cached: 0,
+        header: [0xAA, 0xBB],
+        length: 3,
+        payload: vec![1, 2, 3],
+    };
+    to_network_test(&p, 8, &[1, 0xAA, 0xBB, 0, 3, 1, 2, 3]);
+
+    let mut out = Vectored::new();
+    assert_eq!(p.serialize_vectored(&mut out).unwrap(), 8);
+    let slices = out.io_slices();
+    assert_eq!(slices.len(), 4);
+    assert_eq!(slices[1].as_ptr(), p.header.as_ptr());
+    assert_eq!(slices[3].as_ptr(), p.payload.as_ptr());
+
+    let mut sent = Vec::new();
+    out.write_to(&mut sent).unwrap();
+    assert_eq!(sent, &[1, 0xAA, 0xBB, 0, 3, 1, 2, 3]);
+}
+
+#[test]
+fn struct_attr_magic_resync() {
+    use type2network::resync::{Resync, WireMagic};
+
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    struct Frame {
+        #[network(magic = 0x7E7E)]
+        sync: u16,
+        length: u8,
+        #[network(count = "self.length")]
+        data: Vec<u8>,
+    }
+
+    assert_eq!(Frame::wire_magic(), &[0x7E, 0x7E]);
+
+    // the second frame is corrupted: its length goes past the end of the buffer
+    let bytes = [
+        0x7E, 0x7E, 1, 0xAA, 0x7E, 0x7E, 9, 0x7E, 0x7E, 2, 0xBB, 0xCC,
+    ];
+    let frames: Vec<_> = Resync::<Frame>::new(&bytes).map(|f| f.data).collect();
+    assert_eq!(frames, &[vec![0xAA], vec![0xBB, 0xCC]]);
+}
+
+#[test]
+fn struct_attr_align_from_parent() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct AndX {
+        command: u8,
+        #[network(align_from_parent = 4)]
+        offset: u16,
+    }
+
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    struct Smb {
+        flags: u8,
+        andx: AndX,
+        #[network(align_from_parent = 8)]
+        data: u8,
+    }
+
+    // the offsets are counted from the start of the Smb message
+    let msg = Smb {
+        flags: 1,
+        andx: AndX {
+            command: 2,
+            offset: 3,
+        },
+        data: 4,
+    };
+    to_network_test(&msg, 9, &[1, 2, 0, 0, 0, 3, 0, 0, 4]);
+    from_network_test(None, &msg, &vec![1, 2, 0, 0, 0, 3, 0, 0, 4]);
+
+    // the padding is missing
+    let mut buffer = std::io::Cursor::new(&[1u8, 2, 0][..]);
+    assert!(Smb::deserialize_new(&mut buffer).is_err());
+
+    // the message starts after the frame length
+    let mut framed = Vec::new();
+    type2network::framing::write_frame(&mut framed, &msg).unwrap();
+    assert_eq!(&framed[4..], &[1, 2, 0, 0, 0, 3, 0, 0, 4]);
+}
+
+#[test]
+fn struct_attr_pad_block() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(pad_block = 4)]
+    struct Block {
+        kind: u8,
+        length: u8,
+        #[network(count = "self.length")]
+        data: Vec<u8>,
+    }
+
+    let block = Block {
+        kind: 1,
+        length: 3,
+        data: vec![0xAA, 0xBB, 0xCC],
+    };
+    to_network_test(&block, 8, &[1, 3, 0xAA, 0xBB, 0xCC, 0, 0, 0]);
+    from_network_test(None, &block, &vec![1, 3, 0xAA, 0xBB, 0xCC, 0, 0, 0]);
+
+    // no padding for a full block
+    let block = Block {
+        kind: 1,
+        length: 2,
+        data: vec![0xAA, 0xBB],
+    };
+    to_network_test(&block, 4, &[1, 2, 0xAA, 0xBB]);
+
+    // the padding is consumed, so the next message can be read
+    let bytes = [1, 1, 0xAA, 0, 1, 0, 0, 0];
+    let mut buffer = std::io::Cursor::new(&bytes[..]);
+    let blocks = <[Block; 2]>::deserialize_new(&mut buffer).unwrap();
+    assert_eq!(blocks[1].kind, 1);
+    assert_eq!(buffer.position(), 8);
+
+    // the padding is missing
+    let mut buffer = std::io::Cursor::new(&[1u8, 1, 0xAA][..]);
+    assert!(Block::deserialize_new(&mut buffer).is_err());
+}
+
+#[test]
+fn struct_attr_pad_fill() {
+    #[derive(Debug, Default, PartialEq, ToNetwork, FromNetwork)]
+    #[network(pad_block = 8, pad_fill = 0xFF)]
+    struct Cell {
+        kind: u8,
+        #[network(align_from_parent = 4, pad_fill = b' ', pad_verify)]
+        value: u16,
+    }
+
+    let cell = Cell { kind: 1, value: 2 };
+    to_network_test(&cell, 8, &[1, b' ', b' ', b' ', 0, 2, 0xFF, 0xFF]);
+
+    // the block padding isn't checked
+    from_network_test(None, &cell, &vec![1, b' ', b' ', b' ', 0, 2, 0, 0]);
+
+    // but the alignment one is
+    let bytes = [1, 0, 0, 0, 0, 2, 0xFF, 0xFF];
+    let mut buffer = std::io::Cursor::new(&bytes[..]);
+    let e = Cell::deserialize_new(&mut buffer).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn observer_pass_through() {
+    use type2network::opaque::{Opaque, Raw};
+
+    #[derive(Debug, Default, PartialEq, FromNetwork)]
+    #[network(pad_block = 8)]
+    struct Record {
+        kind: u8,
+        #[network(align_from_parent = 4)]
+        len: u8,
+        #[network(count = "self.len")]
+        data: Raw,
+        extra: Opaque<u8>,
+    }
+
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let observer = {
+        let seen = seen.clone();
+        move |ignored: &type2network::observer::Ignored| {
+            seen.borrow_mut()
+                .push((ignored.offset, ignored.length, ignored.container))
+        }
+    };
+
+    let bytes = [1, 0, 0, 0, 2, 0xAA, 0xBB, 1, 0xCC, 0, 0, 0, 0, 0, 0, 0];
+    let record = type2network::observer::with_observer(observer, || {
+        Record::deserialize_new(&mut std::io::Cursor::new(&bytes[..])).unwrap()
+    });
+    assert_eq!(record.data.0, &[0xAA, 0xBB]);
+    assert_eq!(
+        *seen.borrow(),
+        &[
+            (1, 3, "Record"),
+            (5, 2, "Raw"),
+            (8, 1, "Opaque"),
+            (9, 7, "Record")
+        ]
+    );
+}
+
+#[test]
+fn write_to() {
+    #[derive(Debug, Clone, Copy, PartialEq, ToNetwork)]
+    #[repr(u16)]
+    enum Kind {
+        Hello = 0x0102,
+    }
+
+    #[derive(ToNetwork)]
+    struct Message {
+        kind: Kind,
+        #[network(to(ignore))]
+        _local: u64,
+        name: String,
+        #[network(char_as = "utf8")]
+        sep: char,
+        values: Vec<u16>,
+    }
+
+    let msg = Message {
+        kind: Kind::Hello,
+        _local: 0,
+        name: "ab".to_string(),
+        sep: 'é',
+        values: vec![3, 4],
+    };
+
+    // the same bytes as serialize_to()
+    let mut buffer = Vec::new();
+    let length = msg.serialize_to(&mut buffer).unwrap();
+
+    let mut writer = std::io::BufWriter::new(Vec::new());
+    assert_eq!(msg.write_to(&mut writer).unwrap(), length);
+    assert_eq!(writer.into_inner().unwrap(), buffer);
+    assert_eq!(buffer, &[1, 2, b'a', b'b', 0xC3, 0xA9, 0, 3, 0, 4]);
+
+    // the padding depends on the position in the message
+    #[derive(ToNetwork)]
+    #[network(pad_block = 4)]
+    struct Block {
+        kind: u8,
+    }
+
+    let mut bytes = Vec::new();
+    let writer: &mut dyn std::io::Write = &mut bytes;
+    assert_eq!(Block { kind: 1 }.write_to(writer).unwrap(), 4);
+    assert_eq!(bytes, &[1, 0, 0, 0]);
+
+    // nested alignments are counted from the first byte written
+    #[derive(ToNetwork)]
+    struct AndX {
+        command: u8,
+        #[network(align_from_parent = 4)]
+        offset: u16,
+    }
+
+    #[derive(ToNetwork)]
+    struct Smb {
+        flags: u8,
+        andx: Vec<AndX>,
+        #[network(align_from_parent = 8)]
+        data: u8,
+    }
+
+    let msg = Smb {
+        flags: 1,
+        andx: vec![AndX {
+            command: 2,
+            offset: 3,
+        }],
+        data: 4,
+    };
+    let mut bytes = vec![0xFF];
+    assert_eq!(msg.write_to(&mut bytes).unwrap(), 9);
+    assert_eq!(bytes, &[0xFF, 1, 2, 0, 0, 0, 3, 0, 0, 4]);
+
+    // and through a trait object
+    let values: Vec<Box<dyn ToNetworkOrder>> = vec![Box::new(Kind::Hello), Box::new(msg)];
+    let mut bytes = Vec::new();
+    for value in &values {
+        value.write_to(&mut bytes).unwrap();
     }
+    assert_eq!(bytes, &[1, 2, 1, 2, 0, 0, 0, 3, 0, 0, 4]);
 }
@@ -0,0 +1,17 @@
+// compile tests for the derive macros
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/attr_network.rs");
+    t.pass("tests/ui/attr_deprecated.rs");
+    t.compile_fail("tests/ui/attr_deprecated_warning.rs");
+    t.compile_fail("tests/ui/attr_unknown.rs");
+    t.compile_fail("tests/ui/attr_order.rs");
+    t.compile_fail("tests/ui/enum_derive_tryfrom.rs");
+    t.compile_fail("tests/ui/enum_repr_range.rs");
+    t.compile_fail("tests/ui/message_tag.rs");
+    t.compile_fail("tests/ui/variant_field_attr.rs");
+    t.compile_fail("tests/ui/pod_field.rs");
+    t.compile_fail("tests/ui/view_field.rs");
+    t.compile_fail("tests/ui/skip_attr.rs");
+}
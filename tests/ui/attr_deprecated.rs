@@ -0,0 +1,29 @@
+// the deprecated #[from_network(...)] and #[deser(...)] aliases still compile
+#![allow(deprecated)]
+use byteorder::ReadBytesExt;
+use type2network::FromNetworkOrder;
+use type2network_derive::FromNetwork;
+
+#[derive(Default, FromNetwork)]
+struct Point {
+    #[from_network(debug)]
+    x: u16,
+
+    #[deser(ignore)]
+    y: u16,
+}
+
+#[derive(Copy, Clone, FromNetwork)]
+#[from_network(From)]
+#[repr(u8)]
+enum Color {
+    Black = 0,
+}
+
+impl From<u8> for Color {
+    fn from(_: u8) -> Self {
+        Color::Black
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+// the deprecated aliases emit a warning, which is an error here
+#![deny(deprecated)]
+use type2network::FromNetworkOrder;
+use type2network_derive::FromNetwork;
+
+#[derive(Default, FromNetwork)]
+struct Point {
+    #[from_network(ignore)]
+    x: u16,
+
+    #[deser(ignore)]
+    y: u16,
+}
+
+fn main() {}
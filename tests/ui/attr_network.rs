@@ -0,0 +1,53 @@
+// the #[network(...)] attribute namespace
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use type2network::{FromNetworkOrder, ToNetworkOrder};
+use type2network_derive::{FromNetwork, ToNetwork};
+
+fn update(p: &mut Point) -> std::io::Result<()> {
+    p.w = 3;
+    Ok(())
+}
+
+#[derive(Default, ToNetwork, FromNetwork)]
+struct Point {
+    #[network(from(debug))]
+    x: u16,
+
+    #[network(from(ignore), to(ignore))]
+    y: u16,
+
+    #[network(from(with_code(self.z = 0xFFFF;)))]
+    z: u16,
+
+    #[network(from(with_fn(update)))]
+    w: u16,
+}
+
+// only the skipped fields need to implement Default
+#[derive(ToNetwork, FromNetwork)]
+struct Session {
+    id: u16,
+
+    #[network(skip)]
+    cache: Vec<u8>,
+}
+
+#[derive(Copy, Clone, ToNetwork, FromNetwork)]
+#[network(from(TryFrom))]
+#[repr(u8)]
+enum Color {
+    Black = 0,
+}
+
+impl TryFrom<u8> for Color {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Color::Black),
+            _ => Err(value),
+        }
+    }
+}
+
+fn main() {}
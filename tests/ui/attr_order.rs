@@ -0,0 +1,30 @@
+// order indices must be unique and cover all fields
+use type2network_derive::ToNetwork;
+
+#[derive(ToNetwork)]
+struct Duplicate {
+    #[network(order = 0)]
+    x: u16,
+
+    #[network(order = 0)]
+    y: u16,
+}
+
+#[derive(ToNetwork)]
+struct Missing {
+    #[network(order = 1)]
+    x: u16,
+
+    y: u16,
+}
+
+#[derive(ToNetwork)]
+struct OutOfRange {
+    #[network(order = 0)]
+    x: u16,
+
+    #[network(order = 2)]
+    y: u16,
+}
+
+fn main() {}
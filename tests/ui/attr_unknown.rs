@@ -0,0 +1,10 @@
+// unknown keys are rejected
+use type2network_derive::FromNetwork;
+
+#[derive(Default, FromNetwork)]
+struct Point {
+    #[network(from(foo))]
+    x: u16,
+}
+
+fn main() {}
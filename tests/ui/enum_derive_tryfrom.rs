@@ -0,0 +1,21 @@
+// derive_tryfrom is only for unit-only enums, and duplicate discriminants are left to the compiler (E0081)
+use type2network_derive::FromNetwork;
+
+#[derive(FromNetwork)]
+#[network(derive_tryfrom)]
+#[repr(u8)]
+enum Duplicate {
+    A = 1,
+    B = 0,
+    C,
+}
+
+#[derive(FromNetwork)]
+#[network(derive_tryfrom)]
+#[repr(u8)]
+enum NotUnit {
+    A = 1,
+    B(u8),
+}
+
+fn main() {}
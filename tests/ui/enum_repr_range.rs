@@ -0,0 +1,12 @@
+// discriminants not fitting in the repr are reported on the variant
+use type2network_derive::ToNetwork;
+
+#[derive(ToNetwork)]
+#[repr(u8)]
+enum Code {
+    A = 0,
+    B = 255,
+    C = 256,
+}
+
+fn main() {}
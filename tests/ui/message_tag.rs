@@ -0,0 +1,9 @@
+use type2network::{FromNetworkOrder, ToNetworkOrder};
+use type2network_derive::{FromNetwork, NetworkMessage, ToNetwork};
+
+#[derive(Default, ToNetwork, FromNetwork, NetworkMessage)]
+struct Ping {
+    id: u16,
+}
+
+fn main() {}
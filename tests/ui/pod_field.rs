@@ -0,0 +1,18 @@
+use type2network_derive::ToNetwork;
+
+#[derive(ToNetwork)]
+#[network(pod)]
+#[repr(C, packed)]
+struct Header {
+    version: u8,
+    id: u16,
+}
+
+#[derive(ToNetwork)]
+#[network(pod)]
+#[repr(C)]
+struct NotPacked {
+    version: u8,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use type2network_derive::FromNetwork;
+
+#[derive(Default, FromNetwork)]
+struct Session {
+    id: u16,
+
+    #[network(skip, count = "self.id")]
+    cache: Vec<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use type2network_derive::{FromNetwork, ToNetwork};
+
+// header bindings are only available in structs
+#[derive(ToNetwork, FromNetwork)]
+enum Message {
+    Ping(#[network(header)] u16),
+}
+
+#[derive(ToNetwork, FromNetwork)]
+struct Frame(#[network(header)] u8, u16);
+
+fn main() {}
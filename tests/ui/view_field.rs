@@ -0,0 +1,16 @@
+use type2network_derive::NetworkView;
+
+#[derive(NetworkView)]
+struct Middle {
+    data: Vec<u8>,
+    kind: u8,
+}
+
+#[derive(NetworkView)]
+struct Counted {
+    length: u8,
+    #[network(count = "self.length")]
+    data: Vec<u16>,
+}
+
+fn main() {}
@@ -0,0 +1,1158 @@
+// manage all attributes accepted by the derive macros
+//
+// The attribute namespace is #[network(...)], with the from/to sub-keys for options related to
+// a single direction:
+//
+// #[network(from(ignore))]
+// #[network(to(ignore))]
+//
+// and #[network(skip)] for both directions.
+//
+// The #[from_network(...)] and #[deser(...)] forms are deprecated aliases of #[network(from(...))]
+//
+// With the compat feature, some #[deku(...)] and #[br(...)] options are also accepted (see compat.rs)
+use crate::compat::{self, Dialect};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{
+    meta::ParseNestedMeta, parenthesized, spanned::Spanned, token, AttrStyle, Attribute, Expr,
+    ExprLit, Ident, Lit, LitInt, LitStr, Meta, Path,
+};
+
+// the main attribute namespace
+pub(crate) const NETWORK: &str = "network";
+
+// deprecated aliases of #[network(from(...))]
+const DEPRECATED_ALIASES: [&str; 2] = ["from_network", "deser"];
+
+#[derive(Debug, Default)]
+pub(crate) enum AttrKind {
+    // when no #[network(from(...))] attribute is given
+    #[default]
+    NoAttribute,
+
+    // #[network(from(ignore))]
+    NoAction,
+
+    // #[network(from(with_fn(my_func)))]
+    Call(Ident),
+
+    // #[network(from(with_code( let v = Vec::new(); )))]
+    Block(TokenStream),
+
+    // #[network(from(with_parser(parse_name)))]: a nom parser gives the value of the field
+    Parser(Path),
+
+    // #[network(from(debug))] or #[network(from(debug = "hex"))]
+    Debug(DebugFormat),
+
+    // #[network(from(trace))]
+    Trace,
+}
+
+// how values are printed by #[network(from(debug))]
+#[derive(Debug, Default, PartialEq)]
+pub(crate) enum DebugFormat {
+    // using the Debug trait: dbg!(&self.x)
+    #[default]
+    Default,
+
+    // #[network(from(debug = "hex"))]
+    Hex,
+}
+
+#[derive(Debug, Default)]
+pub(crate) enum ToAttrKind {
+    // when no #[network(to(...))] attribute is given
+    #[default]
+    NoAttribute,
+
+    // #[network(to(ignore))]
+    NoAction,
+
+    // #[network(to(borrow))]: the bytes of the field are borrowed by ToVectored
+    Borrow,
+}
+
+// all options found in the attributes of a single field
+#[derive(Debug, Default)]
+pub(crate) struct FieldAttrs {
+    pub from: AttrKind,
+    pub to: ToAttrKind,
+
+    // #[network(order = N)]: position of the field on the wire
+    pub order: Option<(usize, Span)>,
+
+    // #[network(header)]: the field can be referred to by its name in the expressions of the
+    // next fields
+    pub header: bool,
+
+    // #[network(byte_order)]: the field gives the byte order of the primitive fields following it
+    pub byte_order: bool,
+
+    // #[network(skip)]: the field is neither serialized nor deserialized, and takes its default value
+    pub skip: Option<Span>,
+
+    // #[network(count = "header.qd_count")]: number of elements to deserialize
+    pub count: Option<Expr>,
+
+    // #[network(utf8 = "lossy")]: the string is read with this mode for invalid UTF-8
+    pub utf8: Option<TokenStream>,
+
+    // #[network(char_as = "utf8")]: the char is sent as its UTF-8 bytes instead of a u32
+    pub char_as_utf8: bool,
+
+    // #[network(magic = 0xA1B2C3D4)]: the value set by WireDefault, checked when deserializing
+    pub magic: Option<Expr>,
+
+    // #[network(align_from_parent = 8)]: the field starts at a multiple of 8 bytes from the start of the
+    // outermost message
+    pub align: Option<LitInt>,
+
+    // #[network(pad_fill = 0x20, pad_verify)]: the bytes of the alignment padding
+    pub pad: PadFill,
+
+    // #[network(expect = 4)]: the value set by WireDefault, not checked when deserializing
+    pub expect: Option<Expr>,
+
+    // #[network(compute = "self.data.len() as u16")]: the value set by the generated builder
+    pub compute: Option<Expr>,
+
+    // #[network(from(mask = "self.field_mask", bit = 3))]: the field is only deserialized if the
+    // bit of the mask is set
+    pub mask: Option<(Expr, LitInt)>,
+
+    // #[deku(count = "len")] or #[br(count = len)]: the count expression, referring to the previous
+    // fields by their name, converted to a #[network(count)] one by wire_fields()
+    pub compat_count: Option<(Dialect, TokenStream)>,
+
+    // deprecated attributes found on the field
+    pub deprecated: Vec<(String, Span)>,
+}
+
+impl FieldAttrs {
+    // analyze all attributes of a field
+    pub fn new(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field_attrs = FieldAttrs::default();
+        let mut mask = FromMask::default();
+
+        for attr in attrs {
+            // outer attribute only
+            if attr.style != AttrStyle::Outer {
+                continue;
+            }
+
+            if attr.path().is_ident(NETWORK) {
+                attr.parse_nested_meta(|meta| {
+                    // #[network(from(...))]
+                    if meta.path.is_ident("from") {
+                        return meta.parse_nested_meta(|m| {
+                            parse_from(m, &mut field_attrs.from, &mut mask)
+                        });
+                    }
+
+                    // #[network(to(...))]
+                    if meta.path.is_ident("to") {
+                        return meta.parse_nested_meta(|m| parse_to(m, &mut field_attrs.to));
+                    }
+
+                    // #[network(order = N)]
+                    if meta.path.is_ident("order") {
+                        let lit: LitInt = meta.value()?.parse()?;
+                        field_attrs.order = Some((lit.base10_parse()?, lit.span()));
+                        return Ok(());
+                    }
+
+                    // #[network(header)]
+                    if meta.path.is_ident("header") {
+                        field_attrs.header = true;
+                        return Ok(());
+                    }
+
+                    // #[network(byte_order)]
+                    if meta.path.is_ident("byte_order") {
+                        field_attrs.byte_order = true;
+                        return Ok(());
+                    }
+
+                    // #[network(skip)]
+                    if meta.path.is_ident("skip") {
+                        field_attrs.skip = Some(meta.path.span());
+                        return Ok(());
+                    }
+
+                    // #[network(count = "expr")]
+                    if meta.path.is_ident("count") {
+                        field_attrs.count = Some(parse_expr(&meta)?);
+                        return Ok(());
+                    }
+
+                    // #[network(utf8 = "strict" | "lossy" | "bytes")]
+                    if meta.path.is_ident("utf8") {
+                        field_attrs.utf8 = Some(parse_utf8(&meta)?);
+                        return Ok(());
+                    }
+
+                    // #[network(char_as = "u32" | "utf8")]
+                    if meta.path.is_ident("char_as") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        field_attrs.char_as_utf8 = match lit.value().as_str() {
+                            "u32" => false,
+                            "utf8" => true,
+                            _ => {
+                                return Err(syn::Error::new(
+                                    lit.span(),
+                                    "expected char_as = \"u32\" or \"utf8\"",
+                                ))
+                            }
+                        };
+                        return Ok(());
+                    }
+
+                    // #[network(compute = "expr")]
+                    if meta.path.is_ident("compute") {
+                        field_attrs.compute = Some(parse_expr(&meta)?);
+                        return Ok(());
+                    }
+
+                    // #[network(magic = value)]
+                    if meta.path.is_ident("magic") {
+                        field_attrs.magic = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+
+                    // #[network(align_from_parent = N)]
+                    if meta.path.is_ident("align_from_parent") {
+                        let lit: LitInt = meta.value()?.parse()?;
+                        if lit.base10_parse::<usize>()? == 0 {
+                            return Err(syn::Error::new(lit.span(), "alignment can't be 0"));
+                        }
+                        field_attrs.align = Some(lit);
+                        return Ok(());
+                    }
+
+                    // #[network(pad_fill = value)] or #[network(pad_verify)]
+                    if field_attrs.pad.parse(&meta)? {
+                        return Ok(());
+                    }
+
+                    // #[network(expect = value)]
+                    if meta.path.is_ident("expect") {
+                        field_attrs.expect = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+
+                    Err(meta.error("unrecognized #[network] attribute"))
+                })?;
+            } else if let Some(alias) = deprecated_alias(attr) {
+                attr.parse_nested_meta(|m| parse_from(m, &mut field_attrs.from, &mut mask))?;
+                field_attrs.deprecated.push((alias, attr.path().span()));
+            } else if let Some(dialect) = compat::dialect(attr) {
+                compat::parse_field(attr, dialect, &mut field_attrs)?;
+            }
+        }
+
+        // a single count expression
+        if let (Some(count), Some(_)) = (&field_attrs.count, &field_attrs.compat_count) {
+            return Err(syn::Error::new_spanned(
+                count,
+                "#[network(count)] can't be combined with a deku or binrw count",
+            ));
+        }
+
+        // mask and bit go together
+        field_attrs.mask = match (mask.mask, mask.bit) {
+            (Some(mask), Some(bit)) => Some((mask, bit)),
+            (None, None) => None,
+            (Some(mask), None) => {
+                return Err(syn::Error::new_spanned(
+                    mask,
+                    "from(mask) requires from(bit = N)",
+                ))
+            }
+            (None, Some(bit)) => {
+                return Err(syn::Error::new_spanned(
+                    bit,
+                    "from(bit) requires from(mask = \"expr\")",
+                ))
+            }
+        };
+
+        // #[network(skip)]: the field is not on the wire, whatever the direction
+        if let Some(span) = field_attrs.skip {
+            if !matches!(field_attrs.from, AttrKind::NoAttribute)
+                || !matches!(field_attrs.to, ToAttrKind::NoAttribute)
+                || field_attrs.header
+                || field_attrs.byte_order
+                || field_attrs.count.is_some()
+                || field_attrs.compat_count.is_some()
+                || field_attrs.mask.is_some()
+                || field_attrs.utf8.is_some()
+                || field_attrs.char_as_utf8
+                || field_attrs.magic.is_some()
+                || field_attrs.align.is_some()
+                || field_attrs.expect.is_some()
+                || field_attrs.compute.is_some()
+            {
+                return Err(syn::Error::new(
+                    span,
+                    "#[network(skip)] can only be combined with #[network(order = N)]",
+                ));
+            }
+
+            field_attrs.from = AttrKind::NoAction;
+            field_attrs.to = ToAttrKind::NoAction;
+        }
+
+        // a field has a single default value
+        if let (Some(_), Some(expect)) = (&field_attrs.magic, &field_attrs.expect) {
+            return Err(syn::Error::new_spanned(
+                expect,
+                "#[network(magic)] and #[network(expect)] can't be combined",
+            ));
+        }
+
+        // a computed field has no default value
+        if let Some(compute) = &field_attrs.compute {
+            if field_attrs.magic.is_some() || field_attrs.expect.is_some() {
+                return Err(syn::Error::new_spanned(
+                    compute,
+                    "#[network(compute)] can't be combined with magic or expect",
+                ));
+            }
+        }
+
+        // the fill byte is the one of the alignment padding
+        if field_attrs.align.is_none() {
+            field_attrs.pad.check_unused("align_from_parent")?;
+        }
+
+        // a char is a single value
+        if field_attrs.char_as_utf8
+            && (field_attrs.count.is_some()
+                || field_attrs.compat_count.is_some()
+                || field_attrs.utf8.is_some())
+        {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "#[network(char_as)] can't be combined with count or utf8",
+            ));
+        }
+
+        Ok(field_attrs)
+    }
+}
+
+// the bytes written as padding (0 by default), and whether they're checked when deserializing
+#[derive(Debug, Default)]
+pub(crate) struct PadFill {
+    fill: Option<Expr>,
+    verify: Option<Span>,
+}
+
+impl PadFill {
+    // the pad_fill = value and pad_verify options, returning false for other options
+    fn parse(&mut self, meta: &ParseNestedMeta) -> syn::Result<bool> {
+        if meta.path.is_ident("pad_fill") {
+            self.fill = Some(meta.value()?.parse()?);
+            return Ok(true);
+        }
+
+        if meta.path.is_ident("pad_verify") {
+            self.verify = Some(meta.path.span());
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    // the options are only meaningful with the padding attribute
+    fn check_unused(&self, padding: &str) -> syn::Result<()> {
+        let message = format!(
+            "#[network(pad_fill)] and #[network(pad_verify)] require #[network({})]",
+            padding
+        );
+        match (&self.fill, self.verify) {
+            (Some(fill), _) => Err(syn::Error::new_spanned(fill, message)),
+            (None, Some(span)) => Err(syn::Error::new(span, message)),
+            (None, None) => Ok(()),
+        }
+    }
+
+    // the byte written
+    pub fn fill(&self) -> TokenStream {
+        match &self.fill {
+            Some(fill) => quote!(#fill),
+            None => quote!(0u8),
+        }
+    }
+
+    // the padding skipped by skip, reported to the observer unless its bytes are checked
+    pub fn report_skipped(&self, skip: TokenStream, container: &str) -> TokenStream {
+        if self.verify.is_some() {
+            return skip;
+        }
+        quote! {
+            let __padding = buffer.position();
+            #skip
+            if buffer.position() > __padding {
+                type2network::observer::report(__padding, (buffer.position() - __padding) as usize, #container);
+            }
+        }
+    }
+
+    // the byte expected when deserializing, if checked
+    pub fn expected(&self) -> TokenStream {
+        if self.verify.is_none() {
+            return quote!(None);
+        }
+        let fill = self.fill();
+        quote!(Some(#fill))
+    }
+}
+
+// the from(mask = "expr") and from(bit = N) options, which can be given in any order
+#[derive(Default)]
+struct FromMask {
+    mask: Option<Expr>,
+    bit: Option<LitInt>,
+}
+
+// FromNetwork for enums makes it mandatory to impl either From or TryFrom
+// This is hinted using the #[network(from(From))] ou #[network(from(TryFrom))] outer attribute
+#[derive(Debug, Default)]
+pub(crate) enum TryFromOrFrom {
+    From,
+    TryFrom,
+    #[default]
+    None,
+}
+
+// all options found in the attributes of a struct or an enum
+#[derive(Debug, Default)]
+pub(crate) struct ContainerAttrs {
+    // #[network(from(From))] or #[network(from(TryFrom))] on enums
+    pub from: TryFromOrFrom,
+
+    // #[network(skip_all_if = "feature = \"foo\"")]: condition to skip the whole type on the wire
+    pub skip_all_if: Option<TokenStream>,
+
+    // #[network(derive_tryfrom)]: generate the TryFrom<repr> impl for a unit-only enum
+    pub derive_tryfrom: bool,
+
+    // #[network(tag = 0x01)]: the NetworkMessage::TYPE_TAG constant
+    pub tag: Option<Expr>,
+
+    // #[network(hexdebug)]: generate the fmt_wire() method for a struct
+    pub hexdebug: bool,
+
+    // #[network(pod)]: a #[repr(C, packed)] struct of u8 fields copied as a whole
+    pub pod: bool,
+
+    // #[network(buf)]: also implement FromBuf for a struct
+    pub buf: bool,
+
+    // #[network(convert)]: implement TryFrom<&[u8]> for T and From<&T> for Vec<u8>
+    pub convert: bool,
+
+    // #[network(builder)]: generate a TBuilder for a struct
+    pub builder: bool,
+
+    // #[network(values)]: generate ALL, wire_value() and from_wire_value() for a unit-only enum
+    pub values: bool,
+
+    // #[network(zerocopy)]: a struct implementing the zerocopy traits copied as a whole
+    pub zerocopy: bool,
+
+    // #[network(vectored)]: also implement ToVectored for a struct
+    pub vectored: bool,
+
+    // #[network(pad_block = 16)]: the message is padded to a multiple of 16 bytes
+    pub pad_block: Option<LitInt>,
+
+    // #[network(pad_fill = 0xFF, pad_verify)]: the bytes of the block padding
+    pub pad: PadFill,
+
+    // #[network(digest = "sha256")]: generate the wire_digest() method
+    pub digest: Option<LitStr>,
+
+    // #[network(fuzz)]: implement FuzzTokens, giving the tokens of a fuzzing dictionary
+    pub fuzz: bool,
+
+    // deprecated attributes found on the type
+    pub deprecated: Vec<(String, Span)>,
+}
+
+impl ContainerAttrs {
+    // analyze all attributes of a struct or an enum
+    pub fn new(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container_attrs = ContainerAttrs::default();
+
+        for attr in attrs {
+            if attr.path().is_ident(NETWORK) {
+                attr.parse_nested_meta(|meta| {
+                    // #[network(from(...))]
+                    if meta.path.is_ident("from") {
+                        return meta.parse_nested_meta(|m| {
+                            parse_from_or_tryfrom(m, &mut container_attrs.from)
+                        });
+                    }
+
+                    // #[network(skip_all_if = "...")]
+                    if meta.path.is_ident("skip_all_if") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        container_attrs.skip_all_if = Some(parse_condition(&lit)?);
+                        return Ok(());
+                    }
+
+                    // #[network(derive_tryfrom)]
+                    if meta.path.is_ident("derive_tryfrom") {
+                        container_attrs.derive_tryfrom = true;
+                        return Ok(());
+                    }
+
+                    // #[network(tag = 0x01)]
+                    if meta.path.is_ident("tag") {
+                        container_attrs.tag = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    }
+
+                    // #[network(hexdebug)]
+                    if meta.path.is_ident("hexdebug") {
+                        container_attrs.hexdebug = true;
+                        return Ok(());
+                    }
+
+                    // #[network(pod)]
+                    if meta.path.is_ident("pod") {
+                        container_attrs.pod = true;
+                        return Ok(());
+                    }
+
+                    // #[network(buf)]
+                    if meta.path.is_ident("buf") {
+                        container_attrs.buf = true;
+                        return Ok(());
+                    }
+
+                    // #[network(convert)]
+                    if meta.path.is_ident("convert") {
+                        container_attrs.convert = true;
+                        return Ok(());
+                    }
+
+                    // #[network(builder)]
+                    if meta.path.is_ident("builder") {
+                        container_attrs.builder = true;
+                        return Ok(());
+                    }
+
+                    // #[network(values)]
+                    if meta.path.is_ident("values") {
+                        container_attrs.values = true;
+                        return Ok(());
+                    }
+
+                    // #[network(zerocopy)]
+                    if meta.path.is_ident("zerocopy") {
+                        container_attrs.zerocopy = true;
+                        return Ok(());
+                    }
+
+                    // #[network(vectored)]
+                    if meta.path.is_ident("vectored") {
+                        container_attrs.vectored = true;
+                        return Ok(());
+                    }
+
+                    // #[network(pad_block = N)]
+                    if meta.path.is_ident("pad_block") {
+                        let lit: LitInt = meta.value()?.parse()?;
+                        if lit.base10_parse::<usize>()? == 0 {
+                            return Err(syn::Error::new(lit.span(), "block size can't be 0"));
+                        }
+                        container_attrs.pad_block = Some(lit);
+                        return Ok(());
+                    }
+
+                    // #[network(digest = "sha256")]
+                    if meta.path.is_ident("digest") {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        if lit.value() != "sha256" {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                format!(
+                                    "unsupported digest `{}`, only \"sha256\" is supported",
+                                    lit.value()
+                                ),
+                            ));
+                        }
+                        container_attrs.digest = Some(lit);
+                        return Ok(());
+                    }
+
+                    // #[network(fuzz)]
+                    if meta.path.is_ident("fuzz") {
+                        container_attrs.fuzz = true;
+                        return Ok(());
+                    }
+
+                    // #[network(pad_fill = value)] or #[network(pad_verify)]
+                    if container_attrs.pad.parse(&meta)? {
+                        return Ok(());
+                    }
+
+                    Err(meta.error("unrecognized #[network] attribute"))
+                })?;
+            } else if let Some(alias) = deprecated_alias(attr) {
+                // #[from_network(From)] or #[from_network(TryFrom)]
+                attr.parse_nested_meta(|m| parse_from_or_tryfrom(m, &mut container_attrs.from))?;
+                container_attrs.deprecated.push((alias, attr.path().span()));
+            } else if let Some(dialect) = compat::dialect(attr) {
+                compat::parse_container(attr, dialect)?;
+            }
+        }
+
+        // the fill byte is the one of the block padding
+        if container_attrs.pad_block.is_none() {
+            container_attrs.pad.check_unused("pad_block")?;
+        }
+
+        Ok(container_attrs)
+    }
+
+    // the code skipping the whole type, returning the value passed
+    pub fn skip_code(&self, ret: TokenStream) -> TokenStream {
+        match &self.skip_all_if {
+            None => quote!(),
+            Some(cond) => quote! {
+                if #cond {
+                    return #ret;
+                }
+            },
+        }
+    }
+}
+
+// process From or TryFrom inside the from(...) option
+fn parse_from_or_tryfrom(meta: ParseNestedMeta, result: &mut TryFromOrFrom) -> syn::Result<()> {
+    // from(From)
+    if meta.path.is_ident("From") {
+        *result = TryFromOrFrom::From;
+        return Ok(());
+    }
+
+    // from(TryFrom)
+    if meta.path.is_ident("TryFrom") {
+        *result = TryFromOrFrom::TryFrom;
+        return Ok(());
+    }
+
+    Err(meta.error("expected From or TryFrom"))
+}
+
+// the configuration options without a value, as defined by the language
+const CFG_OPTIONS: [&str; 8] = [
+    "unix",
+    "windows",
+    "test",
+    "debug_assertions",
+    "miri",
+    "doc",
+    "doctest",
+    "proc_macro",
+];
+
+// the condition is either a configuration predicate evaluated at compile time using cfg!(), or a runtime
+// expression. The predicates are recognized by their form, not by their key: any key = "value" (e.g.
+// target_endian = "big"), not(...), any(...) and all(...), and the options without a value
+fn parse_condition(lit: &LitStr) -> syn::Result<TokenStream> {
+    if let Ok(meta) = lit.parse::<Meta>() {
+        if is_cfg_predicate(&meta) {
+            return Ok(quote!(cfg!(#meta)));
+        }
+    }
+
+    let expr: Expr = lit.parse()?;
+    Ok(quote!(#expr))
+}
+
+fn is_cfg_predicate(meta: &Meta) -> bool {
+    match meta {
+        Meta::NameValue(nv) => matches!(
+            &nv.value,
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(_),
+                ..
+            })
+        ),
+        Meta::List(list) => {
+            ["not", "any", "all"].iter().any(|p| list.path.is_ident(p))
+                || syn::parse2::<Expr>(quote!(#list)).is_err()
+        }
+        Meta::Path(path) => CFG_OPTIONS.iter().any(|p| path.is_ident(p)),
+    }
+}
+
+// return the alias name if the attribute is a deprecated one
+pub(crate) fn deprecated_alias(attr: &Attribute) -> Option<String> {
+    DEPRECATED_ALIASES
+        .iter()
+        .find(|a| attr.path().is_ident(a))
+        .map(|a| a.to_string())
+}
+
+// expressions are given as string literals: count = "header.qd_count"
+fn parse_expr(meta: &ParseNestedMeta) -> syn::Result<Expr> {
+    let lit: LitStr = meta.value()?.parse()?;
+    lit.parse()
+}
+
+// the mode of utf8 = "mode", as a path to the text::Utf8Mode variant
+fn parse_utf8(meta: &ParseNestedMeta) -> syn::Result<TokenStream> {
+    let lit: LitStr = meta.value()?.parse()?;
+    let mode = match lit.value().as_str() {
+        "strict" => quote!(Strict),
+        "lossy" => quote!(Lossy),
+        "bytes" => quote!(Bytes),
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "expected utf8 = \"strict\", \"lossy\" or \"bytes\"",
+            ))
+        }
+    };
+    Ok(quote!(type2network::text::Utf8Mode::#mode))
+}
+
+// process the from(...) options for all different cases
+fn parse_from(meta: ParseNestedMeta, kind: &mut AttrKind, mask: &mut FromMask) -> syn::Result<()> {
+    // from(ignore)
+    if meta.path.is_ident("ignore") {
+        *kind = AttrKind::NoAction;
+        return Ok(());
+    }
+
+    // from(with_fn(function))
+    if meta.path.is_ident("with_fn") {
+        if meta.input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in meta.input);
+
+            let function: Ident = content.parse()?;
+            *kind = AttrKind::Call(function);
+
+            return Ok(());
+        }
+
+        return Err(meta.error("malformed with_fn attribute, expected with_fn(function)"));
+    }
+
+    // from(with_parser(parser))
+    if meta.path.is_ident("with_parser") {
+        if meta.input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in meta.input);
+
+            let parser: Path = content.parse()?;
+            *kind = AttrKind::Parser(parser);
+
+            return Ok(());
+        }
+
+        return Err(meta.error("malformed with_parser attribute, expected with_parser(parser)"));
+    }
+
+    // from(with_code({ let x = 9; }))
+    if meta.path.is_ident("with_code") {
+        if meta.input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in meta.input);
+
+            let block: TokenStream = content.parse()?;
+            *kind = AttrKind::Block(block);
+
+            return Ok(());
+        }
+
+        return Err(meta.error("malformed with_code attribute, expected with_code(code)"));
+    }
+
+    // from(debug) or from(debug = "hex")
+    if meta.path.is_ident("debug") {
+        let format = if meta.input.peek(token::Eq) {
+            let lit: LitStr = meta.value()?.parse()?;
+            match lit.value().as_str() {
+                "hex" => DebugFormat::Hex,
+                _ => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "only debug = \"hex\" is supported",
+                    ))
+                }
+            }
+        } else {
+            DebugFormat::Default
+        };
+
+        *kind = AttrKind::Debug(format);
+        return Ok(());
+    }
+
+    // from(trace)
+    if meta.path.is_ident("trace") {
+        *kind = AttrKind::Trace;
+        return Ok(());
+    }
+
+    // from(mask = "self.field_mask")
+    if meta.path.is_ident("mask") {
+        mask.mask = Some(parse_expr(&meta)?);
+        return Ok(());
+    }
+
+    // from(bit = 3)
+    if meta.path.is_ident("bit") {
+        let lit: LitInt = meta.value()?.parse()?;
+        lit.base10_parse::<u32>()?;
+        mask.bit = Some(lit);
+        return Ok(());
+    }
+
+    Err(meta.error("unrecognized from(...) attribute"))
+}
+
+// process the to(...) options
+fn parse_to(meta: ParseNestedMeta, kind: &mut ToAttrKind) -> syn::Result<()> {
+    // to(ignore)
+    if meta.path.is_ident("ignore") {
+        *kind = ToAttrKind::NoAction;
+        return Ok(());
+    }
+
+    // to(borrow)
+    if meta.path.is_ident("borrow") {
+        *kind = ToAttrKind::Borrow;
+        return Ok(());
+    }
+
+    Err(meta.error("unrecognized to(...) attribute"))
+}
+
+// as proc macros can't emit warnings on stable, use a deprecated item to warn the user
+// about a deprecated attribute
+pub(crate) fn deprecation_warning(alias: &str, span: Span) -> TokenStream {
+    let note = format!(
+        "#[{}(...)] is deprecated, use #[network(from(...))] instead",
+        alias
+    );
+    let ident = Ident::new(alias, span);
+
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            struct #ident;
+            let _ = #ident;
+        };
+    }
+}
+
+// emit the warnings for all deprecated attributes found
+pub(crate) fn deprecation_warnings<'a>(
+    deprecated: impl Iterator<Item = &'a (String, Span)>,
+) -> TokenStream {
+    let warnings = deprecated.map(|(alias, span)| deprecation_warning(alias, *span));
+    quote!( #( #warnings )* )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+    use syn::parse_quote;
+
+    #[test]
+    fn condition() {
+        let cond = |s: &str| {
+            parse_condition(&LitStr::new(s, Span::call_site()))
+                .unwrap()
+                .to_string()
+        };
+
+        // configuration predicates, whatever their key
+        assert_eq!(cond("feature = \"ext\""), "cfg ! (feature = \"ext\")");
+        assert_eq!(
+            cond("target_endian = \"big\""),
+            "cfg ! (target_endian = \"big\")"
+        );
+        assert_eq!(cond("panic = \"abort\""), "cfg ! (panic = \"abort\")");
+        assert_eq!(
+            cond("not(target_feature = \"sse2\")"),
+            "cfg ! (not (target_feature = \"sse2\"))"
+        );
+        assert_eq!(cond("miri"), "cfg ! (miri)");
+
+        // runtime expressions
+        assert_eq!(cond("self.x == 0"), "self . x == 0");
+        assert_eq!(cond("DISABLED"), "DISABLED");
+        assert_eq!(cond("is_disabled(self)"), "is_disabled (self)");
+    }
+
+    #[test]
+    fn field_attrs() {
+        let f: syn::Field = parse_quote!(
+            #[network(from(ignore), to(ignore))]
+            x: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert!(matches!(attrs.from, AttrKind::NoAction));
+        assert!(matches!(attrs.to, ToAttrKind::NoAction));
+        assert!(attrs.deprecated.is_empty());
+
+        let f: syn::Field = parse_quote!(
+            #[from_network(with_fn(update))]
+            x: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert!(matches!(attrs.from, AttrKind::Call(_)));
+        assert_eq!(attrs.deprecated[0].0, "from_network");
+
+        let f: syn::Field = parse_quote!(
+            #[deser(debug)]
+            x: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert!(matches!(attrs.from, AttrKind::Debug(DebugFormat::Default)));
+        assert_eq!(attrs.deprecated[0].0, "deser");
+
+        let f: syn::Field = parse_quote!(
+            #[network(from(debug = "hex"))]
+            x: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert!(matches!(attrs.from, AttrKind::Debug(DebugFormat::Hex)));
+
+        let f: syn::Field = parse_quote!(
+            #[network(order = 2)]
+            x: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(attrs.order.unwrap().0, 2);
+
+        let f: syn::Field = parse_quote!(
+            #[network(header)]
+            x: u16
+        );
+        assert!(FieldAttrs::new(&f.attrs).unwrap().header);
+
+        let f: syn::Field = parse_quote!(
+            #[network(header, byte_order)]
+            magic: Magic32<0xA1B2C3D4>
+        );
+        assert!(FieldAttrs::new(&f.attrs).unwrap().byte_order);
+
+        let f: syn::Field = parse_quote!(
+            #[network(skip)]
+            cache: Option<u16>
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert!(attrs.skip.is_some());
+        assert!(matches!(attrs.from, AttrKind::NoAction));
+        assert!(matches!(attrs.to, ToAttrKind::NoAction));
+
+        let f: syn::Field = parse_quote!(
+            #[network(skip, from(debug))]
+            cache: Option<u16>
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(utf8 = "lossy")]
+            name: String
+        );
+        assert_eq!(
+            FieldAttrs::new(&f.attrs).unwrap().utf8.unwrap().to_string(),
+            "type2network :: text :: Utf8Mode :: Lossy"
+        );
+
+        let f: syn::Field = parse_quote!(
+            #[network(char_as = "utf8")]
+            c: char
+        );
+        assert!(FieldAttrs::new(&f.attrs).unwrap().char_as_utf8);
+
+        let f: syn::Field = parse_quote!(
+            #[network(char_as = "utf16")]
+            c: char
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(magic = 0xA1B2C3D4)]
+            magic: u32
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(attrs.magic.to_token_stream().to_string(), "0xA1B2C3D4");
+        assert!(attrs.expect.is_none());
+
+        let f: syn::Field = parse_quote!(
+            #[network(magic = 1, expect = 2)]
+            version: u8
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(align_from_parent = 8)]
+            value: u64
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(attrs.align.unwrap().base10_parse::<usize>().unwrap(), 8);
+
+        let f: syn::Field = parse_quote!(
+            #[network(align_from_parent = 0)]
+            value: u64
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(align_from_parent = 4, pad_fill = 0xFF, pad_verify)]
+            value: u32
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(attrs.pad.expected().to_string(), "Some (0xFF)");
+
+        let f: syn::Field = parse_quote!(
+            #[network(pad_verify)]
+            value: u32
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(compute = "self.data.len() as u16")]
+            length: u16
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(
+            attrs.compute.to_token_stream().to_string(),
+            "self . data . len () as u16"
+        );
+
+        let f: syn::Field = parse_quote!(
+            #[network(skip, compute = "0")]
+            length: u16
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(utf8 = "latin1")]
+            name: String
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(count = "header.qd_count")]
+            x: Vec<u16>
+        );
+        let attrs = FieldAttrs::new(&f.attrs).unwrap();
+        assert_eq!(
+            attrs.count.unwrap().to_token_stream().to_string(),
+            "header . qd_count"
+        );
+
+        let f: syn::Field = parse_quote!(
+            #[from_network(bit = 3, mask = "self.field_mask")]
+            x: u16
+        );
+        let (mask, bit) = FieldAttrs::new(&f.attrs).unwrap().mask.unwrap();
+        assert_eq!(mask.to_token_stream().to_string(), "self . field_mask");
+        assert_eq!(bit.base10_parse::<u32>().unwrap(), 3);
+
+        let f: syn::Field = parse_quote!(
+            #[network(from(mask = "self.field_mask"))]
+            x: u16
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+
+        let f: syn::Field = parse_quote!(
+            #[network(foo)]
+            x: u16
+        );
+        assert!(FieldAttrs::new(&f.attrs).is_err());
+    }
+
+    #[test]
+    fn container_attrs() {
+        let e: syn::ItemEnum = parse_quote!(
+            #[network(from(TryFrom), skip_all_if = "feature = \"legacy\"")]
+            enum Color {
+                Black = 0,
+            }
+        );
+        let attrs = ContainerAttrs::new(&e.attrs).unwrap();
+        assert!(matches!(attrs.from, TryFromOrFrom::TryFrom));
+        assert_eq!(
+            attrs.skip_all_if.unwrap().to_string(),
+            "cfg ! (feature = \"legacy\")"
+        );
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(skip_all_if = "self.x == 0")]
+            struct Point {
+                x: u16,
+            }
+        );
+        let attrs = ContainerAttrs::new(&e.attrs).unwrap();
+        assert_eq!(attrs.skip_all_if.unwrap().to_string(), "self . x == 0");
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(tag = 0x01)]
+            struct Hello;
+        );
+        let attrs = ContainerAttrs::new(&e.attrs).unwrap();
+        assert_eq!(attrs.tag.to_token_stream().to_string(), "0x01");
+        assert!(!attrs.hexdebug);
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(hexdebug)]
+            struct Hello;
+        );
+        assert!(ContainerAttrs::new(&e.attrs).unwrap().hexdebug);
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(digest = "sha256")]
+            struct Hello;
+        );
+        assert!(ContainerAttrs::new(&e.attrs).unwrap().digest.is_some());
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(digest = "md5")]
+            struct Hello;
+        );
+        assert!(ContainerAttrs::new(&e.attrs).is_err());
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(fuzz)]
+            struct Hello;
+        );
+        assert!(ContainerAttrs::new(&e.attrs).unwrap().fuzz);
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(pod)]
+            #[repr(C, packed)]
+            struct Hello {
+                x: u8,
+            }
+        );
+        assert!(ContainerAttrs::new(&e.attrs).unwrap().pod);
+
+        let e: syn::ItemStruct = parse_quote!(
+            #[network(buf, tag = 2)]
+            struct Hello {
+                x: u8,
+            }
+        );
+        let attrs = ContainerAttrs::new(&e.attrs).unwrap();
+        assert!(attrs.buf && !attrs.pod && !attrs.convert);
+    }
+}
@@ -0,0 +1,190 @@
+// the builder generated for #[network(builder)], added to the ToNetwork derive. Ex: for
+//
+// #[network(builder)]
+// struct Record {
+//     #[network(magic = 0xCAFE)]
+//     magic: u16,
+//     #[network(compute = "self.data.len() as u16")]
+//     length: u16,
+//     count: u8,
+//     #[network(count = "self.count")]
+//     items: Vec<u32>,
+//     data: Vec<u8>,
+// }
+//
+// pub struct RecordBuilder { items: Option<Vec<u32>>, data: Option<Vec<u8>> }
+//
+// impl Record {
+//     pub fn builder() -> RecordBuilder { ... }
+//     pub fn sync_wire_fields(&mut self) -> std::io::Result<()> {
+//         self.count = TryFrom::try_from(self.items.len()).map_err(...)?;
+//         self.length = self.data.len() as u16;
+//         Ok(())
+//     }
+// }
+//
+// impl RecordBuilder {
+//     pub fn items(mut self, items: Vec<u32>) -> Self { ... }
+//     pub fn data(mut self, data: Vec<u8>) -> Self { ... }
+//     pub fn build(self) -> std::io::Result<Record> { ... }
+// }
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Expr, Ident, Member};
+
+use crate::{r#struct::WireField, syn_utils::default_value};
+
+// the counter field referred to by a count expression: self.count, header.count or self.count as usize
+fn counter_field(count: &Expr) -> Option<&Ident> {
+    match count {
+        Expr::Cast(ec) => counter_field(&ec.expr),
+        Expr::Paren(ep) => counter_field(&ep.expr),
+        Expr::Field(ef) => match (&*ef.base, &ef.member) {
+            (Expr::Path(base), Member::Named(ident))
+                if base.path.is_ident("self") || base.path.is_ident("header") =>
+            {
+                Some(ident)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn builder(
+    ast: &DeriveInput,
+    fields: &[WireField],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let builder_name = format_ident!("{}Builder", name);
+
+    if let Some(wf) = fields.iter().find(|wf| wf.field.ident.is_none()) {
+        return Err(syn::Error::new_spanned(
+            wf.field,
+            "#[network(builder)] is only supported on structs with named fields",
+        ));
+    }
+
+    // the counters are computed from the length of the vectors, unless an expression is given
+    let mut counters = Vec::new();
+    for wf in fields {
+        let Some(counter) = wf.attrs.count.as_ref().and_then(counter_field) else {
+            continue;
+        };
+        let Some(cf) = fields
+            .iter()
+            .find(|f| f.field.ident.as_ref() == Some(counter))
+        else {
+            continue;
+        };
+        if cf.attrs.compute.is_none() && cf.attrs.magic.is_none() {
+            counters.push((cf, wf));
+        }
+    }
+
+    let is_computed = |wf: &WireField| {
+        wf.attrs.compute.is_some() || counters.iter().any(|(cf, _)| cf.index == wf.index)
+    };
+
+    // sorted by declaration order, as the struct is
+    let mut declared: Vec<_> = fields.iter().collect();
+    declared.sort_by_key(|wf| wf.index);
+
+    // the fields set by the user
+    let payload: Vec<_> = declared
+        .iter()
+        .filter(|wf| wf.attrs.magic.is_none() && !is_computed(wf))
+        .collect();
+    let payload_names: Vec<_> = payload.iter().map(|wf| wf.member()).collect();
+    let payload_types: Vec<_> = payload.iter().map(|wf| &wf.field.ty).collect();
+    let setter_docs = payload
+        .iter()
+        .map(|wf| format!("Sets the `{}` field.", wf.name()));
+
+    // the value of each field before the computed ones are set
+    let values = declared.iter().map(|wf| {
+        let field_name = wf.member();
+        if let Some(magic) = &wf.attrs.magic {
+            return quote!(#field_name: #magic);
+        }
+        let default = default_value(&wf.field.ty);
+        if is_computed(wf) {
+            return quote!(#field_name: #default);
+        }
+        match &wf.attrs.expect {
+            Some(expect) => quote!(#field_name: self.#field_name.unwrap_or_else(|| #expect)),
+            None => quote!(#field_name: self.#field_name.unwrap_or_else(|| #default)),
+        }
+    });
+
+    // counters first, so lengths and checksums can use them
+    let counter_updates = counters.iter().map(|(cf, vf)| {
+        let counter_name = cf.member();
+        let vec_name = vf.member();
+        let error = format!(
+            "{{}} elements of `{}` don't fit in the `{}` counter",
+            vf.name(),
+            cf.name()
+        );
+        quote! {
+            let len = self.#vec_name.len();
+            self.#counter_name = ::core::convert::TryFrom::try_from(len).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, format!(#error, len))
+            })?;
+        }
+    });
+    let compute_updates = declared.iter().filter_map(|wf| {
+        let field_name = wf.member();
+        wf.attrs
+            .compute
+            .as_ref()
+            .map(|compute| quote!(self.#field_name = #compute;))
+    });
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let generics = &ast.generics;
+
+    Ok(quote! {
+        /// Builder of a message, created by its ```builder()``` function.
+        #vis struct #builder_name #generics #where_clause {
+            #( #payload_names: Option<#payload_types>, )*
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Returns a builder of this message, where only the payload fields are set.
+            pub fn builder() -> #builder_name #ty_generics {
+                #builder_name {
+                    #( #payload_names: None, )*
+                }
+            }
+
+            /// Sets the counters of the vectors and the computed fields (lengths, checksums...) from the other fields.
+            /// An ```InvalidData``` error is returned if a vector has more elements than its counter can hold.
+            pub fn sync_wire_fields(&mut self) -> std::io::Result<()> {
+                #( #counter_updates )*
+                #( #compute_updates )*
+                Ok(())
+            }
+        }
+
+        impl #impl_generics #builder_name #ty_generics #where_clause {
+            #(
+                #[doc = #setter_docs]
+                pub fn #payload_names(mut self, #payload_names: #payload_types) -> Self {
+                    self.#payload_names = Some(#payload_names);
+                    self
+                }
+            )*
+
+            /// Builds the message: the fields not set take their default value, and the counters and computed fields are set.
+            /// An ```InvalidData``` error is returned if a vector has more elements than its counter can hold.
+            pub fn build(self) -> std::io::Result<#name #ty_generics> {
+                let mut message = #name {
+                    #( #values, )*
+                };
+                message.sync_wire_fields()?;
+                Ok(message)
+            }
+        }
+    })
+}
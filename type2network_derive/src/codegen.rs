@@ -0,0 +1,138 @@
+// with the debug-codegen feature, the code generated by ToNetwork and FromNetwork is formatted with
+// prettyplease and written to OUT_DIR, so it can be inspected or diffed across crate upgrades:
+//
+// $OUT_DIR/type2network/<crate>/<source file>/<Type>.<Derive>.rs
+//
+// The source file of the type keeps apart the types having the same name in different modules. Those
+// defined in the same file (e.g. in inline modules) are numbered in the order they're derived:
+// <Type>.2.<Derive>.rs for the second one, and so on. A type derived again keeps its number.
+//
+// OUT_DIR is only set for crates having a build script, as documented: nothing is written otherwise.
+// The file is only written when its content changes, and errors are ignored as the code is written
+// for debugging only.
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use syn::DeriveInput;
+
+// lines of the types derived for each file name, in the crate being compiled
+static DERIVED: Mutex<Option<HashMap<PathBuf, Vec<usize>>>> = Mutex::new(None);
+
+pub(crate) fn write_code(derive: &str, ast: &DeriveInput, code: &proc_macro2::TokenStream) {
+    let Some(out_dir) = std::env::var_os("OUT_DIR") else {
+        return;
+    };
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+
+    let span = ast.ident.span().unwrap();
+    let source = source_dir(&span.file());
+    let dir = PathBuf::from(out_dir)
+        .join("type2network")
+        .join(crate_name)
+        .join(source);
+    let path = dir.join(file_name(&dir, &ast.ident.to_string(), span.line(), derive));
+    let formatted = format_code(code);
+
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(formatted.as_str()) {
+        return;
+    }
+    let _ = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, formatted));
+}
+
+// the source file as a relative path, so it can be joined to OUT_DIR
+fn source_dir(file: &str) -> PathBuf {
+    Path::new(file)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+// <Type>.<Derive>.rs, numbered from the second type of the same name in the source file
+fn file_name(dir: &Path, ty: &str, line: usize, derive: &str) -> String {
+    let mut derived = DERIVED.lock().unwrap_or_else(|e| e.into_inner());
+    let lines = derived
+        .get_or_insert_with(HashMap::new)
+        .entry(dir.join(format!("{}.{}", ty, derive)))
+        .or_default();
+
+    let n = match lines.iter().position(|&l| l == line) {
+        Some(i) => i + 1,
+        None => {
+            lines.push(line);
+            lines.len()
+        }
+    };
+
+    match n {
+        1 => format!("{}.{}.rs", ty, derive),
+        n => format!("{}.{}.{}.rs", ty, n, derive),
+    }
+}
+
+// the code as formatted by prettyplease, or as a single line if it can't be parsed as a file
+fn format_code(code: &proc_macro2::TokenStream) -> String {
+    match syn::parse2::<syn::File>(code.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => code.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn codegen() {
+        let code = quote!(impl ToNetworkOrder for Point { fn f(&self) -> u8 { 1 } });
+        assert_eq!(
+            format_code(&code),
+            "impl ToNetworkOrder for Point {\n    fn f(&self) -> u8 {\n        1\n    }\n}\n"
+        );
+
+        // not an item
+        assert_eq!(format_code(&quote!(1 + 1)), "1 + 1");
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(source_dir("src/net/ip.rs"), Path::new("src/net/ip.rs"));
+        assert_eq!(
+            source_dir("/home/user/crate/../src/lib.rs"),
+            Path::new("home/user/crate/src/lib.rs")
+        );
+
+        // the types of the same name in a source file are numbered
+        let dir = Path::new("out/src/lib.rs");
+        assert_eq!(
+            file_name(dir, "Point", 3, "ToNetwork"),
+            "Point.ToNetwork.rs"
+        );
+        assert_eq!(
+            file_name(dir, "Point", 3, "FromNetwork"),
+            "Point.FromNetwork.rs"
+        );
+        assert_eq!(
+            file_name(dir, "Point", 12, "ToNetwork"),
+            "Point.2.ToNetwork.rs"
+        );
+        assert_eq!(
+            file_name(Path::new("out/src/net.rs"), "Point", 12, "ToNetwork"),
+            "Point.ToNetwork.rs"
+        );
+
+        // derived again
+        assert_eq!(
+            file_name(dir, "Point", 3, "ToNetwork"),
+            "Point.ToNetwork.rs"
+        );
+        assert_eq!(
+            file_name(dir, "Point", 12, "ToNetwork"),
+            "Point.2.ToNetwork.rs"
+        );
+    }
+}
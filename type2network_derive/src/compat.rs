@@ -0,0 +1,243 @@
+// migration shims: with the compat feature, a subset of the deku and binrw attributes is accepted
+// and mapped to the equivalent #[network(...)] options, so existing protocol definitions can be
+// ported by replacing their derives first:
+//
+// #[deku(skip)]                             => #[network(skip)]
+// #[deku(count = "len")]                    => #[network(count = "...")], len being a previous field
+// #[deku(update = "self.data.len()")]       => #[network(compute = "self.data.len()")]
+// #[deku(endian = "big")]                   => nothing, the network order is the default one
+// #[br(count = len)]                        => #[network(count = "...")]
+// #[br(ignore)], #[bw(ignore)]              => #[network(from(ignore))], #[network(to(ignore))]
+// #[brw(ignore)]                            => #[network(skip)]
+// #[br(big)], #[bw(big)], #[brw(big)]       => nothing
+//
+// Other options are rejected, as they have no equivalent or a different meaning (e.g. the magic
+// of binrw and deku is not a field)
+use proc_macro2::{Group, Ident, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{meta::ParseNestedMeta, spanned::Spanned, Attribute, Expr, LitStr};
+
+use crate::attrs::{AttrKind, FieldAttrs, ToAttrKind};
+
+// the crate whose attribute spellings are used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Dialect {
+    // #[deku(...)]
+    Deku,
+
+    // #[br(...)], #[bw(...)] or #[brw(...)]
+    Binrw,
+}
+
+// the dialect of a foreign attribute, only with the compat feature
+pub(crate) fn dialect(attr: &Attribute) -> Option<Dialect> {
+    if !cfg!(feature = "compat") {
+        return None;
+    }
+
+    let path = attr.path();
+    if path.is_ident("deku") {
+        Some(Dialect::Deku)
+    } else if ["br", "bw", "brw"].iter().any(|a| path.is_ident(a)) {
+        Some(Dialect::Binrw)
+    } else {
+        None
+    }
+}
+
+// map the options of a foreign field attribute
+pub(crate) fn parse_field(
+    attr: &Attribute,
+    dialect: Dialect,
+    field_attrs: &mut FieldAttrs,
+) -> syn::Result<()> {
+    let name = attr_name(attr);
+
+    attr.parse_nested_meta(|meta| {
+        if parse_endian(&meta, dialect)? {
+            return Ok(());
+        }
+
+        match dialect {
+            Dialect::Deku => {
+                // #[deku(skip)]
+                if meta.path.is_ident("skip") {
+                    field_attrs.skip = Some(meta.path.span());
+                    return Ok(());
+                }
+
+                // #[deku(count = "expr")]
+                if meta.path.is_ident("count") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    field_attrs.compat_count = Some((dialect, lit.parse()?));
+                    return Ok(());
+                }
+
+                // #[deku(update = "expr")]
+                if meta.path.is_ident("update") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    field_attrs.compute = Some(lit.parse()?);
+                    return Ok(());
+                }
+            }
+            Dialect::Binrw => {
+                // #[br(ignore)], #[bw(ignore)] or #[brw(ignore)]
+                if meta.path.is_ident("ignore") {
+                    match name.as_str() {
+                        "br" => field_attrs.from = AttrKind::NoAction,
+                        "bw" => field_attrs.to = ToAttrKind::NoAction,
+                        _ => field_attrs.skip = Some(meta.path.span()),
+                    }
+                    return Ok(());
+                }
+
+                // #[br(count = expr)]
+                if meta.path.is_ident("count") && name == "br" {
+                    let expr: Expr = meta.value()?.parse()?;
+                    field_attrs.compat_count = Some((dialect, expr.into_token_stream()));
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(unsupported(&meta, &name))
+    })
+}
+
+// map the options of a foreign struct or enum attribute: only the byte order is accepted
+pub(crate) fn parse_container(attr: &Attribute, dialect: Dialect) -> syn::Result<()> {
+    let name = attr_name(attr);
+
+    attr.parse_nested_meta(|meta| {
+        if parse_endian(&meta, dialect)? {
+            return Ok(());
+        }
+
+        Err(unsupported(&meta, &name))
+    })
+}
+
+// the count expression of a foreign attribute. The previous fields are referred to by their name,
+// by reference for deku and by value for binrw, and the count can be any unsigned integer
+pub(crate) fn count(dialect: Dialect, tokens: TokenStream, fields: &[&Ident]) -> syn::Result<Expr> {
+    let tokens = with_self(tokens, fields, dialect == Dialect::Deku);
+
+    syn::parse2(quote! {
+        {
+            use type2network::compat::WireCount as _;
+            (#tokens).wire_count()
+        }
+    })
+}
+
+// replace the field names by self.field, unless they are a member or a path segment
+fn with_self(tokens: TokenStream, fields: &[&Ident], by_ref: bool) -> TokenStream {
+    let mut result = TokenStream::new();
+    let mut member = false;
+    let mut dots = false;
+    let mut iter = tokens.into_iter().peekable();
+
+    while let Some(tt) = iter.next() {
+        let path = matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+
+        // the next ident follows a single dot: x.len is a member, 0..len is not. After ::, it's a path segment
+        let (is_dot, joint, colon) = match &tt {
+            TokenTree::Punct(p) => (
+                p.as_char() == '.',
+                p.spacing() == Spacing::Joint,
+                p.as_char() == ':',
+            ),
+            _ => (false, false, false),
+        };
+
+        match tt {
+            TokenTree::Ident(ident) if !member && !path && fields.contains(&&ident) => {
+                if by_ref {
+                    result.extend(quote!((&self.#ident)));
+                } else {
+                    result.extend(quote!(self.#ident));
+                }
+            }
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), with_self(g.stream(), fields, by_ref));
+                group.set_span(g.span());
+                result.extend([TokenTree::Group(group)]);
+            }
+            tt => result.extend([tt]),
+        }
+
+        member = (is_dot && !dots) || colon;
+        dots = is_dot && joint;
+    }
+
+    result
+}
+
+// #[deku(endian = "big")], #[br(big)]: the network order is the only one
+fn parse_endian(meta: &ParseNestedMeta, dialect: Dialect) -> syn::Result<bool> {
+    let little = match dialect {
+        Dialect::Deku if meta.path.is_ident("endian") => {
+            let lit: LitStr = meta.value()?.parse()?;
+            match lit.value().as_str() {
+                "big" => return Ok(true),
+                "little" => lit.span(),
+                _ => return Err(syn::Error::new(lit.span(), "expected endian = \"big\"")),
+            }
+        }
+        Dialect::Binrw if meta.path.is_ident("big") => return Ok(true),
+        Dialect::Binrw if meta.path.is_ident("little") => meta.path.span(),
+        _ => return Ok(false),
+    };
+
+    Err(syn::Error::new(
+        little,
+        "little endian is not supported, type2network uses the network order. Use a #[network(byte_order)] field for formats in both orders",
+    ))
+}
+
+fn attr_name(attr: &Attribute) -> String {
+    attr.path()
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+fn unsupported(meta: &ParseNestedMeta, name: &str) -> syn::Error {
+    let option = meta
+        .path
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    meta.error(format!(
+        "#[{}({})] is not supported, see the type2network::compat module for the equivalent #[network(...)] attributes",
+        name, option
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn compat_count() {
+        let len: Ident = parse_quote!(len);
+        let data: Ident = parse_quote!(data);
+        let fields = [&len, &data];
+
+        let tokens = with_self(quote!(*len + data.len() + x.len), &fields, true);
+        assert_eq!(
+            tokens.to_string(),
+            quote!(*(&self.len) + (&self.data).len() + x.len).to_string()
+        );
+
+        let tokens = with_self(quote!((len as usize) * 2 + Self::len), &fields, false);
+        assert_eq!(
+            tokens.to_string(),
+            quote!((self.len as usize) * 2 + Self::len).to_string()
+        );
+
+        let tokens = with_self(quote!(0..len), &fields, false);
+        assert_eq!(tokens.to_string(), quote!(0..self.len).to_string());
+    }
+}
@@ -0,0 +1,107 @@
+// the std conversions generated for #[network(convert)], wrapping the traits:
+//
+// impl<'a> TryFrom<&'a [u8]> for Point {
+//     type Error = std::io::Error;
+//     fn try_from(bytes: &'a [u8]) -> std::io::Result<Self> { ... }
+// }
+//
+// impl From<&Point> for Vec<u8> {
+//     fn from(value: &Point) -> Vec<u8> { ... }
+// }
+use quote::quote;
+use syn::{parse_quote, DeriveInput};
+
+use crate::attrs::ContainerAttrs;
+use crate::syn_utils::add_lifetime;
+
+// added to the FromNetwork derive
+pub(crate) fn try_from_bytes(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let name_string = name.to_string();
+
+    match ContainerAttrs::new(&ast.attrs) {
+        Ok(attrs) if attrs.convert => (),
+        _ => return quote!(),
+    }
+
+    let mut generics = add_lifetime(ast);
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#name #ty_generics: FromNetworkOrder<'a> + Default));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics TryFrom<&'a [u8]> for #name #ty_generics #where_clause {
+            type Error = std::io::Error;
+
+            fn try_from(bytes: &'a [u8]) -> std::io::Result<Self> {
+                type2network::convert_from_bytes(#name_string, bytes)
+            }
+        }
+    }
+}
+
+// added to the ToNetwork derive
+pub(crate) fn to_vec(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let name_string = name.to_string();
+
+    match ContainerAttrs::new(&ast.attrs) {
+        Ok(attrs) if attrs.convert => (),
+        _ => return quote!(),
+    }
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics From<&#name #ty_generics> for Vec<u8> #where_clause {
+            fn from(value: &#name #ty_generics) -> Vec<u8> {
+                type2network::convert_to_vec(#name_string, value)
+            }
+        }
+    }
+}
+
+// the wire_digest() and try_wire_digest() methods generated for #[network(digest = "sha256")], added to the
+// ToNetwork derive:
+//
+// impl Point {
+//     pub fn wire_digest(&self) -> [u8; 32] { ... }
+//     pub fn try_wire_digest(&self) -> std::io::Result<[u8; 32]> { ... }
+// }
+pub(crate) fn wire_digest(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let name_string = name.to_string();
+
+    match ContainerAttrs::new(&ast.attrs) {
+        Ok(attrs) if attrs.digest.is_some() => (),
+        _ => return quote!(),
+    }
+
+    let mut generics = ast.generics.clone();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#name #ty_generics: ToNetworkOrder));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Returns the SHA-256 digest of the serialized value.
+            ///
+            /// # Panics
+            /// Panics if the value can't be serialized, see ```try_wire_digest()```.
+            pub fn wire_digest(&self) -> [u8; 32] {
+                type2network::digest::wire_sha256(#name_string, self)
+            }
+
+            /// Returns the SHA-256 digest of the serialized value, or the error returned when serializing it.
+            pub fn try_wire_digest(&self) -> std::io::Result<[u8; 32]> {
+                type2network::digest::sha256(self)
+            }
+        }
+    }
+}
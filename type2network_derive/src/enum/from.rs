@@ -1,38 +1,95 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{Attribute, DataEnum, DeriveInput};
+use syn::{DataEnum, DeriveInput, Ident, Variant};
 
+use crate::attrs::{
+    deprecation_warnings, AttrKind, ContainerAttrs, DebugFormat, FieldAttrs, TryFromOrFrom,
+};
 use crate::syn_utils::*;
 
-use super::EnumDeriveBuilder;
+use super::{
+    check_discriminants, discriminant_tokens, variant_fields, variant_pattern, variant_types,
+    EnumDeriveBuilder, VariantField,
+};
 
 impl EnumDeriveBuilder {
-    pub fn from_network(ast: &DeriveInput, _de: &DataEnum) -> proc_macro2::TokenStream {
+    pub fn from_network(ast: &DeriveInput, de: &DataEnum) -> proc_macro2::TokenStream {
         let enum_name = &ast.ident;
         let enum_string = enum_name.to_string();
 
         // which trait does the enum implement ? From or TryFrom or none of these ?
-        let implemented_trait = get_from_or_tryfrom(&ast.attrs);
+        let container_attrs = match ContainerAttrs::new(&ast.attrs) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error(),
+        };
+        let warnings = deprecation_warnings(container_attrs.deprecated.iter());
+        let skip = container_attrs.skip_code(quote!(Ok(())));
+
+        if container_attrs.buf {
+            return syn::Error::new_spanned(
+                enum_name,
+                "#[network(buf)] is only supported on structs",
+            )
+            .to_compile_error();
+        }
+
+        // an enum with data but no conversion from its discriminant: the fields of the current
+        // variant are deserialized in place, the same way Option<T> is
+        if matches!(container_attrs.from, TryFromOrFrom::None)
+            && !container_attrs.derive_tryfrom
+            && find_fallback(ast, de).is_none()
+            && de.variants.iter().any(|v| !v.is_unit())
+        {
+            let code = match build_in_place(ast, de, &skip) {
+                Ok(code) => code,
+                Err(e) => return e.to_compile_error(),
+            };
+            return quote! {
+                #code
+                #warnings
+            };
+        }
 
         let ty = SynUtils::repr_size(&ast.attrs)
             .unwrap_or_else(|| unimplemented!("repr size is mandatory on enum {}", enum_name));
+        if let Err(e) = check_discriminants(de, &ty) {
+            return e.to_compile_error();
+        }
 
         let value_expr = build_value(&ty);
 
+        // #[network(derive_tryfrom)] generates the TryFrom impl, so implies #[network(from(TryFrom))]
+        let tryfrom_impl = if container_attrs.derive_tryfrom {
+            match build_tryfrom(enum_name, de, &ty) {
+                Ok(code) => code,
+                Err(e) => return e.to_compile_error(),
+            }
+        } else {
+            quote!()
+        };
+        let implemented_trait = match container_attrs.from {
+            TryFromOrFrom::None if container_attrs.derive_tryfrom => TryFromOrFrom::TryFrom,
+            t => t,
+        };
+
         // the implementation of FromNetworkOrder depends on whether From or TryFrom is implemented
         match implemented_trait {
             TryFromOrFrom::From => quote! {
                 impl<'a> FromNetworkOrder<'a> for #enum_name {
                     fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                        #skip
                         #value_expr
                         *self = <#enum_name>::from(value);
                         Ok(())
                     }
                 }
+
+                #warnings
             },
             TryFromOrFrom::TryFrom => quote! {
                 impl<'a> FromNetworkOrder<'a> for #enum_name {
                     fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                        #skip
                         #value_expr
                         match <#enum_name>::try_from(value) {
                             Ok(ct) => {
@@ -43,62 +100,295 @@ impl EnumDeriveBuilder {
                         }
                     }
                 }
+
+                #tryfrom_impl
+                #warnings
             },
+            // an enum with a fallback variant: unknown values are kept in the fallback variant
+            TryFromOrFrom::None if find_fallback(ast, de).is_some() => {
+                let fallback = find_fallback(ast, de).unwrap();
+                let conversion = match build_fallback_match(enum_name, de, fallback) {
+                    Ok(code) => code,
+                    Err(e) => return e.to_compile_error(),
+                };
+
+                quote! {
+                    impl<'a> FromNetworkOrder<'a> for #enum_name {
+                        fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                            #skip
+                            let start = buffer.position();
+                            #value_expr
+                            *self = #conversion;
+                            Ok(())
+                        }
+                    }
+
+                    #warnings
+                }
+            }
             TryFromOrFrom::None => panic!(
-                "at least, '{}' should implement From or TryFrom trait",
+                "at least, '{}' should implement From or TryFrom trait, or have a fallback variant",
                 enum_string
             ),
         }
     }
 }
 
-fn build_value(ty: &TokenStream) -> proc_macro2::TokenStream {
-    match ty.to_string().as_str() {
-        "u8" => quote!(let value = buffer.read_u8()?;),
-        "i8" => quote!(let value = buffer.read_i8()?;),
-        _ => {
-            let method = format_ident!("read_{}", ty.to_string());
-            quote!(let value = buffer.#method::<BigEndian>()?;)
+// Build the impl for an enum with data, deserializing the fields of the current variant:
+//
+// impl<'a, T> FromNetworkOrder<'a> for Payload<T> where T: FromNetworkOrder<'a> {
+//     fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+//         match self {
+//             Payload::Raw(f0) => { FromNetworkOrder::deserialize_from(f0, buffer)?; }
+//             Payload::Typed(f0) => { FromNetworkOrder::deserialize_from(f0, buffer)?; }
+//         }
+//         Ok(())
+//     }
+// }
+fn build_in_place(
+    ast: &DeriveInput,
+    de: &DataEnum,
+    skip: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let enum_name = &ast.ident;
+
+    // type parameters used in variant fields need to be deserializable
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    let generics = add_bounds(
+        &add_lifetime(ast),
+        variant_types(de),
+        quote!(FromNetworkOrder<'a>),
+    );
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let mut arms = Vec::new();
+    for v in &de.variants {
+        let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+        let fields = variant_fields(v)?;
+
+        // #[network(from(ignore))]: the field is not deserialized
+        let ignored = |attrs: &FieldAttrs| matches!(attrs.from, AttrKind::NoAction);
+        let pattern = variant_pattern(enum_name, v, &fields, ignored);
+
+        let calls = fields
+            .iter()
+            .filter(|vf| !ignored(&vf.attrs))
+            .map(|vf| variant_field_call(enum_name, v, vf));
+
+        arms.push(quote! {
+            #( #cfgs )*
+            #pattern => {
+                #( #calls )*
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics FromNetworkOrder<'a> for #enum_name #ty_generics #where_clause {
+            fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                #skip
+                match self {
+                    #( #arms )*
+                }
+                Ok(())
+            }
         }
+    })
+}
+
+// the deserialization of a variant field, bound as a &mut in the match arm. The count expression
+// can use the previous fields of the variant by their names
+fn variant_field_call(enum_name: &Ident, v: &Variant, vf: &VariantField) -> TokenStream {
+    let binding = &vf.binding;
+    let field_string = binding.to_string();
+
+    // the path of the field is Enum.Variant.field, or Enum.Variant.0 for a tuple variant
+    let field_path = match &vf.field.ident {
+        Some(ident) => format!("{}.{}", v.ident, ident),
+        None => format!("{}.{}", v.ident, &field_string[1..]),
+    };
+    let context = error_context(enum_name, &field_path);
+
+    let type_name = enum_name.to_string();
+
+    let call = match &vf.attrs.count {
+        None => quote! {
+            FromNetworkOrder::deserialize_from(&mut *#binding, buffer)#context?;
+        },
+        Some(count) => quote! {
+            {
+                let count = (#count) as usize;
+                type2network::budget::take(count)#context?;
+
+                type2network::vec::Push::clear(&mut *#binding);
+                for _ in 0..count {
+                    type2network::vec::Push::try_push(&mut *#binding, FromNetworkOrder::deserialize_new(buffer)#context?)#context?;
+                }
+            }
+        },
+    };
+
+    // record the offsets of the field for the diagnose feature
+    let call = quote! {
+        let __field_start = buffer.position();
+        #call
+        type2network::record_field(#type_name, #field_path, __field_start, buffer.position());
+    };
+
+    match &vf.attrs.from {
+        AttrKind::Debug(DebugFormat::Default) => quote! {
+            #call
+            #[cfg(debug_assertions)]
+            dbg!(&#binding);
+        },
+        AttrKind::Debug(DebugFormat::Hex) => quote! {
+            #call
+            #[cfg(debug_assertions)]
+            eprintln!("[{}:{}] {} = {:02X?}", file!(), line!(), #field_string, &#binding);
+        },
+        AttrKind::Trace => quote! {
+            #call
+            type2network::log::trace!("{}.{} = {:?}", std::any::type_name::<Self>(), #field_string, #binding);
+        },
+        // other attributes were rejected by variant_fields()
+        _ => call,
     }
 }
 
-// FromNetwork for enums makes it mandatory to impl either From or TryFrom
-// This is hinted using the #[from_network(From)] ou #[from_network(TryFrom)] outer attribute
-enum TryFromOrFrom {
-    From,
-    TryFrom,
-    None,
+// the fallback variant receives all unknown values. It's either:
+// - the variant marked with #[network(fallback)] or #[num_enum(catch_all)]
+// - for a #[non_exhaustive] enum, the only variant with a single unnamed field
+pub(crate) fn find_fallback<'a>(ast: &DeriveInput, de: &'a DataEnum) -> Option<&'a Variant> {
+    let marked = de.variants.iter().find(|v| {
+        v.attrs.iter().any(|a| {
+            (a.path().is_ident("network") || a.path().is_ident("num_enum"))
+                && a.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("fallback") || meta.path.is_ident("catch_all") {
+                        Ok(())
+                    } else {
+                        Err(meta.error("not a fallback"))
+                    }
+                })
+                .is_ok()
+        })
+    });
+
+    if marked.is_some() {
+        return marked;
+    }
+
+    if ast
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("non_exhaustive"))
+    {
+        let mut candidates = de
+            .variants
+            .iter()
+            .filter(|v| v.is_unnamed().is_some_and(|f| f.unnamed.len() == 1));
+
+        if let (Some(v), None) = (candidates.next(), candidates.next()) {
+            return Some(v);
+        }
+    }
+
+    None
 }
 
-fn get_from_or_tryfrom(attrs: &[Attribute]) -> TryFromOrFrom {
-    let mut result = TryFromOrFrom::None;
-
-    // loop through attributes
-    for attr in attrs {
-        // we found the #[tonetwork] attributes
-        if attr.path().is_ident("from_network") {
-            attr.parse_nested_meta(|meta| {
-                // #[from_network(From)]
-                if meta.path.is_ident("From") {
-                    result = TryFromOrFrom::From;
-                    return Ok(());
-                }
+// Build the conversion from value for an enum with a fallback variant, the bytes of an unknown
+// value being reported to the observer:
+//
+// match value {
+//     x if x == 0 => OpCode::Query,
+//     x if x == 1 => OpCode::IQuery,
+//     x => {
+//         type2network::observer::report(start, (buffer.position() - start) as usize, "OpCode");
+//         OpCode::Reserved(x.into())
+//     }
+// }
+fn build_fallback_match(
+    enum_name: &Ident,
+    de: &DataEnum,
+    fallback: &Variant,
+) -> syn::Result<TokenStream> {
+    let mut arms = Vec::new();
 
-                // #[from_network(TryFrom)]
-                if meta.path.is_ident("TryFrom") {
-                    result = TryFromOrFrom::TryFrom;
-                    return Ok(());
-                }
+    for (v, discriminant) in de.variants.iter().zip(discriminant_tokens(de)) {
+        if !v.is_unit() {
+            continue;
+        }
 
-                // neither From nor TryFrom was found
-                Ok(())
-            })
-            .unwrap();
+        let Some(discriminant) = discriminant else {
+            return Err(syn::Error::new_spanned(
+                v,
+                "the discriminant of this variant can't be computed, please set it explicitly",
+            ));
+        };
+
+        let variant_ident = &v.ident;
+        let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+        arms.push(quote! {
+            #( #cfgs )*
+            x if x == #discriminant => #enum_name::#variant_ident,
+        });
+    }
+
+    let fallback_ident = &fallback.ident;
+    let enum_string = enum_name.to_string();
+    Ok(quote! {
+        match value {
+            #( #arms )*
+            x => {
+                type2network::observer::report(start, (buffer.position() - start) as usize, #enum_string);
+                #enum_name::#fallback_ident(x.into())
+            }
         }
+    })
+}
+
+// Build the TryFrom impl for a unit-only enum. The match arms compare the value to the discriminants
+// cast to the repr type, so they're computed by the compiler, which also rejects duplicates (E0081).
+fn build_tryfrom(enum_name: &Ident, de: &DataEnum, ty: &TokenStream) -> syn::Result<TokenStream> {
+    if let Some(v) = de.variants.iter().find(|v| !v.is_unit()) {
+        return Err(syn::Error::new_spanned(
+            v,
+            "#[network(derive_tryfrom)] is only supported on unit-only enums",
+        ));
     }
 
-    result
+    let arms = de.variants.iter().map(|v| {
+        let variant_ident = &v.ident;
+        let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+
+        quote! {
+            #( #cfgs )*
+            x if x == #enum_name::#variant_ident as #ty => Ok(#enum_name::#variant_ident),
+        }
+    });
+
+    Ok(quote! {
+        impl TryFrom<#ty> for #enum_name {
+            type Error = #ty;
+
+            fn try_from(value: #ty) -> Result<Self, #ty> {
+                match value {
+                    #( #arms )*
+                    _ => Err(value),
+                }
+            }
+        }
+    })
+}
+
+fn build_value(ty: &TokenStream) -> proc_macro2::TokenStream {
+    match ty.to_string().as_str() {
+        "u8" => quote!(let value = buffer.read_u8()?;),
+        "i8" => quote!(let value = buffer.read_i8()?;),
+        _ => {
+            let method = format_ident!("read_{}", ty.to_string());
+            quote!(let value = buffer.#method::<BigEndian>()?;)
+        }
+    }
 }
 
 #[cfg(test)]
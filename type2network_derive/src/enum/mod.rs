@@ -1,7 +1,296 @@
-use syn::{DataEnum, DeriveInput};
+use std::ops::RangeInclusive;
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    DataEnum, DeriveInput, Expr, ExprLit, ExprUnary, Field, Fields, Ident, Lit, Type, UnOp, Variant,
+};
+
+use crate::attrs::{AttrKind, FieldAttrs, ToAttrKind};
 
 pub struct EnumDeriveBuilder;
 pub type EnumBuilderFunc = fn(&DeriveInput, &DataEnum) -> proc_macro2::TokenStream;
 
+// evaluate the discriminant of each variant when possible, i.e. when it's an integer literal or
+// it's implicit and follows an evaluated one:
+//
+// enum Color {
+//     Black,          => Some(0)
+//     White = 3,      => Some(3)
+//     Yellow,         => Some(4)
+//     Brown = FOO,    => None
+// }
+//
+// A variant with a #[cfg] attribute may not exist, so the implicit discriminant following it is
+// not evaluated.
+pub(crate) fn discriminant_values(de: &DataEnum) -> Vec<Option<i128>> {
+    let mut values = Vec::new();
+    let mut previous: Option<i128> = Some(-1);
+
+    for v in &de.variants {
+        let value = match &v.discriminant {
+            Some((_, expr)) => eval_int(expr),
+            None => previous.and_then(|p| p.checked_add(1)),
+        };
+
+        values.push(value);
+        previous = value.filter(|_| !v.attrs.iter().any(|a| a.path().is_ident("cfg")));
+    }
+
+    values
+}
+
+// the discriminant of each variant as tokens: either the explicit expression, or the evaluated
+// value when implicit
+pub(crate) fn discriminant_tokens(de: &DataEnum) -> Vec<Option<TokenStream>> {
+    de.variants
+        .iter()
+        .zip(discriminant_values(de))
+        .map(|(v, value)| match (&v.discriminant, value) {
+            (Some((_, expr)), _) => Some(quote!(#expr)),
+            (None, Some(value)) => {
+                let lit = Literal::i128_unsuffixed(value);
+                Some(quote!(#lit))
+            }
+            (None, None) => None,
+        })
+        .collect()
+}
+
+// the range of the values of an integer repr, or None if it's not a primitive integer. As the
+// discriminants are evaluated as i128, the u128 range is limited to i128::MAX
+fn repr_range(ty: &TokenStream) -> Option<RangeInclusive<i128>> {
+    let range = match ty.to_string().as_str() {
+        "u8" => 0..=u8::MAX as i128,
+        "u16" => 0..=u16::MAX as i128,
+        "u32" => 0..=u32::MAX as i128,
+        "u64" => 0..=u64::MAX as i128,
+        "u128" => 0..=i128::MAX,
+        "i8" => i8::MIN as i128..=i8::MAX as i128,
+        "i16" => i16::MIN as i128..=i16::MAX as i128,
+        "i32" => i32::MIN as i128..=i32::MAX as i128,
+        "i64" => i64::MIN as i128..=i64::MAX as i128,
+        "i128" => i128::MIN..=i128::MAX,
+        _ => return None,
+    };
+    Some(range)
+}
+
+// check that the discriminants evaluated fit in the repr, as they're written with `as` casts which
+// would silently truncate them. Each variant out of range is reported
+pub(crate) fn check_discriminants(de: &DataEnum, ty: &TokenStream) -> syn::Result<()> {
+    let Some(range) = repr_range(ty) else {
+        return Ok(());
+    };
+    let mut errors: Option<syn::Error> = None;
+
+    for (v, value) in de.variants.iter().zip(discriminant_values(de)) {
+        let Some(value) = value.filter(|value| !range.contains(value)) else {
+            continue;
+        };
+
+        let e = syn::Error::new_spanned(
+            v,
+            format!(
+                "discriminant {} of variant `{}` doesn't fit in {} ({}..={})",
+                value,
+                v.ident,
+                ty,
+                range.start(),
+                range.end()
+            ),
+        );
+        match errors.as_mut() {
+            Some(errors) => errors.combine(e),
+            None => errors = Some(e),
+        }
+    }
+
+    errors.map_or(Ok(()), Err)
+}
+
+// the types of all fields of all variants, used to add bounds on type parameters
+pub(crate) fn variant_types(de: &DataEnum) -> impl Iterator<Item = &Type> {
+    de.variants
+        .iter()
+        .flat_map(|v| v.fields.iter().map(|f| &f.ty))
+}
+
+// evaluate an integer literal, possibly negative
+fn eval_int(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(i), ..
+        }) => i.base10_parse::<i128>().ok(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => eval_int(expr).map(|v| -v),
+        Expr::Group(g) => eval_int(&g.expr),
+        Expr::Paren(p) => eval_int(&p.expr),
+        _ => None,
+    }
+}
+
+// a field of a variant, with its binding in the match arm and its analyzed attributes
+pub(crate) struct VariantField<'a> {
+    pub binding: Ident,
+    pub field: &'a Field,
+    pub attrs: FieldAttrs,
+}
+
+// analyze the attributes of the fields of a variant. As fields are accessed through the bindings
+// of a match arm, only the attributes not depending on self are supported
+pub(crate) fn variant_fields(v: &Variant) -> syn::Result<Vec<VariantField<'_>>> {
+    v.fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let attrs = FieldAttrs::new(&field.attrs)?;
+            let binding = match &field.ident {
+                Some(ident) => ident.clone(),
+                None => format_ident!("f{}", i),
+            };
+
+            if attrs.order.is_some()
+                || attrs.header
+                || attrs.byte_order
+                || attrs.mask.is_some()
+                || attrs.utf8.is_some()
+                || attrs.char_as_utf8
+                || attrs.magic.is_some()
+                || attrs.align.is_some()
+                || attrs.expect.is_some()
+                || attrs.compute.is_some()
+                || attrs.compat_count.is_some()
+                || matches!(attrs.to, ToAttrKind::Borrow)
+                || matches!(
+                    attrs.from,
+                    AttrKind::Call(_) | AttrKind::Block(_) | AttrKind::Parser(_)
+                )
+            {
+                let name = field
+                    .ident
+                    .as_ref()
+                    .map_or_else(|| i.to_string(), |ident| ident.to_string());
+                return Err(syn::Error::new_spanned(
+                    field,
+                    format!(
+                        "field {} of variant `{}`: only skip, from(ignore), from(debug), from(trace), to(ignore) and count are supported on enum variant fields",
+                        name, v.ident
+                    ),
+                ));
+            }
+
+            Ok(VariantField {
+                binding,
+                field,
+                attrs,
+            })
+        })
+        .collect()
+}
+
+// the pattern of a match arm binding the fields of a variant. Fields for which skip() is true are
+// not bound:
+//
+// Message::Move { x, y: _ }
+// Message::ChangeColor(f0, _, f2)
+pub(crate) fn variant_pattern(
+    enum_name: &Ident,
+    v: &Variant,
+    fields: &[VariantField],
+    skip: impl Fn(&FieldAttrs) -> bool,
+) -> TokenStream {
+    let variant_ident = &v.ident;
+
+    let bindings = fields.iter().map(|vf| {
+        let binding = &vf.binding;
+        match (&vf.field.ident, skip(&vf.attrs)) {
+            (Some(ident), true) => quote!(#ident: _),
+            (Some(ident), false) => quote!(#ident),
+            (None, true) => quote!(_),
+            (None, false) => quote!(#binding),
+        }
+    });
+
+    match &v.fields {
+        Fields::Named(_) => quote!(#enum_name::#variant_ident { #( #bindings ),* }),
+        Fields::Unnamed(_) => quote!(#enum_name::#variant_ident( #( #bindings ),* )),
+        Fields::Unit => quote!(#enum_name::#variant_ident),
+    }
+}
+
 pub mod from;
 pub mod to;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn discriminants() {
+        let e: syn::DeriveInput = parse_quote!(
+            enum Color {
+                Black,
+                White = 3,
+                Yellow,
+                Brown = -(5),
+                Blue = FOO,
+                Red,
+                Green = 8,
+                #[cfg(feature = "maybe")]
+                Purple,
+                Orange,
+            }
+        );
+        let syn::Data::Enum(de) = &e.data else {
+            unreachable!()
+        };
+
+        assert_eq!(
+            discriminant_values(de),
+            vec![
+                Some(0),
+                Some(3),
+                Some(4),
+                Some(-5),
+                None,
+                None,
+                Some(8),
+                Some(9),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn discriminants_range() {
+        let e: syn::DeriveInput = parse_quote!(
+            enum Code {
+                A = 254,
+                B,
+                C,
+                D = -1,
+                E = FOO,
+            }
+        );
+        let syn::Data::Enum(de) = &e.data else {
+            unreachable!()
+        };
+
+        let e = check_discriminants(de, &quote!(u8)).unwrap_err();
+        let messages: Vec<_> = e.into_iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "discriminant 256 of variant `C` doesn't fit in u8 (0..=255)",
+                "discriminant -1 of variant `D` doesn't fit in u8 (0..=255)"
+            ]
+        );
+        assert!(check_discriminants(de, &quote!(i16)).is_ok());
+        assert!(check_discriminants(de, &quote!(MyType)).is_ok());
+    }
+}
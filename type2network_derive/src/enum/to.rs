@@ -1,49 +1,124 @@
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{DataEnum, DeriveInput, Fields, Ident, Variant};
 
+use crate::attrs::{ContainerAttrs, FieldAttrs, ToAttrKind};
 use crate::syn_utils::*;
 
-use super::EnumDeriveBuilder;
+use super::{
+    check_discriminants, discriminant_tokens, variant_fields, variant_pattern, variant_types,
+    EnumDeriveBuilder,
+};
 
 impl EnumDeriveBuilder {
     pub fn to_network(ast: &DeriveInput, de: &DataEnum) -> proc_macro2::TokenStream {
         let enum_name = &ast.ident;
-        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+        // type parameters used in variant fields need to be serializable
+        let generics = add_bounds(&ast.generics, variant_types(de), quote!(ToNetworkOrder));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        // analyze attributes of the enum
+        let container_attrs = match ContainerAttrs::new(&ast.attrs) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error(),
+        };
+        let skip = container_attrs.skip_code(quote!(Ok(0)));
+
+        if container_attrs.hexdebug || container_attrs.buf || container_attrs.pad_block.is_some() {
+            let attr = if container_attrs.hexdebug {
+                "hexdebug"
+            } else if container_attrs.buf {
+                "buf"
+            } else {
+                "pad_block"
+            };
+            return syn::Error::new_spanned(
+                enum_name,
+                format!("#[network({})] is only supported on structs", attr),
+            )
+            .to_compile_error();
+        }
 
         // we need the repr size to build the arms
         // get the type inside #[repr()]
         let ty = SynUtils::repr_size(&ast.attrs);
+        if let Some(Err(e)) = ty.as_ref().map(|ty| check_discriminants(de, ty)) {
+            return e.to_compile_error();
+        }
 
         // if all variants are unit, the serialize_from() method is straightforward
         let code = if de.variants.iter().all(|x| x.fields == Fields::Unit) {
             if ty.is_none() {
                 unimplemented!("repr size is mandatory on enum {}", enum_name);
             }
-            let code = build_unit_arms(&ty.unwrap());
+            let ty = ty.unwrap();
+            let code = build_unit_arms(enum_name, de, &ty, &quote!(buffer));
+            let write_code = build_unit_arms(enum_name, de, &ty, &quote!(writer));
+
+            // #[network(values)]: the table of values
+            let values = if container_attrs.values {
+                build_values(enum_name, de, &ty)
+            } else {
+                quote!()
+            };
 
             quote! {
                 impl #impl_generics ToNetworkOrder for #enum_name #ty_generics #where_clause {
                     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                        #skip
                         #code
                     }
+
+                    // the discriminant is written directly to the writer
+                    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                        #skip
+                        #write_code
+                    }
                 }
+
+                #values
             }
+        } else if container_attrs.values {
+            return syn::Error::new_spanned(
+                enum_name,
+                "#[network(values)] is only supported on unit-only enums",
+            )
+            .to_compile_error();
         } else {
             // in this case of a mixed enum, no mandatory #[repr] attribute
-            let arms = de
-                .variants
-                .iter()
-                .map(|v| build_variant_arm(enum_name, v, &ty));
+            let arms = |to_writer: bool| {
+                de.variants
+                    .iter()
+                    .zip(discriminant_tokens(de))
+                    .map(|(v, d)| build_variant_arm(enum_name, v, d, &ty, to_writer))
+                    .collect::<syn::Result<Vec<_>>>()
+            };
+            let (arms, write_arms) = match (arms(false), arms(true)) {
+                (Ok(arms), Ok(write_arms)) => (arms, write_arms),
+                (Err(e), _) | (_, Err(e)) => return e.to_compile_error(),
+            };
 
             quote! {
                 impl #impl_generics ToNetworkOrder for #enum_name #ty_generics #where_clause {
                     fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                        #skip
                         let mut length = 0usize;
                         match self {
                             #( #arms)*
                         }
                     }
+
+                    // the discriminant and the fields are written directly to the writer
+                    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                        #skip
+                        type2network::align::track_writes(writer, |writer| {
+                            let mut length = 0usize;
+                            match self {
+                                #( #write_arms)*
+                            }
+                        })
+                    }
                 }
             }
         };
@@ -52,24 +127,131 @@ impl EnumDeriveBuilder {
     }
 }
 
-fn build_unit_arms(ty: &TokenStream) -> proc_macro2::TokenStream {
-    match ty.to_string().as_str() {
-        "u8" => quote!(buffer.write_u8(*self as u8)?; Ok(1)),
-        "i8" => quote!(buffer.write_i8(*self as i8)?; Ok(1)),
-        _ => {
-            let method = format_ident!("write_{}", ty.to_string());
-            quote!(buffer.#method::<BigEndian>(*self as #ty)?; Ok(std::mem::size_of::<#ty>()))
+// Build the code for a unit-only enum. The discriminant is extracted using a match, so that
+// the enum doesn't need to be Copy:
+//
+// let value = match self {
+//     Boolean::True => { const DISCRIMINANT: u8 = 0; DISCRIMINANT }
+//     Boolean::False => { const DISCRIMINANT: u8 = 1; DISCRIMINANT }
+// };
+fn build_unit_arms(
+    enum_name: &Ident,
+    de: &DataEnum,
+    ty: &TokenStream,
+    out: &TokenStream,
+) -> proc_macro2::TokenStream {
+    let arms = discriminant_arms(enum_name, de, ty);
+    let write = build_write(&quote!(value), ty, out);
+
+    quote! {
+        let value = match self {
+            #( #arms )*
+        };
+        #write
+    }
+}
+
+// the arms of the match giving the discriminant of each variant
+fn discriminant_arms<'a>(
+    enum_name: &'a Ident,
+    de: &'a DataEnum,
+    ty: &'a TokenStream,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    de.variants
+        .iter()
+        .zip(discriminant_tokens(de))
+        .map(move |(v, d)| {
+            let variant_ident = &v.ident;
+
+            // keep #[cfg] attributes to be able to use cfg'd variants
+            let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+
+            // an implicit discriminant following an expression can't be evaluated: the cast of the
+            // variant to its repr is exact
+            let value = match d {
+                Some(d) => checked_discriminant(&d, ty),
+                None => quote!(#enum_name::#variant_ident as #ty),
+            };
+
+            quote! {
+                #( #cfgs )*
+                #enum_name::#variant_ident => #value,
+            }
+        })
+}
+
+// the discriminant as a constant of the repr type, instead of an `as` cast truncating it: a value
+// not fitting in the type, or an expression of another type, is a compile error
+fn checked_discriminant(discriminant: &TokenStream, ty: &TokenStream) -> TokenStream {
+    quote!({
+        const DISCRIMINANT: #ty = #discriminant;
+        DISCRIMINANT
+    })
+}
+
+// Build the table of values of a unit-only enum for #[network(values)]:
+//
+// impl Boolean {
+//     pub const ALL: &'static [Self] = &[Boolean::True, Boolean::False];
+//     pub const fn wire_value(&self) -> u8 { match self { Boolean::True => { const DISCRIMINANT: u8 = 0; DISCRIMINANT } ... } }
+//     pub const fn from_wire_value(value: u8) -> Option<Self> {
+//         match value { x if x == Boolean::True as u8 => Some(Boolean::True), ... _ => None }
+//     }
+// }
+fn build_values(enum_name: &Ident, de: &DataEnum, ty: &TokenStream) -> proc_macro2::TokenStream {
+    let arms = discriminant_arms(enum_name, de, ty);
+
+    let all = de.variants.iter().map(|v| {
+        let variant_ident = &v.ident;
+        let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+        quote!(#( #cfgs )* #enum_name::#variant_ident)
+    });
+
+    let from_arms = de.variants.iter().map(|v| {
+        let variant_ident = &v.ident;
+        let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+        quote! {
+            #( #cfgs )*
+            x if x == #enum_name::#variant_ident as #ty => Some(#enum_name::#variant_ident),
+        }
+    });
+
+    quote! {
+        impl #enum_name {
+            /// All the variants, in declaration order.
+            pub const ALL: &'static [Self] = &[ #( #all ),* ];
+
+            /// Returns the value sent on the wire for this variant.
+            pub const fn wire_value(&self) -> #ty {
+                match self {
+                    #( #arms )*
+                }
+            }
+
+            /// Returns the variant sent on the wire as ```value```, or ```None``` if the value is unknown.
+            pub const fn from_wire_value(value: #ty) -> Option<Self> {
+                match value {
+                    #( #from_arms )*
+                    _ => None,
+                }
+            }
         }
     }
 }
 
-fn build_arms_from_literal(lit: &TokenStream, ty: &TokenStream) -> proc_macro2::TokenStream {
+// write the value of type ty to out, the buffer of serialize_to() or the writer of write_to(). The value is not
+// cast, so it must be of this type
+fn build_write(
+    value: &TokenStream,
+    ty: &TokenStream,
+    out: &TokenStream,
+) -> proc_macro2::TokenStream {
     match ty.to_string().as_str() {
-        "u8" => quote!(buffer.write_u8(#lit as u8)?; Ok(1)),
-        "i8" => quote!(buffer.write_i8(#lit as i8)?; Ok(1)),
+        "u8" => quote!(#out.write_u8(#value)?; Ok(1)),
+        "i8" => quote!(#out.write_i8(#value)?; Ok(1)),
         _ => {
             let method = format_ident!("write_{}", ty.to_string());
-            quote!(buffer.#method::<BigEndian>(#lit as #ty)?; Ok(std::mem::size_of::<#ty>()))
+            quote!(#out.#method::<BigEndian>(#value)?; Ok(std::mem::size_of::<#ty>()))
         }
     }
 }
@@ -95,7 +277,6 @@ fn build_arms_from_literal(lit: &TokenStream, ty: &TokenStream) -> proc_macro2::
 // Ex:
 //
 // Message::ChangeColor(f0, f1, f2) => {
-//        let mut length = 0usize ;
 //        length += ToNetworkOrder ::serialize_to(f0, buffer)?;
 //        length += ToNetworkOrder ::serialize_to(f1, buffer)?;
 //        length += ToNetworkOrder ::serialize_to(f2, buffer)?;
@@ -104,46 +285,45 @@ fn build_arms_from_literal(lit: &TokenStream, ty: &TokenStream) -> proc_macro2::
 fn build_variant_arm(
     enum_name: &Ident,
     variant: &Variant,
+    discriminant: Option<TokenStream>,
     ty: &Option<TokenStream>,
-) -> proc_macro2::TokenStream {
+    to_writer: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
     let variant_ident = &variant.ident;
+    let out = if to_writer {
+        quote!(writer)
+    } else {
+        quote!(buffer)
+    };
 
     match &variant.fields {
-        // unnamed variant like: ChangeColor(i32, i32, i32)
-        Fields::Unnamed(_) => {
-            let field_names = (0..variant.fields.len())
-                .map(|i| Ident::new(&format!("f{}", i), Span::call_site()));
-
-            let method_calls = field_names.clone().map(|f| {
-                quote! {
-                    length += ToNetworkOrder::serialize_to(#f, buffer)?;
-                }
-            });
+        // variant like: ChangeColor(i32, i32, i32) or Move { x: i32, y: i32 }
+        Fields::Unnamed(_) | Fields::Named(_) => {
+            let fields = variant_fields(variant)?;
 
-            quote! {
-                #enum_name::#variant_ident(#(#field_names),*) => {
-                    #( #method_calls)*
-                    Ok(length)
-                },
-            }
-        }
-        // named variant like: Move { x: i32, y: i32 }
-        Fields::Named(_) => {
-            let members = variant.fields.iter().map(|f| &f.ident);
+            // #[network(to(ignore))]: the field is not serialized
+            let ignored = |attrs: &FieldAttrs| matches!(attrs.to, ToAttrKind::NoAction);
+            let pattern = variant_pattern(enum_name, variant, &fields, ignored);
 
-            let method_calls = members.clone().map(|f| {
-                quote! {
-                    length += ToNetworkOrder::serialize_to(#f, buffer)?;
+            let method_calls = fields.iter().filter(|vf| !ignored(&vf.attrs)).map(|vf| {
+                let binding = &vf.binding;
+                if to_writer {
+                    quote! {
+                        length += ToNetworkOrder::write_to(#binding, writer)?;
+                    }
+                } else {
+                    quote! {
+                        length += ToNetworkOrder::serialize_to(#binding, buffer)?;
+                    }
                 }
             });
 
-            quote! {
-                #enum_name::#variant_ident{ #(#members),* } => {
-                    let mut length = 0usize;
+            Ok(quote! {
+                #pattern => {
                     #( #method_calls)*
                     Ok(length)
                 },
-            }
+            })
         }
         // unit variant like: Quit = 1
         Fields::Unit => {
@@ -151,12 +331,14 @@ fn build_variant_arm(
                 unimplemented!("repr size is mandatory on enum {}", enum_name);
             }
 
-            let lit = variant.literal();
-            let code = build_arms_from_literal(&lit, ty.as_ref().unwrap());
+            // explicit or implicit discriminant
+            let ty = ty.as_ref().unwrap();
+            let lit = discriminant.unwrap_or_else(|| variant.literal());
+            let code = build_write(&checked_discriminant(&lit, ty), ty, &out);
 
-            quote!(
+            Ok(quote!(
                 #enum_name::#variant_ident => { #code }
-            )
+            ))
         }
     }
 }
@@ -165,14 +347,36 @@ fn build_variant_arm(
 mod tests {
     use super::*;
 
+    use syn::parse_quote;
+
     #[test]
-    fn unit_arms() {
+    fn write() {
         let ty = quote!(u8);
-        let res = build_unit_arms(&ty).to_string();
-        assert_eq!(res, "buffer . write_u8 (* self as u8) ? ; Ok (1)");
+        let res = build_write(&quote!(value), &ty, &quote!(buffer)).to_string();
+        assert_eq!(res, "buffer . write_u8 (value) ? ; Ok (1)");
 
         let ty = quote!(u32);
-        let res = build_unit_arms(&ty).to_string();
-        assert_eq!(res, "buffer . write_u32 :: < BigEndian > (* self as u32) ? ; Ok (std :: mem :: size_of :: < u32 > ())");
+        let res = build_write(&quote!(value), &ty, &quote!(writer)).to_string();
+        assert_eq!(res, "writer . write_u32 :: < BigEndian > (value) ? ; Ok (std :: mem :: size_of :: < u32 > ())");
+    }
+
+    #[test]
+    fn unit_arms() {
+        let e: syn::DeriveInput = parse_quote!(
+            enum Boolean {
+                True,
+                #[cfg(feature = "maybe")]
+                Maybe,
+            }
+        );
+        let syn::Data::Enum(de) = &e.data else {
+            unreachable!()
+        };
+
+        let res = build_unit_arms(&e.ident, de, &quote!(u8), &quote!(buffer)).to_string();
+        assert_eq!(
+            res,
+            "let value = match self { Boolean :: True => { const DISCRIMINANT : u8 = 0 ; DISCRIMINANT } , # [cfg (feature = \"maybe\")] Boolean :: Maybe => { const DISCRIMINANT : u8 = 1 ; DISCRIMINANT } , } ; buffer . write_u8 (value) ? ; Ok (1)"
+        );
     }
 }
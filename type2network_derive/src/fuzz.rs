@@ -0,0 +1,161 @@
+// the FuzzTokens impl generated for #[network(fuzz)], added to the FromNetwork derive: the bytes of
+// the magic and expected values of a struct, or of the discriminants of an enum. Ex: for
+//
+// #[network(fuzz)]
+// struct Header {
+//     #[network(magic = 0xA1B2C3D4)]
+//     magic: u32,
+//     #[network(expect = 2)]
+//     version: u8,
+//     length: u16,
+// }
+//
+// impl type2network::fuzz::FuzzTokens for Header {
+//     fn fuzz_tokens() -> Vec<(&'static str, Vec<u8>)> {
+//         let mut tokens = Vec::new();
+//         tokens.push(("Header_magic", type2network::fuzz::token::<u32>(0xA1B2C3D4)));
+//         tokens.push(("Header_version", type2network::fuzz::token::<u8>(2)));
+//         tokens
+//     }
+// }
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput};
+
+use crate::attrs::{ContainerAttrs, FieldAttrs};
+use crate::r#enum::discriminant_tokens;
+use crate::r#enum::from::find_fallback;
+use crate::syn_utils::SynUtils;
+
+pub(crate) fn fuzz_tokens(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    match ContainerAttrs::new(&ast.attrs) {
+        Ok(attrs) if attrs.fuzz => (),
+        _ => return quote!(),
+    }
+
+    match build_fuzz_tokens(ast) {
+        Ok(code) => code,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn build_fuzz_tokens(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let mut generics = ast.generics.clone();
+    let mut statements = Vec::new();
+
+    match &ast.data {
+        Data::Struct(ds) => {
+            for (index, field) in ds.fields.iter().enumerate() {
+                let attrs = FieldAttrs::new(&field.attrs)?;
+                let Some(value) = attrs.magic.or(attrs.expect) else {
+                    continue;
+                };
+
+                let token_name = match &field.ident {
+                    Some(ident) => {
+                        format!("{}_{}", name, ident.to_string().trim_start_matches("r#"))
+                    }
+                    None => format!("{}_{}", name, index),
+                };
+                let ty = &field.ty;
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote!(#ty: type2network::ToNetworkOrder));
+                statements.push(
+                    quote!(tokens.push((#token_name, type2network::fuzz::token::<#ty>(#value)));),
+                );
+            }
+        }
+        Data::Enum(de) => {
+            let Some(ty) = SynUtils::repr_size(&ast.attrs) else {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "#[network(fuzz)] requires a #[repr] on enums",
+                ));
+            };
+
+            // the fallback variant, and the variants whose discriminant can't be computed, are left out
+            let fallback = find_fallback(ast, de).map(|v| &v.ident);
+            for (v, discriminant) in de.variants.iter().zip(discriminant_tokens(de)) {
+                let Some(discriminant) = discriminant else {
+                    continue;
+                };
+                if Some(&v.ident) == fallback {
+                    continue;
+                }
+
+                let token_name = format!("{}_{}", name, v.ident);
+                let cfgs = v.attrs.iter().filter(|a| a.path().is_ident("cfg"));
+                statements.push(quote! {
+                    #( #cfgs )*
+                    tokens.push((#token_name, type2network::fuzz::token::<#ty>(#discriminant)));
+                });
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "#[network(fuzz)] is only supported on structs and enums",
+            ))
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics type2network::fuzz::FuzzTokens for #name #ty_generics #where_clause {
+            fn fuzz_tokens() -> Vec<(&'static str, Vec<u8>)> {
+                #[allow(unused_mut)]
+                let mut tokens = Vec::new();
+                #( #statements )*
+                tokens
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzz() {
+        let ast: DeriveInput = parse_quote!(
+            #[network(fuzz)]
+            #[repr(u8)]
+            enum Kind {
+                A = 1,
+                B,
+                #[network(fallback)]
+                Other(u8),
+            }
+        );
+        let code = fuzz_tokens(&ast).to_string();
+        assert!(code.contains("\"Kind_A\" , type2network :: fuzz :: token :: < u8 > (1)"));
+        assert!(code.contains("\"Kind_B\" , type2network :: fuzz :: token :: < u8 > (2)"));
+        assert!(!code.contains("Kind_Other"));
+
+        let ast: DeriveInput = parse_quote!(
+            #[network(fuzz)]
+            struct Header(#[network(magic = 0xCAFE)] u16, u8);
+        );
+        let code = fuzz_tokens(&ast).to_string();
+        assert!(code.contains("\"Header_0\" , type2network :: fuzz :: token :: < u16 > (0xCAFE)"));
+
+        // no repr
+        let ast: DeriveInput = parse_quote!(
+            #[network(fuzz)]
+            enum Kind {
+                A,
+            }
+        );
+        assert!(fuzz_tokens(&ast).to_string().contains("compile_error"));
+
+        // not asked
+        let ast: DeriveInput = parse_quote!(
+            struct Header(u16);
+        );
+        assert!(fuzz_tokens(&ast).is_empty());
+    }
+}
@@ -8,32 +8,92 @@ use r#struct::{StructBuilderFunc, StructDeriveBuilder};
 mod r#enum;
 use r#enum::{EnumBuilderFunc, EnumDeriveBuilder};
 
+mod attrs;
+mod builder;
+#[cfg(feature = "debug-codegen")]
+mod codegen;
+mod compat;
+mod convert;
+mod fuzz;
+mod message;
 mod syn_utils;
+mod view;
+mod wire_default;
 
-#[proc_macro_derive(ToNetwork)]
+// with the compat feature, the deku and binrw attributes are accepted as well
+#[cfg_attr(
+    feature = "compat",
+    proc_macro_derive(ToNetwork, attributes(network, deku, br, bw, brw))
+)]
+#[cfg_attr(
+    not(feature = "compat"),
+    proc_macro_derive(ToNetwork, attributes(network))
+)]
 pub fn to_network(input: TokenStream) -> TokenStream {
     derive_helper(
+        "ToNetwork",
         input,
         Some(EnumDeriveBuilder::to_network),
         StructDeriveBuilder::to_network,
+        |ast| {
+            let to_vec = convert::to_vec(ast);
+            let wire_digest = convert::wire_digest(ast);
+            quote::quote!(#to_vec #wire_digest)
+        },
         false,
     )
 }
 
-#[proc_macro_derive(FromNetwork, attributes(from_network))]
+#[cfg_attr(
+    feature = "compat",
+    proc_macro_derive(
+        FromNetwork,
+        attributes(network, from_network, deser, deku, br, bw, brw)
+    )
+)]
+#[cfg_attr(
+    not(feature = "compat"),
+    proc_macro_derive(FromNetwork, attributes(network, from_network, deser))
+)]
 pub fn from_network(input: TokenStream) -> TokenStream {
     derive_helper(
+        "FromNetwork",
         input,
         Some(EnumDeriveBuilder::from_network),
         StructDeriveBuilder::from_network,
+        |ast| {
+            let try_from_bytes = convert::try_from_bytes(ast);
+            let fuzz_tokens = fuzz::fuzz_tokens(ast);
+            quote::quote!(#try_from_bytes #fuzz_tokens)
+        },
         false,
     )
 }
 
+#[proc_macro_derive(NetworkMessage, attributes(network))]
+pub fn network_message(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    message::network_message(&ast).into()
+}
+
+#[proc_macro_derive(NetworkView, attributes(network))]
+pub fn network_view(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    view::network_view(&ast).into()
+}
+
+#[proc_macro_derive(WireDefault, attributes(network))]
+pub fn wire_default(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    wire_default::wire_default(&ast).into()
+}
+
 fn derive_helper(
+    derive: &str,
     input: TokenStream,
     enum_builder: Option<EnumBuilderFunc>,
     struct_builder: StructBuilderFunc,
+    convert: fn(&DeriveInput) -> proc_macro2::TokenStream,
     print_code: bool,
 ) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -47,9 +107,22 @@ fn derive_helper(
         _ => unimplemented!("{} is neither a struct, nor an enum", ast.ident.to_string()),
     };
 
+    // #[network(convert)]: std conversions are added
+    let conversions = convert(&ast);
+    let code = quote::quote! {
+        #code
+        #conversions
+    };
+
     if print_code {
         println!("{}", code);
     }
 
+    // with the debug-codegen feature, the code is also written to OUT_DIR
+    #[cfg(feature = "debug-codegen")]
+    codegen::write_code(derive, &ast, &code);
+    #[cfg(not(feature = "debug-codegen"))]
+    let _ = derive;
+
     code.into()
 }
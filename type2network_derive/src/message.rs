@@ -0,0 +1,33 @@
+// the NetworkMessage derive: only the constants are generated, the ToNetworkOrder and
+// FromNetworkOrder traits should be derived or implemented separately
+use quote::quote;
+use syn::DeriveInput;
+
+use crate::attrs::ContainerAttrs;
+
+pub(crate) fn network_message(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let name_string = name.to_string();
+
+    let container_attrs = match ContainerAttrs::new(&ast.attrs) {
+        Ok(attrs) => attrs,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    let Some(tag) = container_attrs.tag else {
+        return syn::Error::new_spanned(
+            name,
+            "#[network(tag = N)] is mandatory to derive NetworkMessage",
+        )
+        .to_compile_error();
+    };
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics type2network::NetworkMessage for #name #ty_generics #where_clause {
+            const TYPE_TAG: u32 = #tag;
+            const NAME: &'static str = #name_string;
+        }
+    }
+}
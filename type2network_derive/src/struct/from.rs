@@ -1,11 +1,19 @@
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{
-    parenthesized, token, AttrStyle, Attribute, DataStruct, DeriveInput, Field, Ident, Index,
+    parse_quote_spanned, spanned::Spanned, DataStruct, DeriveInput, Expr, ExprLit, Ident, Lit,
+    Member, Type,
 };
 
-use crate::{r#struct::is_unit, syn_utils::add_lifetime};
+use crate::{
+    attrs::{deprecation_warnings, AttrKind, ContainerAttrs, DebugFormat, FieldAttrs},
+    r#struct::{
+        byte_order_field, check_pod, check_zerocopy, is_unit, pod_layout_check, wire_fields,
+        zerocopy_layout_check, WireField,
+    },
+    syn_utils::{add_lifetime, default_type, default_value, error_context},
+};
 
-use super::{AttrKind, StructDeriveBuilder};
+use super::StructDeriveBuilder;
 
 impl StructDeriveBuilder {
     pub fn from_network(ast: &DeriveInput, ds: &DataStruct) -> proc_macro2::TokenStream {
@@ -16,149 +24,726 @@ impl StructDeriveBuilder {
 
         let struct_name = &ast.ident;
 
-        // call deserialize_from() call for each field
-        let method_calls = ds.fields.iter().enumerate().map(|field| {
-            match &field.1.ident {
-                // case of a struct with named fields
-                Some(_) => process_named_field(field.1),
-                // case of a tuple struct
-                None => {
-                    let index = Index::from(field.0);
-                    quote! {
-                        FromNetworkOrder::deserialize_from(&mut self.#index, buffer)?;
+        // analyze attributes of the struct
+        let container_attrs = match ContainerAttrs::new(&ast.attrs) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error(),
+        };
+        let skip = container_attrs.skip_code(quote!(Ok(())));
+
+        // #[network(pad_block = N)]: the padding after the last field is skipped
+        let pad_block = match &container_attrs.pad_block {
+            Some(block) => {
+                let expected = container_attrs.pad.expected();
+                let skip = quote! {
+                    type2network::align::skip_block_padding(buffer, (buffer.position() - __start) as usize, #block, #expected)?;
+                };
+                container_attrs
+                    .pad
+                    .report_skipped(skip, &struct_name.to_string())
+            }
+            None => quote!(),
+        };
+
+        // #[network(pod)]: the struct is copied as is
+        if container_attrs.pod {
+            if let Err(e) = check_pod(ast, ds, &container_attrs) {
+                return e.to_compile_error();
+            }
+            let layout_check = pod_layout_check(ast, ds);
+
+            return quote! {
+                #layout_check
+
+                // SAFETY: the layout is checked above
+                unsafe impl type2network::endian::Overlay for #struct_name {}
+
+                impl<'a> FromNetworkOrder<'a> for #struct_name {
+                    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                        let size = std::mem::size_of::<Self>();
+
+                        // SAFETY: the struct only holds bytes and has no padding (checked at compile time),
+                        // so any byte written is a valid value
+                        let bytes = unsafe { std::slice::from_raw_parts_mut((self as *mut Self).cast::<u8>(), size) };
+                        std::io::Read::read_exact(buffer, bytes)
                     }
                 }
+            };
+        }
+
+        // #[network(zerocopy)]: the bytes of the struct are copied using the zerocopy traits
+        if container_attrs.zerocopy {
+            if let Err(e) = check_zerocopy(ast, ds, &container_attrs) {
+                return e.to_compile_error();
             }
+            let layout_check = zerocopy_layout_check(ds);
+
+            return quote! {
+                #layout_check
+
+                impl<'a> FromNetworkOrder<'a> for #struct_name {
+                    fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                        *self = type2network::zerocopy::read_bytes(buffer)?;
+                        Ok(())
+                    }
+                }
+            };
+        }
+
+        // analyze attributes of all fields and get them in wire order
+        let fields = match wire_fields(ds) {
+            Ok(fields) => fields,
+            Err(e) => return e.to_compile_error(),
+        };
+
+        // call deserialize_from() call for each field
+        let method_calls = fields.iter().enumerate().map(|(pos, wf)| {
+            // fields marked with #[network(header)] already deserialized
+            let headers: Vec<_> = fields[..pos]
+                .iter()
+                .filter(|f| f.attrs.header)
+                .filter_map(|f| f.field.ident.as_ref())
+                .collect();
+
+            // #[network(byte_order)]: the field is read in the byte order detected
+            let adjust = byte_order_field(&fields, pos).is_some();
+
+            process_field(wf, &headers, struct_name, adjust)
         });
 
+        // warn if deprecated attributes are used
+        let warnings = deprecation_warnings(fields.iter().flat_map(|wf| &wf.attrs.deprecated));
+
+        // build the value directly when all fields are overwritten anyway
+        let new_method =
+            if container_attrs.skip_all_if.is_none() && container_attrs.pad_block.is_none() {
+                build_new(&fields, struct_name)
+            } else {
+                quote!()
+            };
+
+        // #[network(buf)]: FromBuf is implemented too
+        let buf_impl = if container_attrs.buf {
+            if container_attrs.pad_block.is_some() {
+                return syn::Error::new_spanned(
+                    struct_name,
+                    "#[network(pad_block)] is not supported with #[network(buf)]",
+                )
+                .to_compile_error();
+            }
+
+            match build_from_buf(ast, &fields, &skip) {
+                Ok(code) => code,
+                Err(e) => return e.to_compile_error(),
+            }
+        } else {
+            quote!()
+        };
+
         // add lifetime specific to our trait ('a)
         let (_, ty_generics, where_clause) = ast.generics.split_for_impl();
         let gen_clone = add_lifetime(ast);
         let (new_impl_generics, _, _) = gen_clone.split_for_impl();
 
+        // a magic first on the wire identifies the messages when resynchronizing a stream
+        let wire_magic = build_wire_magic(ast, &fields);
+
+        // #[network(skip)]: the runtime-only fields can be given when deserializing
+        let wire_parts = if fields.iter().any(|wf| wf.attrs.skip.is_some()) {
+            build_from_wire_parts(ast, &fields)
+        } else {
+            quote!()
+        };
+
         quote! {
             impl #new_impl_generics FromNetworkOrder<'a> for #struct_name #ty_generics #where_clause {
                 fn deserialize_from(&mut self, buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<()> {
+                    #skip
+                    #[allow(unused_variables)]
+                    let __start = buffer.position();
+                    #[allow(unused_mut, unused_variables)]
+                    let mut __byte_order = type2network::byte_order::ByteOrder::Big;
                     #( #method_calls)*
+                    #pad_block
                     Ok(())
                 }
+
+                #new_method
             }
+
+            #wire_parts
+            #wire_magic
+            #buf_impl
+            #warnings
         }
     }
 }
 
-// in case of a named field, process potential attribute and inject code
-fn process_named_field(field: &Field) -> proc_macro2::TokenStream {
-    let field_name = field.ident.as_ref().unwrap();
+// Implement WireMagic when the first field on the wire has the #[network(magic = value)] attribute:
+//
+// impl type2network::resync::WireMagic for PcapHeader {
+//     fn wire_magic() -> Vec<u8> {
+//         let magic: u32 = 0xA1B2C3D4;
+//         let mut bytes = Vec::new();
+//         let _ = type2network::ToNetworkOrder::serialize_to(&magic, &mut bytes);
+//         bytes
+//     }
+// }
+fn build_wire_magic(ast: &DeriveInput, fields: &[WireField]) -> proc_macro2::TokenStream {
+    let Some((wf, magic)) = fields
+        .first()
+        .and_then(|wf| wf.attrs.magic.as_ref().map(|magic| (wf, magic)))
+    else {
+        return quote!();
+    };
+
+    let struct_name = &ast.ident;
+    let ty = &wf.field.ty;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    //find the attribute containing #[deser]
-    let from_attr = field
-        .attrs
+    quote! {
+        impl #impl_generics type2network::resync::WireMagic for #struct_name #ty_generics #where_clause {
+            fn wire_magic() -> Vec<u8> {
+                let magic: #ty = #magic;
+                let mut bytes = Vec::new();
+                let _ = type2network::ToNetworkOrder::serialize_to(&magic, &mut bytes);
+                bytes
+            }
+        }
+    }
+}
+
+// Build the from_wire_parts() constructor of a struct having fields marked with #[network(skip)]. These
+// runtime-only fields are given in declaration order, the other ones being deserialized:
+//
+// impl Session {
+//     pub fn from_wire_parts<'a>(extra: (Handle,), buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<Self> {
+//         let (__e0,) = extra;
+//         let mut value = Self { id: Default::default(), handle: __e0 };
+//
+// (the arrays being built with std::array::from_fn())
+//         FromNetworkOrder::deserialize_from(&mut value, buffer)?;
+//         Ok(value)
+//     }
+// }
+fn build_from_wire_parts(ast: &DeriveInput, fields: &[WireField]) -> proc_macro2::TokenStream {
+    let struct_name = &ast.ident;
+
+    // the 'a lifetime is given to the method, unless the struct already has it
+    let has_lifetime = ast.generics.lifetimes().any(|l| l.lifetime.ident == "a");
+    let lifetime = if has_lifetime { quote!() } else { quote!(<'a>) };
+
+    // the fields are given in declaration order
+    let mut sorted: Vec<_> = fields.iter().collect();
+    sorted.sort_by_key(|wf| wf.index);
+
+    let skipped: Vec<_> = sorted.iter().filter(|wf| wf.attrs.skip.is_some()).collect();
+    let types = skipped.iter().map(|wf| &wf.field.ty);
+    let vars: Vec<_> = skipped
         .iter()
-        .find(|attr| attr.path().is_ident("from_network"));
+        .map(|wf| format_ident!("__e{}", wf.index))
+        .collect();
 
-    // analyze attribute
-    let kind = if let Some(deser) = from_attr {
-        process_attr(deser)
-    } else {
-        AttrKind::NoAttribute
+    // the wire fields are overwritten when deserialized, so they only need to implement Default
+    let mut generics = ast.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for wf in sorted.iter().filter(|wf| wf.attrs.skip.is_none()) {
+        let ty = default_type(&wf.field.ty);
+        where_clause
+            .predicates
+            .push(parse_quote_spanned!(ty.span()=> #ty: Default));
+    }
+    let (impl_generics, ty_generics, _) = ast.generics.split_for_impl();
+
+    let values = sorted.iter().map(|wf| {
+        if wf.attrs.skip.is_some() {
+            let var = format_ident!("__e{}", wf.index);
+            quote!(#var)
+        } else {
+            default_value(&wf.field.ty)
+        }
+    });
+    let value = match sorted.first().and_then(|wf| wf.field.ident.as_ref()) {
+        Some(_) => {
+            let names = sorted.iter().map(|wf| wf.field.ident.as_ref().unwrap());
+            quote!(Self { #( #names: #values ),* })
+        }
+        None => quote!(Self( #( #values ),* )),
     };
 
-    // no return code depending on attribute
-    match kind {
-        // no attribute provided: just deserialize the field as other ones
-        AttrKind::NoAttribute => {
+    let doc = format!(
+        "Deserializes a new ```{}``` from the buffer, the fields marked with ```#[network(skip)]``` being given by ```extra``` in declaration order.",
+        struct_name
+    );
+
+    quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            #[doc = #doc]
+            #[allow(dead_code)]
+            pub fn from_wire_parts #lifetime (extra: ( #( #types, )* ), buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<Self> {
+                let ( #( #vars, )* ) = extra;
+                let mut value = #value;
+                FromNetworkOrder::deserialize_from(&mut value, buffer)?;
+                Ok(value)
+            }
+        }
+    }
+}
+
+// Build the deserialize_new() method when all fields are primitive types without any attribute:
+// their default values are always overwritten, so the value can be built without them:
+//
+// fn deserialize_new(buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<Self> {
+//     let __f0 = FromNetworkOrder::deserialize_new(buffer).map_err(...)?;
+//     let __f1 = FromNetworkOrder::deserialize_new(buffer).map_err(...)?;
+//     Ok(Self { x: __f0, y: __f1 })
+// }
+fn build_new(fields: &[WireField], struct_name: &Ident) -> proc_macro2::TokenStream {
+    let plain = fields.iter().all(|wf| {
+        matches!(wf.attrs.from, AttrKind::NoAttribute)
+            && !wf.attrs.byte_order
+            && wf.attrs.count.is_none()
+            && wf.attrs.mask.is_none()
+            && wf.attrs.utf8.is_none()
+            && !wf.attrs.char_as_utf8
+            && wf.attrs.magic.is_none()
+            && wf.attrs.align.is_none()
+            && is_primitive(&wf.field.ty)
+    });
+    if !plain {
+        return quote!();
+    }
+
+    // fields are read in wire order
+    let reads = fields.iter().map(|wf| {
+        let var = format_ident!("__f{}", wf.index);
+        let context = error_context(struct_name, &wf.name());
+        quote!(let #var = FromNetworkOrder::deserialize_new(buffer)#context?;)
+    });
+
+    // but the value is built in declaration order
+    let mut sorted: Vec<_> = fields.iter().collect();
+    sorted.sort_by_key(|wf| wf.index);
+    let vars = sorted.iter().map(|wf| format_ident!("__f{}", wf.index));
+
+    let value = match sorted.first().and_then(|wf| wf.field.ident.as_ref()) {
+        Some(_) => {
+            let names = sorted.iter().map(|wf| wf.field.ident.as_ref().unwrap());
+            quote!(Self { #( #names: #vars ),* })
+        }
+        None => quote!(Self( #( #vars ),* )),
+    };
+
+    quote! {
+        fn deserialize_new(buffer: &mut std::io::Cursor<&'a [u8]>) -> std::io::Result<Self> {
+            #( #reads )*
+            Ok(#value)
+        }
+    }
+}
+
+// true if the type is an integer, a float, a char or an array of those (arrays implement Default
+// up to 32 elements)
+fn is_primitive(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64", "char",
+    ];
+
+    match ty {
+        Type::Path(tp) => tp.qself.is_none() && PRIMITIVES.iter().any(|p| tp.path.is_ident(p)),
+        Type::Array(ta) => {
+            let len = match &ta.len {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(n), ..
+                }) => n.base10_parse::<usize>().ok(),
+                _ => None,
+            };
+            len.is_some_and(|n| n <= 32) && is_primitive(&ta.elem)
+        }
+        Type::Paren(tp) => is_primitive(&tp.elem),
+        _ => false,
+    }
+}
+
+// build the deserialize_from() call for a field, taking into account the number of
+// elements to read given by #[network(count = "expr")]. Errors are given the path of the field
+// by the context code
+fn deserialize_call(
+    field_name: &Member,
+    attrs: &FieldAttrs,
+    headers: &[&Ident],
+    context: &proc_macro2::TokenStream,
+    adjust: bool,
+) -> proc_macro2::TokenStream {
+    // #[network(utf8 = "mode")]: the string is read from the remaining bytes or count bytes
+    if let Some(mode) = &attrs.utf8 {
+        let length = match &attrs.count {
+            None => quote!(None),
+            Some(count) => quote!(Some((#count) as usize)),
+        };
+        return quote! {
+            {
+                // headers are accessible by their name in the count expression
+                #(
+                    #[allow(unused_variables)]
+                    let #headers = &self.#headers;
+                )*
+                self.#field_name = type2network::text::read_utf8(buffer, #length, #mode)#context?;
+            }
+        };
+    }
+
+    // #[network(char_as = "utf8")]: the char is read from its UTF-8 bytes
+    if attrs.char_as_utf8 {
+        return quote! {
+            self.#field_name = <type2network::text::Utf8Char as FromNetworkOrder>::deserialize_new(buffer)#context?.0;
+        };
+    }
+
+    match &attrs.count {
+        None if adjust => quote! {
+            FromNetworkOrder::deserialize_from(&mut self.#field_name, buffer)#context?;
+            __byte_order.adjust(&mut self.#field_name);
+        },
+        None => quote! {
+            FromNetworkOrder::deserialize_from(&mut self.#field_name, buffer)#context?;
+        },
+        Some(count) => quote! {
+            {
+                // headers are accessible by their name in the count expression
+                #(
+                    #[allow(unused_variables)]
+                    let #headers = &self.#headers;
+                )*
+                let count = (#count) as usize;
+                type2network::budget::take(count)#context?;
+
+                let __pushed = buffer.position();
+                type2network::vec::Push::clear(&mut self.#field_name);
+                for _ in 0..count {
+                    type2network::vec::Push::try_push(&mut self.#field_name, FromNetworkOrder::deserialize_new(buffer)#context?)#context?;
+                }
+
+                // the bytes of the collections passing them through are reported
+                if let Some(container) = type2network::vec::Push::pass_through(&self.#field_name) {
+                    if buffer.position() > __pushed {
+                        type2network::observer::report(__pushed, (buffer.position() - __pushed) as usize, container);
+                    }
+                }
+            }
+        },
+    }
+}
+
+// process potential attribute of a named or a tuple struct field and inject code. With
+// #[network(from(mask = "expr", bit = N))], the code is only run if the bit N of the mask is set,
+// otherwise the field keeps its current value:
+//
+// if ((self.field_mask) >> 3) & 1 != 0 {
+//     FromNetworkOrder::deserialize_from(&mut self.x, buffer)?;
+// }
+fn process_field(
+    wf: &WireField,
+    headers: &[&Ident],
+    struct_name: &Ident,
+    adjust: bool,
+) -> proc_macro2::TokenStream {
+    let code = field_code(wf, headers, struct_name, adjust);
+    let code = with_magic_check(code, wf, struct_name);
+
+    // #[network(align_from_parent = N)]: the padding bytes before the field are skipped
+    let code = match &wf.attrs.align {
+        Some(align) if !matches!(wf.attrs.from, AttrKind::NoAction) => {
+            let context = error_context(struct_name, &wf.name());
+            let expected = wf.attrs.pad.expected();
+            let skip = wf.attrs.pad.report_skipped(
+                quote!(type2network::align::skip_padding(buffer, #align, #expected)#context?;),
+                &struct_name.to_string(),
+            );
             quote! {
-                FromNetworkOrder::deserialize_from(&mut self.#field_name, buffer)?;
+                #skip
+                #code
             }
         }
+        _ => code,
+    };
+
+    // #[network(byte_order)]: the byte order detected is kept for the next fields
+    let code = if wf.attrs.byte_order {
+        let field_name = wf.member();
+        quote! {
+            #code
+            __byte_order = type2network::byte_order::DetectByteOrder::byte_order(&self.#field_name);
+        }
+    } else {
+        code
+    };
+
+    // record the offsets of the field for the diagnose feature
+    let code = if matches!(wf.attrs.from, AttrKind::NoAction) {
+        code
+    } else {
+        let type_name = struct_name.to_string();
+        let field_string = wf.name();
+        quote! {
+            let __field_start = buffer.position();
+            #code
+            type2network::record_field(#type_name, #field_string, __field_start, buffer.position());
+        }
+    };
+
+    match &wf.attrs.mask {
+        None => code,
+        Some((mask, bit)) => quote! {
+            if {
+                // headers are accessible by their name in the mask expression
+                #(
+                    #[allow(unused_variables)]
+                    let #headers = &self.#headers;
+                )*
+                ((#mask) >> #bit) & 1 != 0
+            } {
+                #code
+            }
+        },
+    }
+}
+
+// #[network(magic = value)]: the value read is compared to the magic
+//
+// if self.magic != 0xA1B2C3D4 {
+//     return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "unexpected magic value")).map_err(...);
+// }
+fn with_magic_check(
+    code: proc_macro2::TokenStream,
+    wf: &WireField,
+    struct_name: &Ident,
+) -> proc_macro2::TokenStream {
+    let Some(magic) = &wf.attrs.magic else {
+        return code;
+    };
+    let field_name = wf.member();
+    let context = error_context(struct_name, &wf.name());
+
+    quote! {
+        #code
+        if self.#field_name != #magic {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "unexpected magic value"))#context;
+        }
+    }
+}
+
+// the code deserializing a field, depending on its attributes
+fn field_code(
+    wf: &WireField,
+    headers: &[&Ident],
+    struct_name: &Ident,
+    adjust: bool,
+) -> proc_macro2::TokenStream {
+    let field_name = &wf.member();
+    let attrs = &wf.attrs;
+    let context = error_context(struct_name, &wf.name());
+    let call = deserialize_call(field_name, attrs, headers, &context, adjust);
 
-        // #[deser(ignore)]: don't do anything
+    // no return code depending on attribute
+    match &attrs.from {
+        // no attribute provided: just deserialize the field as other ones
+        AttrKind::NoAttribute => call,
+
+        // #[network(from(ignore))]: don't do anything
         AttrKind::NoAction => quote!(),
 
-        // a function was provided to the deser attribute: so just call it
-        // e.g.: #[deser(my_func)]
+        // a function was provided to the attribute: so just call it
+        // e.g.: #[network(from(with_fn(my_func)))]
         AttrKind::Call(func) => {
             quote! {
-                #func(self)?;
+                #func(self)#context?;
+            }
+        }
+
+        // the field is read by a nom parser
+        // e.g.: #[network(from(with_parser(parse_name)))]
+        AttrKind::Parser(parser) => {
+            quote! {
+                self.#field_name = type2network::nom::run_parser(buffer, #parser)#context?;
             }
         }
 
         // a block was provided
-        // #[deser({ self.z = 0xFFFF })]
-        AttrKind::Block(block) => quote!(
-            #block
-            FromNetworkOrder::deserialize_from(&mut self.#field_name, buffer)?;
-        ),
+        // #[network(from(with_code({ self.z = 0xFFFF })))]
+        // the block can use the buffer and ctx variables
+        AttrKind::Block(block) => {
+            let field_string = wf.name();
+            quote!(
+                #[allow(unused_variables)]
+                let ctx = type2network::DeserializationContext {
+                    type_name: std::any::type_name::<Self>(),
+                    field_name: #field_string,
+                    start: __start,
+                    byte_order: __byte_order,
+                };
+                #block
+                #call
+            )
+        }
 
         // debug is requested
-        // #[deser(debug)]
-        AttrKind::Debug => quote!(
-            FromNetworkOrder::deserialize_from(&mut self.#field_name, buffer)?;
-            dbg!(self.#field_name);
+        // #[network(from(debug))]
+        // only in debug builds
+        AttrKind::Debug(DebugFormat::Default) => quote!(
+            #call
+            #[cfg(debug_assertions)]
+            dbg!(&self.#field_name);
         ),
-    }
-}
 
-// process the #[from_network] attribute for all different cases
-fn process_attr(attr: &Attribute) -> AttrKind {
-    // outer attribute only
-    if attr.style != AttrStyle::Outer {
-        unimplemented!("attribute {:?} is not on outer attribute", attr);
-    }
+        // same as dbg! but values are printed in hexadecimal
+        // #[network(from(debug = "hex"))]
+        AttrKind::Debug(DebugFormat::Hex) => {
+            let field_string = wf.name();
+            quote!(
+                #call
+                #[cfg(debug_assertions)]
+                eprintln!("[{}:{}] self.{} = {:02X?}", file!(), line!(), #field_string, &self.#field_name);
+            )
+        }
 
-    // the only attribute we process is "deser"
-    if !attr.path().is_ident("from_network") {
-        unimplemented!("only #[from_network] is a valid attribute");
+        // the field value is logged at the trace level using the log crate
+        // #[network(from(trace))]
+        AttrKind::Trace => {
+            let field_string = wf.name();
+            quote!(
+                #call
+                type2network::log::trace!("{}.{} = {:?}", std::any::type_name::<Self>(), #field_string, self.#field_name);
+            )
+        }
     }
+}
 
-    let mut kind = AttrKind::default();
+// the FromBuf impl generated for #[network(buf)], reading from a bytes::Buf instead of a Cursor:
+//
+// impl FromBuf for Point {
+//     fn deserialize_from_buf<__B: type2network::buf::Buf>(&mut self, buf: &mut __B) -> std::io::Result<()> {
+//         type2network::buf::FromBuf::deserialize_from_buf(&mut self.x, buf)?;
+//         type2network::buf::FromBuf::deserialize_from_buf(&mut self.y, buf)?;
+//         Ok(())
+//     }
+// }
+fn build_from_buf(
+    ast: &DeriveInput,
+    fields: &[WireField],
+    skip: &proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &ast.ident;
+    let mut calls = Vec::new();
 
-    let _ = attr.parse_nested_meta(|meta| {
-        // #[deser(ignore)]
-        if meta.path.is_ident("ignore") {
-            kind = AttrKind::NoAction;
-            return Ok(());
+    for (pos, wf) in fields.iter().enumerate() {
+        if wf.attrs.byte_order {
+            return Err(syn::Error::new_spanned(
+                wf.field,
+                "#[network(byte_order)] is not supported with #[network(buf)]",
+            ));
         }
 
-        // #[deser(with_fn(function))]
-        if meta.path.is_ident("with_fn") {
-            if meta.input.peek(token::Paren) {
-                let content;
-                parenthesized!(content in meta.input);
+        if wf.attrs.utf8.is_some() || wf.attrs.char_as_utf8 {
+            return Err(syn::Error::new_spanned(
+                wf.field,
+                "#[network(utf8)] and #[network(char_as)] are not supported with #[network(buf)]",
+            ));
+        }
 
-                let function: Ident = content.parse()?;
-                kind = AttrKind::Call(function);
+        if wf.attrs.align.is_some() {
+            return Err(syn::Error::new_spanned(
+                wf.field,
+                "#[network(align_from_parent)] is not supported with #[network(buf)]",
+            ));
+        }
 
-                return Ok(());
-            }
+        let headers: Vec<_> = fields[..pos]
+            .iter()
+            .filter(|f| f.attrs.header)
+            .filter_map(|f| f.field.ident.as_ref())
+            .collect();
 
-            unimplemented!("malformed from_network(with_fn) attribute")
-        }
+        let field_name = wf.member();
+        let context = error_context(struct_name, &wf.name());
 
-        // #[deser(with_block({ let x = 9; }))]
-        if meta.path.is_ident("with_code") {
-            if meta.input.peek(token::Paren) {
-                let content;
-                parenthesized!(content in meta.input);
+        let call = match &wf.attrs.count {
+            None => quote! {
+                type2network::buf::FromBuf::deserialize_from_buf(&mut self.#field_name, buf)#context?;
+            },
+            Some(count) => quote! {
+                {
+                    #(
+                        #[allow(unused_variables)]
+                        let #headers = &self.#headers;
+                    )*
+                    let count = (#count) as usize;
+                    type2network::budget::take(count)#context?;
 
-                let block: proc_macro2::TokenStream = content.parse()?;
-                kind = AttrKind::Block(block);
+                    type2network::vec::Push::clear(&mut self.#field_name);
+                    for _ in 0..count {
+                        type2network::vec::Push::try_push(&mut self.#field_name, type2network::buf::FromBuf::deserialize_new_buf(buf)#context?)#context?;
+                    }
+                }
+            },
+        };
 
-                return Ok(());
+        // debug and trace attributes only apply to the Cursor path
+        let code = match &wf.attrs.from {
+            AttrKind::NoAttribute | AttrKind::Debug(_) | AttrKind::Trace => call,
+            AttrKind::NoAction => quote!(),
+            AttrKind::Call(func) => quote!(#func(self)#context?;),
+            AttrKind::Block(_) | AttrKind::Parser(_) => {
+                return Err(syn::Error::new_spanned(
+                    wf.field,
+                    "#[network(from(with_code(...)))] and #[network(from(with_parser(...)))] are not supported with #[network(buf)]",
+                ))
             }
+        };
+        let code = with_magic_check(code, wf, struct_name);
 
-            unimplemented!("#from_network attribute meta not supported")
-        }
+        calls.push(match &wf.attrs.mask {
+            None => code,
+            Some((mask, bit)) => quote! {
+                if {
+                    #(
+                        #[allow(unused_variables)]
+                        let #headers = &self.#headers;
+                    )*
+                    ((#mask) >> #bit) & 1 != 0
+                } {
+                    #code
+                }
+            },
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-        // #[deser(debug)]
-        if meta.path.is_ident("debug") {
-            kind = AttrKind::Debug;
-            return Ok(());
+    Ok(quote! {
+        impl #impl_generics type2network::buf::FromBuf for #struct_name #ty_generics #where_clause {
+            fn deserialize_from_buf<__B: type2network::buf::Buf>(&mut self, buf: &mut __B) -> std::io::Result<()> {
+                #skip
+                #( #calls)*
+                Ok(())
+            }
         }
+    })
+}
 
-        Err(meta.error("unrecognized #from_network attribute"))
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
 
-    kind
+    #[test]
+    fn primitive() {
+        assert!(is_primitive(&parse_quote!(u16)));
+        assert!(is_primitive(&parse_quote!([u8; 4])));
+        assert!(is_primitive(&parse_quote!([[char; 2]; 32])));
+        assert!(!is_primitive(&parse_quote!([u8; 33])));
+        assert!(!is_primitive(&parse_quote!([u8; N])));
+        assert!(!is_primitive(&parse_quote!(Vec<u8>)));
+        assert!(!is_primitive(&parse_quote!(std::net::Ipv4Addr)));
+    }
 }
@@ -1,30 +1,327 @@
-use syn::{DataStruct, DeriveInput, Fields, Ident};
+use quote::quote;
+use syn::{DataStruct, DeriveInput, Field, Fields, Index, Member, Type};
+
+use crate::attrs::{deprecated_alias, ContainerAttrs, FieldAttrs, NETWORK};
+use crate::compat;
 
 pub struct StructDeriveBuilder;
 pub type StructBuilderFunc = fn(&DeriveInput, &DataStruct) -> proc_macro2::TokenStream;
 
-#[derive(Debug, Default)]
-enum AttrKind {
-    // when no #[from_network] attribute is given
-    #[default]
-    NoAttribute,
+// Test whether the struct is a unit struct
+fn is_unit(ds: &DataStruct) -> bool {
+    matches!(ds.fields, Fields::Unit)
+}
+
+// a field with its declaration index and its analyzed attributes
+pub(crate) struct WireField<'a> {
+    pub(crate) index: usize,
+    pub(crate) field: &'a Field,
+    pub(crate) attrs: FieldAttrs,
+}
+
+impl WireField<'_> {
+    // the field as used in self.x or self.0
+    pub(crate) fn member(&self) -> Member {
+        match &self.field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(self.index)),
+        }
+    }
+
+    // the field name, or its index for a tuple struct
+    pub(crate) fn name(&self) -> String {
+        match &self.field.ident {
+            Some(ident) => ident.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+}
+
+// return all fields in the order they are found on the wire. This is the declaration order,
+// unless #[network(order = N)] is used
+pub(crate) fn wire_fields(ds: &DataStruct) -> syn::Result<Vec<WireField<'_>>> {
+    let mut fields = ds
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            Ok(WireField {
+                index,
+                field,
+                attrs: FieldAttrs::new(&field.attrs)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // #[deku(count)] and #[br(count)] refer to the fields declared before by their name
+    for (pos, f) in fields.iter_mut().enumerate() {
+        if let Some((dialect, tokens)) = f.attrs.compat_count.take() {
+            let previous: Vec<_> = ds
+                .fields
+                .iter()
+                .take(pos)
+                .filter_map(|f| f.ident.as_ref())
+                .collect();
+            f.attrs.count = Some(compat::count(dialect, tokens, &previous)?);
+        }
+    }
 
-    // #[from_network(ignore)]
-    NoAction,
+    // headers are referred to by their name
+    if let Some(f) = fields
+        .iter()
+        .find(|f| f.attrs.header && f.field.ident.is_none())
+    {
+        return Err(syn::Error::new_spanned(
+            f.field,
+            format!(
+                "#[network(header)] is not supported on tuple struct field {}",
+                f.index
+            ),
+        ));
+    }
 
-    // #[from_network(with_fn(my_func))]
-    Call(Ident),
+    // only one field can give the byte order
+    if let Some(f) = fields.iter().filter(|f| f.attrs.byte_order).nth(1) {
+        return Err(syn::Error::new_spanned(
+            f.field,
+            "#[network(byte_order)] can only be used on a single field",
+        ));
+    }
 
-    // #[from_network(with_code( let v = Vec::new(); ))]
-    Block(proc_macro2::TokenStream),
+    // no order attribute: keep declaration order
+    if fields.iter().all(|f| f.attrs.order.is_none()) {
+        return Ok(fields);
+    }
 
-    // #[from_network(debug)]
-    Debug,
+    // otherwise, all fields should have a unique order within 0..number of fields
+    let mut seen = vec![false; fields.len()];
+    for f in &fields {
+        let Some((order, span)) = f.attrs.order else {
+            return Err(syn::Error::new_spanned(
+                f.field,
+                "#[network(order = N)] is missing on this field",
+            ));
+        };
+
+        if order >= fields.len() {
+            return Err(syn::Error::new(
+                span,
+                format!("order {} is out of range 0..{}", order, fields.len()),
+            ));
+        }
+
+        if seen[order] {
+            return Err(syn::Error::new(span, format!("duplicate order {}", order)));
+        }
+        seen[order] = true;
+    }
+
+    fields.sort_by_key(|f| f.attrs.order.map(|o| o.0));
+    Ok(fields)
 }
 
-// Test whether the struct is a unit struct
-fn is_unit(ds: &DataStruct) -> bool {
-    matches!(ds.fields, Fields::Unit)
+// #[network(pod)]: the struct is copied as a whole, so its layout should be the one on the wire:
+// #[repr(C, packed)], no generics, and only u8, BeU16, BeU32, BeU64 or arrays of those fields without attributes
+fn check_pod(
+    ast: &DeriveInput,
+    ds: &DataStruct,
+    container_attrs: &ContainerAttrs,
+) -> syn::Result<()> {
+    let mut c = false;
+    let mut packed = false;
+    for attr in ast.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                c = true;
+            } else if meta.path.is_ident("packed") {
+                packed = true;
+                // packed(N) is only accepted for N = 1
+                if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let n: syn::LitInt = content.parse()?;
+                    packed = n.base10_parse::<usize>()? == 1;
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    if !c || !packed {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(pod)] requires #[repr(C, packed)]",
+        ));
+    }
+
+    if !ast.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ast.generics,
+            "#[network(pod)] is not supported on generic structs",
+        ));
+    }
+
+    if container_attrs.skip_all_if.is_some() {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(pod)] can't be used with #[network(skip_all_if)]",
+        ));
+    }
+
+    if container_attrs.buf {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(pod)] can't be used with #[network(buf)]",
+        ));
+    }
+
+    if container_attrs.zerocopy {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(pod)] can't be used with #[network(zerocopy)]",
+        ));
+    }
+
+    if container_attrs.pad_block.is_some() {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(pod)] can't be used with #[network(pad_block)]",
+        ));
+    }
+
+    check_no_field_attrs(ds, "pod")?;
+
+    for field in &ds.fields {
+        if !is_byte_type(&field.ty) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "only u8, BeU16, BeU32, BeU64 or arrays of those are supported with #[network(pod)]",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// the struct is copied as a whole with #[network(option)], so field attributes can't apply
+fn check_no_field_attrs(ds: &DataStruct, option: &str) -> syn::Result<()> {
+    for field in &ds.fields {
+        if field
+            .attrs
+            .iter()
+            .any(|a| a.path().is_ident(NETWORK) || deprecated_alias(a).is_some())
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!(
+                    "field attributes are not supported with #[network({})]",
+                    option
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// #[network(zerocopy)]: the struct is copied using the zerocopy traits, which check its layout. Its
+// fields should be in network order, which is checked at compile time by zerocopy_layout_check()
+fn check_zerocopy(
+    ast: &DeriveInput,
+    ds: &DataStruct,
+    container_attrs: &ContainerAttrs,
+) -> syn::Result<()> {
+    if !ast.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ast.generics,
+            "#[network(zerocopy)] is not supported on generic structs",
+        ));
+    }
+
+    if container_attrs.buf
+        || container_attrs.skip_all_if.is_some()
+        || container_attrs.pad_block.is_some()
+    {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "#[network(zerocopy)] can't be used with #[network(buf)], #[network(skip_all_if)] or #[network(pad_block)]",
+        ));
+    }
+
+    check_no_field_attrs(ds, "zerocopy")
+}
+
+// all fields should implement NetworkLayout, so their bytes are in network order
+fn zerocopy_layout_check(ds: &DataStruct) -> proc_macro2::TokenStream {
+    let types = ds.fields.iter().map(|f| &f.ty);
+
+    quote! {
+        const _: () = {
+            fn network_layout<T: type2network::zerocopy::NetworkLayout>() {}
+            #( let _ = network_layout::<#types>; )*
+        };
+    }
+}
+
+// u8, [u8; N], [[u8; N]; M], BeU16... The types are also checked to implement the Overlay trait
+fn is_byte_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(tp) => {
+            tp.qself.is_none()
+                && tp.path.segments.last().is_some_and(|s| {
+                    s.arguments.is_empty()
+                        && ["u8", "BeU16", "BeU32", "BeU64"]
+                            .iter()
+                            .any(|t| s.ident == t)
+                })
+        }
+        Type::Array(ta) => is_byte_type(&ta.elem),
+        _ => false,
+    }
+}
+
+// integers, floats or arrays of those, which are read and written in the byte order given by the
+// #[network(byte_order)] field
+fn is_swappable(ty: &Type) -> bool {
+    const NUMBERS: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64",
+    ];
+
+    match ty {
+        Type::Path(tp) => tp.qself.is_none() && NUMBERS.iter().any(|n| tp.path.is_ident(n)),
+        Type::Array(ta) => is_swappable(&ta.elem),
+        Type::Paren(tp) => is_swappable(&tp.elem),
+        _ => false,
+    }
+}
+
+// the #[network(byte_order)] field found before the field at pos on the wire, if the byte order
+// applies to this field
+pub(crate) fn byte_order_field<'f, 'a>(
+    fields: &'f [WireField<'a>],
+    pos: usize,
+) -> Option<&'f WireField<'a>> {
+    if !is_swappable(&fields[pos].field.ty) {
+        return None;
+    }
+    fields[..pos].iter().find(|f| f.attrs.byte_order)
+}
+
+// the layout is checked at compile time: no padding, so all bytes are initialized, and all
+// fields accept any bytes
+fn pod_layout_check(ast: &DeriveInput, ds: &DataStruct) -> proc_macro2::TokenStream {
+    let struct_name = &ast.ident;
+    let types: Vec<_> = ds.fields.iter().map(|f| &f.ty).collect();
+
+    quote! {
+        const _: () = {
+            assert!(std::mem::align_of::<#struct_name>() == 1);
+            assert!(std::mem::size_of::<#struct_name>() == 0 #(+ std::mem::size_of::<#types>())*);
+
+            fn overlay<T: type2network::endian::Overlay>() {}
+            #( let _ = overlay::<#types>; )*
+        };
+    }
 }
 
 pub mod from;
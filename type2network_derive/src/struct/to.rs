@@ -1,7 +1,14 @@
 use quote::quote;
-use syn::{DataStruct, DeriveInput, Index};
+use syn::{DataStruct, DeriveInput};
 
-use crate::r#struct::is_unit;
+use crate::{
+    attrs::{ContainerAttrs, ToAttrKind},
+    builder,
+    r#struct::{
+        byte_order_field, check_pod, check_zerocopy, is_unit, pod_layout_check, wire_fields,
+        zerocopy_layout_check, WireField,
+    },
+};
 
 use super::StructDeriveBuilder;
 
@@ -14,34 +21,361 @@ impl StructDeriveBuilder {
 
         let struct_name = &ast.ident;
 
-        let method_calls = ds.fields.iter().enumerate().map(|field| {
-            match &field.1.ident {
-                // case of a struct with named fields
-                Some(field_name) => {
+        // analyze attributes of the struct
+        let container_attrs = match ContainerAttrs::new(&ast.attrs) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error(),
+        };
+        let skip = container_attrs.skip_code(quote!(Ok(0)));
+
+        // #[network(pad_block = N)]: the message is padded after its last field
+        let (pad_block, pad_block_to) = match &container_attrs.pad_block {
+            Some(block) => {
+                let fill = container_attrs.pad.fill();
+                (
+                    quote! {
+                        length += type2network::align::write_block_padding(buffer, length, #block, #fill);
+                    },
                     quote! {
-                        length += ToNetworkOrder::serialize_to(&self.#field_name, buffer)?;
+                        length += type2network::align::write_block_padding_to(writer, length, #block, #fill)?;
+                    },
+                )
+            }
+            None => (quote!(), quote!()),
+        };
+
+        // #[network(pod)]: the struct is copied as is
+        if container_attrs.pod {
+            if let Err(e) = check_pod(ast, ds, &container_attrs) {
+                return e.to_compile_error();
+            }
+            let layout_check = pod_layout_check(ast, ds);
+
+            return quote! {
+                #layout_check
+
+                impl ToNetworkOrder for #struct_name {
+                    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                        let size = std::mem::size_of::<Self>();
+
+                        // SAFETY: the struct only holds bytes and has no padding (checked at compile time),
+                        // so all its bytes are initialized
+                        let bytes = unsafe { std::slice::from_raw_parts((self as *const Self).cast::<u8>(), size) };
+                        buffer.extend_from_slice(bytes);
+                        Ok(size)
+                    }
+
+                    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                        let size = std::mem::size_of::<Self>();
+
+                        // SAFETY: see serialize_to()
+                        let bytes = unsafe { std::slice::from_raw_parts((self as *const Self).cast::<u8>(), size) };
+                        writer.write_all(bytes)?;
+                        Ok(size)
                     }
                 }
-                // case of a tuple struct
-                None => {
-                    let index = Index::from(field.0);
+            };
+        }
+
+        // #[network(zerocopy)]: the bytes of the struct are copied using the zerocopy traits
+        if container_attrs.zerocopy {
+            if let Err(e) = check_zerocopy(ast, ds, &container_attrs) {
+                return e.to_compile_error();
+            }
+            let layout_check = zerocopy_layout_check(ds);
+
+            return quote! {
+                #layout_check
+
+                impl type2network::zerocopy::NetworkLayout for #struct_name {}
+
+                impl ToNetworkOrder for #struct_name {
+                    fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                        Ok(type2network::zerocopy::write_bytes(self, buffer))
+                    }
+
+                    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                        type2network::zerocopy::write_bytes_to(self, writer)
+                    }
+                }
+            };
+        }
+
+        // analyze attributes of all fields and get them in wire order
+        let fields = match wire_fields(ds) {
+            Ok(fields) => fields,
+            Err(e) => return e.to_compile_error(),
+        };
+
+        let method_calls: Vec<_> = fields
+            .iter()
+            .enumerate()
+            .map(|(pos, wf)| {
+                // #[network(to(ignore))]: the field is not serialized
+                if matches!(wf.attrs.to, ToAttrKind::NoAction) {
+                    return quote!();
+                }
+
+                // #[network(align_from_parent = N)]: padding bytes are written before the field
+                let call = field_call(&fields, pos, false);
+                match &wf.attrs.align {
+                    Some(align) => {
+                        let fill = wf.attrs.pad.fill();
+                        quote! {
+                            length += type2network::align::write_padding(buffer, #align, #fill);
+                            #call
+                        }
+                    }
+                    None => call,
+                }
+            })
+            .collect();
+
+        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+        // #[network(buf)]: ToBufMut is implemented the same way
+        let buf_impl = if container_attrs.buf {
+            if container_attrs.pad_block.is_some() {
+                return syn::Error::new_spanned(
+                    struct_name,
+                    "#[network(pad_block)] is not supported with #[network(buf)]",
+                )
+                .to_compile_error();
+            }
+
+            if let Some(wf) = fields.iter().find(|wf| wf.attrs.byte_order) {
+                return syn::Error::new_spanned(
+                    wf.field,
+                    "#[network(byte_order)] is not supported with #[network(buf)]",
+                )
+                .to_compile_error();
+            }
+
+            if let Some(wf) = fields.iter().find(|wf| wf.attrs.char_as_utf8) {
+                return syn::Error::new_spanned(
+                    wf.field,
+                    "#[network(char_as)] is not supported with #[network(buf)]",
+                )
+                .to_compile_error();
+            }
+
+            if let Some(wf) = fields.iter().find(|wf| wf.attrs.align.is_some()) {
+                return syn::Error::new_spanned(
+                    wf.field,
+                    "#[network(align_from_parent)] is not supported with #[network(buf)]",
+                )
+                .to_compile_error();
+            }
+
+            let calls = fields
+                .iter()
+                .filter(|wf| !matches!(wf.attrs.to, ToAttrKind::NoAction))
+                .map(|wf| {
+                    let field_name = wf.member();
                     quote! {
-                        length += ToNetworkOrder::serialize_to(&self.#index, buffer)?;
+                        length += type2network::buf::ToBufMut::serialize_to_buf(&self.#field_name, buf)?;
+                    }
+                });
+
+            quote! {
+                impl #impl_generics type2network::buf::ToBufMut for #struct_name #ty_generics #where_clause {
+                    fn serialize_to_buf<__B: type2network::buf::BufMut>(&self, buf: &mut __B) -> std::io::Result<usize> {
+                        #skip
+                        let mut length = 0usize;
+                        #( #calls)*
+                        Ok(length)
                     }
                 }
             }
-        });
+        } else {
+            quote!()
+        };
 
-        let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+        // #[network(vectored)]: ToVectored borrows the #[network(to(borrow))] fields, and copies the other ones
+        let vectored_impl = if container_attrs.vectored {
+            // the offset of the fields in the message isn't known from the owned buffer
+            if let Some(wf) = fields.iter().find(|wf| wf.attrs.align.is_some()) {
+                return syn::Error::new_spanned(
+                    wf.field,
+                    "#[network(align_from_parent)] is not supported with #[network(vectored)]",
+                )
+                .to_compile_error();
+            }
+
+            let calls = fields
+                .iter()
+                .zip(&method_calls)
+                .filter(|(wf, _)| !matches!(wf.attrs.to, ToAttrKind::NoAction))
+                .map(|(wf, call)| {
+                    if matches!(wf.attrs.to, ToAttrKind::Borrow) {
+                        let field_name = wf.member();
+                        quote! {
+                            length += out.borrow(AsRef::<[u8]>::as_ref(&self.#field_name));
+                        }
+                    } else {
+                        quote! {
+                            {
+                                let buffer = out.buffer();
+                                #call
+                            }
+                        }
+                    }
+                });
+
+            quote! {
+                impl #impl_generics type2network::vectored::ToVectored for #struct_name #ty_generics #where_clause {
+                    fn serialize_vectored<'__v>(&'__v self, out: &mut type2network::vectored::Vectored<'__v>) -> std::io::Result<usize> {
+                        #skip
+                        let mut length = 0usize;
+                        #( #calls)*
+                        {
+                            let buffer = out.buffer();
+                            #pad_block
+                        }
+                        Ok(length)
+                    }
+                }
+            }
+        } else if let Some(wf) = fields
+            .iter()
+            .find(|wf| matches!(wf.attrs.to, ToAttrKind::Borrow))
+        {
+            return syn::Error::new_spanned(
+                wf.field,
+                "#[network(to(borrow))] requires #[network(vectored)]",
+            )
+            .to_compile_error();
+        } else {
+            quote!()
+        };
+
+        // #[network(hexdebug)]: each serialized field is shown with its value and its bytes
+        let fmt_wire = if container_attrs.hexdebug {
+            let shown: Vec<_> = fields
+                .iter()
+                .filter(|wf| !matches!(wf.attrs.to, ToAttrKind::NoAction))
+                .collect();
+            let width = shown.iter().map(|wf| wf.name().len()).max().unwrap_or(0);
+            let calls = shown.iter().map(|wf| {
+                let field_name = wf.member();
+                let name = wf.name();
+                if wf.attrs.char_as_utf8 {
+                    return quote! {
+                        type2network::fmt_wire_field(&mut out, #name, #width, &type2network::text::Utf8Char(self.#field_name));
+                    };
+                }
+                quote! {
+                    type2network::fmt_wire_field(&mut out, #name, #width, &self.#field_name);
+                }
+            });
+
+            quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    /// Returns the value of each serialized field alongside its bytes in hex, one field per line.
+                    pub fn fmt_wire(&self) -> String {
+                        let mut out = String::new();
+                        #( #calls)*
+                        out
+                    }
+                }
+            }
+        } else {
+            quote!()
+        };
+
+        // write_to() writes the fields directly to the writer, the bytes being counted for the padding
+        let write_calls = fields
+            .iter()
+            .enumerate()
+            .filter(|(_, wf)| !matches!(wf.attrs.to, ToAttrKind::NoAction))
+            .map(|(pos, wf)| {
+                let call = field_call(&fields, pos, true);
+                match &wf.attrs.align {
+                    Some(align) => {
+                        let fill = wf.attrs.pad.fill();
+                        quote! {
+                            length += type2network::align::write_padding_to(writer, #align, #fill)?;
+                            #call
+                        }
+                    }
+                    None => call,
+                }
+            });
+
+        // #[network(builder)]: the TBuilder struct
+        let builder = if container_attrs.builder {
+            match builder::builder(ast, &fields) {
+                Ok(code) => code,
+                Err(e) => return e.to_compile_error(),
+            }
+        } else {
+            quote!()
+        };
 
         quote! {
+            #fmt_wire
+            #buf_impl
+            #vectored_impl
+            #builder
+
             impl #impl_generics ToNetworkOrder for #struct_name #ty_generics #where_clause {
                 fn serialize_to(&self, buffer: &mut Vec<u8>) -> std::io::Result<usize> {
+                    #skip
                     let mut length = 0usize;
                     #( #method_calls)*
+                    #pad_block
                     Ok(length)
                 }
+
+                fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                    #skip
+                    type2network::align::track_writes(writer, |writer| {
+                        let mut length = 0usize;
+                        #( #write_calls)*
+                        #pad_block_to
+                        Ok(length)
+                    })
+                }
             }
         }
     }
 }
+
+// the call serializing a single field, to the buffer of serialize_to() or to the writer of write_to()
+fn field_call(fields: &[WireField], pos: usize, to_writer: bool) -> proc_macro2::TokenStream {
+    let wf = &fields[pos];
+    let field_name = wf.member();
+    let serialize = |value: proc_macro2::TokenStream| {
+        if to_writer {
+            quote!(ToNetworkOrder::write_to(#value, writer))
+        } else {
+            quote!(ToNetworkOrder::serialize_to(#value, buffer))
+        }
+    };
+
+    // #[network(byte_order)]: the field is written in the byte order of the magic
+    if let Some(order) = byte_order_field(fields, pos) {
+        let order_name = order.member();
+        let write_value = serialize(quote!(&value));
+        return quote! {
+            {
+                let mut value = self.#field_name;
+                type2network::byte_order::DetectByteOrder::byte_order(&self.#order_name).adjust(&mut value);
+                length += #write_value?;
+            }
+        };
+    }
+
+    // #[network(char_as = "utf8")]: the char is written as its UTF-8 bytes
+    if wf.attrs.char_as_utf8 {
+        let write_char = serialize(quote!(&type2network::text::Utf8Char(self.#field_name)));
+        return quote! {
+            length += #write_char?;
+        };
+    }
+
+    let write_field = serialize(quote!(&self.#field_name));
+    quote! {
+        length += #write_field?;
+    }
+}
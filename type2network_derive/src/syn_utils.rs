@@ -1,9 +1,9 @@
 // this will help managing enum variants
 use proc_macro2::{Span, TokenStream};
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::{
-    Attribute, DeriveInput, Expr, Fields, FieldsNamed, FieldsUnnamed, GenericParam,
-    Generics, Lifetime, LifetimeParam, Variant,
+    parse_quote, visit::Visit, Attribute, DeriveInput, Expr, Fields, FieldsNamed, FieldsUnnamed,
+    GenericParam, Generics, Ident, Lifetime, LifetimeParam, Type, TypePath, Variant,
 };
 
 #[allow(dead_code)]
@@ -101,6 +101,55 @@ pub(super) fn add_lifetime(ast: &DeriveInput) -> Generics {
     gen_clone
 }
 
+// add the bound to all type parameters used in the types of the fields, so that the user
+// doesn't need to add them. Ex: for
+//
+// enum Payload<T> {
+//     Raw(Vec<u8>),
+//     Typed(T),
+// }
+//
+// add the where T: ToNetworkOrder clause
+pub(super) fn add_bounds<'a>(
+    generics: &Generics,
+    types: impl Iterator<Item = &'a Type>,
+    bound: TokenStream,
+) -> Generics {
+    // collect type params found in field types
+    struct TypeParamVisitor<'b> {
+        params: Vec<&'b Ident>,
+        used: Vec<Ident>,
+    }
+
+    impl<'ast> Visit<'ast> for TypeParamVisitor<'_> {
+        fn visit_type_path(&mut self, tp: &'ast TypePath) {
+            if tp.qself.is_none() && tp.path.segments.len() == 1 {
+                let ident = &tp.path.segments[0].ident;
+                if self.params.contains(&ident) && !self.used.contains(ident) {
+                    self.used.push(ident.clone());
+                }
+            }
+            syn::visit::visit_type_path(self, tp);
+        }
+    }
+
+    let mut visitor = TypeParamVisitor {
+        params: generics.type_params().map(|tp| &tp.ident).collect(),
+        used: Vec::new(),
+    };
+    for ty in types {
+        visitor.visit_type(ty);
+    }
+
+    let mut gen_clone = generics.clone();
+    let where_clause = gen_clone.make_where_clause();
+    for ident in visitor.used {
+        where_clause.predicates.push(parse_quote!(#ident: #bound));
+    }
+
+    gen_clone
+}
+
 // pub(super) enum TraitTypeParam {
 //     Reader,
 //     Writer,
@@ -143,10 +192,77 @@ pub(super) fn add_lifetime(ast: &DeriveInput) -> Generics {
 //     gen_clone
 // }
 
+// the code adding the field path to the error of a field, for the generated code:
+//
+// FromNetworkOrder::deserialize_from(&mut self.x, buffer).map_err(|e| type2network::field_context(e, "Point", "x"))?;
+pub(super) fn error_context(type_name: &Ident, field: &str) -> TokenStream {
+    let type_name = type_name.to_string();
+    quote!(.map_err(|e| type2network::field_context(e, #type_name, #field)))
+}
+
+// the default value of a field: arrays only implement Default up to 32 elements, and not when their
+// length is a const generic (struct Block<const N: usize> { data: [u8; N] }), so they're built
+// element by element:
+//
+// std::array::from_fn(|_| Default::default())
+pub(super) fn default_value(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Array(array) => {
+            let elem = default_value(&array.elem);
+            quote!(std::array::from_fn(|_| #elem))
+        }
+        Type::Paren(paren) => default_value(&paren.elem),
+        _ => quote!(Default::default()),
+    }
+}
+
+// the type which should implement Default for default_value() to build the field: the elements
+// for an array
+pub(super) fn default_type(ty: &Type) -> &Type {
+    match ty {
+        Type::Array(array) => default_type(&array.elem),
+        Type::Paren(paren) => default_type(&paren.elem),
+        _ => ty,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use syn::parse_quote;
+
+    #[test]
+    fn default_array() {
+        let ty: Type = parse_quote!([[u8; N]; 4]);
+        assert_eq!(
+            default_value(&ty).to_string(),
+            "std :: array :: from_fn (| _ | std :: array :: from_fn (| _ | Default :: default ()))"
+        );
+        assert_eq!(default_type(&ty).to_token_stream().to_string(), "u8");
+    }
+
+    #[test]
+    fn bounds() {
+        let e: syn::DeriveInput = parse_quote!(
+            enum Payload<T, U, V> {
+                Raw(Vec<U>),
+                Typed(T),
+                Nothing(std::marker::PhantomData<u8>),
+            }
+        );
+        let syn::Data::Enum(de) = &e.data else {
+            unreachable!()
+        };
+        let types = de
+            .variants
+            .iter()
+            .flat_map(|v| v.fields.iter().map(|f| &f.ty));
+
+        let gen = add_bounds(&e.generics, types, quote::quote!(ToNetworkOrder));
+        assert_eq!(
+            gen.where_clause.to_token_stream().to_string(),
+            "where U : ToNetworkOrder , T : ToNetworkOrder"
+        );
+    }
 
     #[test]
     fn enum_opcode_reserved() {
@@ -199,11 +315,11 @@ mod tests {
                 }
                 "Move" => {
                     assert!(v.is_named().is_some());
-                    assert!(!v.has_attribute("foo").is_some());
+                    assert!(v.has_attribute("foo").is_none());
                 }
                 "Write" => {
                     assert!(v.is_unnamed().is_some());
-                    assert!(!v.has_attribute("foo").is_some());
+                    assert!(v.has_attribute("foo").is_none());
                 }
                 _ => (),
             }
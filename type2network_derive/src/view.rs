@@ -0,0 +1,163 @@
+// the NetworkView derive: a read-only view over the bytes of a struct, with an accessor per field.
+// Ex: for
+//
+// struct Header {
+//     kind: u8,
+//     id: u32,
+//     payload: Vec<u8>,
+// }
+//
+// HeaderView<'v> is generated, with kind() reading a u8 at offset 0, id() a u32 at offset 1 and
+// payload() returning the bytes from offset 5
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Type};
+
+use crate::attrs::AttrKind;
+use crate::r#struct::wire_fields;
+
+pub(crate) fn network_view(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    match build_view(ast) {
+        Ok(code) => code,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+// the last field can be a Vec<u8> or a &[u8]: the bytes left after the fixed size fields
+fn is_variable_bytes(ty: &Type) -> bool {
+    let is_u8 = |ty: &Type| matches!(ty, Type::Path(tp) if tp.path.is_ident("u8"));
+
+    match ty {
+        Type::Reference(r) => matches!(&*r.elem, Type::Slice(s) if is_u8(&s.elem)),
+        Type::Path(tp) => {
+            let Some(last) = tp.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                syn::PathArguments::AngleBracketed(args) if last.ident == "Vec" => {
+                    matches!(args.args.first(), Some(syn::GenericArgument::Type(t)) if is_u8(t))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn build_view(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &ast.ident;
+    let name_string = struct_name.to_string();
+    let view_name = format_ident!("{}View", struct_name);
+    let vis = &ast.vis;
+
+    let Data::Struct(ds) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "NetworkView can only be derived for structs",
+        ));
+    };
+
+    if !matches!(ds.fields, Fields::Named(_)) {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "NetworkView requires a struct with named fields",
+        ));
+    }
+
+    if ast.generics.type_params().next().is_some() || ast.generics.const_params().next().is_some() {
+        return Err(syn::Error::new_spanned(
+            &ast.generics,
+            "NetworkView is not supported on generic structs",
+        ));
+    }
+
+    // the offsets are only known if all fields are on the wire at a fixed place
+    let fields = wire_fields(ds)?;
+    for wf in &fields {
+        let unsupported = match &wf.attrs.from {
+            AttrKind::NoAction | AttrKind::Call(_) | AttrKind::Block(_) | AttrKind::Parser(_) => {
+                true
+            }
+            AttrKind::NoAttribute | AttrKind::Debug(_) | AttrKind::Trace => false,
+        };
+        if unsupported
+            || wf.attrs.count.is_some()
+            || wf.attrs.mask.is_some()
+            || wf.attrs.byte_order
+            || wf.attrs.utf8.is_some()
+            || wf.attrs.char_as_utf8
+            || wf.attrs.magic.is_some()
+            || wf.attrs.align.is_some()
+        {
+            return Err(syn::Error::new_spanned(
+                wf.field,
+                "the offset of this field is not known: attribute not supported by NetworkView",
+            ));
+        }
+    }
+
+    let mut accessors = Vec::new();
+    let mut sizes = Vec::new();
+
+    for (pos, wf) in fields.iter().enumerate() {
+        let name = &wf.field.ident;
+        let ty = &wf.field.ty;
+        let doc = format!("Returns the ```{}``` field.", wf.name());
+
+        if is_variable_bytes(ty) {
+            if pos != fields.len() - 1 {
+                return Err(syn::Error::new_spanned(
+                    wf.field,
+                    "only the last field can have a variable length in a NetworkView",
+                ));
+            }
+
+            accessors.push(quote! {
+                #[doc = #doc]
+                #vis fn #name(&self) -> &'v [u8] {
+                    &self.bytes[Self::FIXED_SIZE..]
+                }
+            });
+            continue;
+        }
+
+        accessors.push(quote! {
+            #[doc = #doc]
+            #vis fn #name(&self) -> #ty {
+                const OFFSET: usize = 0 #(+ #sizes)*;
+                <#ty as type2network::view::FixedSize>::read_at(&self.bytes[OFFSET..])
+            }
+        });
+        sizes.push(quote!(<#ty as type2network::view::FixedSize>::SIZE));
+    }
+
+    let doc = format!(
+        "A read-only view over the bytes of a ```{}```.",
+        name_string
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy)]
+        #vis struct #view_name<'v> {
+            bytes: &'v [u8],
+        }
+
+        impl<'v> #view_name<'v> {
+            /// Number of bytes of the fields having a fixed size.
+            #vis const FIXED_SIZE: usize = 0 #(+ #sizes)*;
+
+            /// Creates a view over the bytes, or returns an error if there are not enough bytes for the fixed size fields.
+            #vis fn new(bytes: &'v [u8]) -> std::io::Result<Self> {
+                type2network::view::check_view_size(#name_string, bytes, Self::FIXED_SIZE)?;
+                Ok(Self { bytes })
+            }
+
+            /// Returns the bytes of the view.
+            #vis fn as_bytes(&self) -> &'v [u8] {
+                self.bytes
+            }
+
+            #( #accessors )*
+        }
+    })
+}
@@ -0,0 +1,70 @@
+// the WireDefault derive: a Default impl where the fields with #[network(magic = value)] or
+// #[network(expect = value)] are set to this value, and the other ones to their default value.
+// Ex: for
+//
+// struct Header {
+//     #[network(magic = 0xA1B2C3D4)]
+//     magic: u32,
+//     #[network(expect = 2)]
+//     version: u8,
+//     length: u16,
+// }
+//
+// impl Default for Header {
+//     fn default() -> Self {
+//         Self { magic: 0xA1B2C3D4, version: 2, length: Default::default() }
+//     }
+// }
+use quote::quote;
+use syn::{Data, DeriveInput, Fields};
+
+use crate::{attrs::FieldAttrs, syn_utils::default_value};
+
+pub(crate) fn wire_default(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    match build_default(ast) {
+        Ok(code) => code,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn build_default(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+
+    let Data::Struct(ds) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "WireDefault can only be derived for structs",
+        ));
+    };
+
+    let values = ds
+        .fields
+        .iter()
+        .map(|field| {
+            let attrs = FieldAttrs::new(&field.attrs)?;
+            Ok(match attrs.magic.or(attrs.expect) {
+                Some(value) => quote!(#value),
+                None => default_value(&field.ty),
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let value = match &ds.fields {
+        Fields::Named(_) => {
+            let names = ds.fields.iter().map(|f| f.ident.as_ref().unwrap());
+            quote!(Self { #( #names: #values ),* })
+        }
+        Fields::Unnamed(_) => quote!(Self( #( #values ),* )),
+        Fields::Unit => quote!(Self),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics Default for #name #ty_generics #where_clause {
+            fn default() -> Self {
+                #value
+            }
+        }
+    })
+}